@@ -0,0 +1,452 @@
+// Admin settings and the platform's own books: configuration, storage lifetimes, fees, the
+// curated registries and layout migrations.
+
+use soroban_sdk::{ token, Address, Env, Map, String, Symbol, Vec };
+
+use crate::escrow::{ check_reentry, is_active, milestone_records, transfer, withdrawable_fees };
+use crate::index::{ IndexKind, IndexReport };
+use crate::page::RegistryPage;
+use crate::ttl::{ TtlClass, TtlPolicy };
+use crate::{ events, index, legacy, migration, page, storage, ttl, CheckResult, Config, Description, Error, FeeTerms, Invariant, LaunchMode, MilestoneStatus, PlatformStats, ReferenceRate, RegistryEntry, RegistryKind, RegistryListing, RiskCaps, BPS_DENOMINATOR, DAY_SECS, DEFAULT_LANGUAGE, INTERFACE_VERSION, MAX_LABEL_LANGUAGES, MAX_SELF_CHECK_SAMPLE };
+
+// Writes the admin and every tunable's default, so a fresh deployment runs end to end
+// without a single admin action
+pub fn init(env: &Env, admin: Address) {
+  storage::admin().set(env, &admin);
+  storage::data_version().set(env, &migration::DATA_VERSION);
+  storage::config().set(env, &Config::default_values());
+}
+
+pub fn migrate(env: &Env, admin: Address, limit: u32) -> Result<u32, Error> {
+  require_admin(env, &admin)?;
+  migration::run(env, limit)
+}
+
+// Applied through the admin queue
+pub fn set_legacy_asset(env: &Env, asset: Address) {
+  storage::legacy_asset().set(env, &asset);
+}
+
+pub fn get_data_version(env: &Env) -> u32 {
+  migration::data_version(env)
+}
+
+pub fn describe(env: &Env) -> Description {
+  Description { interface_version: INTERFACE_VERSION, data_version: migration::data_version(env), deprecated: legacy::deprecated_calls(env) }
+}
+
+// Applied through the admin queue
+pub fn set_config(env: &Env, config: Config) -> Result<(), Error> {
+  check_config_change(env, &config)?;
+  storage::config().set(env, &config);
+  Ok(())
+}
+
+// Leaving allowlist mode is one-way here; only RestrictToAllowlist goes back
+pub fn check_config_change(env: &Env, config: &Config) -> Result<(), Error> {
+  check_config(config)?;
+  if get_config(env).launch_mode == LaunchMode::Open && config.launch_mode == LaunchMode::Allowlist {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+// Applied through the admin queue
+pub fn restrict_to_allowlist(env: &Env) {
+  let mut config = config(env);
+  config.launch_mode = LaunchMode::Allowlist;
+  storage::config().set(env, &config);
+}
+
+// Applied through the admin queue
+pub fn set_allowlisted(env: &Env, user: Address, allowed: bool) {
+  let key = storage::allowlisted(user);
+  if allowed {
+    key.set(env, &true);
+  } else {
+    key.remove(env);
+  }
+  ttl::bump(env, &key, TtlClass::Warm);
+}
+
+pub fn is_allowlisted(env: &Env, user: Address) -> bool {
+  storage::allowlisted(user).has(env)
+}
+
+// Gate on calls that start something new: in allowlist mode only allowlisted callers pass
+pub fn require_launch_access(env: &Env, user: &Address) -> Result<(), Error> {
+  if config(env).launch_mode == LaunchMode::Allowlist && !is_allowlisted(env, user.clone()) {
+    return Err(Error::Unauthorized);
+  }
+  Ok(())
+}
+
+pub fn check_config(config: &Config) -> Result<(), Error> {
+  if config.review_period_secs == 0 || config.bundle_grace_secs == 0 || config.contest_window_secs == 0 || config.rate_max_age_secs == 0 {
+    return Err(Error::InvalidConfig);
+  }
+  if config.risk_caps.new_cap < 0 || config.risk_caps.established_cap < 0 {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+pub fn get_config(env: &Env) -> Config {
+  config(env)
+}
+
+pub fn get_ttl_policy(env: &Env, class: TtlClass) -> TtlPolicy {
+  ttl::policy(env, class)
+}
+
+// Applied through the admin queue
+pub fn set_platform_fee(env: &Env, bps: u32) -> Result<(), Error> {
+  check_platform_fee(bps)?;
+  storage::platform_fee().set(env, &bps);
+  Ok(())
+}
+
+pub fn check_platform_fee(bps: u32) -> Result<(), Error> {
+  if bps > BPS_DENOMINATOR {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+pub fn get_platform_fee(env: &Env) -> u32 {
+  storage::platform_fee().get(env).unwrap_or(0)
+}
+
+// Applied through the admin queue
+pub fn set_reference_rate(env: &Env, asset: Address, rate: i128, timestamp: u64) -> Result<(), Error> {
+  check_reference_rate(env, &asset, rate, timestamp)?;
+  storage::reference_rates(asset).set(env, &ReferenceRate { rate, timestamp });
+  Ok(())
+}
+
+pub fn check_reference_rate(env: &Env, asset: &Address, rate: i128, timestamp: u64) -> Result<(), Error> {
+  if rate <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let now = env.ledger().timestamp();
+  if timestamp > now || now - timestamp > config(env).rate_max_age_secs {
+    return Err(Error::InvalidConfig);
+  }
+  if storage::reference_rates(asset.clone()).get(env).is_some_and(|current| current.timestamp > timestamp) {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+pub fn get_reference_rate(env: &Env, asset: Address) -> Option<ReferenceRate> {
+  storage::reference_rates(asset).get(env)
+}
+
+// The asset's rate, unless it is older than the config allows
+pub fn fresh_reference_rate(env: &Env, asset: Address) -> Result<ReferenceRate, Error> {
+  let rate = storage::reference_rates(asset).get(env).ok_or(Error::NotFound)?;
+  if env.ledger().timestamp().saturating_sub(rate.timestamp) > config(env).rate_max_age_secs {
+    return Err(Error::NotFound);
+  }
+  Ok(rate)
+}
+
+// Applied through the admin queue
+pub fn set_min_deposit_increment(env: &Env, asset: Address, increment: Option<i128>) -> Result<(), Error> {
+  let increment = deposit_increment(env, &asset, increment)?;
+  storage::deposit_increments(asset).set(env, &increment);
+  Ok(())
+}
+
+pub fn check_min_deposit_increment(env: &Env, asset: &Address, increment: Option<i128>) -> Result<(), Error> {
+  deposit_increment(env, asset, increment).map(|_| ())
+}
+
+// Without an increment the smallest deposit is one whole token, by the token's decimals. An
+// increment of 1 takes any deposit.
+fn deposit_increment(env: &Env, asset: &Address, increment: Option<i128>) -> Result<i128, Error> {
+  let increment = match increment {
+    Some(increment) => increment,
+    None => 10i128.checked_pow(token::Client::new(env, asset).decimals()).ok_or(Error::InvalidAmount)?,
+  };
+  if increment <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  Ok(increment)
+}
+
+// 1 for an asset the admin never set one for
+pub fn get_min_deposit_increment(env: &Env, asset: Address) -> i128 {
+  storage::deposit_increments(asset).get(env).unwrap_or(1)
+}
+
+// Applied through the admin queue
+pub fn set_nonprofit(env: &Env, user: Address, nonprofit: bool) {
+  let key = storage::nonprofits(user);
+  if nonprofit {
+    key.set(env, &true);
+  } else {
+    key.remove(env);
+  }
+}
+
+pub fn is_nonprofit(env: &Env, user: Address) -> bool {
+  storage::nonprofits(user).has(env)
+}
+
+pub fn get_fee_terms(env: &Env, escrow_id: u64) -> FeeTerms {
+  storage::fee_terms(escrow_id).get(env).unwrap_or_default()
+}
+
+pub fn get_platform_stats(env: &Env) -> PlatformStats {
+  storage::platform_stats().get(env).unwrap_or_default()
+}
+
+pub fn withdraw_fees(env: &Env, admin: Address, asset: Address, to: Address, amount: i128) -> Result<(), Error> {
+  require_admin(env, &admin)?;
+  check_reentry(env)?;
+  if amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  // Error codes carry no data; get_withdrawable_fees tells the caller how much fits
+  if amount > withdrawable_fees(env, &asset) {
+    return Err(Error::ExceedsSurplus);
+  }
+  let pool = storage::fee_pool(asset.clone()).get(env).unwrap_or(0);
+  storage::fee_pool(asset.clone()).set(env, &(pool - amount));
+  transfer(env, &asset, &env.current_contract_address(), &to, amount);
+  events::fees_withdrawn(env, &asset, &to, amount);
+  Ok(())
+}
+
+pub fn get_withdrawable_fees(env: &Env, asset: Address) -> i128 {
+  withdrawable_fees(env, &asset)
+}
+
+pub fn get_fee_pool(env: &Env, asset: Address) -> i128 {
+  storage::fee_pool(asset).get(env).unwrap_or(0)
+}
+
+pub fn get_obligations(env: &Env, asset: Address) -> i128 {
+  storage::obligations(asset).get(env).unwrap_or(0)
+}
+
+pub fn check_asset_migration(old: &Address, new: &Address) -> Result<(), Error> {
+  if old == new {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+// Applied through the admin queue. A later mapping for the same old asset replaces it.
+pub fn set_asset_migration(env: &Env, old: Address, new: Address) {
+  storage::asset_migrations(old).set(env, &new);
+}
+
+pub fn get_asset_migration(env: &Env, asset: Address) -> Option<Address> {
+  storage::asset_migrations(asset).get(env)
+}
+
+pub fn fund_migration_pool(env: &Env, admin: Address, asset: Address, amount: i128) -> Result<(), Error> {
+  require_admin(env, &admin)?;
+  check_reentry(env)?;
+  if amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let key = storage::migration_pools(asset.clone());
+  key.set(env, &(key.get(env).unwrap_or(0) + amount));
+  transfer(env, &asset, &admin, &env.current_contract_address(), amount);
+  Ok(())
+}
+
+pub fn get_migration_pool(env: &Env, asset: Address) -> i128 {
+  storage::migration_pools(asset).get(env).unwrap_or(0)
+}
+
+// Applied through the admin queue. The entry takes the registry's next ID, starting at 1.
+pub fn add_registry_entry(env: &Env, registry: RegistryKind, lang: Symbol, label: String) {
+  let id = storage::registry_count(registry).get(env).unwrap_or(0) + 1;
+  let mut labels = Map::new(env);
+  labels.set(lang.clone(), label);
+  let entry = RegistryEntry { home_lang: lang, labels };
+  storage::registry(registry, id).set(env, &entry);
+  storage::registry_count(registry).set(env, &id);
+}
+
+// Applied through the admin queue
+pub fn set_label(env: &Env, registry: RegistryKind, id: u32, lang: Symbol, label: String) -> Result<(), Error> {
+  check_label(env, registry, id, &lang)?;
+  let key = storage::registry(registry, id);
+  let mut entry = key.get(env).unwrap();
+  entry.labels.set(lang, label);
+  key.set(env, &entry);
+  Ok(())
+}
+
+pub fn check_label(env: &Env, registry: RegistryKind, id: u32, lang: &Symbol) -> Result<(), Error> {
+  let entry = storage::registry(registry, id).get(env).ok_or(Error::NotFound)?;
+  if !entry.labels.contains_key(lang.clone()) && entry.labels.len() >= MAX_LABEL_LANGUAGES {
+    return Err(Error::LimitReached);
+  }
+  Ok(())
+}
+
+pub fn get_labels(env: &Env, registry: RegistryKind, id: u32) -> Result<Map<Symbol, String>, Error> {
+  let entry = storage::registry(registry, id).get(env).ok_or(Error::NotFound)?;
+  Ok(entry.labels)
+}
+
+pub fn list_registry(env: &Env, registry: RegistryKind, cursor: u64, limit: u32) -> RegistryPage {
+  let count = storage::registry_count(registry).get(env).unwrap_or(0);
+  let (listings, next_cursor) = page::fill(env, count, cursor, limit, |position| {
+    // IDs start at 1
+    let id = position + 1;
+    let (lang, label) = listed_label(storage::registry(registry, id).get(env).unwrap());
+    Some(RegistryListing { id, lang, label })
+  });
+  RegistryPage { items: listings, next_cursor, total: Some(count) }
+}
+
+// The label list_registry shows for an entry
+pub fn display_label(env: &Env, registry: RegistryKind, id: u32) -> Result<String, Error> {
+  let entry = storage::registry(registry, id).get(env).ok_or(Error::NotFound)?;
+  Ok(listed_label(entry).1)
+}
+
+fn listed_label(entry: RegistryEntry) -> (Symbol, String) {
+  let lang = if entry.labels.contains_key(DEFAULT_LANGUAGE) { DEFAULT_LANGUAGE } else { entry.home_lang };
+  let label = entry.labels.get(lang.clone()).unwrap();
+  (lang, label)
+}
+
+pub fn check_index_integrity(env: &Env, index: IndexKind, offset: u32) -> IndexReport {
+  index::check_integrity(env, &index, offset)
+}
+
+// Unknown and collected IDs are skipped, and a repeated one is checked once
+pub fn self_check(env: &Env, sample: Vec<u64>) -> Result<Vec<CheckResult>, Error> {
+  if sample.len() > MAX_SELF_CHECK_SAMPLE {
+    return Err(Error::LimitReached);
+  }
+  let mut results = Vec::new(env);
+  let mut checked = Vec::new(env);
+  let mut held: Map<Address, i128> = Map::new(env);
+  for escrow_id in sample {
+    if checked.contains(escrow_id) {
+      continue;
+    }
+    checked.push_back(escrow_id);
+    let Some(escrow) = storage::escrows(escrow_id).get(env) else {
+      continue;
+    };
+    let records = milestone_records(env, escrow_id);
+    let remaining = escrow.deposited_amount - escrow.released_amount - escrow.refunded_amount;
+    let scheduled: i128 = escrow.milestones.iter().map(|milestone| milestone.amount).sum();
+    let frozen: i128 = records
+      .iter()
+      .enumerate()
+      .filter(|(_, record)| record.status == MilestoneStatus::Disputed)
+      .filter_map(|(index, _)| storage::disputes(escrow_id, index as u32).get(env))
+      .map(|dispute| dispute.pot)
+      .sum();
+    for (invariant, passed) in [
+      (Invariant::Funds, remaining >= 0),
+      (Invariant::MilestoneSum, escrow.lump_sum || scheduled == escrow.total_amount && records.len() == escrow.milestones.len()),
+      (Invariant::StateIndex, index::read(env, &IndexKind::EscrowsByState(escrow.state)).contains(escrow_id)),
+      (Invariant::Frozen, frozen <= remaining),
+    ] {
+      if !passed {
+        events::check_failed(env, escrow_id, &escrow, invariant);
+      }
+      results.push_back(CheckResult { invariant, escrow_id: Some(escrow_id), asset: escrow.asset.clone(), passed });
+    }
+    if is_active(&escrow) {
+      held.set(escrow.asset.clone(), held.get(escrow.asset.clone()).unwrap_or(0) + remaining);
+    }
+  }
+  // The sample is part of the escrows, so the obligations cover at least what it holds
+  for (asset, held) in held.iter() {
+    let passed = get_obligations(env, asset.clone()) >= held;
+    if !passed {
+      events::asset_check_failed(env, &asset, Invariant::Obligations);
+    }
+    results.push_back(CheckResult { invariant: Invariant::Obligations, escrow_id: None, asset, passed });
+  }
+  Ok(results)
+}
+
+pub fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  admin.require_auth();
+  let stored = storage::admin().get(env).ok_or(Error::Unauthorized)?;
+  if stored != *admin {
+    return Err(Error::Unauthorized);
+  }
+  Ok(())
+}
+
+pub fn config(env: &Env) -> Config {
+  storage::config().get(env).unwrap_or(Config::default_values())
+}
+
+impl Config {
+  // What the constructor stores, and what deployments from before it stored one read. Layout
+  // migrations fill the fields an older config lacked from here, so every new field gets its
+  // default here first.
+  pub fn default_values() -> Config {
+    Config {
+      review_period_secs: 7 * DAY_SECS,
+      bundle_grace_secs: 30 * DAY_SECS,
+      contest_window_secs: 5 * DAY_SECS,
+      revoke_window_secs: 10 * 60,
+      rate_max_age_secs: DAY_SECS,
+      // Anyone can post and take work
+      launch_mode: LaunchMode::Open,
+      // Nobody is capped until the admin sets caps
+      risk_caps: RiskCaps { new_cap: 0, established_cap: 0, established_after: 0 },
+      // Callers on the version 1 signatures keep working until the admin says otherwise
+      legacy_api: true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::Address as _;
+  use soroban_sdk::Address;
+
+  use crate::admin::{ config, require_admin, set_platform_fee };
+  use crate::test::harness;
+  use crate::{ storage, Config, Error };
+
+  #[test]
+  fn only_the_stored_admin_passes_the_admin_check() {
+    let (env, contract) = harness();
+    let (admin, stranger) = (Address::generate(&env), Address::generate(&env));
+    // Each check authorizes, and an address authorizes once per call frame
+    assert_eq!(env.as_contract(&contract, || require_admin(&env, &admin)), Err(Error::Unauthorized));
+    env.as_contract(&contract, || storage::admin().set(&env, &admin));
+    assert_eq!(env.as_contract(&contract, || require_admin(&env, &stranger)), Err(Error::Unauthorized));
+    assert_eq!(env.as_contract(&contract, || require_admin(&env, &admin)), Ok(()));
+  }
+
+  #[test]
+  fn the_default_config_applies_until_one_is_stored() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      assert_eq!(config(&env), Config::default_values());
+      let mut stored = Config::default_values();
+      stored.review_period_secs = 1;
+      storage::config().set(&env, &stored);
+      assert_eq!(config(&env).review_period_secs, 1);
+    });
+  }
+
+  #[test]
+  fn a_fee_above_the_whole_amount_is_rejected() {
+    let (env, contract) = harness();
+    assert_eq!(env.as_contract(&contract, || set_platform_fee(&env, 10_001)), Err(Error::InvalidConfig));
+    assert_eq!(env.as_contract(&contract, || set_platform_fee(&env, 10_000)), Ok(()));
+    assert_eq!(env.as_contract(&contract, || storage::platform_fee().get(&env)), Some(10_000));
+  }
+}
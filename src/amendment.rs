@@ -0,0 +1,162 @@
+// Scope changes on a milestone escrow, kept as one history. An amendment bundles changes to
+// the terms that one party proposes and the other accepts or rejects as a unit. Accepting
+// applies every change in order, through the same code the single-change calls use, and an
+// error in any of them fails the call, which undoes the changes before it. The single-change
+// calls (accept_reprice, add_milestone, void_milestone) record themselves as accepted
+// amendments of one change, so the history covers every change to the terms.
+
+use soroban_sdk::{ Address, BytesN, Env, Vec };
+
+use crate::escrow::{ apply_reprice, apply_void, check_reentry, load_escrow_in_mode, milestone_records, note_client_activity, put_escrow, terms_digest };
+use crate::milestone_schedule::append_milestone;
+use crate::page::AmendmentPage;
+use crate::{ events, page, storage, Amendment, AmendmentChange, AmendmentStatus, Error, Escrow, MilestoneStatus, MAX_AMENDMENTS, MAX_AMENDMENT_CHANGES };
+
+pub fn propose_amendment(env: &Env, from: Address, escrow_id: u64, changes: Vec<AmendmentChange>) -> Result<u32, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  if changes.is_empty() {
+    return Err(Error::InvalidConfig);
+  }
+  if changes.len() > MAX_AMENDMENT_CHANGES {
+    return Err(Error::LimitReached);
+  }
+  push(env, escrow_id, from, changes, AmendmentStatus::Proposed, None)
+}
+
+// Applies the amendment's changes and returns the terms digest they leave
+pub fn accept_amendment(env: &Env, from: Address, escrow_id: u64, amendment_id: u32) -> Result<BytesN<32>, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  let (mut amendments, mut amendment) = open_amendment(env, &escrow, &from, escrow_id, amendment_id)?;
+  if from == escrow.client {
+    note_client_activity(env, escrow_id, &mut escrow);
+    put_escrow(env, escrow_id, &escrow);
+  }
+
+  for change in amendment.changes.iter() {
+    apply(env, escrow_id, change)?;
+  }
+  let digest = terms_digest(env, escrow_id)?;
+  amendment.status = AmendmentStatus::Accepted;
+  amendment.decided_at = env.ledger().timestamp();
+  amendment.terms_digest = Some(digest.clone());
+  amendments.set(amendment_id, amendment);
+  storage::amendments(escrow_id).set(env, &amendments);
+  events::amended(env, escrow_id, &escrow, amendment_id, &digest);
+  Ok(digest)
+}
+
+// Nothing changes but the amendment's record
+pub fn reject_amendment(env: &Env, from: Address, escrow_id: u64, amendment_id: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  let (mut amendments, mut amendment) = open_amendment(env, &escrow, &from, escrow_id, amendment_id)?;
+  amendment.status = AmendmentStatus::Rejected;
+  amendment.decided_at = env.ledger().timestamp();
+  amendments.set(amendment_id, amendment);
+  storage::amendments(escrow_id).set(env, &amendments);
+  Ok(())
+}
+
+pub fn get_amendments(env: &Env, escrow_id: u64, cursor: u64, limit: u32) -> AmendmentPage {
+  let amendments = history(env, escrow_id);
+  let (items, next_cursor) = page::fill(env, amendments.len(), cursor, limit, |position| amendments.get(position));
+  AmendmentPage { items, next_cursor, total: Some(amendments.len()) }
+}
+
+// Records a change a single-change call already applied, as an amendment `proposer` made
+// and the other party accepted
+pub fn record_change(env: &Env, escrow_id: u64, escrow: &Escrow, proposer: Address, change: AmendmentChange) -> Result<(), Error> {
+  let digest = terms_digest(env, escrow_id)?;
+  let amendment_id = push(env, escrow_id, proposer, Vec::from_array(env, [change]), AmendmentStatus::Accepted, Some(digest.clone()))?;
+  events::amended(env, escrow_id, escrow, amendment_id, &digest);
+  Ok(())
+}
+
+// A proposed amendment the caller can decide: a party to the escrow, but not its proposer
+fn open_amendment(env: &Env, escrow: &Escrow, from: &Address, escrow_id: u64, amendment_id: u32) -> Result<(Vec<Amendment>, Amendment), Error> {
+  if escrow.client != *from && escrow.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  let amendments = history(env, escrow_id);
+  let amendment = amendments.get(amendment_id).filter(|amendment| amendment.status == AmendmentStatus::Proposed).ok_or(Error::NotFound)?;
+  if amendment.proposer == *from {
+    return Err(Error::Unauthorized);
+  }
+  Ok((amendments, amendment))
+}
+
+fn push(env: &Env, escrow_id: u64, proposer: Address, changes: Vec<AmendmentChange>, status: AmendmentStatus, terms_digest: Option<BytesN<32>>) -> Result<u32, Error> {
+  let mut amendments = history(env, escrow_id);
+  if amendments.len() >= MAX_AMENDMENTS {
+    return Err(Error::LimitReached);
+  }
+  let now = env.ledger().timestamp();
+  let amendment_id = amendments.len();
+  let decided_at = if status == AmendmentStatus::Proposed { 0 } else { now };
+  amendments.push_back(Amendment { id: amendment_id, proposer, changes, status, proposed_at: now, decided_at, terms_digest });
+  storage::amendments(escrow_id).set(env, &amendments);
+  Ok(amendment_id)
+}
+
+// Each change reads the escrow as the changes before it left it
+fn apply(env: &Env, escrow_id: u64, change: AmendmentChange) -> Result<(), Error> {
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  match change {
+    AmendmentChange::AddMilestone(milestone, depends_on) => append_milestone(env, escrow_id, &mut escrow, milestone, depends_on).map(|_| ()),
+    AmendmentChange::VoidMilestone(milestone_index) => apply_void(env, escrow_id, &mut escrow, milestone_index),
+    AmendmentChange::Reprice(milestone_index, new_amount) => {
+      // The amendment settles any reprice still on the table for the milestone
+      storage::reprices(escrow_id, milestone_index).remove(env);
+      apply_reprice(env, escrow_id, &mut escrow, milestone_index, new_amount)
+    }
+    AmendmentChange::MilestoneDeadline(milestone_index, deadline) => shift_milestone_deadline(env, escrow_id, &mut escrow, milestone_index, deadline),
+    AmendmentChange::ProjectDeadline(deadline) => shift_project_deadline(env, escrow_id, &escrow, deadline),
+  }
+}
+
+// Only a milestone no work was handed in for moves. Its new deadline, unless 0 for the
+// project's, can't have passed or fall after the project deadline.
+fn shift_milestone_deadline(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone_index: u32, deadline: u64) -> Result<(), Error> {
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Pending {
+    return Err(Error::WorkUnderReview);
+  }
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  if deadline != 0 && (deadline <= env.ledger().timestamp() || (project.deadline != 0 && deadline > project.deadline)) {
+    return Err(Error::InvalidSchedule);
+  }
+  let mut milestone = escrow.milestones.get(milestone_index).unwrap();
+  milestone.deadline = deadline;
+  escrow.milestones.set(milestone_index, milestone);
+  put_escrow(env, escrow_id, escrow);
+  Ok(())
+}
+
+// The new project deadline, unless 0 for none, can't have passed or come before a
+// milestone's own deadline
+fn shift_project_deadline(env: &Env, escrow_id: u64, escrow: &Escrow, deadline: u64) -> Result<(), Error> {
+  let mut project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  if deadline != 0 && (deadline <= env.ledger().timestamp() || escrow.milestones.iter().any(|milestone| milestone.deadline > deadline)) {
+    return Err(Error::InvalidSchedule);
+  }
+  project.deadline = deadline;
+  storage::projects(escrow.project_id).set(env, &project);
+  put_escrow(env, escrow_id, escrow);
+  Ok(())
+}
+
+fn history(env: &Env, escrow_id: u64) -> Vec<Amendment> {
+  storage::amendments(escrow_id).get(env).unwrap_or(Vec::new(env))
+}
@@ -0,0 +1,582 @@
+// Disputes and out-of-scope contests on milestones, and the arbitrators who rule on them
+
+use soroban_sdk::{ Address, Env, Map, String, Vec };
+
+use crate::admin::config;
+use crate::escrow::{ available_funds, complete_if_settled, ledger_line, load_escrow_in_mode, milestone_amount, milestone_records, note_client_activity, record_line, record_payout, record_review, save_escrow, settle_early_bonus };
+use crate::index::{ IndexKind, MAX_OPEN_DISPUTES };
+use crate::subcontract::{ check_parent_dispute, fund_subcontracts };
+use crate::{ epoch, events, index, storage, ArbitrationPolicy, Arbitrator, ArbitratorStats, ArbitratorTally, ArbitratorTier, Assignment, Contest, Dispute, DisputeRef, Error, Escrow, EscrowState, LedgerKind, MilestoneStatus, Ruling, BPS_DENOMINATOR, MAX_ARBITRATORS, MAX_LIST_LIMIT };
+
+pub fn raise_dispute(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<Dispute, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  if escrow.state != EscrowState::InProgress {
+    return Err(Error::NotFullyFunded);
+  }
+  if escrow.client == from {
+    note_client_activity(env, escrow_id, &mut escrow);
+  }
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    // Escalating a contested submission ends the contest, within its window
+    MilestoneStatus::Contested | MilestoneStatus::PartialOffered => {
+      open_contest(env, escrow_id, milestone_index)?;
+      storage::contests(escrow_id, milestone_index).remove(env);
+    }
+    MilestoneStatus::Pending | MilestoneStatus::Submitted => {}
+  }
+
+  // A kickoff paid for work that was never submitted goes back into the pot
+  let mut clawback = 0;
+  if record.status == MilestoneStatus::Pending {
+    clawback = record.paid;
+    record.paid = 0;
+    escrow.released_amount -= clawback;
+    if clawback > 0 {
+      record_line(env, escrow_id, ledger_line(env, LedgerKind::Clawback, &escrow.freelancer, &escrow.asset, Some(milestone_index), clawback));
+    }
+  }
+  let dispute = Dispute {
+    raised_by: from,
+    raised_at: env.ledger().timestamp(),
+    clawback,
+    pot: milestone_amount(&escrow, milestone_index) - record.paid,
+  };
+  record.status = MilestoneStatus::Disputed;
+  records.set(milestone_index, record);
+  if !index::append_unique(env, &IndexKind::OpenDisputes, index::pack(escrow_id, milestone_index), MAX_OPEN_DISPUTES) {
+    return Err(Error::LimitReached);
+  }
+  storage::disputes(escrow_id, milestone_index).set(env, &dispute);
+  save_escrow(env, escrow_id, &escrow, &records);
+  if storage::random_assignment().has(env) {
+    if let Some(arbitrator) = draw_arbitrator(env, escrow_id, milestone_index, &escrow, &dispute) {
+      assign_dispute(env, &arbitrator, escrow_id, milestone_index);
+    }
+  }
+  Ok(dispute)
+}
+
+pub fn strike_arbitrator(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<Option<Address>, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let dispute = open_dispute(env, escrow_id, milestone_index)?;
+  let assigned_key = storage::dispute_arbitrators(escrow_id, milestone_index);
+  let assigned = assigned_key.get(env).ok_or(Error::NotFound)?;
+  if storage::pending_rulings(escrow_id, milestone_index).has(env) {
+    return Err(Error::RulingPending);
+  }
+  let strikes_key = storage::dispute_strikes(escrow_id, milestone_index);
+  let mut strikes = strikes_key.get(env).unwrap_or(Map::new(env));
+  if strikes.contains_key(from.clone()) {
+    return Err(Error::StrikeUsed);
+  }
+  strikes.set(from, assigned);
+  strikes_key.set(env, &strikes);
+  assigned_key.remove(env);
+
+  let redrawn = draw_arbitrator(env, escrow_id, milestone_index, &escrow, &dispute);
+  match &redrawn {
+    Some(arbitrator) => assign_dispute(env, arbitrator, escrow_id, milestone_index),
+    None => {
+      index::append_unique(env, &IndexKind::OpenDisputes, index::pack(escrow_id, milestone_index), MAX_OPEN_DISPUTES);
+    }
+  }
+  Ok(redrawn)
+}
+
+pub fn get_dispute_strikes(env: &Env, escrow_id: u64, milestone_index: u32) -> Map<Address, Address> {
+  storage::dispute_strikes(escrow_id, milestone_index).get(env).unwrap_or(Map::new(env))
+}
+
+pub fn resolve_dispute(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let admin = storage::admin().get(env).ok_or(Error::Unauthorized)?;
+  let dispute = check_split(env, escrow_id, milestone_index, freelancer_amount)?;
+  let assigned = storage::dispute_arbitrators(escrow_id, milestone_index).get(env);
+  // Overriding an assigned arbitrator is a force-resolve, which goes through the admin queue
+  if from == admin && assigned.is_none() {
+    return settle_dispute(env, escrow_id, milestone_index, freelancer_amount);
+  }
+
+  if assigned != Some(from.clone()) {
+    return Err(Error::Unauthorized);
+  }
+  let arbitrator = storage::arbitrators(from.clone()).get(env).ok_or(Error::Unauthorized)?;
+  if arbitrator.tier == ArbitratorTier::Probationary && arbitrator.rulings < arbitration_policy(env).probation_cosigned {
+    events::ruled(env, &from, escrow_id, milestone_index, freelancer_amount, true);
+    let ruling = Ruling { arbitrator: from, freelancer_amount };
+    storage::pending_rulings(escrow_id, milestone_index).set(env, &ruling);
+    return Ok(());
+  }
+  settle_dispute(env, escrow_id, milestone_index, freelancer_amount)?;
+  events::ruled(env, &from, escrow_id, milestone_index, freelancer_amount, false);
+  count_ruling(env, &from, arbitrator, &dispute, freelancer_amount);
+  Ok(())
+}
+
+pub fn get_dispute(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Dispute> {
+  storage::disputes(escrow_id, milestone_index).get(env)
+}
+
+pub fn cosign_ruling(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let cosigner = storage::arbitrators(from).get(env).ok_or(Error::Unauthorized)?;
+  if cosigner.tier != ArbitratorTier::Full {
+    return Err(Error::Unauthorized);
+  }
+  let key = storage::pending_rulings(escrow_id, milestone_index);
+  let ruling = key.get(env).ok_or(Error::NotFound)?;
+  let dispute = open_dispute(env, escrow_id, milestone_index)?;
+  settle_dispute(env, escrow_id, milestone_index, ruling.freelancer_amount)?;
+  events::ruled(env, &ruling.arbitrator, escrow_id, milestone_index, ruling.freelancer_amount, false);
+  if let Some(arbitrator) = storage::arbitrators(ruling.arbitrator.clone()).get(env) {
+    count_ruling(env, &ruling.arbitrator, arbitrator, &dispute, ruling.freelancer_amount);
+  }
+  Ok(())
+}
+
+// Applied through the admin queue
+pub fn overturn_ruling(env: &Env, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<(), Error> {
+  let ruling = storage::pending_rulings(escrow_id, milestone_index).get(env).ok_or(Error::NotFound)?;
+  check_split(env, escrow_id, milestone_index, freelancer_amount)?;
+  settle_dispute(env, escrow_id, milestone_index, freelancer_amount)?;
+  update_arbitrator_tally(env, &ruling.arbitrator, |tally| tally.overturned += 1);
+  Ok(())
+}
+
+// Applied through the admin queue: the admin settles a dispute in place of its assigned
+// arbitrator
+pub fn force_resolve(env: &Env, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<(), Error> {
+  check_split(env, escrow_id, milestone_index, freelancer_amount)?;
+  settle_dispute(env, escrow_id, milestone_index, freelancer_amount)
+}
+
+pub fn get_pending_ruling(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Ruling> {
+  storage::pending_rulings(escrow_id, milestone_index).get(env)
+}
+
+// Applied through the admin queue
+pub fn add_arbitrator(env: &Env, arbitrator: Address) -> Result<(), Error> {
+  check_arbitrator_room(env, &arbitrator)?;
+  // Adding an arbitrator again puts them on the roster without resetting their record
+  let mut roster = storage::arbitrator_roster().get(env).unwrap_or(Vec::new(env));
+  if !roster.contains(&arbitrator) {
+    roster.push_back(arbitrator.clone());
+    storage::arbitrator_roster().set(env, &roster);
+  }
+  let key = storage::arbitrators(arbitrator);
+  if !key.has(env) {
+    key.set(env, &Arbitrator { tier: ArbitratorTier::Probationary, rulings: 0 });
+  }
+  Ok(())
+}
+
+// Applied through the admin queue
+pub fn set_random_assignment(env: &Env, enabled: bool) {
+  if enabled {
+    storage::random_assignment().set(env, &());
+  } else {
+    storage::random_assignment().remove(env);
+  }
+}
+
+pub fn get_random_assignment(env: &Env) -> bool {
+  storage::random_assignment().has(env)
+}
+
+// A full roster has no room for anyone not already on it
+pub fn check_arbitrator_room(env: &Env, arbitrator: &Address) -> Result<(), Error> {
+  let roster = storage::arbitrator_roster().get(env).unwrap_or(Vec::new(env));
+  if !roster.contains(arbitrator) && roster.len() >= MAX_ARBITRATORS {
+    return Err(Error::LimitReached);
+  }
+  Ok(())
+}
+
+// Applied through the admin queue
+pub fn promote_arbitrator(env: &Env, arbitrator: Address) -> Result<(), Error> {
+  let key = storage::arbitrators(arbitrator);
+  let mut record = key.get(env).ok_or(Error::NotFound)?;
+  record.tier = ArbitratorTier::Full;
+  key.set(env, &record);
+  Ok(())
+}
+
+pub fn get_arbitrator(env: &Env, arbitrator: Address) -> Option<Arbitrator> {
+  storage::arbitrators(arbitrator).get(env)
+}
+
+pub fn get_arbitrator_stats(env: &Env, arbitrator: Address) -> ArbitratorStats {
+  arbitrator_stats(env, &arbitrator)
+}
+
+// Applied through the admin queue
+pub fn set_arbitration_policy(env: &Env, policy: ArbitrationPolicy) -> Result<(), Error> {
+  check_arbitration_policy(&policy)?;
+  storage::arbitration_policy().set(env, &policy);
+  Ok(())
+}
+
+pub fn check_arbitration_policy(policy: &ArbitrationPolicy) -> Result<(), Error> {
+  if policy.probation_max_pot < 0 {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+pub fn get_arbitration_policy(env: &Env) -> ArbitrationPolicy {
+  arbitration_policy(env)
+}
+
+pub fn next_dispute(env: &Env, from: Address) -> Result<DisputeRef, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let arbitrator = storage::arbitrators(from.clone()).get(env).ok_or(Error::Unauthorized)?;
+  let max_pot = match arbitrator.tier {
+    ArbitratorTier::Probationary => arbitration_policy(env).probation_max_pot,
+    ArbitratorTier::Full => i128::MAX,
+  };
+  for dispute_ref in index::paged_read(env, &IndexKind::OpenDisputes, 0, MAX_LIST_LIMIT).items.iter() {
+    let (escrow_id, milestone_index) = index::unpack(dispute_ref);
+    let Some(escrow) = storage::escrows(escrow_id).get(env) else {
+      continue;
+    };
+    let Some(dispute) = storage::disputes(escrow_id, milestone_index).get(env) else {
+      continue;
+    };
+    if escrow.client == from || escrow.freelancer == from || dispute.pot > max_pot || struck(env, escrow_id, milestone_index, &from) {
+      continue;
+    }
+    assign_dispute(env, &from, escrow_id, milestone_index);
+    return Ok(DisputeRef { escrow_id, milestone_index });
+  }
+  Err(Error::NotFound)
+}
+
+pub fn get_dispute_arbitrator(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Address> {
+  storage::dispute_arbitrators(escrow_id, milestone_index).get(env)
+}
+
+pub fn get_dispute_assignment(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Assignment> {
+  let arbitrator = storage::dispute_arbitrators(escrow_id, milestone_index).get(env)?;
+  let stats = arbitrator_stats(env, &arbitrator);
+  Some(Assignment { arbitrator, stats })
+}
+
+pub fn flag_out_of_scope(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, note: String) -> Result<Contest, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Submitted {
+    return Err(Error::MilestoneNotCompleted);
+  }
+  record_review(env, escrow_id, record.submitted_at, false);
+  let now = env.ledger().timestamp();
+  let contest = Contest { note, flagged_at: now, respond_by: now + config(env).contest_window_secs, offer: 0 };
+  record.status = MilestoneStatus::Contested;
+  records.set(milestone_index, record);
+  storage::contests(escrow_id, milestone_index).set(env, &contest);
+  save_escrow(env, escrow_id, &escrow, &records);
+  epoch::record_message(env, escrow_id, &escrow);
+  Ok(contest)
+}
+
+pub fn offer_partial(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, amount: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Contested && record.status != MilestoneStatus::PartialOffered {
+    return Err(Error::NotFound);
+  }
+  let mut contest = open_contest(env, escrow_id, milestone_index)?;
+  if amount <= 0 || amount >= milestone_amount(&escrow, milestone_index) - record.paid {
+    return Err(Error::InvalidAmount);
+  }
+  contest.offer = amount;
+  record.status = MilestoneStatus::PartialOffered;
+  records.set(milestone_index, record);
+  storage::contests(escrow_id, milestone_index).set(env, &contest);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn accept_partial(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::PartialOffered {
+    return Err(Error::NoPartialOffer);
+  }
+  let contest = open_contest(env, escrow_id, milestone_index)?;
+  check_parent_dispute(env, escrow_id)?;
+  let due = milestone_amount(&escrow, milestone_index) - record.paid;
+  if available_funds(&escrow) < due {
+    return Err(Error::InsufficientFunds);
+  }
+
+  record.paid += contest.offer;
+  record.status = MilestoneStatus::Released;
+  records.set(milestone_index, record);
+  if let Some(mut milestone) = escrow.milestones.get(milestone_index) {
+    milestone.completed = true;
+    escrow.milestones.set(milestone_index, milestone);
+  }
+  escrow.released_amount += contest.offer;
+  escrow.refunded_amount += due - contest.offer;
+  record_payout(env, escrow_id, &escrow, LedgerKind::PartialRelease, milestone_index, contest.offer);
+  fund_subcontracts(env, escrow_id, &escrow, milestone_index, records.get(milestone_index).unwrap().paid)?;
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, Some(milestone_index), due - contest.offer));
+  settle_early_bonus(env, escrow_id, &mut escrow, milestone_index, |_| false);
+  storage::contests(escrow_id, milestone_index).remove(env);
+  complete_if_settled(env, escrow_id, &mut escrow, &records);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn lapse_contest(env: &Env, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  load_escrow_in_mode(env, escrow_id, false)?;
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Contested && record.status != MilestoneStatus::PartialOffered {
+    return Err(Error::NotFound);
+  }
+  let key = storage::contests(escrow_id, milestone_index);
+  let contest = key.get(env).ok_or(Error::NotFound)?;
+  if env.ledger().timestamp() <= contest.respond_by {
+    return Err(Error::TooEarly);
+  }
+  record.status = MilestoneStatus::Pending;
+  records.set(milestone_index, record);
+  key.remove(env);
+  storage::milestone_records(escrow_id).set(env, &records);
+  Ok(())
+}
+
+pub fn get_contest(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Contest> {
+  storage::contests(escrow_id, milestone_index).get(env)
+}
+
+// Loads a dispute that has not been settled yet
+pub fn open_dispute(env: &Env, escrow_id: u64, milestone_index: u32) -> Result<Dispute, Error> {
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Disputed {
+    return Err(Error::NoDispute);
+  }
+  storage::disputes(escrow_id, milestone_index).get(env).ok_or(Error::NoDispute)
+}
+
+// The open dispute, provided the split fits its pot
+pub fn check_split(env: &Env, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<Dispute, Error> {
+  let dispute = open_dispute(env, escrow_id, milestone_index)?;
+  if freelancer_amount < 0 || freelancer_amount > dispute.pot {
+    return Err(Error::InvalidAmount);
+  }
+  Ok(dispute)
+}
+
+// Applies a ruling on an open dispute
+pub fn settle_dispute(env: &Env, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<(), Error> {
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let dispute = open_dispute(env, escrow_id, milestone_index)?;
+  check_parent_dispute(env, escrow_id)?;
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+
+  record.paid += freelancer_amount;
+  record.status = MilestoneStatus::Resolved;
+  records.set(milestone_index, record);
+  if let Some(mut milestone) = escrow.milestones.get(milestone_index) {
+    milestone.completed = true;
+    escrow.milestones.set(milestone_index, milestone);
+  }
+  escrow.released_amount += freelancer_amount;
+  escrow.refunded_amount += dispute.pot - freelancer_amount;
+  record_payout(env, escrow_id, &escrow, LedgerKind::DisputeRelease, milestone_index, freelancer_amount);
+  fund_subcontracts(env, escrow_id, &escrow, milestone_index, records.get(milestone_index).unwrap().paid)?;
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, Some(milestone_index), dispute.pot - freelancer_amount));
+  settle_early_bonus(env, escrow_id, &mut escrow, milestone_index, |_| false);
+  index::remove_value(env, &IndexKind::OpenDisputes, index::pack(escrow_id, milestone_index));
+  storage::pending_rulings(escrow_id, milestone_index).remove(env);
+  events::resolved(env, escrow_id, &escrow, milestone_index, freelancer_amount);
+  complete_if_settled(env, escrow_id, &mut escrow, &records);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+// Picks an arbitrator at random from those who may take the dispute, by the same rules as
+// next_dispute. The host seeds the PRNG afresh for every transaction, so the draw can't be
+// known before the transaction runs and can't be replayed to a different result within it.
+pub fn draw_arbitrator(env: &Env, escrow_id: u64, milestone_index: u32, escrow: &Escrow, dispute: &Dispute) -> Option<Address> {
+  let roster = storage::arbitrator_roster().get(env).unwrap_or(Vec::new(env));
+  let probation_max_pot = arbitration_policy(env).probation_max_pot;
+  let mut eligible = Vec::new(env);
+  for address in roster.iter() {
+    if address == escrow.client || address == escrow.freelancer || struck(env, escrow_id, milestone_index, &address) {
+      continue;
+    }
+    let Some(arbitrator) = storage::arbitrators(address.clone()).get(env) else {
+      continue;
+    };
+    if arbitrator.tier == ArbitratorTier::Probationary && dispute.pot > probation_max_pot {
+      continue;
+    }
+    eligible.push_back(address);
+  }
+  if eligible.is_empty() {
+    return None;
+  }
+  let pick = env.prng().gen_range::<u64>(0..eligible.len() as u64) as u32;
+  eligible.get(pick)
+}
+
+pub fn struck(env: &Env, escrow_id: u64, milestone_index: u32, arbitrator: &Address) -> bool {
+  let strikes = storage::dispute_strikes(escrow_id, milestone_index).get(env);
+  strikes.is_some_and(|strikes| strikes.values().contains(arbitrator))
+}
+
+pub fn assign_dispute(env: &Env, arbitrator: &Address, escrow_id: u64, milestone_index: u32) {
+  index::remove_value(env, &IndexKind::OpenDisputes, index::pack(escrow_id, milestone_index));
+  storage::dispute_arbitrators(escrow_id, milestone_index).set(env, arbitrator);
+  events::assigned(env, arbitrator, escrow_id, milestone_index);
+}
+
+pub fn count_ruling(env: &Env, address: &Address, mut arbitrator: Arbitrator, dispute: &Dispute, freelancer_amount: i128) {
+  arbitrator.rulings += 1;
+  storage::arbitrators(address.clone()).set(env, &arbitrator);
+  let resolution_secs = env.ledger().timestamp().saturating_sub(dispute.raised_at);
+  // An empty pot has no split to count, so it counts as an even one
+  let client_share_bps = ((dispute.pot - freelancer_amount) * BPS_DENOMINATOR as i128).checked_div(dispute.pot).unwrap_or(BPS_DENOMINATOR as i128 / 2);
+  update_arbitrator_tally(env, address, |tally| {
+    tally.resolved += 1;
+    tally.resolution_secs += resolution_secs;
+    tally.client_share_bps += client_share_bps as u64;
+  });
+}
+
+pub fn update_arbitrator_tally(env: &Env, arbitrator: &Address, update: impl FnOnce(&mut ArbitratorTally)) {
+  let key = storage::arbitrator_tallies(arbitrator.clone());
+  let mut tally = key.get(env).unwrap_or_default();
+  update(&mut tally);
+  key.set(env, &tally);
+}
+
+pub fn arbitrator_stats(env: &Env, arbitrator: &Address) -> ArbitratorStats {
+  let tally = storage::arbitrator_tallies(arbitrator.clone()).get(env).unwrap_or_default();
+  let rulings = tally.resolved + tally.overturned;
+  ArbitratorStats {
+    resolved: tally.resolved,
+    avg_resolution_secs: tally.resolution_secs.checked_div(tally.resolved as u64).unwrap_or(0),
+    avg_client_share_bps: tally.client_share_bps.checked_div(tally.resolved as u64).unwrap_or(0) as u32,
+    overturned: tally.overturned,
+    credibility_bps: (tally.resolved * BPS_DENOMINATOR).checked_div(rulings).unwrap_or(BPS_DENOMINATOR),
+  }
+}
+
+pub fn arbitration_policy(env: &Env) -> ArbitrationPolicy {
+  storage::arbitration_policy().get(env).unwrap_or(ArbitrationPolicy {
+    probation_max_pot: 10_000_000_000, // 1000 units of a 7-decimal asset
+    probation_cosigned: 5,
+  })
+}
+
+// Loads a milestone's contest while the freelancer can still answer it
+pub fn open_contest(env: &Env, escrow_id: u64, milestone_index: u32) -> Result<Contest, Error> {
+  let contest = storage::contests(escrow_id, milestone_index).get(env).ok_or(Error::NotFound)?;
+  if env.ledger().timestamp() > contest.respond_by {
+    return Err(Error::ContestLapsed);
+  }
+  Ok(contest)
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::{ Address as _, Ledger as _ };
+  use soroban_sdk::{ map, Address };
+
+  use crate::dispute::{ arbitrator_stats, count_ruling, struck };
+  use crate::test::harness;
+  use crate::{ storage, Arbitrator, ArbitratorTier, Dispute };
+
+  #[test]
+  fn an_arbitrator_with_no_rulings_is_fully_credible() {
+    let (env, contract) = harness();
+    let arbitrator = Address::generate(&env);
+    env.as_contract(&contract, || {
+      let stats = arbitrator_stats(&env, &arbitrator);
+      assert_eq!((stats.resolved, stats.avg_resolution_secs, stats.avg_client_share_bps, stats.credibility_bps), (0, 0, 0, 10_000));
+    });
+  }
+
+  #[test]
+  fn counted_rulings_average_the_time_taken_and_the_client_share() {
+    let (env, contract) = harness();
+    let (arbitrator, client) = (Address::generate(&env), Address::generate(&env));
+    env.ledger().with_mut(|ledger| ledger.timestamp = 1_000);
+    env.as_contract(&contract, || {
+      let seated = Arbitrator { tier: ArbitratorTier::Full, rulings: 0 };
+      count_ruling(&env, &arbitrator, seated, &Dispute { raised_by: client.clone(), raised_at: 800, clawback: 0, pot: 100 }, 100);
+      let seated = storage::arbitrators(arbitrator.clone()).get(&env).unwrap();
+      count_ruling(&env, &arbitrator, seated, &Dispute { raised_by: client, raised_at: 600, clawback: 0, pot: 0 }, 0);
+      assert_eq!(storage::arbitrators(arbitrator.clone()).get(&env).unwrap().rulings, 2);
+      let stats = arbitrator_stats(&env, &arbitrator);
+      // Nothing to the client on the first pot, and an empty pot counts as an even split
+      assert_eq!((stats.resolved, stats.avg_resolution_secs, stats.avg_client_share_bps), (2, 300, 2_500));
+    });
+  }
+
+  #[test]
+  fn only_the_struck_arbitrator_is_out_of_the_dispute() {
+    let (env, contract) = harness();
+    let (client, first, second) = (Address::generate(&env), Address::generate(&env), Address::generate(&env));
+    env.as_contract(&contract, || {
+      assert!(!struck(&env, 1, 0, &first));
+      storage::dispute_strikes(1, 0).set(&env, &map![&env, (client, first.clone())]);
+      assert!(struck(&env, 1, 0, &first));
+      assert!(!struck(&env, 1, 0, &second));
+      assert!(!struck(&env, 1, 1, &first));
+    });
+  }
+}
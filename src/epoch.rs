@@ -0,0 +1,74 @@
+// Weekly activity digests, kept lazily so notification services need not replay every event.
+// Each escrow counts its deposits, releases, messages and state changes for the current epoch,
+// a week of ledger time. The first call to touch the escrow in a later epoch publishes the
+// counts in a rollover event and starts again from zero. Nothing runs in between: however
+// many epochs pass untouched, the next touch publishes one rollover, for the epoch the counts
+// were made in, and the epochs without a touch have nothing to report.
+
+use soroban_sdk::Env;
+
+use crate::{ events, storage, EpochActivity, Escrow, EPOCH_SECS };
+
+// Counts what an escrow write changed, given the escrow as it was and as it is now. A new
+// escrow counts its entry into Created.
+pub fn record_write(env: &Env, escrow_id: u64, previous: Option<&Escrow>, escrow: &Escrow) {
+  let (mut activity, rolled) = current(env, escrow_id, escrow);
+  let counted = (activity.deposits, activity.releases, activity.state_changes);
+  match previous {
+    None => activity.state_changes += 1,
+    Some(previous) => {
+      if escrow.deposited_amount > previous.deposited_amount {
+        activity.deposits += 1;
+      }
+      if escrow.released_amount > previous.released_amount {
+        activity.releases += 1;
+      }
+      if escrow.state != previous.state {
+        activity.state_changes += 1;
+      }
+    }
+  }
+  // Most writes count nothing, and those leave the entry alone
+  if rolled || counted != (activity.deposits, activity.releases, activity.state_changes) {
+    storage::epoch_activity(escrow_id).set(env, &activity);
+  }
+}
+
+// Starts the current epoch on a call that touches the escrow without writing it, such as a
+// submission, so its counts go to the right epoch
+pub fn touch(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  let (activity, rolled) = current(env, escrow_id, escrow);
+  if rolled {
+    storage::epoch_activity(escrow_id).set(env, &activity);
+  }
+}
+
+// A note one party sends the other through the escrow: a progress report or an out-of-scope
+// flag
+pub fn record_message(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  let (mut activity, _) = current(env, escrow_id, escrow);
+  activity.messages += 1;
+  storage::epoch_activity(escrow_id).set(env, &activity);
+}
+
+// The counts so far in the epoch the escrow was last touched in
+pub fn get_epoch_activity(env: &Env, escrow_id: u64) -> Option<EpochActivity> {
+  storage::epoch_activity(escrow_id).get(env)
+}
+
+// The counters for the current epoch, and whether they replace an earlier epoch's. Those are
+// published first, unless they counted nothing.
+fn current(env: &Env, escrow_id: u64, escrow: &Escrow) -> (EpochActivity, bool) {
+  let epoch = env.ledger().timestamp() / EPOCH_SECS;
+  let fresh = EpochActivity { epoch, deposits: 0, releases: 0, messages: 0, state_changes: 0 };
+  match storage::epoch_activity(escrow_id).get(env) {
+    Some(activity) if activity.epoch == epoch => (activity, false),
+    Some(activity) => {
+      if activity.deposits + activity.releases + activity.messages + activity.state_changes > 0 {
+        events::epoch_rollover(env, escrow_id, escrow, &activity);
+      }
+      (fresh, true)
+    }
+    None => (fresh, true),
+  }
+}
@@ -0,0 +1,2197 @@
+// The escrow lifecycle, from opening through engagement, deposits, milestone review and payout
+// to refunds and cleanup, and the bookkeeping every money movement goes through: commitments,
+// obligations, fees and the ledger.
+
+use core::ops::Range;
+
+use soroban_sdk::{ panic_with_error, token, Address, BytesN, Env, Map, Vec };
+use soroban_sdk::xdr::ToXdr;
+
+use crate::admin::{ config, fresh_reference_rate, get_min_deposit_increment, require_admin };
+use crate::index::{ IndexKind, MAX_ACTIVITY_BUCKETS, MAX_ACTIVITY_BUCKET_LEN, MAX_PAIR_INDEX_LEN, MAX_STATE_INDEX_LEN };
+use crate::math::{ bps_of, to_reference };
+use crate::milestone_schedule::check_turn;
+use crate::observer::observing;
+use crate::page::{ DepositPage, IdPage, LedgerPage };
+use crate::project::check_milestones;
+use crate::risk::{ release_exposure, take_exposure };
+use crate::storage::{ self, Entry };
+use crate::subcontract::{ check_parent_dispute, fund_subcontracts, funded_subcontracts, subcontracted };
+use crate::ttl::TtlClass;
+use crate::user::{ check_wip_limit, update_freelancer_stats };
+use crate::{ amendment, epoch, events, index, page, progress, release_guard, ttl, AcceptanceRecord, ActionItem, ActionKind, AmendmentChange, Approval, Bond, Bounty, Bundle, CancellationSchedule, Checklist, ChecklistItem, ClosingReport, Commitments, CompletionCertificate, ContactConsent, Contribution, Deposit, EarlyBonus, Ending, Error, Escrow, EscrowHeader, EscrowState, EscrowStateCounts, EscrowView, FeeTerms, Kickoff, LedgerKind, LedgerLine, Milestone, MilestoneProgress, MilestoneRecord, MilestoneStatus, ProgressView, Project, ProjectStatus, ReferenceView, Reprice, TermsSource, TimeStatus, Tombstone, UserType, ACTIVITY_BUCKET_SECS, BPS_DENOMINATOR, DAY_SECS, LEDGER_SECS, MAX_BOUNTY_CONTRIBUTORS, MAX_CANCELLATION_FEE_BPS, MAX_CHECKLIST_ITEMS, MAX_DEPOSITS_PER_ESCROW };
+
+pub fn initiate_escrow(
+  env: &Env,
+  from: Address, // Client address
+  project_id: u64,
+  freelancer: Address, // Freelancer address
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  // Ensure client address matches the project owner
+  if project.client != from {
+    return Err(Error::Unauthorized);
+  }
+
+  // Projects posted before their milestones were validated get the same checks here
+  check_milestones(env, &project.milestones, project.budget, project.deadline)?;
+
+  let total_amount = project.budget;
+  let milestones = project.milestones.clone();
+  open_escrow(env, &mut project, freelancer, total_amount, milestones, TermsSource::Project)
+}
+
+pub fn require_bond(env: &Env, from: Address, escrow_id: u64, token: Address, amount: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  // The bond is part of what the freelancer accepts, so it cannot change afterwards
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let posted = storage::bonds(escrow_id).get(env).is_some_and(|bond| bond.posted);
+  if posted {
+    return Err(Error::AlreadyAccepted);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+  put_escrow(env, escrow_id, &escrow);
+  storage::bonds(escrow_id).set(env, &Bond { token, amount, posted: false });
+  Ok(())
+}
+
+pub fn get_bond(env: &Env, escrow_id: u64) -> Option<Bond> {
+  storage::bonds(escrow_id).get(env)
+}
+
+pub fn set_checklist(env: &Env, from: Address, escrow_id: u64, items: Vec<ChecklistItem>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if items.len() > MAX_CHECKLIST_ITEMS {
+    return Err(Error::LimitReached);
+  }
+  let key = storage::checklists(escrow_id);
+  if items.is_empty() {
+    key.remove(env);
+  } else {
+    let mut checked_at = Vec::new(env);
+    for _ in items.iter() {
+      checked_at.push_back(None);
+    }
+    key.set(env, &Checklist { items, checked_at });
+  }
+  Ok(())
+}
+
+pub fn complete_checklist_item(env: &Env, from: Address, escrow_id: u64, item_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  let key = storage::checklists(escrow_id);
+  let mut checklist = key.get(env).ok_or(Error::NotFound)?;
+  let item = checklist.items.get(item_index).ok_or(Error::NotFound)?;
+  if party_address(&escrow, item.party) != from {
+    return Err(Error::Unauthorized);
+  }
+  if checklist.checked_at.get(item_index).unwrap().is_none() {
+    checklist.checked_at.set(item_index, Some(env.ledger().timestamp()));
+    key.set(env, &checklist);
+  }
+  Ok(())
+}
+
+pub fn get_checklist(env: &Env, escrow_id: u64) -> Option<Checklist> {
+  storage::checklists(escrow_id).get(env)
+}
+
+pub fn set_early_bonus(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, amount: i128, min_days_early: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if escrow.deposited_amount > 0 {
+    return Err(Error::HasDeposits);
+  }
+  if amount < 0 {
+    return Err(Error::InvalidAmount);
+  }
+  if milestone_index >= escrow.milestones.len() {
+    return Err(Error::InvalidMilestoneIndex);
+  }
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  if amount > 0 && milestone_deadline(&escrow, &project, milestone_index) == 0 {
+    return Err(Error::InvalidConfig);
+  }
+
+  let key = storage::early_bonuses(escrow_id);
+  let mut bonuses = key.get(env).unwrap_or(Map::new(env));
+  let previous = bonuses.get(milestone_index).map_or(0, |bonus| bonus.amount);
+  escrow.total_amount = escrow.total_amount.checked_add(amount - previous).ok_or(Error::InvalidAmount)?;
+  if amount == 0 {
+    bonuses.remove(milestone_index);
+  } else {
+    bonuses.set(milestone_index, EarlyBonus { amount, min_days_early, earned: None });
+  }
+  if bonuses.is_empty() {
+    key.remove(env);
+  } else {
+    key.set(env, &bonuses);
+  }
+  put_escrow(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn get_early_bonus(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<EarlyBonus> {
+  storage::early_bonuses(escrow_id).get(env).and_then(|bonuses| bonuses.get(milestone_index))
+}
+
+pub fn post_bond(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+  let escrow = load_unaccepted_escrow(env, &from, escrow_id)?;
+  take_bond(env, &escrow, escrow_id)
+}
+
+pub fn terms_digest(env: &Env, escrow_id: u64) -> Result<BytesN<32>, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  Ok(digest_terms(env, escrow_id, &escrow))
+}
+
+pub fn accept_engagement(env: &Env, from: Address, escrow_id: u64, digest: BytesN<32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  let mut escrow = load_unaccepted_escrow(env, &from, escrow_id)?;
+  accept_terms(env, &mut escrow, escrow_id, digest)
+}
+
+pub fn accept_engagement_bundle(env: &Env, from: Address, escrow_id: u64, digest: BytesN<32>, pref: Option<BytesN<32>>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+  let mut escrow = load_unaccepted_escrow(env, &from, escrow_id)?;
+  take_bond(env, &escrow, escrow_id)?;
+  accept_terms(env, &mut escrow, escrow_id, digest)?;
+  if let Some(pref) = pref {
+    storage::notification_prefs(from).set(env, &pref);
+  }
+  Ok(())
+}
+
+pub fn deposit_funds(env: &Env, from: Address, escrow_id: u64, amount: i128, memo: Option<BytesN<32>>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+  record_deposit(env, from, escrow_id, amount, memo)
+}
+
+// Books a deposit the caller has already authorized
+pub fn record_deposit(env: &Env, from: Address, escrow_id: u64, amount: i128, memo: Option<BytesN<32>>) -> Result<(), Error> {
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let mut bounty = storage::bounties(escrow_id).get(env);
+
+  // Verify if sender is involved in the escrow (client or freelancer address). Anyone may
+  // sponsor a bounty.
+  if bounty.is_none() && escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  // Empty deposits would only fill the bounded deposit log
+  if amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  // So would installments too small to matter in a coarse token. The one that tops the
+  // escrow off exactly is whatever it takes.
+  if amount < get_min_deposit_increment(env, escrow.asset.clone()) && amount != escrow.total_amount - escrow.deposited_amount {
+    return Err(Error::InvalidAmount);
+  }
+
+  // Record who deposited and any memo so support can match the deposit later
+  let mut deposits = storage::deposits(escrow_id).get(env).unwrap_or(Vec::new(env));
+  if deposits.len() >= MAX_DEPOSITS_PER_ESCROW {
+    return Err(Error::LimitReached);
+  }
+  deposits.push_back(Deposit {
+    depositor: from.clone(),
+    amount,
+    memo: memo.clone(),
+    timestamp: env.ledger().timestamp(),
+  });
+  storage::deposits(escrow_id).set(env, &deposits);
+  record_line(env, escrow_id, LedgerLine { memo: memo.clone(), ..ledger_line(env, LedgerKind::Deposit, &from, &escrow.asset, None, amount) });
+  if let Some(bounty) = bounty.as_mut() {
+    add_contribution(bounty, &from, amount)?;
+    storage::bounties(escrow_id).set(env, bounty);
+  }
+
+  if from == escrow.client {
+    note_client_activity(env, escrow_id, &mut escrow);
+  }
+
+  // Update escrow state and deposited amount
+  escrow.deposited_amount = escrow.deposited_amount.checked_add(amount).ok_or(Error::InvalidAmount)?;
+  start_if_funded(env, escrow_id, &mut escrow);
+  put_escrow(env, escrow_id, &escrow);
+
+  events::deposit(env, escrow_id, &escrow, &from, amount, &memo);
+
+  Ok(())
+}
+
+// Moves a created escrow in progress once its deposits cover the total
+fn start_if_funded(env: &Env, escrow_id: u64, escrow: &mut Escrow) {
+  if escrow.state == EscrowState::Created && escrow.deposited_amount >= escrow.total_amount {
+    // Work can't be expected on an unfunded escrow, so every milestone gets the time it
+    // waited for its funding
+    let waited_secs = env.ledger().timestamp().saturating_sub(escrow.created_at);
+    credit_client_delay(env, escrow_id, 0..milestone_records(env, escrow_id).len(), waited_secs);
+    set_escrow_state(env, escrow_id, escrow, EscrowState::InProgress);
+  }
+}
+
+pub fn export_ledger(env: &Env, escrow_id: u64, cursor: u64, limit: u32) -> LedgerPage {
+  let lines = storage::ledger_lines(escrow_id).get(env).unwrap_or(Vec::new(env));
+  let (items, next_cursor) = page::fill(env, lines.len(), cursor, limit, |position| lines.get(position));
+  LedgerPage { items, next_cursor, total: Some(lines.len()) }
+}
+
+pub fn get_deposits(env: &Env, escrow_id: u64, cursor: u64, limit: u32) -> DepositPage {
+  let deposits = storage::deposits(escrow_id).get(env).unwrap_or(Vec::new(env));
+  let (items, next_cursor) = page::fill(env, deposits.len(), cursor, limit, |position| deposits.get(position));
+  DepositPage { items, next_cursor, total: Some(deposits.len()) }
+}
+
+pub fn find_deposit_by_memo(env: &Env, escrow_id: u64, memo: BytesN<32>) -> Option<Deposit> {
+  let deposits = storage::deposits(escrow_id).get(env).unwrap_or(Vec::new(env));
+  deposits.iter().find(|deposit| deposit.memo == Some(memo.clone()))
+}
+
+pub fn submit_milestone(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, deliverable: BytesN<32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  submit_work(env, &from, escrow_id, false, milestone_index, deliverable)
+}
+
+pub fn release_funds(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+  approve_work(env, &from, escrow_id, false, milestone_index)
+}
+
+pub fn revoke_approval(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  let key = storage::approvals(escrow_id, milestone_index);
+  let approval = key.get(env).ok_or(Error::NotApproved)?;
+  if env.ledger().timestamp() > approval.approved_at.saturating_add(config(env).revoke_window_secs) {
+    return Err(Error::RevokeWindowClosed);
+  }
+  // The payout has moved on to the subcontractors, or the freelancer has drawn it
+  if funded_subcontracts(env, escrow_id, milestone_index) || storage::withdrawals(escrow_id, milestone_index).has(env) {
+    return Err(Error::RevokeWindowClosed);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  record.paid -= approval.paid;
+  record.status = MilestoneStatus::Submitted;
+  records.set(milestone_index, record);
+  let mut milestone = escrow.milestones.get(milestone_index).unwrap();
+  milestone.completed = false;
+  escrow.milestones.set(milestone_index, milestone);
+  escrow.released_amount -= approval.paid;
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Revoked, &escrow.freelancer, &escrow.asset, Some(milestone_index), approval.paid));
+  unsettle_early_bonus(env, escrow_id, &mut escrow, milestone_index);
+  key.remove(env);
+  storage::acceptances(escrow_id, milestone_index).remove(env);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn get_approval(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Approval> {
+  storage::approvals(escrow_id, milestone_index).get(env)
+}
+
+// Pull payments: the freelancer draws what an approval paid, which makes it final before the
+// revoke window runs out. Payouts are booked like deposits, so drawing one moves no tokens;
+// it records the payout as taken. Drawing it again returns what was drawn the first time.
+pub fn withdraw_payout(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<i128, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let approval = storage::approvals(escrow_id, milestone_index).get(env).ok_or(Error::NotApproved)?;
+  let key = storage::withdrawals(escrow_id, milestone_index);
+  if let Some(drawn) = key.get(env) {
+    return Ok(drawn);
+  }
+  key.set(env, &approval.paid);
+  events::withdrawn(env, escrow_id, &escrow, milestone_index, approval.paid);
+  Ok(approval.paid)
+}
+
+pub fn get_withdrawal(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<i128> {
+  storage::withdrawals(escrow_id, milestone_index).get(env)
+}
+
+pub fn void_milestone(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  // Only the freelancer gives anything up, so the call is theirs alone
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  apply_void(env, escrow_id, &mut escrow, milestone_index)?;
+  amendment::record_change(env, escrow_id, &escrow, from, AmendmentChange::VoidMilestone(milestone_index))
+}
+
+// Voids a milestone, refunding what it still had due to the client, and saves the escrow
+pub fn apply_void(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone_index: u32) -> Result<(), Error> {
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    MilestoneStatus::Pending | MilestoneStatus::Submitted | MilestoneStatus::Contested | MilestoneStatus::PartialOffered => {}
+  }
+  let due = milestone_amount(escrow, milestone_index) - record.paid;
+  if available_funds(escrow) < due {
+    return Err(Error::InsufficientFunds);
+  }
+
+  record.status = MilestoneStatus::Voided;
+  records.set(milestone_index, record);
+  escrow.refunded_amount += due;
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, Some(milestone_index), due));
+  settle_early_bonus(env, escrow_id, escrow, milestone_index, |_| false);
+  storage::contests(escrow_id, milestone_index).remove(env);
+  complete_if_settled(env, escrow_id, escrow, &records);
+  save_escrow(env, escrow_id, escrow, &records);
+  Ok(())
+}
+
+pub fn auto_release(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  auto_release_work(env, &from, escrow_id, false, milestone_index)
+}
+
+pub fn reject_milestone(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  reject_work(env, &from, escrow_id, false, milestone_index)
+}
+
+pub fn submit_bundle(env: &Env, from: Address, escrow_id: u64, deliverables: Vec<BytesN<32>>) -> Result<Bundle, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  let config = config(env);
+  let now = env.ledger().timestamp();
+  if project.deadline == 0 || now < project.deadline + config.bundle_grace_secs {
+    return Err(Error::TooEarly);
+  }
+  if escrow.client_active_at >= project.deadline {
+    return Err(Error::ClientActive);
+  }
+  check_checklist(env, escrow_id)?;
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut deliverables = deliverables.iter();
+  for i in 0..records.len() {
+    let mut record = records.get(i).unwrap();
+    // Paid and disputed milestones stay out of the bundle. The milestone order is not
+    // checked: a client silent past the deadline gets everything left at once.
+    if record.status != MilestoneStatus::Pending && record.status != MilestoneStatus::Submitted {
+      continue;
+    }
+    record.status = MilestoneStatus::Submitted;
+    record.deliverable = Some(deliverables.next().ok_or(Error::DeliverableMismatch)?);
+    record.submitted_at = now;
+    records.set(i, record);
+  }
+  if deliverables.next().is_some() {
+    return Err(Error::DeliverableMismatch);
+  }
+
+  let bundle = Bundle { started_at: now, release_at: now + config.review_period_secs };
+  storage::milestone_records(escrow_id).set(env, &records);
+  storage::bundles(escrow_id).set(env, &bundle);
+  touch_escrow(env, escrow_id);
+  epoch::touch(env, escrow_id, &escrow);
+  Ok(bundle)
+}
+
+pub fn release_bundle(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let bundle = storage::bundles(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if env.ledger().timestamp() < bundle.release_at {
+    return Err(Error::TooEarly);
+  }
+
+  let mut records = milestone_records(env, escrow_id);
+  for i in 0..records.len() {
+    let record = records.get(i).unwrap();
+    if record.status == MilestoneStatus::Submitted {
+      record_review(env, escrow_id, record.submitted_at, true);
+      pay_milestone(env, escrow_id, &mut escrow, &mut records, i)?;
+    }
+  }
+  storage::bundles(escrow_id).remove(env);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn get_bundle(env: &Env, escrow_id: u64) -> Option<Bundle> {
+  storage::bundles(escrow_id).get(env)
+}
+
+pub fn get_milestone_records(env: &Env, escrow_id: u64) -> Result<Vec<MilestoneRecord>, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.lump_sum {
+    return Ok(Vec::new(env));
+  }
+  Ok(milestone_records(env, escrow_id))
+}
+
+pub fn countersign_acceptance(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<AcceptanceRecord, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Released {
+    return Err(Error::NotApproved);
+  }
+  let key = storage::acceptances(escrow_id, milestone_index);
+  if key.has(env) {
+    return Err(Error::AlreadyAccepted);
+  }
+  let acceptance = AcceptanceRecord {
+    client: escrow.client.clone(),
+    freelancer: escrow.freelancer.clone(),
+    deliverable: record.deliverable,
+    amount: milestone_amount(&escrow, milestone_index),
+    paid: record.paid,
+    submitted_at: record.submitted_at,
+    countersigned_at: env.ledger().timestamp(),
+  };
+  key.set(env, &acceptance);
+  Ok(acceptance)
+}
+
+pub fn get_acceptance(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<AcceptanceRecord> {
+  storage::acceptances(escrow_id, milestone_index).get(env)
+}
+
+pub fn get_completion_certificate(env: &Env, escrow_id: u64) -> Result<CompletionCertificate, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.state != EscrowState::Completed {
+    return Err(Error::EscrowNotCompleted);
+  }
+  let mut acceptances = Map::new(env);
+  for milestone_index in 0..milestone_records(env, escrow_id).len() {
+    if let Some(acceptance) = storage::acceptances(escrow_id, milestone_index).get(env) {
+      acceptances.set(milestone_index, acceptance);
+    }
+  }
+  Ok(CompletionCertificate {
+    project_id: escrow.project_id,
+    client: escrow.client,
+    freelancer: escrow.freelancer,
+    asset: escrow.asset,
+    released: escrow.released_amount,
+    acceptances,
+  })
+}
+
+pub fn set_kickoff(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, kickoff_bps: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  // The kickoff shares are part of the terms the freelancer signed
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if kickoff_bps > BPS_DENOMINATOR {
+    return Err(Error::InvalidShares);
+  }
+  milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  let key = storage::kickoffs(escrow_id, milestone_index);
+  if key.get(env).is_some_and(|kickoff| kickoff.started) {
+    return Err(Error::AlreadyStarted);
+  }
+  key.set(env, &Kickoff { bps: kickoff_bps, started: false, paid: 0 });
+  Ok(())
+}
+
+pub fn start_milestone(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<i128, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if escrow.state != EscrowState::InProgress {
+    return Err(Error::NotFullyFunded);
+  }
+  if milestone_index == 0 {
+    check_checklist(env, escrow_id)?;
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Pending => {}
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    _ => return Err(Error::AlreadyStarted),
+  }
+  check_turn(env, escrow_id, &escrow, &records, milestone_index)?;
+  let key = storage::kickoffs(escrow_id, milestone_index);
+  let mut kickoff = key.get(env).unwrap_or(Kickoff { bps: 0, started: false, paid: 0 });
+  if kickoff.started {
+    return Err(Error::AlreadyStarted);
+  }
+
+  check_parent_dispute(env, escrow_id)?;
+  let amount = bps_of(milestone_amount(&escrow, milestone_index), kickoff.bps).ok_or(Error::InvalidAmount)?;
+  if available_funds(&escrow) < amount {
+    return Err(Error::InsufficientFunds);
+  }
+  kickoff.started = true;
+  kickoff.paid = amount;
+  record.paid += amount;
+  records.set(milestone_index, record);
+  escrow.released_amount += amount;
+  record_payout(env, escrow_id, &escrow, LedgerKind::Kickoff, milestone_index, amount);
+  key.set(env, &kickoff);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(amount)
+}
+
+pub fn get_kickoff(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Kickoff> {
+  storage::kickoffs(escrow_id, milestone_index).get(env)
+}
+
+pub fn submit_deliverable(env: &Env, from: Address, escrow_id: u64, deliverable: BytesN<32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  submit_work(env, &from, escrow_id, true, 0, deliverable)
+}
+
+pub fn approve_deliverable(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  approve_work(env, &from, escrow_id, true, 0)
+}
+
+pub fn auto_release_deliverable(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  auto_release_work(env, &from, escrow_id, true, 0)
+}
+
+pub fn reject_deliverable(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  reject_work(env, &from, escrow_id, true, 0)
+}
+
+pub fn get_deliverable(env: &Env, escrow_id: u64) -> Result<Option<BytesN<32>>, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if !escrow.lump_sum {
+    return Err(Error::ModeMismatch);
+  }
+  Ok(milestone_records(env, escrow_id).get(0).unwrap().deliverable)
+}
+
+pub fn refund_funds(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+
+  // Ensure escrow is in a refundable state. Agreed kill fees are settled by cancel_escrow,
+  // which a refund must not bypass.
+  if escrow.state != EscrowState::Created || storage::cancellation_schedules(escrow_id).has(env) {
+    return Err(Error::RefundNotAllowed);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  // Return whatever was deposited and update escrow state
+  refund_contributors(env, escrow_id, &escrow, available_funds(&escrow));
+  escrow.refunded_amount += available_funds(&escrow);
+  set_escrow_state(env, escrow_id, &mut escrow, EscrowState::Refunded);
+  put_escrow(env, escrow_id, &escrow);
+
+  Ok(())
+}
+
+pub fn set_cancellation_schedule(env: &Env, from: Address, escrow_id: u64, schedule: CancellationSchedule) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  check_cancellation_schedule(&schedule)?;
+  storage::cancellation_schedules(escrow_id).set(env, &schedule);
+  Ok(())
+}
+
+pub fn get_cancellation_schedule(env: &Env, escrow_id: u64) -> Option<CancellationSchedule> {
+  storage::cancellation_schedules(escrow_id).get(env)
+}
+
+pub fn check_cancellation_schedule(schedule: &CancellationSchedule) -> Result<(), Error> {
+  let rising = schedule.before_acceptance_bps <= schedule.after_acceptance_bps && schedule.after_acceptance_bps <= schedule.after_submission_bps;
+  if !rising || schedule.after_submission_bps > MAX_CANCELLATION_FEE_BPS {
+    return Err(Error::InvalidSchedule);
+  }
+  Ok(())
+}
+
+pub fn cancel_escrow(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  let schedule = storage::cancellation_schedules(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let records = milestone_records(env, escrow_id);
+  // A dispute already has someone to settle it
+  if records.iter().any(|record| record.status == MilestoneStatus::Disputed) {
+    return Err(Error::MilestoneDisputed);
+  }
+  // A milestone sent back to Pending keeps its deliverable, so it still counts as submitted
+  let submitted = records.iter().any(|record| record.deliverable.is_some() || !matches!(record.status, MilestoneStatus::Pending | MilestoneStatus::Voided));
+  let bps = if submitted {
+    schedule.after_submission_bps
+  } else if is_accepted(env, escrow_id) {
+    schedule.after_acceptance_bps
+  } else {
+    schedule.before_acceptance_bps
+  };
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let held = available_funds(&escrow);
+  let fee = bps_of(held, bps).ok_or(Error::InvalidAmount)?;
+  if fee > 0 {
+    // Charged the platform fee like any other payout
+    let platform_fee = bps_of(fee, storage::fee_terms(escrow_id).get(env).unwrap_or_default().bps).unwrap_or(0);
+    let line = ledger_line(env, LedgerKind::KillFee, &escrow.freelancer, &escrow.asset, None, fee);
+    record_line(env, escrow_id, LedgerLine { fee: platform_fee, net: fee - platform_fee, ..line });
+    escrow.released_amount += fee;
+  }
+  refund_contributors(env, escrow_id, &escrow, held - fee);
+  escrow.refunded_amount += held - fee;
+  events::kill_fee(env, escrow_id, &escrow, bps, fee);
+  set_escrow_state(env, escrow_id, &mut escrow, EscrowState::Refunded);
+  put_escrow(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn open_bounty(env: &Env, from: Address, escrow_id: u64, expires_at: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if escrow.state != EscrowState::Created {
+    return Err(Error::EscrowNotActive);
+  }
+  if escrow.deposited_amount > 0 {
+    return Err(Error::HasDeposits);
+  }
+  if expires_at <= env.ledger().timestamp() {
+    return Err(Error::InvalidConfig);
+  }
+  storage::bounties(escrow_id).set(env, &Bounty { expires_at, contributions: Vec::new(env) });
+  Ok(())
+}
+
+pub fn expire_bounty(env: &Env, escrow_id: u64) -> Result<(), Error> {
+  check_reentry(env)?;
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let bounty = storage::bounties(escrow_id).get(env).ok_or(Error::NotBounty)?;
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if env.ledger().timestamp() < bounty.expires_at {
+    return Err(Error::TooEarly);
+  }
+  let in_review = milestone_records(env, escrow_id).iter().any(|record| {
+    matches!(record.status, MilestoneStatus::Submitted | MilestoneStatus::Disputed | MilestoneStatus::Contested | MilestoneStatus::PartialOffered)
+  });
+  if in_review {
+    return Err(Error::WorkUnderReview);
+  }
+
+  refund_contributors(env, escrow_id, &escrow, available_funds(&escrow));
+  escrow.refunded_amount += available_funds(&escrow);
+  set_escrow_state(env, escrow_id, &mut escrow, EscrowState::Refunded);
+  put_escrow(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn get_bounty(env: &Env, escrow_id: u64) -> Result<Bounty, Error> {
+  storage::bounties(escrow_id).get(env).ok_or(Error::NotBounty)
+}
+
+pub fn decline_escrow(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_unaccepted_escrow(env, &from, escrow_id)?;
+  if escrow.deposited_amount > 0 {
+    return Err(Error::HasDeposits);
+  }
+  let mut project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  project.status = ProjectStatus::Open;
+  storage::projects(escrow.project_id).set(env, &project);
+
+  release_exposure(env, escrow_id, &escrow);
+  update_freelancer_stats(env, &escrow.freelancer, |stats| stats.active_escrows = stats.active_escrows.saturating_sub(1));
+  index::remove_value(env, &IndexKind::PairEscrows(escrow.client.clone(), escrow.freelancer.clone()), escrow_id);
+  bury_escrow(env, escrow_id, &escrow, Ending::EscrowDeclined, env.ledger().timestamp());
+  Ok(())
+}
+
+pub fn collect_escrow(env: &Env, admin: Address, escrow_id: u64) -> Result<(), Error> {
+  require_admin(env, &admin)?;
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let ending = match escrow.state {
+    EscrowState::Completed => Ending::EscrowCompleted,
+    EscrowState::Refunded => Ending::EscrowRefunded,
+    _ => return Err(Error::EscrowActive),
+  };
+  // The escrow's last change was its closing
+  let closed_at = storage::escrow_activity(escrow_id).get(env).unwrap_or(escrow.created_at);
+  bury_escrow(env, escrow_id, &escrow, ending, closed_at);
+  Ok(())
+}
+
+pub fn get_escrow_tombstone(env: &Env, escrow_id: u64) -> Option<Tombstone> {
+  storage::escrow_tombstones(escrow_id).get(env)
+}
+
+pub fn get_commitments(env: &Env, client: Address, asset: Address) -> Commitments {
+  storage::commitments(client, asset).get(env).unwrap_or_default()
+}
+
+pub fn get_escrow(env: &Env, escrow_id: u64) -> Result<Escrow, Error> {
+  storage::escrows(escrow_id).get(env).ok_or_else(|| missing(env, storage::escrow_tombstones(escrow_id)))
+}
+
+pub fn view_escrow(env: &Env, viewer: Address, escrow_id: u64) -> Result<EscrowView, Error> {
+  // Ensure the viewer authorized this call, so nobody reads as someone else
+  viewer.require_auth();
+
+  let escrow = get_escrow(env, escrow_id)?;
+  if can_view_full(env, &viewer, escrow_id, &escrow) {
+    return Ok(EscrowView::Full(escrow));
+  }
+  Ok(EscrowView::Header(EscrowHeader {
+    escrow_id,
+    project_id: escrow.project_id,
+    state: escrow.state,
+    asset: escrow.asset,
+    total_amount: escrow.total_amount,
+    created_at: escrow.created_at,
+  }))
+}
+
+// Each party keeps one note per escrow, a hash of text held off-chain; setting it again
+// replaces it. Notes are stored apart from the escrow, so no view or export includes them.
+pub fn set_private_note(env: &Env, from: Address, escrow_id: u64, note_hash: BytesN<32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = get_escrow(env, escrow_id)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  storage::private_notes(escrow_id, from).set(env, &note_hash);
+  Ok(())
+}
+
+// Only ever the viewer's own note: the counterparty reads theirs, never this one, and anyone
+// else (the admin included) is Unauthorized
+pub fn get_private_note(env: &Env, viewer: Address, escrow_id: u64) -> Result<Option<BytesN<32>>, Error> {
+  // Ensure the viewer authorized this call, so nobody reads as someone else
+  viewer.require_auth();
+
+  let escrow = get_escrow(env, escrow_id)?;
+  if escrow.client != viewer && escrow.freelancer != viewer {
+    return Err(Error::Unauthorized);
+  }
+  Ok(storage::private_notes(escrow_id, viewer).get(env))
+}
+
+// An arbitrator sees the escrow only while assigned to a milestone still in dispute
+fn can_view_full(env: &Env, viewer: &Address, escrow_id: u64, escrow: &Escrow) -> bool {
+  if escrow.client == *viewer || escrow.freelancer == *viewer || storage::admin().get(env).as_ref() == Some(viewer) {
+    return true;
+  }
+  if observing(env, escrow_id).contains(viewer) {
+    return true;
+  }
+  milestone_records(env, escrow_id).iter().enumerate().any(|(index, record)| {
+    record.status == MilestoneStatus::Disputed && storage::dispute_arbitrators(escrow_id, index as u32).get(env).as_ref() == Some(viewer)
+  })
+}
+
+pub fn consent_contact_exchange(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_accepted(env, escrow_id) {
+    return Err(Error::NotAcknowledged);
+  }
+  // Consent can't be taken back, so giving it again changes nothing
+  let key = storage::contact_consents(escrow_id, from);
+  if key.has(env) {
+    return Ok(());
+  }
+  key.set(env, &());
+  if contact_consent(env, escrow_id, &escrow) == ContactConsent::Both {
+    events::contact(env, escrow_id, &escrow);
+  }
+  Ok(())
+}
+
+pub fn get_contact_consent(env: &Env, escrow_id: u64) -> Result<ContactConsent, Error> {
+  let escrow = get_escrow(env, escrow_id)?;
+  Ok(contact_consent(env, escrow_id, &escrow))
+}
+
+// Counts the current parties' consents, so a client who took the project over starts out
+// without one
+fn contact_consent(env: &Env, escrow_id: u64, escrow: &Escrow) -> ContactConsent {
+  let client = storage::contact_consents(escrow_id, escrow.client.clone()).has(env);
+  let freelancer = storage::contact_consents(escrow_id, escrow.freelancer.clone()).has(env);
+  match (client, freelancer) {
+    (true, true) => ContactConsent::Both,
+    (false, false) => ContactConsent::None,
+    _ => ContactConsent::One,
+  }
+}
+
+pub fn get_escrow_in_reference(env: &Env, escrow_id: u64) -> Result<ReferenceView, Error> {
+  let escrow = get_escrow(env, escrow_id)?;
+  let rate = fresh_reference_rate(env, escrow.asset.clone())?;
+  let convert = |amount: i128| to_reference(amount, rate.rate).ok_or(Error::InvalidAmount);
+  let mut milestones = Vec::new(env);
+  for milestone in escrow.milestones.iter() {
+    milestones.push_back(convert(milestone.amount)?);
+  }
+  Ok(ReferenceView {
+    rate: rate.rate,
+    rate_timestamp: rate.timestamp,
+    total: convert(escrow.total_amount)?,
+    deposited: convert(escrow.deposited_amount)?,
+    released: convert(escrow.released_amount)?,
+    refunded: convert(escrow.refunded_amount)?,
+    milestones,
+  })
+}
+
+pub fn get_progress(env: &Env, escrow_id: u64) -> Result<ProgressView, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  let estimate = progress::get_effort_estimates(env, escrow_id);
+  let reports = progress::get_progress_reports(env, escrow_id);
+  let (mut estimated_hours, mut remaining_hours, mut done_weight, mut total_weight) = (0u32, 0u32, 0i128, 0i128);
+  let mut milestones = Vec::new(env);
+  for (milestone_index, record) in milestone_records(env, escrow_id).iter().enumerate() {
+    let milestone_index = milestone_index as u32;
+    let amount = milestone_amount(&escrow, milestone_index);
+    let report = reports.get(milestone_index);
+    let hours = estimate.as_ref().and_then(|estimate| estimate.hours.get(milestone_index)).unwrap_or(0);
+    let percent_done = progress::percent_done(&record, report.as_ref());
+    estimated_hours = estimated_hours.saturating_add(hours);
+    remaining_hours = remaining_hours.saturating_add(hours * (100 - percent_done) / 100);
+    done_weight += amount * percent_done as i128;
+    total_weight += amount;
+    milestones.push_back(MilestoneProgress {
+      amount,
+      paid: record.paid,
+      client_delay_secs: client_delay(env, escrow_id, milestone_index),
+      deadline: time_status(env, effective_deadline(env, escrow_id, &escrow, &project, milestone_index)),
+      estimated_hours: hours,
+      percent_done,
+      reported_at: report.as_ref().map_or(0, |report| report.reported_at),
+      stale: progress::stale_since(&escrow, &record, estimate.as_ref(), report.as_ref(), env.ledger().timestamp()).is_some(),
+      status: record.status,
+    });
+  }
+  // percent_done is out of 100, completion out of BPS_DENOMINATOR
+  let completion_bps = if total_weight > 0 { (done_weight * 100 / total_weight) as u32 } else { 0 };
+  Ok(ProgressView {
+    state: escrow.state,
+    total: escrow.total_amount,
+    deposited: escrow.deposited_amount,
+    released: escrow.released_amount,
+    last_activity_at: storage::escrow_activity(escrow_id).get(env).unwrap_or(0),
+    deadline: time_status(env, project.deadline),
+    estimated_hours,
+    remaining_hours,
+    completion_bps,
+    shortfall: shortfall(&escrow),
+    milestones,
+  })
+}
+
+pub fn get_client_delays(env: &Env, escrow_id: u64) -> Map<u32, u64> {
+  storage::client_delays(escrow_id).get(env).unwrap_or(Map::new(env))
+}
+
+pub fn get_action_items(env: &Env, escrow_id: u64, user: Address) -> Result<Vec<ActionItem>, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  let mut items = Vec::new(env);
+  if !is_active(&escrow) {
+    return Ok(items);
+  }
+  let is_client = escrow.client == user;
+  let is_freelancer = escrow.freelancer == user;
+  if is_client && escrow.deposited_amount < escrow.total_amount {
+    let kind = if escrow.state == EscrowState::InProgress { ActionKind::CoverShortfall } else { ActionKind::Deposit };
+    items.push_back(ActionItem { kind, milestone_index: 0, due: time_status(env, project.deadline) });
+  }
+  if let Some(checklist) = storage::checklists(escrow_id).get(env) {
+    for (item_index, item) in checklist.items.iter().enumerate() {
+      let item_index = item_index as u32;
+      if checklist.checked_at.get(item_index).unwrap().is_none() && party_address(&escrow, item.party) == user {
+        items.push_back(ActionItem { kind: ActionKind::CheckItem, milestone_index: item_index, due: time_status(env, 0) });
+      }
+    }
+  }
+
+  let review_period_secs = config(env).review_period_secs;
+  let estimate = progress::get_effort_estimates(env, escrow_id);
+  let reports = progress::get_progress_reports(env, escrow_id);
+  for (milestone_index, record) in milestone_records(env, escrow_id).iter().enumerate() {
+    let milestone_index = milestone_index as u32;
+    if is_client {
+      let stale_since = progress::stale_since(&escrow, &record, estimate.as_ref(), reports.get(milestone_index).as_ref(), env.ledger().timestamp());
+      if let Some(stale_since) = stale_since {
+        items.push_back(ActionItem { kind: ActionKind::CheckIn, milestone_index, due: time_status(env, stale_since) });
+      }
+    }
+    let item = match record.status {
+      MilestoneStatus::Pending if is_freelancer => Some((ActionKind::Submit, effective_deadline(env, escrow_id, &escrow, &project, milestone_index))),
+      MilestoneStatus::Submitted => {
+        let review_by = record.submitted_at + review_period_secs;
+        if is_client && env.ledger().timestamp() < review_by {
+          Some((ActionKind::Review, review_by))
+        } else if is_freelancer && env.ledger().timestamp() >= review_by {
+          Some((ActionKind::AutoRelease, review_by))
+        } else {
+          None
+        }
+      }
+      MilestoneStatus::Contested | MilestoneStatus::PartialOffered if is_freelancer => storage::contests(escrow_id, milestone_index)
+        .get(env)
+        .map(|contest| (ActionKind::AnswerContest, contest.respond_by)),
+      _ => None,
+    };
+    if let Some((kind, deadline)) = item {
+      items.push_back(ActionItem { kind, milestone_index, due: time_status(env, deadline) });
+    }
+  }
+  Ok(items)
+}
+
+pub fn count_escrows_by_state(env: &Env) -> EscrowStateCounts {
+  let count = |state| storage::escrow_state_count(state).get(env).unwrap_or(0);
+  EscrowStateCounts {
+    created: count(EscrowState::Created),
+    in_progress: count(EscrowState::InProgress),
+    completed: count(EscrowState::Completed),
+    refunded: count(EscrowState::Refunded),
+  }
+}
+
+pub fn list_escrows_by_state(env: &Env, state: EscrowState, cursor: u64, limit: u32) -> IdPage {
+  index::paged_read(env, &IndexKind::EscrowsByState(state), cursor, limit)
+}
+
+pub fn list_stale_escrows(env: &Env, older_than_secs: u64, cursor: u64, limit: u32) -> IdPage {
+  let mut page = IdPage { items: Vec::new(env), next_cursor: None, total: None };
+  let Some(cutoff) = env.ledger().timestamp().checked_sub(older_than_secs) else {
+    return page;
+  };
+  let limit = page::limit(limit);
+  let (first_bucket, mut offset) = index::unpack(cursor);
+  // Bounded by MAX_ACTIVITY_BUCKETS
+  let buckets = index::read(env, &IndexKind::ActivityBuckets);
+  for bucket in buckets.iter().filter(|bucket| *bucket >= first_bucket) {
+    // Buckets are in time order, so nothing from here on is stale
+    if bucket * ACTIVITY_BUCKET_SECS > cutoff {
+      break;
+    }
+    let index = IndexKind::ActivityBucket(bucket);
+    if bucket > first_bucket {
+      offset = 0;
+    }
+    let ids = index::paged_read(env, &index, offset as u64, limit - page.items.len()).items;
+    for id in ids.iter() {
+      let last_activity = storage::escrow_activity(id).get(env).unwrap_or(0);
+      if last_activity <= cutoff {
+        page.items.push_back(id);
+      }
+    }
+    offset += ids.len();
+    if page.items.len() == limit {
+      if offset < index::len(env, &index) || buckets.last().is_some_and(|last| last > bucket) {
+        page.next_cursor = Some(index::pack(bucket, offset));
+      }
+      break;
+    }
+  }
+  page
+}
+
+// Opens an escrow on an open project with the given terms, on behalf of its client
+pub fn open_escrow(env: &Env, project: &mut Project, freelancer: Address, total_amount: i128, milestones: Vec<Milestone>, source: TermsSource) -> Result<u64, Error> {
+  // Ensure project is open
+  if project.status != ProjectStatus::Open {
+    return Err(Error::ProjectNotOpen);
+  }
+  check_wip_limit(env, project.id, &project.client, &freelancer)?;
+
+  let lump_sum = milestones.is_empty();
+  let escrow = Escrow {
+    project_id: project.id,
+    client: project.client.clone(),
+    freelancer,
+    total_amount,
+    asset: project.asset.clone(),
+    milestones,
+    deposited_amount: 0,
+    released_amount: 0,
+    refunded_amount: 0,
+    state: EscrowState::Created,
+    created_at: env.ledger().timestamp(),
+    client_active_at: env.ledger().timestamp(),
+    lump_sum,
+  };
+
+  // Store escrow details. A lump-sum escrow is reviewed as one implicit milestone.
+  let escrow_id = storage::escrow_count().get(env).unwrap_or(0) + 1;
+  let mut records = Vec::new(env);
+  for _ in 0..escrow.milestones.len().max(1) {
+    records.push_back(MilestoneRecord { status: MilestoneStatus::Pending, deliverable: None, submitted_at: 0, paid: 0 });
+  }
+  if !index::append_unique(env, &IndexKind::PairEscrows(escrow.client.clone(), escrow.freelancer.clone()), escrow_id, MAX_PAIR_INDEX_LEN) {
+    return Err(Error::LimitReached);
+  }
+  take_exposure(env, escrow_id, &escrow, escrow.total_amount)?;
+  save_escrow(env, escrow_id, &escrow, &records);
+  storage::escrow_count().set(env, &escrow_id);
+  storage::project_escrows(project.id).set(env, &escrow_id);
+  enter_escrow_state(env, escrow_id, EscrowState::Created);
+  storage::terms_sources(escrow_id).set(env, &source);
+  update_freelancer_stats(env, &escrow.freelancer, |stats| stats.active_escrows += 1);
+
+  // Snapshot the fee so later changes only reach new escrows
+  let public_goods = storage::nonprofits(escrow.client.clone()).has(env);
+  let fee_terms = FeeTerms {
+    bps: if public_goods { 0 } else { storage::platform_fee().get(env).unwrap_or(0) },
+    public_goods,
+  };
+  storage::fee_terms(escrow_id).set(env, &fee_terms);
+  events::opened(env, escrow_id, &escrow, &fee_terms);
+
+  // Update project status
+  project.status = ProjectStatus::InProgress;
+  storage::projects(project.id).set(env, project);
+
+  Ok(escrow_id)
+}
+
+// Loads an active escrow the caller is the freelancer of and has not accepted yet
+pub fn load_unaccepted_escrow(env: &Env, from: &Address, escrow_id: u64) -> Result<Escrow, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  Ok(escrow)
+}
+
+// Transfers a required, unposted bond from the freelancer. Does nothing when the escrow
+// requires no bond or it is already posted. The transfer runs under the freelancer's auth
+// for the calling function, so their signature has to cover it as a sub-invocation.
+fn take_bond(env: &Env, escrow: &Escrow, escrow_id: u64) -> Result<(), Error> {
+  let Some(mut bond) = storage::bonds(escrow_id).get(env) else {
+    return Ok(());
+  };
+  if bond.posted {
+    return Ok(());
+  }
+  adjust_obligations(env, &bond.token, bond.amount);
+  bond.posted = true;
+  storage::bonds(escrow_id).set(env, &bond);
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::BondPosted, &escrow.freelancer, &bond.token, None, bond.amount));
+  transfer(env, &bond.token, &escrow.freelancer, &env.current_contract_address(), bond.amount);
+  Ok(())
+}
+
+fn accept_terms(env: &Env, escrow: &mut Escrow, escrow_id: u64, digest: BytesN<32>) -> Result<(), Error> {
+  if digest != digest_terms(env, escrow_id, escrow) {
+    return Err(Error::DigestMismatch);
+  }
+  let bond = storage::bonds(escrow_id).get(env);
+  if bond.is_some_and(|bond| !bond.posted) {
+    return Err(Error::BondNotPosted);
+  }
+  storage::accepted_terms(escrow_id).set(env, &digest);
+  put_escrow(env, escrow_id, escrow);
+  let response_secs = env.ledger().timestamp().saturating_sub(escrow.created_at);
+  update_freelancer_stats(env, &escrow.freelancer, |stats| {
+    stats.responses += 1;
+    stats.response_secs += response_secs;
+  });
+  Ok(())
+}
+
+// sha256 over the XDR of the economic terms: asset, each milestone's amount, deadline and
+// kickoff share, the fee terms, the bond, the kickoff checklist and the milestone order.
+// Descriptions, deposits and preferences are left out, as is whether the bond is posted yet,
+// so the digest taken before posting still holds.
+fn digest_terms(env: &Env, escrow_id: u64, escrow: &Escrow) -> BytesN<32> {
+  let mut milestones = Vec::new(env);
+  for milestone_index in 0..milestone_records(env, escrow_id).len() {
+    let kickoff = storage::kickoffs(escrow_id, milestone_index).get(env).map_or(0, |kickoff| kickoff.bps);
+    let deadline = escrow.milestones.get(milestone_index).map_or(0, |milestone| milestone.deadline);
+    milestones.push_back((milestone_amount(escrow, milestone_index), deadline, kickoff));
+  }
+  let fees = storage::fee_terms(escrow_id).get(env).unwrap_or_default();
+  let bond = storage::bonds(escrow_id).get(env).map(|bond| (bond.token, bond.amount));
+  let checklist = storage::checklists(escrow_id).get(env).map(|checklist| checklist.items);
+  let bonuses = storage::early_bonuses(escrow_id).get(env);
+  let cancellation = storage::cancellation_schedules(escrow_id).get(env);
+  let order = storage::milestone_orders(escrow_id).get(env);
+  let terms = (escrow.asset.clone(), escrow.total_amount, escrow.lump_sum, milestones, (fees.bps, fees.public_goods), bond, checklist, bonuses, cancellation, order);
+  env.crypto().sha256(&terms.to_xdr(env)).into()
+}
+
+pub fn party_address(escrow: &Escrow, party: UserType) -> Address {
+  match party {
+    UserType::Client => escrow.client.clone(),
+    UserType::Freelancer => escrow.freelancer.clone(),
+  }
+}
+
+pub fn check_checklist(env: &Env, escrow_id: u64) -> Result<(), Error> {
+  let checklist = storage::checklists(escrow_id).get(env);
+  if checklist.is_some_and(|checklist| checklist.checked_at.contains(None::<u64>)) {
+    return Err(Error::ChecklistPending);
+  }
+  Ok(())
+}
+
+// Hands a posted bond back to the freelancer once the escrow has ended. put_escrow calls it
+// after its own writes, so the transfer is the last thing an ending call does.
+pub fn return_bond(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  let Some(bond) = storage::bonds(escrow_id).get(env) else {
+    return;
+  };
+  storage::bonds(escrow_id).remove(env);
+  if bond.posted {
+    adjust_obligations(env, &bond.token, -bond.amount);
+    record_line(env, escrow_id, ledger_line(env, LedgerKind::BondReturned, &escrow.freelancer, &bond.token, None, bond.amount));
+    transfer(env, &bond.token, &env.current_contract_address(), &escrow.freelancer, bond.amount);
+  }
+}
+
+// Every token transfer goes through here, after the calling function has finished its own
+// storage writes. The guard makes any call back into a money function, or any escrow write,
+// fail for as long as the token contract has control.
+pub fn transfer(env: &Env, token: &Address, from: &Address, to: &Address, amount: i128) {
+  storage::transfer_guard().set(env, &true);
+  token::Client::new(env, token).transfer(from, to, &amount);
+  storage::transfer_guard().remove(env);
+}
+
+pub fn migrate_escrow_asset(env: &Env, client: Address, freelancer: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure both parties authorized this call
+  client.require_auth();
+  freelancer.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != client || escrow.freelancer != freelancer {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  let old = escrow.asset.clone();
+  let new = storage::asset_migrations(old.clone()).get(env).ok_or(Error::NotFound)?;
+  // Deposits are only booked, so nothing of the old asset leaves the contract: whatever it
+  // holds there backs bonds, fees and other pools. The pool covers the escrow instead.
+  let held = available_funds(&escrow);
+  let pool_key = storage::migration_pools(new.clone());
+  let pool = pool_key.get(env).unwrap_or(0);
+  if pool < held {
+    return Err(Error::InsufficientFunds);
+  }
+
+  pool_key.set(env, &(pool - held));
+  let mut project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  project.asset = new.clone();
+  storage::projects(escrow.project_id).set(env, &project);
+  // The commitments and obligations move to the new asset with the write
+  escrow.asset = new.clone();
+  put_escrow(env, escrow_id, &escrow);
+  events::asset_migrated(env, escrow_id, &escrow, &old, held);
+  Ok(())
+}
+
+pub fn check_reentry(env: &Env) -> Result<(), Error> {
+  if storage::transfer_guard().has(env) {
+    return Err(Error::Reentrant);
+  }
+  Ok(())
+}
+
+pub fn is_active(escrow: &Escrow) -> bool {
+  escrow.state == EscrowState::Created || escrow.state == EscrowState::InProgress
+}
+
+pub fn time_status(env: &Env, deadline: u64) -> TimeStatus {
+  let now = env.ledger().timestamp();
+  let sequence = env.ledger().sequence();
+  let has_deadline = deadline != 0;
+  let remaining_secs = if has_deadline { deadline.saturating_sub(now) } else { 0 };
+  let overdue_secs = if has_deadline { now.saturating_sub(deadline) } else { 0 };
+  let deadline_ledger = if !has_deadline {
+    0
+  } else if remaining_secs > 0 {
+    sequence.saturating_add((remaining_secs / LEDGER_SECS).min(u32::MAX as u64) as u32)
+  } else {
+    sequence.saturating_sub((overdue_secs / LEDGER_SECS).min(u32::MAX as u64) as u32)
+  };
+  TimeStatus { now, deadline, remaining_secs, overdue_secs, overdue: overdue_secs > 0, deadline_ledger }
+}
+
+// A milestone's own deadline, falling back to the project's
+pub fn milestone_deadline(escrow: &Escrow, project: &Project, milestone_index: u32) -> u64 {
+  match escrow.milestones.get(milestone_index) {
+    Some(milestone) if milestone.deadline != 0 => milestone.deadline,
+    _ => project.deadline,
+  }
+}
+
+// The milestone's deadline pushed back by the delay the client caused. Every deadline check
+// on the freelancer's work runs against this one.
+pub fn effective_deadline(env: &Env, escrow_id: u64, escrow: &Escrow, project: &Project, milestone_index: u32) -> u64 {
+  let deadline = milestone_deadline(escrow, project, milestone_index);
+  if deadline == 0 {
+    return 0;
+  }
+  deadline.saturating_add(client_delay(env, escrow_id, milestone_index))
+}
+
+pub fn client_delay(env: &Env, escrow_id: u64, milestone_index: u32) -> u64 {
+  let delays = storage::client_delays(escrow_id).get(env);
+  delays.and_then(|delays| delays.get(milestone_index)).unwrap_or(0)
+}
+
+pub fn credit_client_delay(env: &Env, escrow_id: u64, milestone_indexes: Range<u32>, secs: u64) {
+  if secs == 0 || milestone_indexes.is_empty() {
+    return;
+  }
+  let key = storage::client_delays(escrow_id);
+  let mut delays = key.get(env).unwrap_or(Map::new(env));
+  for milestone_index in milestone_indexes {
+    delays.set(milestone_index, delays.get(milestone_index).unwrap_or(0).saturating_add(secs));
+  }
+  key.set(env, &delays);
+}
+
+// A review that took longer than the review period held the milestone up by the excess
+pub fn credit_late_review(env: &Env, escrow_id: u64, milestone_index: u32, submitted_at: u64) {
+  let review_by = submitted_at + config(env).review_period_secs;
+  credit_client_delay(env, escrow_id, milestone_index..milestone_index + 1, env.ledger().timestamp().saturating_sub(review_by));
+}
+
+// Loads an active escrow for a call that only applies to lump-sum escrows, or only to
+// milestone escrows
+pub fn load_escrow_in_mode(env: &Env, escrow_id: u64, lump_sum: bool) -> Result<Escrow, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.lump_sum != lump_sum {
+    return Err(Error::ModeMismatch);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  Ok(escrow)
+}
+
+// Amount due for a milestone, or for the whole escrow when it is a lump sum
+pub fn milestone_amount(escrow: &Escrow, milestone_index: u32) -> i128 {
+  if escrow.lump_sum {
+    return escrow.total_amount;
+  }
+  escrow.milestones.get(milestone_index).unwrap().amount
+}
+
+pub fn submit_work(env: &Env, from: &Address, escrow_id: u64, lump_sum: bool, milestone_index: u32, deliverable: BytesN<32>) -> Result<(), Error> {
+  let escrow = load_escrow_in_mode(env, escrow_id, lump_sum)?;
+  if escrow.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  if escrow.lump_sum && escrow.state != EscrowState::InProgress {
+    return Err(Error::NotFullyFunded);
+  }
+  if milestone_index == 0 {
+    check_checklist(env, escrow_id)?;
+  }
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  check_turn(env, escrow_id, &escrow, &records, milestone_index)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    // Resubmitting answers an out-of-scope flag
+    MilestoneStatus::Contested | MilestoneStatus::PartialOffered => {
+      storage::contests(escrow_id, milestone_index).remove(env);
+    }
+    MilestoneStatus::Pending | MilestoneStatus::Submitted => {}
+  }
+
+  // A resubmission replaces the deliverable and restarts the review clock
+  record.status = MilestoneStatus::Submitted;
+  record.deliverable = Some(deliverable);
+  record.submitted_at = env.ledger().timestamp();
+  records.set(milestone_index, record);
+  storage::milestone_records(escrow_id).set(env, &records);
+  touch_escrow(env, escrow_id);
+  epoch::touch(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn approve_work(env: &Env, from: &Address, escrow_id: u64, lump_sum: bool, milestone_index: u32) -> Result<(), Error> {
+  let mut escrow = load_escrow_in_mode(env, escrow_id, lump_sum)?;
+  if escrow.client != *from {
+    return Err(Error::Unauthorized);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  // An approval over the client's cap waits for their confirmation, and is reviewed when it pays
+  if release_guard::hold(env, escrow_id, &escrow, milestone_index, &record, milestone_amount(&escrow, milestone_index) - record.paid)? {
+    put_escrow(env, escrow_id, &escrow);
+    return Ok(());
+  }
+  record_review(env, escrow_id, record.submitted_at, false);
+  credit_late_review(env, escrow_id, milestone_index, record.submitted_at);
+  pay_milestone(env, escrow_id, &mut escrow, &mut records, milestone_index)?;
+  // What this approval paid, kept so revoke_approval can take it back
+  let approval = Approval { approved_at: env.ledger().timestamp(), paid: records.get(milestone_index).unwrap().paid - record.paid };
+  storage::approvals(escrow_id, milestone_index).set(env, &approval);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn reject_work(env: &Env, from: &Address, escrow_id: u64, lump_sum: bool, milestone_index: u32) -> Result<(), Error> {
+  let mut escrow = load_escrow_in_mode(env, escrow_id, lump_sum)?;
+  if escrow.client != *from {
+    return Err(Error::Unauthorized);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+
+  let mut records = milestone_records(env, escrow_id);
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status != MilestoneStatus::Submitted {
+    return Err(Error::MilestoneNotCompleted);
+  }
+  record_review(env, escrow_id, record.submitted_at, false);
+  credit_late_review(env, escrow_id, milestone_index, record.submitted_at);
+  record.status = MilestoneStatus::Pending;
+  records.set(milestone_index, record);
+  // Nothing is left to confirm once the work goes back
+  storage::pending_releases(escrow_id, milestone_index).remove(env);
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+pub fn auto_release_work(env: &Env, from: &Address, escrow_id: u64, lump_sum: bool, milestone_index: u32) -> Result<(), Error> {
+  let mut escrow = load_escrow_in_mode(env, escrow_id, lump_sum)?;
+  if escrow.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  let mut records = milestone_records(env, escrow_id);
+  let record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  if record.status == MilestoneStatus::Submitted && env.ledger().timestamp() < record.submitted_at + config(env).review_period_secs {
+    return Err(Error::TooEarly);
+  }
+  record_review(env, escrow_id, record.submitted_at, true);
+
+  pay_milestone(env, escrow_id, &mut escrow, &mut records, milestone_index)?;
+  save_escrow(env, escrow_id, &escrow, &records);
+  Ok(())
+}
+
+// Whether the freelancer accepted the terms. The accepted digest is kept beside the escrow,
+// since only calls made before acceptance need it.
+pub fn is_accepted(env: &Env, escrow_id: u64) -> bool {
+  storage::accepted_terms(escrow_id).has(env)
+}
+
+pub fn get_accepted_terms(env: &Env, escrow_id: u64) -> Option<BytesN<32>> {
+  storage::accepted_terms(escrow_id).get(env)
+}
+
+pub fn milestone_records(env: &Env, escrow_id: u64) -> Vec<MilestoneRecord> {
+  storage::milestone_records(escrow_id).get(env).unwrap_or(Vec::new(env))
+}
+
+pub fn save_escrow(env: &Env, escrow_id: u64, escrow: &Escrow, records: &Vec<MilestoneRecord>) {
+  put_escrow(env, escrow_id, escrow);
+  storage::milestone_records(escrow_id).set(env, records);
+}
+
+// Every escrow write goes through here so the client's commitments move with the change in
+// what the escrow still needs and holds
+pub fn put_escrow(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  if check_reentry(env).is_err() {
+    panic_with_error!(env, Error::Reentrant);
+  }
+  let key = storage::escrows(escrow_id);
+  let previous = key.get(env);
+  adjust_commitments(env, previous.as_ref(), Some(escrow));
+  key.set(env, escrow);
+  epoch::record_write(env, escrow_id, previous.as_ref(), escrow);
+  // An escrow still being funded is short by design; once work runs, every growth of the gap
+  // is news to the parties
+  if let Some(previous) = &previous {
+    if escrow.state == EscrowState::InProgress && shortfall(escrow) > shortfall(previous) {
+      events::underfunded(env, escrow_id, escrow, shortfall(escrow));
+    }
+  }
+  let class = if is_active(escrow) { TtlClass::Hot } else { TtlClass::Cold };
+  ttl::bump(env, &key, class);
+  // The accepted digest is checked with the escrow, so it lives as long; extending it writes
+  // nothing, where keeping it inside the escrow rewrote it with every change
+  let accepted = storage::accepted_terms(escrow_id);
+  if accepted.has(env) {
+    ttl::bump(env, &accepted, class);
+  }
+  if is_active(escrow) {
+    touch_escrow(env, escrow_id);
+  } else {
+    leave_activity_bucket(env, escrow_id);
+    storage::escrow_activity(escrow_id).set(env, &env.ledger().timestamp());
+    return_bond(env, escrow_id, escrow);
+  }
+}
+
+// NotFound, or Collected when the ID has a tombstone
+pub fn missing(env: &Env, tombstone: Entry<Tombstone>) -> Error {
+  if tombstone.has(env) {
+    Error::Collected
+  } else {
+    Error::NotFound
+  }
+}
+
+// Removes an escrow and everything stored alongside it, except the records that refer to it
+// from outside (acceptance receipts, ratings), and leaves a tombstone in its place
+pub fn bury_escrow(env: &Env, escrow_id: u64, escrow: &Escrow, ending: Ending, closed_at: u64) {
+  leave_escrow_state(env, escrow_id, escrow.state);
+  leave_activity_bucket(env, escrow_id);
+  adjust_commitments(env, Some(escrow), None);
+  for milestone_index in 0..escrow.milestones.len().max(1) {
+    storage::kickoffs(escrow_id, milestone_index).remove(env);
+    storage::disputes(escrow_id, milestone_index).remove(env);
+    storage::dispute_arbitrators(escrow_id, milestone_index).remove(env);
+    storage::pending_rulings(escrow_id, milestone_index).remove(env);
+    storage::contests(escrow_id, milestone_index).remove(env);
+    storage::dispute_strikes(escrow_id, milestone_index).remove(env);
+    storage::approvals(escrow_id, milestone_index).remove(env);
+    storage::withdrawals(escrow_id, milestone_index).remove(env);
+    storage::pending_releases(escrow_id, milestone_index).remove(env);
+  }
+  storage::remove_escrow_payload(env, escrow_id);
+  storage::private_notes(escrow_id, escrow.client.clone()).remove(env);
+  storage::private_notes(escrow_id, escrow.freelancer.clone()).remove(env);
+  let tombstone = storage::escrow_tombstones(escrow_id);
+  tombstone.set(env, &Tombstone { id: escrow_id, ending, closed_at });
+  ttl::bump(env, &tombstone, TtlClass::Cold);
+}
+
+// Records activity on an active escrow and moves it into the current time bucket
+pub fn touch_escrow(env: &Env, escrow_id: u64) {
+  let now = env.ledger().timestamp();
+  let bucket = now / ACTIVITY_BUCKET_SECS;
+  let key = storage::escrow_activity(escrow_id);
+  let previous = key.get(env);
+  key.set(env, &now);
+  if previous.is_some_and(|previous| previous / ACTIVITY_BUCKET_SECS == bucket) {
+    return;
+  }
+  leave_activity_bucket(env, escrow_id);
+  if index::append_unique(env, &IndexKind::ActivityBucket(bucket), escrow_id, MAX_ACTIVITY_BUCKET_LEN) {
+    index::append_unique(env, &IndexKind::ActivityBuckets, bucket, MAX_ACTIVITY_BUCKETS);
+  }
+}
+
+// Takes an escrow out of its activity bucket, dropping the bucket once it is empty. The
+// last activity time is kept for views.
+pub fn leave_activity_bucket(env: &Env, escrow_id: u64) {
+  let Some(last_activity) = storage::escrow_activity(escrow_id).get(env) else {
+    return;
+  };
+  let bucket = last_activity / ACTIVITY_BUCKET_SECS;
+  let index = IndexKind::ActivityBucket(bucket);
+  if index::remove_value(env, &index, escrow_id) && index::len(env, &index) == 0 {
+    index::remove_value(env, &IndexKind::ActivityBuckets, bucket);
+  }
+}
+
+// Moves the client's commitments from what one version of an escrow counted to what the
+// next one counts
+pub fn adjust_commitments(env: &Env, previous: Option<&Escrow>, current: Option<&Escrow>) {
+  // A handed-off escrow moves its whole commitment to the new client, and a migrated one to
+  // the new asset
+  if let (Some(previous), Some(current)) = (previous, current) {
+    if previous.client != current.client || previous.asset != current.asset {
+      adjust_commitments(env, Some(previous), None);
+      adjust_commitments(env, None, Some(current));
+      return;
+    }
+  }
+  let (unfunded_before, held_before) = previous.map_or((0, 0), commitment);
+  let (unfunded, held) = current.map_or((0, 0), commitment);
+  if unfunded == unfunded_before && held == held_before {
+    return;
+  }
+  let escrow = current.or(previous).unwrap();
+  let key = storage::commitments(escrow.client.clone(), escrow.asset.clone());
+  let mut commitments = key.get(env).unwrap_or_default();
+  commitments.unfunded += unfunded - unfunded_before;
+  commitments.held += held - held_before;
+  adjust_obligations(env, &escrow.asset, held - held_before);
+  if commitments == Commitments::default() {
+    key.remove(env);
+  } else {
+    key.set(env, &commitments);
+  }
+}
+
+pub fn adjust_obligations(env: &Env, asset: &Address, change: i128) {
+  if change == 0 {
+    return;
+  }
+  let key = storage::obligations(asset.clone());
+  let obligations = key.get(env).unwrap_or(0) + change;
+  if obligations == 0 {
+    key.remove(env);
+  } else {
+    key.set(env, &obligations);
+  }
+}
+
+// Accrued fees, capped at what the contract holds in the asset beyond its obligations and
+// its migration pool. The fee pool alone is not enough: fees are counted when escrows close,
+// and a pool that ran ahead of the balance would otherwise be paid from live escrows' deposits.
+pub fn withdrawable_fees(env: &Env, asset: &Address) -> i128 {
+  let pool = storage::fee_pool(asset.clone()).get(env).unwrap_or(0);
+  let obligations = storage::obligations(asset.clone()).get(env).unwrap_or(0);
+  let migration_pool = storage::migration_pools(asset.clone()).get(env).unwrap_or(0);
+  let balance = token::Client::new(env, asset).balance(&env.current_contract_address());
+  pool.min(balance - obligations - migration_pool).max(0)
+}
+
+// What an escrow counts towards its client's commitments: nothing once it has ended
+pub fn commitment(escrow: &Escrow) -> (i128, i128) {
+  if !is_active(escrow) {
+    return (0, 0);
+  }
+  ((escrow.total_amount - escrow.deposited_amount).max(0), available_funds(escrow))
+}
+
+// Pays out the unpaid part of a submitted milestone from the escrow's deposits, completing
+// the escrow once every milestone is paid
+pub fn pay_milestone(env: &Env, escrow_id: u64, escrow: &mut Escrow, records: &mut Vec<MilestoneRecord>, milestone_index: u32) -> Result<(), Error> {
+  check_parent_dispute(env, escrow_id)?;
+  let mut record = records.get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    MilestoneStatus::Pending => return Err(Error::MilestoneNotCompleted),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    MilestoneStatus::Contested | MilestoneStatus::PartialOffered => return Err(Error::MilestoneContested),
+    MilestoneStatus::Submitted => {}
+  }
+  let due = milestone_amount(escrow, milestone_index) - record.paid;
+
+  // Ensure sufficient funds are available
+  if available_funds(escrow) < due {
+    return Err(Error::InsufficientFunds);
+  }
+
+  record.paid += due;
+  record.status = MilestoneStatus::Released;
+  records.set(milestone_index, record);
+  storage::pending_releases(escrow_id, milestone_index).remove(env);
+  if let Some(mut milestone) = escrow.milestones.get(milestone_index) {
+    milestone.completed = true;
+    escrow.milestones.set(milestone_index, milestone);
+  }
+  escrow.released_amount += due;
+  record_payout(env, escrow_id, escrow, LedgerKind::Release, milestone_index, due);
+  fund_subcontracts(env, escrow_id, escrow, milestone_index, milestone_amount(escrow, milestone_index))?;
+  let project = storage::projects(escrow.project_id).get(env).unwrap();
+  let deadline = effective_deadline(env, escrow_id, escrow, &project, milestone_index);
+  settle_early_bonus(env, escrow_id, escrow, milestone_index, |bonus| {
+    env.ledger().timestamp().saturating_add(bonus.min_days_early as u64 * DAY_SECS) <= deadline
+  });
+  complete_if_settled(env, escrow_id, escrow, records);
+  Ok(())
+}
+
+// Puts a settled bonus back into the escrow, to be settled again on the next approval
+pub fn unsettle_early_bonus(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone_index: u32) {
+  let key = storage::early_bonuses(escrow_id);
+  let Some(mut bonuses) = key.get(env) else {
+    return;
+  };
+  let Some(mut bonus) = bonuses.get(milestone_index) else {
+    return;
+  };
+  let party = match bonus.earned {
+    Some(true) => {
+      escrow.released_amount -= bonus.amount;
+      escrow.freelancer.clone()
+    }
+    Some(false) => {
+      escrow.refunded_amount -= bonus.amount;
+      escrow.client.clone()
+    }
+    None => return,
+  };
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Revoked, &party, &escrow.asset, Some(milestone_index), bonus.amount));
+  bonus.earned = None;
+  bonuses.set(milestone_index, bonus);
+  key.set(env, &bonuses);
+}
+
+// Pays a milestone's bonus on top of it when `earned` says so, and otherwise returns it to
+// the client
+pub fn settle_early_bonus(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone_index: u32, earned: impl FnOnce(&EarlyBonus) -> bool) {
+  let key = storage::early_bonuses(escrow_id);
+  let Some(mut bonuses) = key.get(env) else {
+    return;
+  };
+  let Some(mut bonus) = bonuses.get(milestone_index).filter(|bonus| bonus.earned.is_none()) else {
+    return;
+  };
+  let paid = earned(&bonus);
+  if paid {
+    escrow.released_amount += bonus.amount;
+    record_payout(env, escrow_id, escrow, LedgerKind::Bonus, milestone_index, bonus.amount);
+  } else {
+    escrow.refunded_amount += bonus.amount;
+    record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, Some(milestone_index), bonus.amount));
+  }
+  bonus.earned = Some(paid);
+  bonuses.set(milestone_index, bonus);
+  key.set(env, &bonuses);
+}
+
+pub fn propose_reprice(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  check_repriceable(env, escrow_id, &escrow, milestone_index, new_amount)?;
+  // A counter-proposal replaces the one it answers
+  storage::reprices(escrow_id, milestone_index).set(env, &Reprice { proposer: from, new_amount });
+  Ok(())
+}
+
+pub fn accept_reprice(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from && escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  let key = storage::reprices(escrow_id, milestone_index);
+  let reprice = key.get(env).ok_or(Error::NotFound)?;
+  if reprice.proposer == from {
+    return Err(Error::Unauthorized);
+  }
+  // The amount the caller agrees to has to be the one on the table
+  if reprice.new_amount != new_amount {
+    return Err(Error::DigestMismatch);
+  }
+  if from == escrow.client {
+    note_client_activity(env, escrow_id, &mut escrow);
+  }
+  key.remove(env);
+  apply_reprice(env, escrow_id, &mut escrow, milestone_index, new_amount)?;
+  amendment::record_change(env, escrow_id, &escrow, reprice.proposer, AmendmentChange::Reprice(milestone_index, new_amount))
+}
+
+// Reprices a milestone both parties agreed on and saves the escrow
+pub fn apply_reprice(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+  check_repriceable(env, escrow_id, escrow, milestone_index, new_amount)?;
+  let mut milestone = escrow.milestones.get(milestone_index).unwrap();
+  let old_amount = milestone.amount;
+  milestone.amount = new_amount;
+  escrow.milestones.set(milestone_index, milestone);
+  escrow.total_amount = escrow.total_amount.checked_add(new_amount - old_amount).ok_or(Error::InvalidAmount)?;
+  // A decrease frees the deposits it no longer needs for the client
+  let surplus = (old_amount - new_amount).min(escrow.deposited_amount - escrow.total_amount);
+  if surplus > 0 {
+    escrow.refunded_amount += surplus;
+    record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, Some(milestone_index), surplus));
+  }
+  start_if_funded(env, escrow_id, escrow);
+  put_escrow(env, escrow_id, escrow);
+  events::repriced(env, escrow_id, escrow, milestone_index, old_amount, new_amount);
+  Ok(())
+}
+
+pub fn get_reprice(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<Reprice> {
+  storage::reprices(escrow_id, milestone_index).get(env)
+}
+
+// Only a milestone no work has been handed in or paid for can be repriced, and not below
+// what its subcontracts were promised
+fn check_repriceable(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => return Err(Error::MilestoneDisputed),
+    MilestoneStatus::Submitted | MilestoneStatus::Contested | MilestoneStatus::PartialOffered => return Err(Error::WorkUnderReview),
+    MilestoneStatus::Pending => {}
+  }
+  if record.paid > 0 {
+    return Err(Error::AlreadyStarted);
+  }
+  if new_amount <= 0 || new_amount == milestone_amount(escrow, milestone_index) || new_amount < subcontracted(env, escrow_id, milestone_index) {
+    return Err(Error::InvalidAmount);
+  }
+  Ok(())
+}
+
+// Completes the escrow once is_complete holds. Every payout and void path ends here.
+pub fn complete_if_settled(env: &Env, escrow_id: u64, escrow: &mut Escrow, records: &Vec<MilestoneRecord>) {
+  if is_complete(escrow, records) {
+    set_escrow_state(env, escrow_id, escrow, EscrowState::Completed);
+  }
+}
+
+// An escrow is done when no milestone is left open (each one is paid out, settled by a
+// dispute or voided) and the whole agreed amount, bonuses included, has gone either to the
+// freelancer or back to the client
+pub fn is_complete(escrow: &Escrow, records: &Vec<MilestoneRecord>) -> bool {
+  let settled = records.iter().all(|record| matches!(record.status, MilestoneStatus::Released | MilestoneStatus::Resolved | MilestoneStatus::Voided));
+  settled && escrow.released_amount + escrow.refunded_amount >= escrow.total_amount
+}
+
+// Adds `amount` to the sponsor's contribution to a bounty, taking a new sponsor slot only for
+// an address that has not contributed yet (LimitReached past MAX_BOUNTY_CONTRIBUTORS)
+pub fn add_contribution(bounty: &mut Bounty, contributor: &Address, amount: i128) -> Result<(), Error> {
+  for position in 0..bounty.contributions.len() {
+    let mut contribution = bounty.contributions.get(position).unwrap();
+    if contribution.contributor == *contributor {
+      contribution.amount += amount;
+      bounty.contributions.set(position, contribution);
+      return Ok(());
+    }
+  }
+  if bounty.contributions.len() >= MAX_BOUNTY_CONTRIBUTORS {
+    return Err(Error::LimitReached);
+  }
+  bounty.contributions.push_back(Contribution { contributor: contributor.clone(), amount, refunded: 0 });
+  Ok(())
+}
+
+// Splits a bounty's refund between its sponsors in proportion to what they put in. Each
+// share is rounded down, and the dust goes to the largest contributor (the earliest one on a
+// tie), so the split is the same however often it is computed. Outside bounty mode the
+// whole refund goes to the client.
+pub fn refund_contributors(env: &Env, escrow_id: u64, escrow: &Escrow, refund: i128) {
+  let Some(mut bounty) = storage::bounties(escrow_id).get(env) else {
+    record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &escrow.client, &escrow.asset, None, refund));
+    return;
+  };
+  let contributed: i128 = bounty.contributions.iter().map(|contribution| contribution.amount).sum();
+  if refund <= 0 || contributed <= 0 {
+    return;
+  }
+
+  let mut dust = refund;
+  let mut largest = 0;
+  for position in 0..bounty.contributions.len() {
+    let mut contribution = bounty.contributions.get(position).unwrap();
+    contribution.refunded = refund * contribution.amount / contributed;
+    dust -= contribution.refunded;
+    if contribution.amount > bounty.contributions.get(largest).unwrap().amount {
+      largest = position;
+    }
+    bounty.contributions.set(position, contribution);
+  }
+  let mut contribution = bounty.contributions.get(largest).unwrap();
+  contribution.refunded += dust;
+  bounty.contributions.set(largest, contribution);
+
+  for contribution in bounty.contributions.iter() {
+    events::refund(env, escrow_id, escrow, &contribution.contributor, contribution.refunded);
+    record_line(env, escrow_id, ledger_line(env, LedgerKind::Refund, &contribution.contributor, &escrow.asset, None, contribution.refunded));
+  }
+  storage::bounties(escrow_id).set(env, &bounty);
+}
+
+// A ledger line with no fee, numbered when it is recorded
+pub fn ledger_line(env: &Env, kind: LedgerKind, party: &Address, asset: &Address, milestone_index: Option<u32>, amount: i128) -> LedgerLine {
+  LedgerLine {
+    line: 0,
+    kind,
+    party: party.clone(),
+    asset: asset.clone(),
+    milestone_index,
+    gross: amount,
+    fee: 0,
+    net: amount,
+    memo: None,
+    at: env.ledger().timestamp(),
+  }
+}
+
+// Appends a line to the escrow's ledger. Lines for nothing are left out, except the Closed
+// line, which every ended escrow gets, and the Recovered line, which records an address.
+pub fn record_line(env: &Env, escrow_id: u64, mut line: LedgerLine) {
+  if line.gross == 0 && line.kind != LedgerKind::Closed && line.kind != LedgerKind::Recovered {
+    return;
+  }
+  let key = storage::ledger_lines(escrow_id);
+  let mut lines = key.get(env).unwrap_or(Vec::new(env));
+  line.line = lines.len() + 1;
+  lines.push_back(line);
+  key.set(env, &lines);
+}
+
+// A payment to the freelancer, with its share of the escrow's fee
+pub fn record_payout(env: &Env, escrow_id: u64, escrow: &Escrow, kind: LedgerKind, milestone_index: u32, gross: i128) {
+  let terms = storage::fee_terms(escrow_id).get(env).unwrap_or_default();
+  let fee = bps_of(gross, terms.bps).unwrap_or(0);
+  let line = ledger_line(env, kind, &escrow.freelancer, &escrow.asset, Some(milestone_index), gross);
+  record_line(env, escrow_id, LedgerLine { fee, net: gross - fee, ..line });
+}
+
+// Deposits not yet released to the freelancer or returned to the client
+pub fn available_funds(escrow: &Escrow) -> i128 {
+  escrow.deposited_amount - escrow.released_amount - escrow.refunded_amount
+}
+
+// What the unsettled milestones still need beyond available_funds. Every payout and refund
+// settles part of an obligation or returns a surplus, so this is the total not yet deposited.
+pub fn shortfall(escrow: &Escrow) -> i128 {
+  (escrow.total_amount - escrow.deposited_amount).max(0)
+}
+
+pub fn get_shortfall(env: &Env, escrow_id: u64) -> Result<i128, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  Ok(shortfall(&escrow))
+}
+
+// Adds one review to the escrow's tally. An auto-release counts as the full review period.
+pub fn record_review(env: &Env, escrow_id: u64, submitted_at: u64, auto_release: bool) {
+  let key = storage::review_tallies(escrow_id);
+  let mut tally = key.get(env).unwrap_or_default();
+  let secs = if auto_release {
+    tally.auto_releases += 1;
+    config(env).review_period_secs
+  } else {
+    env.ledger().timestamp().saturating_sub(submitted_at)
+  };
+  tally.reviews += 1;
+  tally.total_secs += secs;
+  tally.worst_secs = tally.worst_secs.max(secs);
+  key.set(env, &tally);
+}
+
+// Folds a finished escrow's review tally into the client's stats. Only completed escrows
+// count; the tally of a refunded escrow is dropped.
+pub fn settle_review_tally(env: &Env, escrow_id: u64, escrow: &Escrow, state: EscrowState) {
+  let key = storage::review_tallies(escrow_id);
+  let tally = key.get(env).unwrap_or_default();
+  key.remove(env);
+  if state != EscrowState::Completed {
+    return;
+  }
+
+  let stats_key = storage::user_stats(escrow.client.clone());
+  let mut stats = stats_key.get(env).unwrap_or_default();
+  stats.completed_as_client += 1;
+  stats.reviews.reviews += tally.reviews;
+  stats.reviews.total_secs += tally.total_secs;
+  stats.reviews.worst_secs = stats.reviews.worst_secs.max(tally.worst_secs);
+  stats.reviews.auto_releases += tally.auto_releases;
+  stats_key.set(env, &stats);
+}
+
+// Every escrow state change goes through here so the per-state counters and indexes
+// stay in step with the stored state
+pub fn set_escrow_state(env: &Env, escrow_id: u64, escrow: &mut Escrow, state: EscrowState) {
+  if escrow.state == state {
+    return;
+  }
+  leave_escrow_state(env, escrow_id, escrow.state);
+  enter_escrow_state(env, escrow_id, state);
+  escrow.state = state;
+  if !is_active(escrow) {
+    report_closing(env, escrow_id, escrow);
+    close_project(env, escrow);
+    settle_review_tally(env, escrow_id, escrow, state);
+    release_exposure(env, escrow_id, escrow);
+    update_freelancer_stats(env, &escrow.freelancer, |stats| stats.active_escrows = stats.active_escrows.saturating_sub(1));
+    // Escrows that are no longer active leave the client/freelancer pair index
+    index::remove_value(env, &IndexKind::PairEscrows(escrow.client.clone(), escrow.freelancer.clone()), escrow_id);
+  }
+}
+
+// The project follows its escrow to the end: completed with it, or cancelled when it is
+// refunded
+fn close_project(env: &Env, escrow: &Escrow) {
+  let key = storage::projects(escrow.project_id);
+  let Some(mut project) = key.get(env) else {
+    return;
+  };
+  project.status = if escrow.state == EscrowState::Completed { ProjectStatus::Completed } else { ProjectStatus::Cancelled };
+  key.set(env, &project);
+}
+
+// Charges the escrow's fee on what it released, adds it to the platform stats and emits the
+// closing report
+pub fn report_closing(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  let terms = storage::fee_terms(escrow_id).get(env).unwrap_or_default();
+  let report = ClosingReport {
+    state: escrow.state,
+    released: escrow.released_amount,
+    refunded: escrow.refunded_amount,
+    fee: bps_of(escrow.released_amount, terms.bps).unwrap_or(0),
+    public_goods: terms.public_goods,
+  };
+
+  let mut stats = storage::platform_stats().get(env).unwrap_or_default();
+  stats.volume += report.released;
+  stats.fees += report.fee;
+  if report.public_goods {
+    stats.public_goods_volume += report.released;
+  }
+  storage::platform_stats().set(env, &stats);
+  if report.fee > 0 {
+    let pool_key = storage::fee_pool(escrow.asset.clone());
+    let pool = pool_key.get(env).unwrap_or(0);
+    pool_key.set(env, &(pool + report.fee));
+  }
+  let closed = ledger_line(env, LedgerKind::Closed, &escrow.freelancer, &escrow.asset, None, report.released);
+  record_line(env, escrow_id, LedgerLine { fee: report.fee, net: report.released - report.fee, ..closed });
+  events::closed(env, escrow_id, escrow, &report);
+}
+
+pub fn enter_escrow_state(env: &Env, escrow_id: u64, state: EscrowState) {
+  let count_key = storage::escrow_state_count(state);
+  let count = count_key.get(env).unwrap_or(0);
+  count_key.set(env, &(count + 1));
+  index::append_unique(env, &IndexKind::EscrowsByState(state), escrow_id, MAX_STATE_INDEX_LEN);
+}
+
+pub fn leave_escrow_state(env: &Env, escrow_id: u64, state: EscrowState) {
+  let count_key = storage::escrow_state_count(state);
+  let count = count_key.get(env).unwrap_or(0);
+  count_key.set(env, &count.saturating_sub(1));
+  index::remove_value(env, &IndexKind::EscrowsByState(state), escrow_id);
+}
+
+// Records that the client called in on the escrow. A running bundle stops here: its
+// milestones stay submitted and go through normal per-milestone review from now on.
+pub fn note_client_activity(env: &Env, escrow_id: u64, escrow: &mut Escrow) {
+  escrow.client_active_at = env.ledger().timestamp();
+  storage::bundles(escrow_id).remove(env);
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::{ Address as _, Ledger as _ };
+  use soroban_sdk::Address;
+
+  use crate::escrow::{ adjust_obligations, check_reentry, client_delay, credit_client_delay, missing, time_status };
+  use crate::test::harness;
+  use crate::{ storage, Ending, Error, Tombstone };
+
+  #[test]
+  fn time_status_counts_down_to_the_deadline_and_then_up_past_it() {
+    let (env, contract) = harness();
+    env.ledger().with_mut(|ledger| {
+      ledger.timestamp = 1_000;
+      ledger.sequence_number = 100;
+    });
+    env.as_contract(&contract, || {
+      let ahead = time_status(&env, 1_050);
+      assert_eq!((ahead.remaining_secs, ahead.overdue_secs, ahead.overdue, ahead.deadline_ledger), (50, 0, false, 110));
+      let past = time_status(&env, 950);
+      assert_eq!((past.remaining_secs, past.overdue_secs, past.overdue, past.deadline_ledger), (0, 50, true, 90));
+      let none = time_status(&env, 0);
+      assert_eq!((none.remaining_secs, none.overdue, none.deadline_ledger), (0, false, 0));
+    });
+  }
+
+  #[test]
+  fn a_missing_id_with_a_tombstone_reads_as_collected() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      assert_eq!(missing(&env, storage::escrow_tombstones(1)), Error::NotFound);
+      storage::escrow_tombstones(1).set(&env, &Tombstone { id: 1, ending: Ending::EscrowCompleted, closed_at: 0 });
+      assert_eq!(missing(&env, storage::escrow_tombstones(1)), Error::Collected);
+    });
+  }
+
+  #[test]
+  fn client_delay_adds_up_over_the_credited_milestones_only() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      credit_client_delay(&env, 1, 1..3, 60);
+      credit_client_delay(&env, 1, 2..3, 30);
+      credit_client_delay(&env, 1, 0..3, 0);
+      assert_eq!([client_delay(&env, 1, 0), client_delay(&env, 1, 1), client_delay(&env, 1, 2)], [0, 60, 90]);
+      assert_eq!(client_delay(&env, 2, 2), 0);
+    });
+  }
+
+  #[test]
+  fn obligations_back_to_zero_leave_no_entry() {
+    let (env, contract) = harness();
+    let asset = Address::generate(&env);
+    env.as_contract(&contract, || {
+      adjust_obligations(&env, &asset, 100);
+      adjust_obligations(&env, &asset, -40);
+      assert_eq!(storage::obligations(asset.clone()).get(&env), Some(60));
+      adjust_obligations(&env, &asset, -60);
+      assert!(!storage::obligations(asset).has(&env));
+    });
+  }
+
+  #[test]
+  fn calls_are_refused_while_a_transfer_is_in_flight() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      assert_eq!(check_reentry(&env), Ok(()));
+      storage::transfer_guard().set(&env, &true);
+      assert_eq!(check_reentry(&env), Err(Error::Reentrant));
+    });
+  }
+}
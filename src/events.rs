@@ -0,0 +1,149 @@
+// Every event the contract publishes goes through here. The topic layout is part of the
+// contract interface, so wallets can follow everything about one address by matching a
+// fixed topic position instead of decoding payloads:
+//
+//   escrow events      (name, client, freelancer, id)   id is the escrow ID, or the project
+//                                                        ID before an escrow exists
+//   observer events    (observed, observer, escrow ID)  one per acknowledged observer for
+//                                                        each escrow event, naming it
+//   arbitrator events  (name, arbitrator, escrow ID)
+//   platform events    (name, asset)
+//   admin events       (name, action ID)
+
+use soroban_sdk::{ symbol_short, Address, BytesN, Env, IntoVal, String, Symbol, Val };
+
+use crate::observer::observing;
+use crate::{ AdminAction, ClosingReport, EpochActivity, Escrow, FeeTerms, Invariant, PendingRelease };
+
+fn escrow_event(env: &Env, name: Symbol, escrow_id: u64, escrow: &Escrow, data: impl IntoVal<Env, Val>) {
+  env.events().publish((name.clone(), escrow.client.clone(), escrow.freelancer.clone(), escrow_id), data);
+  for observer in observing(env, escrow_id) {
+    env.events().publish((symbol_short!("observed"), observer, escrow_id), name.clone());
+  }
+}
+
+pub fn opened(env: &Env, escrow_id: u64, escrow: &Escrow, terms: &FeeTerms) {
+  escrow_event(env, symbol_short!("opened"), escrow_id, escrow, (terms.bps, terms.public_goods));
+}
+
+pub fn deposit(env: &Env, escrow_id: u64, escrow: &Escrow, depositor: &Address, amount: i128, memo: &Option<BytesN<32>>) {
+  escrow_event(env, symbol_short!("deposit"), escrow_id, escrow, (depositor.clone(), amount, memo.clone()));
+}
+
+// A bounty sponsor's share of a refund
+pub fn refund(env: &Env, escrow_id: u64, escrow: &Escrow, contributor: &Address, amount: i128) {
+  escrow_event(env, symbol_short!("refund"), escrow_id, escrow, (contributor.clone(), amount));
+}
+
+pub fn resolved(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, freelancer_amount: i128) {
+  escrow_event(env, symbol_short!("resolved"), escrow_id, escrow, (milestone_index, freelancer_amount));
+}
+
+pub fn closed(env: &Env, escrow_id: u64, escrow: &Escrow, report: &ClosingReport) {
+  escrow_event(env, symbol_short!("closed"), escrow_id, escrow, report.clone());
+}
+
+pub fn repriced(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, old_amount: i128, new_amount: i128) {
+  escrow_event(env, symbol_short!("reprice"), escrow_id, escrow, (milestone_index, old_amount, new_amount));
+}
+
+pub fn milestone_added(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, amount: i128) {
+  escrow_event(env, symbol_short!("ms_added"), escrow_id, escrow, (milestone_index, amount));
+}
+
+// A running escrow's remaining milestones need `shortfall` more than it holds, more than before
+pub fn underfunded(env: &Env, escrow_id: u64, escrow: &Escrow, shortfall: i128) {
+  escrow_event(env, symbol_short!("underfund"), escrow_id, escrow, shortfall);
+}
+
+// The digest of an epoch the escrow has left: data is (epoch, deposits, releases, messages,
+// state changes)
+pub fn epoch_rollover(env: &Env, escrow_id: u64, escrow: &Escrow, activity: &EpochActivity) {
+  let counts = (activity.epoch, activity.deposits, activity.releases, activity.messages, activity.state_changes);
+  escrow_event(env, symbol_short!("rollover"), escrow_id, escrow, counts);
+}
+
+// The terms changed under an accepted amendment; data is its ID and the new terms digest
+pub fn amended(env: &Env, escrow_id: u64, escrow: &Escrow, amendment_id: u32, terms_digest: &BytesN<32>) {
+  escrow_event(env, symbol_short!("amended"), escrow_id, escrow, (amendment_id, terms_digest.clone()));
+}
+
+// The freelancer drew the payout an approval made, closing its revoke window
+pub fn withdrawn(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, amount: i128) {
+  escrow_event(env, symbol_short!("withdrawn"), escrow_id, escrow, (milestone_index, amount));
+}
+
+// An approval over the client's cap waits for their confirmation
+pub fn release_held(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, pending: &PendingRelease) {
+  escrow_event(env, symbol_short!("held"), escrow_id, escrow, (milestone_index, pending.amount, pending.confirmable_at));
+}
+
+// The client cancelled under the escrow's schedule, paying `fee` at the `bps` tier
+pub fn kill_fee(env: &Env, escrow_id: u64, escrow: &Escrow, bps: u32, fee: i128) {
+  escrow_event(env, symbol_short!("kill_fee"), escrow_id, escrow, (bps, fee));
+}
+
+// An observer joined the escrow once acknowledged, or left it when the client removed it
+pub fn observer(env: &Env, escrow_id: u64, escrow: &Escrow, observer: &Address, active: bool) {
+  escrow_event(env, symbol_short!("observer"), escrow_id, escrow, (observer.clone(), active));
+}
+
+// Both parties agreed to exchange contact details
+pub fn contact(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  escrow_event(env, symbol_short!("contact"), escrow_id, escrow, ());
+}
+
+// The escrow moved from `old` to the asset now on it, carrying `held` over
+pub fn asset_migrated(env: &Env, escrow_id: u64, escrow: &Escrow, old: &Address, held: i128) {
+  escrow_event(env, symbol_short!("migrated"), escrow_id, escrow, (old.clone(), escrow.asset.clone(), held));
+}
+
+// A key recovery moved the escrow from the `old` freelancer address to the one now on it
+pub fn recovered(env: &Env, escrow_id: u64, escrow: &Escrow, old: &Address) {
+  escrow_event(env, symbol_short!("recovered"), escrow_id, escrow, old.clone());
+}
+
+// self_check found the escrow breaking an invariant
+pub fn check_failed(env: &Env, escrow_id: u64, escrow: &Escrow, invariant: Invariant) {
+  escrow_event(env, symbol_short!("chk_fail"), escrow_id, escrow, invariant);
+}
+
+// Raised before the escrow exists, so the ID is the project's
+pub fn wip_warning(env: &Env, client: &Address, freelancer: &Address, project_id: u64, active: u32, max_active: u32) {
+  env.events().publish((symbol_short!("wip_warn"), client.clone(), freelancer.clone(), project_id), (active, max_active));
+}
+
+// Raised before any escrow exists, so the ID is the project's
+pub fn invited(env: &Env, client: &Address, freelancer: &Address, project_id: u64, message: &String) {
+  env.events().publish((symbol_short!("invited"), client.clone(), freelancer.clone(), project_id), message.clone());
+}
+
+pub fn assigned(env: &Env, arbitrator: &Address, escrow_id: u64, milestone_index: u32) {
+  env.events().publish((symbol_short!("assigned"), arbitrator.clone(), escrow_id), milestone_index);
+}
+
+// `pending` while the ruling waits for a co-signature
+pub fn ruled(env: &Env, arbitrator: &Address, escrow_id: u64, milestone_index: u32, freelancer_amount: i128, pending: bool) {
+  env.events().publish((symbol_short!("ruled"), arbitrator.clone(), escrow_id), (milestone_index, freelancer_amount, pending));
+}
+
+pub fn fees_withdrawn(env: &Env, asset: &Address, to: &Address, amount: i128) {
+  env.events().publish((symbol_short!("fees_out"), asset.clone()), (to.clone(), amount));
+}
+
+// self_check found the asset's books breaking an invariant
+pub fn asset_check_failed(env: &Env, asset: &Address, invariant: Invariant) {
+  env.events().publish((symbol_short!("chk_fail"), asset.clone()), invariant);
+}
+
+pub fn action_queued(env: &Env, action_id: u64, action: &AdminAction, execute_after: u64) {
+  env.events().publish((symbol_short!("queued"), action_id), (action.clone(), execute_after));
+}
+
+pub fn action_executed(env: &Env, action_id: u64) {
+  env.events().publish((symbol_short!("executed"), action_id), ());
+}
+
+pub fn action_cancelled(env: &Env, action_id: u64) {
+  env.events().publish((symbol_short!("cancelled"), action_id), ());
+}
@@ -1,14 +1,179 @@
 #![no_std]
 
-use soroban_sdk::{ contract, contractimpl, contracttype, Address, Env, Vec, String };
+use page::{ AddressPage, AmendmentPage, DepositPage, IdPage, LedgerPage, ObserverPage, ProjectPage, ProposalPage, RatingPage, RegistryPage, TemplatePage };
+use ttl::{ TtlClass, TtlPolicy };
+use index::{ IndexKind, IndexReport };
+use soroban_sdk::{ contract, contracterror, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Map, String, Symbol, Vec };
 
+mod admin;
+mod amendment;
+mod dispute;
+mod epoch;
+mod escrow;
+mod events;
+mod index;
+mod invite;
+mod legacy;
+mod math;
+mod migration;
+mod milestone_schedule;
+mod observer;
+mod page;
+mod progress;
+mod project;
+mod rating;
+mod recovery;
+mod release_guard;
+mod risk;
+mod service;
+mod snapshot;
+mod storage;
+mod subcontract;
+mod template;
+mod test;
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+mod timelock;
+mod ttl;
+mod user;
+
+// Version of the call interface. Bumped whenever a call's arguments or results change shape;
+// releases before describe() existed count as version 1.
+const INTERFACE_VERSION: u32 = 35;
+// Language whose label listing getters return, when the entry has one
+const DEFAULT_LANGUAGE: Symbol = symbol_short!("en");
+// Upper bound on the number of languages labelled per registry entry
+const MAX_LABEL_LANGUAGES: u32 = 10;
+// Upper bound on the number of milestone templates per category
+const MAX_TEMPLATES_PER_CATEGORY: u32 = 5;
+// Upper bound on the number of entries returned by a single listing call
+const MAX_LIST_LIMIT: u32 = 50;
+const DAY_SECS: u64 = 86_400;
+// Milestone shares are expressed in basis points of the project budget
+const BPS_DENOMINATOR: u32 = 10_000;
+// Width of the time buckets escrows are indexed by for stale-engagement listings
+const ACTIVITY_BUCKET_SECS: u64 = 7 * DAY_SECS;
+// Width of the weekly epochs each escrow's activity digest covers
+const EPOCH_SECS: u64 = 7 * DAY_SECS;
+// Average ledger close time, used to estimate the ledger a deadline falls in
+const LEDGER_SECS: u64 = 5;
+// Upper bound on the number of working languages a user declares
+const MAX_USER_LANGUAGES: u32 = 5;
+// Real-world UTC offsets run from UTC-12:00 to UTC+14:00
+const MIN_TZ_OFFSET_MINS: i32 = -12 * 60;
+const MAX_TZ_OFFSET_MINS: i32 = 14 * 60;
+// Upper bound on the number of observers granted on one escrow
+const MAX_OBSERVERS: u32 = 5;
+// Upper bound on the number of amendments recorded per escrow, single changes included
+const MAX_AMENDMENTS: u32 = 50;
+// Upper bound on the number of changes one amendment bundles
+const MAX_AMENDMENT_CHANGES: u32 = 10;
+// Wait before the client can confirm an approval the release guard held
+const RELEASE_CONFIRM_SECS: u64 = 60 * 60;
+// Upper bound on the number of kickoff checklist items per escrow
+const MAX_CHECKLIST_ITEMS: u32 = 5;
+// Upper bound on the number of outcome tags one rating carries
+const MAX_RATING_TAGS: u32 = 5;
+// Ratings shown on a profile
+const PROFILE_RATINGS: u32 = 3;
+// Upper bound on the number of contributors to one bounty
+const MAX_BOUNTY_CONTRIBUTORS: u32 = 20;
+// Upper bound on the number of deposits recorded per escrow
+const MAX_DEPOSITS_PER_ESCROW: u32 = 100;
+// Upper bound on the number of arbitrators on the roster random assignment draws from
+const MAX_ARBITRATORS: u32 = 100;
+// How long an invite code resolves after it is created
+const INVITE_SECS: u64 = 7 * DAY_SECS;
+// Invite codes drawn before giving up when each one is already in use
+const MAX_INVITE_DRAWS: u32 = 5;
+// Upper bound on the number of freelancers one invite_many call takes
+const MAX_INVITES_PER_CALL: u32 = 10;
+// Upper bound on the number of freelancers invited to one project
+const MAX_INVITES_PER_PROJECT: u32 = 50;
+// Largest share of the undisbursed funds a cancellation schedule may pay the freelancer
+const MAX_CANCELLATION_FEE_BPS: u32 = 5_000;
+// Upper bound on the number of milestones a project is posted with or add_milestone grows an
+// escrow to
+const MAX_MILESTONES: u32 = 20;
+// Upper bound on the hours a freelancer estimates for one milestone
+const MAX_EFFORT_HOURS: u32 = 10_000;
+// Shortest gap between two progress reports on the same milestone
+const PROGRESS_REPORT_SECS: u64 = DAY_SECS;
+// A pending milestone with no progress report for this long shows up in the client's action items
+const PROGRESS_STALE_SECS: u64 = 7 * DAY_SECS;
+// Delay before a replacement recovery address applies, and notice the admin gives before
+// moving an escrow to a recovered address over the client's head
+const RECOVERY_DELAY_SECS: u64 = 30 * DAY_SECS;
+// Upper bound on the number of escrows one self_check call samples
+const MAX_SELF_CHECK_SAMPLE: u32 = 20;
+// Reference rates carry 7 decimals, like Stellar amounts
+const RATE_SCALE: i128 = 10_000_000;
+
+// Every fallible call in the contract fails with one of these. The codes are part of the
+// contract interface: new failures get the next free code, existing codes never move.
+#[contracterror]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+  Unauthorized = 1, // The caller does not hold the role the call requires
+  NotFound = 2, // No project, escrow or registry entry with this ID
+  ProjectNotOpen = 3,
+  EscrowNotActive = 4, // The escrow was completed or refunded
+  InvalidMilestoneIndex = 5,
+  MilestoneNotCompleted = 6, // The milestone has not been submitted for review
+  AlreadyReleased = 7, // The milestone was already paid out
+  InsufficientFunds = 8, // A payout needs more deposited than the escrow holds; get_shortfall says how much more the escrow needs
+  InvalidAmount = 9, // Also a deposit under the asset's get_min_deposit_increment
+  RefundNotAllowed = 10,
+  LimitReached = 11, // A bounded list (e.g. label languages) is full
+  TooEarly = 12, // A clock (review period, bundle grace) has not run out yet, or the milestone waits for earlier ones
+  ClientActive = 13, // The client has interacted with the escrow since the project deadline
+  DeliverableMismatch = 14, // A bundle needs exactly one deliverable per unpaid milestone
+  InvalidConfig = 15,
+  ModeMismatch = 16, // Milestone call on a lump-sum escrow, or the reverse
+  NotFullyFunded = 17,
+  InvalidShares = 18, // Milestone shares must be non-empty and sum to 10000 basis points
+  AlreadyAccepted = 19,
+  BondNotPosted = 20, // The escrow requires a bond the freelancer has not posted yet
+  WipLimitReached = 21, // The client's hard limit on active escrows with this freelancer
+  AlreadyStarted = 22, // The milestone's kickoff has already been paid
+  MilestoneDisputed = 23, // The milestone is in dispute or was settled by one
+  NoDispute = 24,
+  MilestoneContested = 25, // The client flagged the submission as out of scope
+  ContestLapsed = 26, // The freelancer's window to answer an out-of-scope flag has run out
+  NoPartialOffer = 27,
+  NotApproved = 28, // The milestone has not been paid out on approval yet
+  EscrowNotCompleted = 29,
+  ExceedsSurplus = 30, // More than get_withdrawable_fees allows; nothing was withdrawn
+  InvalidRating = 31, // Ratings are 1 to 5 stars
+  AlreadyRated = 32,
+  HasDeposits = 33, // Bounty mode has to be chosen before the first deposit
+  WorkUnderReview = 34, // A milestone is submitted, contested or disputed
+  NotBounty = 35,
+  Collected = 36, // The entry was voided or collected; its tombstone is still readable
+  EscrowActive = 37, // Only completed or refunded escrows can be collected
+  Reentrant = 38, // Called back while one of the contract's token transfers was in flight
+  UnknownTag = 39, // Outcome tag not in the OutcomeTag registry
+  DigestMismatch = 40, // The terms changed since the digest was taken
+  ChecklistPending = 41, // Milestone 0 waits for the kickoff checklist
+  InvalidOwner = 42, // A project can't pass to its current owner or to its escrow's freelancer, and an escrow's parties can't observe it or recover into it
+  NotAcknowledged = 43, // The escrow's freelancer has not acknowledged the project transfer, or not accepted the engagement yet
+  StrikeUsed = 44, // Each party can strike one arbitrator per dispute
+  RulingPending = 45, // The assigned arbitrator has already ruled
+  RevokeWindowClosed = 46, // The approval can no longer be taken back
+  InvalidSchedule = 47, // Cancellation fees must not fall from one tier to the next or pass the cap; an added milestone can't fall due before its dependencies; posted milestones can't repeat or fall due out of order (validate_milestones says which)
+  ReportTooSoon = 48, // The milestone's last progress report is less than PROGRESS_REPORT_SECS old
+  Deprecated = 49, // The admin turned the version 1 entry points off; call the current ones
+  ReleaseTooEarly = 50, // A held approval can be confirmed RELEASE_CONFIRM_SECS after it was held
+}
+
+// Profile data is not stored yet, so this is not a contract type
 #[derive(Clone)]
-#[contracttype]
 pub struct User {
- 
+
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[contracttype]
 pub enum UserType {
   Client,
@@ -23,13 +188,88 @@ pub struct Project {
   title: String,
   description: String,
   category: String,
-  budget: u64,
+  budget: i128,
+  asset: Address, // Token the budget is denominated in
   deadline: u64, // Unix timestamp for deadline
   milestones: Vec<Milestone>,
   status: ProjectStatus, // Open, InProgress, Completed, Cancelled
 }
 
-#[derive(Clone)]
+// Ownership handoff offered by a project's client and waiting for the new owner
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ProjectTransfer {
+  new_owner: Address,
+  freelancer_ack: bool, // Needed when the project has an active escrow
+}
+
+// What project listings show of each project. get_project has the rest.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ProjectSummary {
+  id: u64,
+  title: String,
+  client: Address,
+  budget: i128,
+  asset: Address,
+  status: ProjectStatus,
+  deadline: u64,
+  category: String,
+}
+
+// An invite code handed to a freelancer, standing in for the escrow ID
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Invite {
+  escrow_id: u64,
+  expires_at: u64,
+}
+
+// Everything a freelancer needs to review and accept an engagement, resolved from an invite
+// code. The digest is what accept_engagement and accept_invite expect.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct EngagementOffer {
+  escrow_id: u64,
+  project_id: u64,
+  client: Address,
+  freelancer: Address,
+  asset: Address,
+  total_amount: i128,
+  lump_sum: bool,
+  milestones: Vec<Milestone>, // Amounts and deadlines; empty for a lump sum
+  terms_digest: BytesN<32>,
+  accept_by: u64, // When the code expires
+}
+
+// A child escrow a freelancer opened to subcontract part of one of their milestones. The
+// freelancer is the child's client, and the child is funded out of the parent milestone's
+// payout when it is released.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Subcontract {
+  parent_id: u64, // Escrow the work was subcontracted from
+  milestone_index: u32, // Parent milestone whose payout funds the child
+  amount: i128, // Child escrow total
+  credited: i128, // Credited to the child out of the parent payout so far
+}
+
+// A fixed-price service a freelancer publishes, e.g. "logo design, 3 days, 100 USDC".
+// Buying one opens a funded lump-sum escrow; later edits only reach later purchases.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ServiceListing {
+  freelancer: Address,
+  title: String,
+  description: String,
+  category: String, // Fixed once created, as the listing is indexed under it
+  price: i128,
+  asset: Address, // Token the price is denominated in
+  delivery_days: u32, // The purchase's deadline is this many days after it is bought
+  active: bool, // Cleared by deactivate_service; inactive listings can't be bought
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[contracttype]
 pub enum ProjectStatus {
   Open,
@@ -38,22 +278,236 @@ pub enum ProjectStatus {
   Cancelled,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 #[contracttype]
 pub struct Milestone {
+  description: String,
+  amount: i128,
+  completed: bool, // Set by the contract once the milestone is paid out
+  deadline: u64, // Unix timestamp for deadline (optional)
+}
+
+// A milestone as post_project_v1 takes it, with the version 1 amount type
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct LegacyMilestone {
   description: String,
   amount: u64,
   completed: bool,
-  deadline: u64, // Unix timestamp for deadline (optional)
+  deadline: u64,
 }
 
-#[derive(Clone)]
+// A freelancer's bid on an open project
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Proposal {
+  id: u64,
+  project_id: u64,
+  freelancer: Address,
+  amount: i128, // Price asked for the whole project
+  milestones: Vec<Milestone>, // Proposed schedule summing to the amount (empty = the project's, scaled to it)
+  cover: BytesN<32>, // Hash of the off-chain cover letter
+  submitted_at: u64,
+}
+
+// Where an escrow's milestone schedule came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum TermsSource {
+  Project, // Opened with initiate_escrow on the project's own schedule
+  Proposal(u64), // Opened by accepting this proposal, on its schedule and amount
+  Service(u64), // Opened by buying this service listing, at the price it had then
+}
+
+// A proposal as listed to the client. With enrichment the bidder's current load is read
+// from their stats at listing time; without it those fields stay zero.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ProposalListing {
+  proposal: Proposal,
+  enriched: bool,
+  active_escrows: u32, // Escrows the bidder is currently working as the freelancer
+  available: bool, // The bidder has not marked themselves unavailable
+  avg_response_secs: u64, // Average time the bidder took to accept an engagement
+  language_match: bool, // The bidder declared the project's working language (or it has none)
+}
+
+// What invite_many did for one address of the shortlist. Only Invited stores anything; the
+// other outcomes skip the address and leave the rest of the list to go ahead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum InviteOutcome {
+  Invited,
+  AlreadyInvited, // Invited before, by this call or an earlier one
+  Client, // The project's own client
+  Deactivated, // The admin deactivated the account
+  Unavailable, // The freelancer marked themselves unavailable
+  ListFull, // The project already has MAX_INVITES_PER_PROJECT invitations
+}
+
+// Working language and timezone a client expects on a project. Informational only.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[contracttype]
+pub struct Locale {
+  language: Option<Symbol>, // e.g. "en", "es"
+  tz_offset_mins: Option<i32>, // Client's offset from UTC in minutes
+}
+
+// Workload of one freelancer, kept up to date as their escrows open, get accepted and close
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct FreelancerStats {
+  active_escrows: u32,
+  responses: u32, // Engagements accepted
+  response_secs: u64, // Sum of the time from an escrow opening to its acceptance
+}
+
+// What validate_milestones can find wrong with a milestone schedule
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum IssueCode {
+  TooMany, // More than MAX_MILESTONES; reported once, at the first one over
+  NegativeAmount,
+  Completed, // Sent in as completed; only a payout completes a milestone
+  Duplicate, // Same description, amount and deadline as an earlier milestone
+  DeadlineOrder, // Due before an earlier milestone with a deadline
+  PastProjectDeadline, // Due after the project's deadline
+  BudgetMismatch, // The amounts don't add up to the budget; reported once, at the last milestone
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ValidationIssue {
+  code: IssueCode,
+  index: u32, // The milestone at fault
+  other: u32, // The earlier milestone it clashes with for Duplicate and DeadlineOrder, else `index`
+}
+
+// Every issue with a milestone schedule, in the order the checks find them
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ValidationReport {
+  valid: bool,
+  issues: Vec<ValidationIssue>,
+}
+
+// A milestone given as a share of the project budget rather than an absolute amount
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct MilestoneShare {
+  description: String,
+  bps: u32, // Basis points of the budget; all shares of a project sum to 10000
+  deadline: u64,
+}
+
+// A milestone layout the admin curates for a Category registry entry. Its shares carry no
+// deadlines; each milestone falls due by the project deadline.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct CategoryTemplate {
+  id: u32,
+  category_id: u32,
+  name: String,
+  shares: Vec<MilestoneShare>,
+  version: u32, // 1 when added, one more on every edit
+}
+
+// The template, and its version, a project's milestones were expanded from
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct TemplateRef {
+  template_id: u32,
+  version: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum MilestoneStatus {
+  Pending,
+  Submitted, // Awaiting the client's review
+  Released, // Paid out
+  Disputed, // Frozen until the admin resolves the dispute
+  Resolved, // Settled by a dispute resolution
+  Contested, // Flagged out of scope by the client; the review clock is paused
+  PartialOffered, // Contested, with a partial payout offered by the client
+  Voided, // Given up by the freelancer; what it had not paid went back to the client
+}
+
+// Review state of one escrow milestone, kept next to the escrow rather than in the
+// posted milestone terms
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct MilestoneRecord {
+  status: MilestoneStatus,
+  deliverable: Option<BytesN<32>>, // Hash of the latest submission
+  submitted_at: u64, // Ledger timestamp of the latest submission (0 = never submitted)
+  paid: i128, // Amount paid out for this milestone
+}
+
+// Every unpaid milestone submitted at once after the client went silent past the project
+// deadline, released together when the review clock runs out
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Bundle {
+  started_at: u64,
+  release_at: u64,
+}
+
+// A client's rating of the freelancer on a completed escrow, one per escrow
+#[derive(Clone, Debug, PartialEq)]
 #[contracttype]
 pub struct Rating {
+  escrow_id: u64,
   from: Address, // rater (client)
   to: Address, // freelancer being rated
-  rating: u8, // 1-5 star rating
+  rating: u32, // 1-5 star rating
   comment: String, // Optional comment
+  rated_at: u64,
+}
+
+// Every rating a freelancer received, summed up
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct RatingSummary {
+  count: u32,
+  stars: u32, // Sum of the stars given
+}
+
+// Everything a freelancer's public profile shows, in one read. A deactivated account shows
+// only its address and the flag.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Profile {
+  user: Address,
+  deactivated: bool,
+  available: bool,
+  languages: Vec<Symbol>, // Declared working languages
+  stats: FreelancerStats,
+  completed_as_client: u32,
+  ratings: RatingSummary,
+  average_rating: u32, // Average stars x100 (0 = unrated)
+  latest_ratings: Vec<Rating>, // Up to PROFILE_RATINGS, newest first
+  reputation: u32, // See reputation()
+  tag_counts: Map<u32, u32>, // Outcome tag ID to the number of ratings carrying it
+  recovery: Option<Address>, // Recovery address in effect
+}
+
+// Who can ask for a user's escrows to move to a new address if the user loses their key
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct RecoverySetting {
+  address: Address, // In effect until `next` takes over
+  next: Option<Address>, // Replacement waiting out RECOVERY_DELAY_SECS
+  next_at: u64, // When `next` takes effect
+}
+
+// A recovery address asking for an escrow to move to a new freelancer address
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct RecoveryRequest {
+  recovery: Address, // The recovery address that asked
+  new_freelancer: Address,
+  requested_at: u64,
 }
 
 #[derive(Clone)]
@@ -62,14 +516,187 @@ pub struct Escrow {
   project_id: u64,
   client: Address,
   freelancer: Address,
-  total_amount: u64,
+  total_amount: i128,
+  asset: Address, // Token all amounts of the escrow are denominated in
   milestones: Vec<Milestone>,
-  released_amount: u64,
+  deposited_amount: i128, // Total funds deposited so far
+  released_amount: i128, // Total funds released to the freelancer so far
+  refunded_amount: i128, // Total funds returned to the client so far
   state: EscrowState,
+  created_at: u64, // Ledger timestamp the escrow was opened
+  client_active_at: u64, // Ledger timestamp of the client's last call on this escrow
+  lump_sum: bool, // No milestones: one deliverable, one approval, one payment
 }
 
+// What get_escrow shows a viewer. The parties, the arbitrator of an open dispute on the
+// escrow and the admin get the full record; anyone else gets the header.
+// Contract types can't be boxed, so the full record is carried inline
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 #[contracttype]
+pub enum EscrowView {
+  Full(Escrow),
+  Header(EscrowHeader),
+}
+
+// The part of an escrow anyone may read. Built field by field, so fields added to Escrow stay
+// out of it until listed here.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct EscrowHeader {
+  escrow_id: u64,
+  project_id: u64,
+  state: EscrowState,
+  asset: Address,
+  total_amount: i128,
+  created_at: u64,
+}
+
+// A read-only third party on an escrow, such as an auditor or a grant program manager. The
+// client grants it and it counts once the freelancer acknowledges it.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Observer {
+  address: Address,
+  acknowledged: bool,
+}
+
+// How many of the escrow's two parties agreed to exchange contact details
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[contracttype]
+pub enum ContactConsent {
+  None,
+  One,
+  Both,
+}
+
+// Collateral the client asks the freelancer to lock before accepting. It is held by the
+// contract and returned to the freelancer when the escrow ends.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Bond {
+  token: Address,
+  amount: i128,
+  posted: bool,
+}
+
+// A client's own cap on how many active escrows one freelancer may have across the
+// client's projects
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct WipLimit {
+  max_active: u32, // 0 = unlimited
+  hard: bool, // Block new escrows at the limit instead of only emitting a warning
+}
+
+// Upfront share of a milestone, paid when the client starts it. The rest is paid on approval.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Kickoff {
+  bps: u32, // Basis points of the milestone amount
+  started: bool,
+  paid: i128, // Kickoff amount paid when the milestone started
+}
+
+// A disputed milestone. The pot is whatever of the milestone is still unpaid; a kickoff paid
+// before submission is clawed back into it.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Dispute {
+  raised_by: Address,
+  raised_at: u64,
+  clawback: i128, // Kickoff taken back from the freelancer when the dispute opened
+  pot: i128, // Amount the admin splits between the parties
+}
+
+// A submission the client flagged as out of scope. Until respond_by the freelancer can
+// resubmit, accept the client's partial offer or escalate to a dispute; after that anyone
+// can send the milestone back to Pending.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Contest {
+  note: String,
+  flagged_at: u64,
+  respond_by: u64,
+  offer: i128, // Partial payout offered by the client (0 = none yet)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum ArbitratorTier {
+  Probationary, // Small disputes only, first rulings co-signed by a full arbitrator
+  Full,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Arbitrator {
+  tier: ArbitratorTier,
+  rulings: u32, // Disputes settled on this arbitrator's ruling
+}
+
+// Running sums behind an arbitrator's public stats
+#[derive(Clone, Debug, Default, PartialEq)]
+#[contracttype]
+pub struct ArbitratorTally {
+  resolved: u32, // Disputes settled on the arbitrator's ruling
+  resolution_secs: u64, // From dispute to settlement, summed
+  client_share_bps: u64, // Client's share of each pot, summed
+  overturned: u32, // Pending rulings the admin replaced on appeal
+}
+
+// What parties see of an arbitrator before relying on them
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ArbitratorStats {
+  resolved: u32,
+  avg_resolution_secs: u64,
+  avg_client_share_bps: u32, // 0 with no resolutions
+  overturned: u32,
+  credibility_bps: u32, // Share of the arbitrator's rulings that stood (10000 with none)
+}
+
+// A dispute assignment, with the arbitrator's stats for the parties to judge it by
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Assignment {
+  arbitrator: Address,
+  stats: ArbitratorStats,
+}
+
+// Limits on probationary arbitrators
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ArbitrationPolicy {
+  probation_max_pot: i128, // Largest dispute pot a probationary arbitrator is assigned
+  probation_cosigned: u32, // Rulings of a probationary arbitrator that need a co-signature
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct DisputeRef {
+  escrow_id: u64,
+  milestone_index: u32,
+}
+
+// A probationary arbitrator's ruling waiting for a full arbitrator to co-sign it
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Ruling {
+  arbitrator: Address,
+  freelancer_amount: i128,
+}
+
+// What a client has committed in one asset across their active escrows
+#[derive(Clone, Debug, Default, PartialEq)]
+#[contracttype]
+pub struct Commitments {
+  unfunded: i128, // Escrow totals the client has not deposited yet
+  held: i128, // Deposits not yet released to the freelancer or returned to the client
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
 pub enum EscrowState {
   Created,
   InProgress,
@@ -77,181 +704,1683 @@ pub enum EscrowState {
   Refunded,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Config {
+  review_period_secs: u64, // Time the client has to review a submission before the freelancer can release it
+  bundle_grace_secs: u64, // Wait after the project deadline before unreviewed work can be submitted as a bundle
+  contest_window_secs: u64, // Time the freelancer has to answer an out-of-scope flag
+  revoke_window_secs: u64, // Time the client has to take back an approval; zero turns revocation off
+  rate_max_age_secs: u64, // Age past which a reference rate is too stale to convert with
+  launch_mode: LaunchMode, // SetConfig can open an allowlisted marketplace but never close it again
+  risk_caps: RiskCaps,
+  legacy_api: bool, // The version 1 entry points still answer; off, they fail with Deprecated
+}
+
+// Caps on the value a freelancer can have in active escrows, in reference units as
+// get_escrow_in_reference converts them, by risk tier (e.g. 1k for new accounts and 10k once
+// established). A cap of 0 leaves its tier uncapped; verified freelancers are never capped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct RiskCaps {
+  new_cap: i128,
+  established_cap: i128,
+  established_after: u32, // Escrows completed as the freelancer before the established cap applies; 0 keeps everyone new
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum RiskTier {
+  New,
+  Established,
+  Verified, // Flagged by the admin through the queue
+}
+
+// A freelancer's standing against their tier's cap
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct RiskLimit {
+  tier: RiskTier,
+  cap: i128, // 0 = uncapped
+  usage: i128, // Reference value of the active escrows counted against the cap
+  completed: u32, // Escrows completed as the freelancer since caps were tracked
+}
+
+// What a freelancer's active escrows count for against their cap, and their track record
 #[derive(Clone)]
 #[contracttype]
-pub enum StorageKey {
-  ProjectCount,
-  UserCount, // Removed as user data is not stored
-  Projects(u64), // Key for each project by ID
-  Escrows(u64),  // Key for each escrow by ID
+pub struct Exposure {
+  usage: i128,
+  completed: u32,
 }
 
-pub struct EscrowServiceContract;
+// Who may start something new on the marketplace. Calls on projects, escrows and listings
+// that already exist work the same in both modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum LaunchMode {
+  Open,
+  Allowlist, // Only allowlisted addresses post projects, bid or publish services
+}
 
-#[contractimpl]
-impl EscrowServiceContract {
+// An admin change that waits in the queue for its class's delay before it applies
+#[derive(Clone)]
+#[contracttype]
+pub enum AdminAction {
+  SetConfig(Config),
+  SetTtlPolicy(TtlClass, TtlPolicy), // Threshold of at least a day, below extend_to, which the network's maximum TTL caps
+  SetPlatformFee(u32), // Applies to escrows opened after the change
+  SetNonprofit(Address, bool), // Escrows a flagged client opens carry no fee and count as public goods
+  SetRandomAssignment(bool), // While on, raise_dispute draws the arbitrator instead of queueing the dispute for next_dispute
+  SetArbitrationPolicy(ArbitrationPolicy),
+  ForceResolve(u64, u32, i128), // Settles a dispute in place of its assigned arbitrator: escrow ID, milestone index, freelancer amount
+  OverturnRuling(u64, u32, i128), // Appeal: replaces a pending ruling, counting an overturn against its arbitrator
+  SetDeactivated(Address, bool), // A ban, immediate
+  SetAssetMigration(Address, Address), // Old asset to the one replacing it, 1:1; escrows move over one by one
+  RestrictToAllowlist, // Puts an open marketplace back in allowlist mode, on a week's notice
+  RecoverFreelancer(u64, Address), // Completes a recovery request the client won't confirm: escrow ID, the requested address
+  SetVerified(Address, bool), // Verified freelancers take escrows past the risk-tier caps
+  SetLegacyAsset(Address), // Asset given to projects and escrows stored before they named one; set it before migrating them
+  SetReferenceRate(Address, i128, u64), // Asset, rate and observation time, which can't be in the future, older than the config's maximum age or older than the rate it replaces; immediate
+  SetMinDepositIncrement(Address, Option<i128>), // Smallest deposit deposit_funds takes in the asset, except one topping an escrow off; None for one whole token
+  AddRegistryEntry(RegistryKind, Symbol, String), // New entry with its label in one language, under the registry's next ID
+  SetLabel(RegistryKind, u32, Symbol, String), // Adds or replaces an entry's label in one language
+  AddCategoryTemplate(u32, String, Vec<MilestoneShare>), // Category ID, name and shares summing to 10000 bps without deadlines, under the next template ID
+  UpdateCategoryTemplate(u32, Vec<MilestoneShare>), // Replaces a template's shares and bumps its version; posted projects keep theirs
+  AddArbitrator(Address), // New arbitrators start on probation
+  PromoteArbitrator(Address), // Lifts both the pot limit and the co-signature requirement
+  SetAllowlisted(Address, bool), // Only matters in allowlist mode
+}
 
-  // Project Management
-  pub fn post_project(
-    env: Env,
-    from: Address, // Client address
-    title: String,
-    description: String,
-    category: String,
-    budget: u64,
-    deadline: u64, // Unix timestamp for deadline
-    milestones: Vec<Milestone>,
-  ) -> Result<u64, String> {
-    // Ensure sender address is valid (basic check)
-    if !env.accounts().is_valid_address(&from) {
-      return Err(String::from("Invalid client address"));
-    }
-
-    let project_count = env.storage().instance().get::<_, u64>(&StorageKey::ProjectCount).unwrap_or(0);
-    let project = Project {
-      id: project_count + 1,
-      client: from,
-      title,
-      description,
-      category,
-      budget,
-      deadline,
-      milestones,
-      status: ProjectStatus::Open,
-    };
-    // Store project details in separate storage (consider database)
-    env.storage().instance().set(&StorageKey::Projects(project_count + 1), &project);
-    env.storage().instance().set(&StorageKey::ProjectCount, &(project_count + 1));
-    Ok(project_count + 1)
-  }
-
-  // ... other project management functions (e.g., view projects, update project)
+#[derive(Clone)]
+#[contracttype]
+pub struct QueuedAction {
+  action: AdminAction,
+  queued_at: u64,
+  execute_after: u64, // Earliest time execute_admin_action applies it
+}
 
-  // Escrow Management
-  pub fn initiate_escrow(
-    env: Env,
-    from: Address, // Client address
-    project_id: u64,
-    freelancer: Address, // Freelancer address
-  ) -> Result<(), String> {
-    // Ensure sender address is valid (basic check)
-    if !env.accounts().is_valid_address(&from) {
-      return Err(String::from("Invalid client address"));
-    }
-
-    let project = env.storage().instance().get::<_, Project>(&StorageKey::Projects(project_id))?;
-    // Ensure project exists and client address matches the project owner
-    if project.is_none() || project.unwrap().client != from {
-        return Err(String::from("Unauthorized: Only client who posted the project can initiate escrow"));
-      }
-  
-      // Ensure project is open
-      if project.unwrap().status != ProjectStatus::Open {
-        return Err(String::from("Project is not open for escrow initiation"));
-      }
-  
-      let escrow = Escrow {
-        project_id,
-        client: project.unwrap().client,
-        freelancer,
-        total_amount: project.unwrap().budget,
-        milestones: project.unwrap().milestones.clone(),
-        released_amount: 0,
-        state: EscrowState::Created,
-      };
-  
-      // Store escrow details
-      let escrow_id = env.storage().instance().get::<_, u64>(&StorageKey::EscrowCount).unwrap_or(0) + 1;
-      env.storage().instance().set(&StorageKey::Escrows(escrow_id), &escrow);
-      env.storage().instance().set(&StorageKey::EscrowCount, &escrow_id);
-  
-      // Update project status
-      let mut updated_project = project.unwrap().clone();
-      updated_project.status = ProjectStatus::InProgress;
-      env.storage().instance().set(&StorageKey::Projects(project_id), &updated_project);
-  
-      Ok(())
-    }
-  
-    pub fn deposit_funds(env: Env, from: Address, escrow_id: u64, amount: u64) -> Result<(), String> {
-      // Ensure sender address is valid (basic check)
-      if !env.accounts().is_valid_address(&from) {
-        return Err(String::from("Invalid address"));
-      }
-  
-      let escrow = env.storage().instance().get::<_, Escrow>(&StorageKey::Escrows(escrow_id))?;
-      
-      // Verify if sender is involved in the escrow (client or freelancer address)
-      if escrow.client != from && escrow.freelancer != from {
-        return Err(String::from("Unauthorized: Only client or freelancer can deposit funds"));
-      }
-  
-      // Update escrow state and released amount
-      let mut updated_escrow = escrow.clone();
-      updated_escrow.released_amount += amount;
-      if updated_escrow.released_amount == updated_escrow.total_amount {
-        updated_escrow.state = EscrowState::InProgress;
-      }
-      env.storage().instance().set(&StorageKey::Escrows(escrow_id), &updated_escrow);
-  
-      Ok(())
-    }
-  
-    pub fn release_funds(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), String> {
-      // Ensure sender address is valid (basic check)
-      if !env.accounts().is_valid_address(&from) {
-        return Err(String::from("Invalid client address"));
-      }
-  
-      let mut escrow = env.storage().instance().get::<_, Escrow>(&StorageKey::Escrows(escrow_id))?;
-  
-      // Verify milestone index and completion
-      if milestone_index >= escrow.milestones.len() as u32 {
-        return Err(String::from("Invalid milestone index"));
-      }
-      if !escrow.milestones[milestone_index as usize].completed {
-        return Err(String::from("Milestone not marked as completed"));
-      }
-  
-      // Calculate amount to release for the milestone
-      let mut released_amount = 0;
-      for i in 0..milestone_index as usize {
-        released_amount += escrow.milestones[i].amount;
-      }
-  
-      // Ensure sufficient funds are available
-      if escrow.released_amount < released_amount {
-        return Err(String::from("Insufficient funds deposited in escrow"));
-      }
-  
-      // Update escrow state and released amount
-      escrow.released_amount = released_amount;
-      if escrow.released_amount == escrow.total_amount {
-        escrow.state = EscrowState::Completed;
-      }
-      env.storage().instance().set(&StorageKey::Escrows(escrow_id), &escrow);
-  
-      Ok(())
-    }
-  
-    pub fn refund_funds(env: Env, from: Address, escrow_id: u64) -> Result<(), String> {
-      // Ensure sender address is valid (basic check)
-      if !env.accounts().is_valid_address(&from) {
-        return Err(String::from("Invalid client address"));
-      }
-  
-      let mut escrow = env.storage().instance().get::<_, Escrow>(&StorageKey::Escrows(escrow_id))?;
-  
-      // Ensure escrow is in a refundable state
-      if escrow.state != EscrowState::Created {
-        return Err(String::from("Refund not allowed in current escrow state"));
-      }
-  
-      // Update escrow state
-      escrow.state = EscrowState::Refunded;
-      env.storage().instance().set(&StorageKey::Escrows(escrow_id), &escrow);
-  
-      Ok(())
-    }
-  
-    
+// A client approval that can still be revoked: when it happened and what it paid
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Approval {
+  approved_at: u64,
+  paid: i128, // Milestone amount released by the approval, bonus not included
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Deposit {
+  depositor: Address, // Address that actually sent the funds
+  amount: i128,
+  memo: Option<BytesN<32>>, // Informational reference (e.g. exchange memo), never used in accounting
+  timestamp: u64,
+}
+
+// What moved money on an escrow
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum LedgerKind {
+  Deposit,
+  Kickoff, // Upfront share of a milestone
+  Release, // Milestone paid on approval
+  PartialRelease, // Partial offer the freelancer accepted
+  DisputeRelease, // Freelancer's share of a dispute ruling
+  Bonus, // Early-completion bonus earned
+  Clawback, // Kickoff taken back into a dispute pot
+  Refund, // Returned to the client or a bounty sponsor
+  KillFee, // Paid to the freelancer when the client cancelled
+  Revoked, // Payout taken back when the client revoked the approval
+  BondPosted,
+  BondReturned,
+  Closed, // Totals when the escrow ended: released, fee charged, net of the fee
+  Recovered, // The freelancer address (party) was replaced by a recovery; nothing moved
+}
+
+// One line of an escrow's ledger export. Amounts in and out are positive; the kind says
+// which way they went.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct LedgerLine {
+  line: u32, // Numbered from 1 in the order the lines were written
+  kind: LedgerKind,
+  party: Address, // Who paid in or was paid
+  asset: Address, // The escrow's asset, or the bond token
+  milestone_index: Option<u32>,
+  gross: i128,
+  fee: i128, // Platform fee share of a payout; charged in full when the escrow closes
+  net: i128,
+  memo: Option<BytesN<32>>, // Deposit memo
+  at: u64, // Ledger timestamp
+}
+
+// How a project or escrow ended before its payload was freed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum Ending {
+  ProjectCancelled, // Withdrawn by the client before an escrow opened
+  EscrowDeclined, // Turned down by the freelancer before acceptance or funding
+  EscrowCompleted,
+  EscrowRefunded,
+}
+
+// What is kept of a project or escrow whose payload was freed, so that anything referring
+// to its ID (ratings, acceptance receipts, logs) still resolves
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Tombstone {
+  id: u64,
+  ending: Ending,
+  closed_at: u64,
+}
+
+// One sponsor's share of a bounty's funding
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct Contribution {
+  contributor: Address,
+  amount: i128, // Total deposited
+  refunded: i128, // Returned when the bounty was cancelled or expired
+}
+
+// An escrow funded by any number of sponsors. The client acts as the maintainer: they review
+// and approve milestones, and can cancel the bounty while it is not fully funded.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Bounty {
+  expires_at: u64, // After this anyone can expire the bounty, refunding what is left
+  contributions: Vec<Contribution>, // In order of first contribution
+}
+
+// Review times of one escrow's milestones, folded into the client's stats once the
+// escrow completes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct ReviewTally {
+  reviews: u32, // Approvals, rejections and auto-releases
+  total_secs: u64, // Sum of the time from submission to review
+  worst_secs: u64,
+  auto_releases: u32, // Counted at the full review period
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct UserStats {
+  completed_as_client: u32, // Escrows completed with this address as the client
+  reviews: ReviewTally, // Review times over those escrows
+}
+
+// A milestone's acceptance, countersigned by the freelancer after the client approved it.
+// Written once and never changed.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct AcceptanceRecord {
+  client: Address,
+  freelancer: Address,
+  deliverable: Option<BytesN<32>>, // Hash of the accepted submission
+  amount: i128, // Milestone amount
+  paid: i128, // Paid out for it
+  submitted_at: u64,
+  countersigned_at: u64,
+}
+
+// Issued for a completed escrow, with every countersigned acceptance
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct CompletionCertificate {
+  project_id: u64,
+  client: Address,
+  freelancer: Address,
+  asset: Address,
+  released: i128,
+  acceptances: Map<u32, AcceptanceRecord>, // By milestone index
+}
+
+// A deadline worked out against the chain's clock, so frontends never compare it with their
+// own. A deadline of 0 means none and is never overdue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct TimeStatus {
+  now: u64, // Current ledger timestamp
+  deadline: u64,
+  remaining_secs: u64, // Until the deadline (0 once it is reached)
+  overdue_secs: u64, // Past the deadline (0 until it is passed)
+  overdue: bool,
+  deadline_ledger: u32, // Estimated ledger sequence at the deadline (0 = none)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct MilestoneProgress {
+  status: MilestoneStatus,
+  amount: i128,
+  paid: i128,
+  client_delay_secs: u64, // Delay the client caused, credited to the deadline
+  deadline: TimeStatus, // The milestone's own deadline, or the project's when it has none, plus the client delay
+  estimated_hours: u32, // The freelancer's effort estimate (0 = none)
+  percent_done: u32, // 100 once submitted or settled, else the latest progress report
+  reported_at: u64, // Ledger timestamp of the latest progress report (0 = never reported)
+  stale: bool, // Pending, and no progress report for PROGRESS_STALE_SECS
+}
+
+// Where an escrow stands, one entry per milestone (one for a lump sum)
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ProgressView {
+  state: EscrowState,
+  total: i128,
+  deposited: i128,
+  released: i128,
+  last_activity_at: u64, // Ledger timestamp of the last change to the escrow (0 = none since tracking began)
+  deadline: TimeStatus, // Project deadline
+  estimated_hours: u32, // Sum of the effort estimates
+  remaining_hours: u32, // What the estimates leave of the work not done yet, by percent_done
+  completion_bps: u32, // percent_done across the milestones, weighted by amount
+  shortfall: i128, // What the remaining milestones need beyond what the escrow still holds
+  milestones: Vec<MilestoneProgress>,
+}
+
+// Hours the freelancer expects each milestone to take, recorded once after acceptance
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct EffortEstimate {
+  hours: Vec<u32>, // By milestone index
+  set_at: u64,
+}
+
+// The freelancer's latest word on how far a milestone has come. Never read by payouts.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ProgressReport {
+  percent: u32,
+  reported_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum ActionKind {
+  Deposit, // Client: fund the rest of the escrow
+  Submit, // Freelancer: deliver a pending milestone
+  Review, // Client: approve or reject a submission
+  AutoRelease, // Freelancer: the review period ran out, the submission can be released
+  AnswerContest, // Freelancer: resubmit, accept the offer or escalate an out-of-scope flag
+  CheckItem, // Either party: check off a kickoff checklist item (milestone_index is the item's)
+  CheckIn, // Client: the freelancer has not reported progress on a pending milestone for a week
+  CoverShortfall, // Client: a running escrow now owes more than it holds (see get_shortfall)
+}
+
+// Something one party can do on an escrow now, and by when
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ActionItem {
+  kind: ActionKind,
+  milestone_index: u32, // 0 for escrow-wide actions
+  due: TimeStatus,
+}
+
+// Something a party confirms before work starts, e.g. "NDA signed"
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ChecklistItem {
+  label: String,
+  party: UserType, // Who checks it off
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Checklist {
+  items: Vec<ChecklistItem>,
+  checked_at: Vec<Option<u64>>, // By item; when it was checked off
+}
+
+// Extra pay for approving a milestone well ahead of its deadline. It is funded with the
+// escrow and, once the milestone is approved, paid with it or returned to the client.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct EarlyBonus {
+  amount: i128,
+  min_days_early: u32, // Approval has to come at least this many days before the deadline
+  earned: Option<bool>, // Set when the milestone is settled
+}
+
+// Kill fees the client agrees to pay if they cancel the escrow, in basis points of what it
+// still holds at the time. The tier is the furthest point the engagement reached.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct CancellationSchedule {
+  before_acceptance_bps: u32,
+  after_acceptance_bps: u32,
+  after_submission_bps: u32, // Once any milestone has been submitted
+}
+
+// Platform fee an escrow was opened under. Later changes to the global fee or to the
+// client's nonprofit flag do not reach it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct FeeTerms {
+  bps: u32, // Basis points of what the freelancer is paid
+  public_goods: bool, // Opened by a client flagged as a nonprofit, so no fee applies
+}
+
+// Emitted with the `closed` event once an escrow completes or is refunded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct ClosingReport {
+  state: EscrowState,
+  released: i128, // Paid to the freelancer over the escrow's life
+  refunded: i128, // Returned to the client
+  fee: i128, // Platform fee on the released amount
+  public_goods: bool,
+}
+
+// Totals over every closed escrow
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[contracttype]
+pub struct PlatformStats {
+  volume: i128, // Released to freelancers
+  fees: i128,
+  public_goods_volume: i128, // Part of the volume released in public-goods escrows
+}
+
+// What a freelancer can learn about a client's review habits before engaging
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct CounterpartyRisk {
+  completed_escrows: u32,
+  reviews: u32,
+  average_review_secs: u64,
+  worst_review_secs: u64,
+  auto_releases: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct EscrowStateCounts {
+  created: u32,
+  in_progress: u32,
+  completed: u32,
+  refunded: u32,
+}
+
+// Admin-curated lists that projects and profiles refer to by ID
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum RegistryKind {
+  Category,
+  Skill,
+  OutcomeTag, // e.g. OnTime, ScopeCreep; attached to ratings
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RegistryEntry {
+  home_lang: Symbol, // Language the entry was registered in, listed when there is no default-language label
+  labels: Map<Symbol, String>, // Display label per language
+}
+
+// One row of a registry listing, labelled in the default language where available
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct RegistryListing {
+  id: u32,
+  lang: Symbol, // Language of the label below
+  label: String,
+}
+
+// One party's proposal to change an unstarted milestone's amount, waiting for the other
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Reprice {
+  proposer: Address,
+  new_amount: i128,
+}
+
+// The order an escrow's milestones have to be worked in. In sequential mode a milestone waits
+// for every earlier one; `depends_on` names, by milestone index, the milestones one added
+// later waits for.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct MilestoneOrder {
+  sequential: bool,
+  depends_on: Map<u32, Vec<u32>>,
+}
+
+// One change an amendment makes to a milestone escrow's terms. The budget moves with the
+// milestones added, voided and repriced.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum AmendmentChange {
+  AddMilestone(Milestone, Vec<u32>), // The milestone and the existing milestones it depends on
+  VoidMilestone(u32),
+  Reprice(u32, i128), // Milestone index and new amount
+  MilestoneDeadline(u32, u64), // Milestone index and new deadline (0 = the project's)
+  ProjectDeadline(u64), // 0 = none
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum AmendmentStatus {
+  Proposed,
+  Accepted,
+  Rejected,
+}
+
+// A set of changes to an escrow's terms that one party proposed and the other accepts or
+// rejects as a unit
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Amendment {
+  id: u32, // Position in the escrow's amendment history
+  proposer: Address,
+  changes: Vec<AmendmentChange>,
+  status: AmendmentStatus,
+  proposed_at: u64,
+  decided_at: u64, // 0 while proposed
+  terms_digest: Option<BytesN<32>>, // terms_digest right after the changes applied
+}
+
+// What happened on an escrow during one epoch, published in a rollover event on the first
+// touch of a later one
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct EpochActivity {
+  epoch: u64, // Ledger timestamp / EPOCH_SECS
+  deposits: u32,
+  releases: u32, // Writes that paid the freelancer anything
+  messages: u32, // Progress reports and out-of-scope flags
+  state_changes: u32,
+}
+
+// An approval over the escrow's max_single_release, waiting for the client to confirm it
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct PendingRelease {
+  amount: i128, // What the approval pays, bonus not included
+  confirmable_at: u64,
+}
+
+// Admin-posted price of one unit of an asset in the platform's reference unit, e.g. USD,
+// scaled by RATE_SCALE
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ReferenceRate {
+  rate: i128,
+  timestamp: u64, // When the price was observed, as posted by the admin
+}
+
+// An escrow's amounts converted at its asset's reference rate, rounded down. Informational
+// only; the escrow itself stays in its asset.
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct ReferenceView {
+  rate: i128,
+  rate_timestamp: u64,
+  total: i128,
+  deposited: i128,
+  released: i128,
+  refunded: i128,
+  milestones: Vec<i128>, // Each milestone's amount; empty for a lump sum
+}
+
+// An accounting invariant self_check verifies
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[contracttype]
+pub enum Invariant {
+  Funds, // Payouts and refunds stay within deposits
+  MilestoneSum, // Milestone amounts add up to the total, one record per milestone
+  StateIndex, // The escrow is listed under its state
+  Frozen, // Pots of open disputes stay within the funds still held
+  Obligations, // The asset's obligations cover what its sampled active escrows hold
+}
+
+// One invariant checked on one sampled escrow, or across an asset for Obligations
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct CheckResult {
+  invariant: Invariant,
+  escrow_id: Option<u64>, // None for the asset-wide check
+  asset: Address,
+  passed: bool,
+}
+
+// What describe() reports about the deployed contract
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct Description {
+  interface_version: u32,
+  data_version: u32, // Storage layout the stored entries are in
+  deprecated: Vec<Symbol>, // Legacy entry points that still answer but are due to be turned off
+}
+
+#[contract]
+pub struct EscrowServiceContract;
+
+// The contract interface. Each call forwards to the module that owns it.
+#[contractimpl]
+impl EscrowServiceContract {
+  pub fn __constructor(env: Env, admin: Address) {
+    admin::init(&env, admin)
+  }
+
+  // Rewrites entries stored under an older layout, up to `limit` IDs per call. Returns the
+  // data version afterwards; call again until it matches the current layout.
+  pub fn migrate(env: Env, admin: Address, limit: u32) -> Result<u32, Error> {
+    admin::migrate(&env, admin, limit)
+  }
+
+  pub fn get_data_version(env: Env) -> u32 {
+    admin::get_data_version(&env)
+  }
+
+  pub fn describe(env: Env) -> Description {
+    admin::describe(&env)
+  }
+
+  // Admin queue
+  // Announces an admin change, which anyone can execute once its class's delay has passed:
+  // a day for settings, three days for a force-resolve, none for a ban. Returns the action ID.
+  pub fn queue_admin_action(env: Env, admin: Address, action: AdminAction) -> Result<u64, Error> {
+    timelock::queue_admin_action(&env, admin, action)
+  }
+
+  // Fails with TooEarly before the action's execute_after
+  pub fn execute_admin_action(env: Env, action_id: u64) -> Result<(), Error> {
+    timelock::execute_admin_action(&env, action_id)
+  }
+
+  pub fn cancel_admin_action(env: Env, admin: Address, action_id: u64) -> Result<(), Error> {
+    timelock::cancel_admin_action(&env, admin, action_id)
+  }
+
+  // Queued and not yet executed or cancelled
+  pub fn get_admin_action(env: Env, action_id: u64) -> Option<QueuedAction> {
+    timelock::get_admin_action(&env, action_id)
+  }
+
+  pub fn get_config(env: Env) -> Config {
+    admin::get_config(&env)
+  }
+
+  pub fn get_ttl_policy(env: Env, class: TtlClass) -> TtlPolicy {
+    admin::get_ttl_policy(&env, class)
+  }
+
+  // Platform fees
+  pub fn get_platform_fee(env: Env) -> u32 {
+    admin::get_platform_fee(&env)
+  }
+
+  // Reference rates
+  pub fn get_reference_rate(env: Env, asset: Address) -> Option<ReferenceRate> {
+    admin::get_reference_rate(&env, asset)
+  }
+
+  // Deposit increments
+  pub fn get_min_deposit_increment(env: Env, asset: Address) -> i128 {
+    admin::get_min_deposit_increment(&env, asset)
+  }
+
+  pub fn is_nonprofit(env: Env, user: Address) -> bool {
+    admin::is_nonprofit(&env, user)
+  }
+
+  // Escrows stored before fees existed carry no fee
+  pub fn get_fee_terms(env: Env, escrow_id: u64) -> FeeTerms {
+    admin::get_fee_terms(&env, escrow_id)
+  }
+
+  pub fn get_platform_stats(env: Env) -> PlatformStats {
+    admin::get_platform_stats(&env)
+  }
+
+  // Pays accrued fees out to `to`. Only the asset's free surplus can leave: whatever of the
+  // contract's balance is not backing live escrows or posted bonds.
+  pub fn withdraw_fees(env: Env, admin: Address, asset: Address, to: Address, amount: i128) -> Result<(), Error> {
+    admin::withdraw_fees(&env, admin, asset, to, amount)
+  }
+
+  // The most withdraw_fees takes now: the fee pool, capped at the free surplus
+  pub fn get_withdrawable_fees(env: Env, asset: Address) -> i128 {
+    admin::get_withdrawable_fees(&env, asset)
+  }
+
+  pub fn get_fee_pool(env: Env, asset: Address) -> i128 {
+    admin::get_fee_pool(&env, asset)
+  }
+
+  // Funds the contract holds for others in an asset, which no withdrawal may touch
+  pub fn get_obligations(env: Env, asset: Address) -> i128 {
+    admin::get_obligations(&env, asset)
+  }
+
+  // Asset migration
+  // Puts `amount` of the asset the admin declared as a replacement into the migration pool,
+  // from the admin's own balance
+  pub fn fund_migration_pool(env: Env, admin: Address, asset: Address, amount: i128) -> Result<(), Error> {
+    admin::fund_migration_pool(&env, admin, asset, amount)
+  }
+
+  pub fn get_migration_pool(env: Env, asset: Address) -> i128 {
+    admin::get_migration_pool(&env, asset)
+  }
+
+  // The asset an old one migrates to, once the admin queued and executed SetAssetMigration
+  pub fn get_asset_migration(env: Env, asset: Address) -> Option<Address> {
+    admin::get_asset_migration(&env, asset)
+  }
+
+  // Moves an active escrow to its asset's replacement, signed by both parties. What the
+  // escrow holds is taken 1:1 from the migration pool; no tokens move, and the contract's
+  // balance in the old asset is left alone. Fails with InsufficientFunds, changing nothing,
+  // while the pool can't cover it.
+  pub fn migrate_escrow_asset(env: Env, client: Address, freelancer: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::migrate_escrow_asset(&env, client, freelancer, escrow_id)
+  }
+
+  // Registries
+  pub fn get_labels(env: Env, registry: RegistryKind, id: u32) -> Result<Map<Symbol, String>, Error> {
+    admin::get_labels(&env, registry, id)
+  }
+
+  // Lists entries in ID order, each with its default-language label
+  pub fn list_registry(env: Env, registry: RegistryKind, cursor: u64, limit: u32) -> RegistryPage {
+    admin::list_registry(&env, registry, cursor, limit)
+  }
+
+  // A category's templates in the order they were added, for the project form's picker
+  pub fn get_category_templates(env: Env, category_id: u32, cursor: u64, limit: u32) -> TemplatePage {
+    template::get_category_templates(&env, category_id, cursor, limit)
+  }
+
+  // Project Management
+  pub fn post_project(
+    env: Env,
+    from: Address, // Client address
+    title: String,
+    description: String,
+    category: String,
+    budget: i128,
+    asset: Address, // Token the budget is denominated in
+    deadline: u64, // Unix timestamp for deadline
+    milestones: Vec<Milestone>,
+    locale: Locale,
+  ) -> Result<u64, Error> {
+    project::post_project(&env, from, title, description, category, budget, asset, deadline, milestones, locale)
+  }
+
+  // Posts a project whose milestones are basis-point shares of the budget. The stored project
+  // holds the expanded absolute amounts, so everything downstream works on amounts.
+  pub fn post_project_with_shares(
+    env: Env,
+    from: Address, // Client address
+    title: String,
+    description: String,
+    category: String,
+    budget: i128,
+    asset: Address, // Token the budget is denominated in
+    deadline: u64, // Unix timestamp for deadline
+    shares: Vec<MilestoneShare>,
+    locale: Locale,
+  ) -> Result<u64, Error> {
+    project::post_project_with_shares(&env, from, title, description, category, budget, asset, deadline, shares, locale)
+  }
+
+  // Posts a project with the milestones of one of its category's templates, expanded against
+  // the budget as post_project_with_shares would. The project is filed under the category's
+  // listed label and remembers the template version it was posted with; later edits to the
+  // template leave it alone. A template of another category is NotFound.
+  pub fn post_project_from_template(
+    env: Env,
+    from: Address, // Client address
+    title: String,
+    description: String,
+    category_id: u32, // Category registry ID
+    template_id: u32,
+    budget: i128,
+    asset: Address, // Token the budget is denominated in
+    deadline: u64, // Unix timestamp for deadline
+    locale: Locale,
+  ) -> Result<u64, Error> {
+    project::post_project_from_template(&env, from, title, description, category_id, template_id, budget, asset, deadline, locale)
+  }
+
+  // Runs the checks posting and opening an escrow make on a milestone schedule, without
+  // posting, and reports every issue with the index of the milestone at fault. The real
+  // calls fail on the first issue: InvalidAmount for an amount, LimitReached for too many
+  // milestones, InvalidConfig for a completed one and InvalidSchedule for the rest.
+  // Deadlines of 0 are unset and skip the deadline checks, and a lump sum has nothing to check.
+  pub fn validate_milestones(env: Env, milestones: Vec<Milestone>, budget: i128, project_deadline: u64) -> ValidationReport {
+    project::validate_milestones(&env, &milestones, budget, project_deadline)
+  }
+
+  pub fn get_project_template(env: Env, project_id: u64) -> Option<TemplateRef> {
+    template::get_project_template(&env, project_id)
+  }
+
+  // Proposals
+  // Bids on an open project. A proposed schedule must add up to the amount asked.
+  pub fn submit_proposal(env: Env, from: Address, project_id: u64, amount: i128, milestones: Vec<Milestone>, cover: BytesN<32>) -> Result<u64, Error> {
+    project::submit_proposal(&env, from, project_id, amount, milestones, cover)
+  }
+
+  // Invites up to MAX_INVITES_PER_CALL freelancers to the client's open project, with one
+  // invited event each. Returns one outcome per address, in the order given; an ineligible
+  // or already invited address is reported there instead of failing the call.
+  pub fn invite_many(env: Env, from: Address, project_id: u64, freelancers: Vec<Address>, message: String) -> Result<Vec<InviteOutcome>, Error> {
+    project::invite_many(&env, from, project_id, freelancers, message)
+  }
+
+  pub fn get_project_invites(env: Env, project_id: u64, cursor: u64, limit: u32) -> AddressPage {
+    project::get_project_invites(&env, project_id, cursor, limit)
+  }
+
+  pub fn get_proposal(env: Env, proposal_id: u64) -> Result<Proposal, Error> {
+    project::get_proposal(&env, proposal_id)
+  }
+
+  // Proposals on a project in the order they came in. `enriched` adds each bidder's current
+  // load, at the cost of reading their stats.
+  pub fn list_proposals(env: Env, project_id: u64, cursor: u64, limit: u32, enriched: bool) -> ProposalPage {
+    project::list_proposals(&env, project_id, cursor, limit, enriched)
+  }
+
+  // Proposals on a project, cheapest first
+  pub fn list_proposals_sorted(env: Env, project_id: u64, cursor: u64, limit: u32, enriched: bool) -> ProposalPage {
+    project::list_proposals_sorted(&env, project_id, cursor, limit, enriched)
+  }
+
+  // Projects by ID, as summaries. Cancelled projects are skipped, so a page can hold fewer
+  // than `limit` items.
+  pub fn list_projects(env: Env, cursor: u64, limit: u32) -> ProjectPage {
+    project::list_projects(&env, cursor, limit)
+  }
+
+  pub fn get_project(env: Env, project_id: u64) -> Result<Project, Error> {
+    project::get_project(&env, project_id)
+  }
+
+  // Withdraws a project no escrow was opened for. Only its tombstone is kept.
+  pub fn cancel_project(env: Env, from: Address, project_id: u64) -> Result<(), Error> {
+    project::cancel_project(&env, from, project_id)
+  }
+
+  // Handoff
+  // The client names a new owner for the project. A new offer replaces a pending one,
+  // acknowledgement included.
+  pub fn offer_project_transfer(env: Env, from: Address, project_id: u64, new_owner: Address) -> Result<(), Error> {
+    project::offer_project_transfer(&env, from, project_id, new_owner)
+  }
+
+  // The freelancer of the project's active escrow agrees to work for the new owner
+  pub fn acknowledge_project_transfer(env: Env, from: Address, project_id: u64) -> Result<(), Error> {
+    project::acknowledge_project_transfer(&env, from, project_id)
+  }
+
+  // The named owner takes the project over, and its active escrow with it. Escrows that
+  // already ended stay with the original client, and so do their ratings and stats.
+  pub fn accept_project_transfer(env: Env, from: Address, project_id: u64) -> Result<(), Error> {
+    project::accept_project_transfer(&env, from, project_id)
+  }
+
+  pub fn get_project_transfer(env: Env, project_id: u64) -> Option<ProjectTransfer> {
+    project::get_project_transfer(&env, project_id)
+  }
+
+  pub fn get_project_tombstone(env: Env, project_id: u64) -> Option<Tombstone> {
+    project::get_project_tombstone(&env, project_id)
+  }
+
+  pub fn get_project_locale(env: Env, project_id: u64) -> Locale {
+    project::get_project_locale(&env, project_id)
+  }
+
+  // Whether the freelancer declared the project's working language. A project without one
+  // matches everyone.
+  pub fn language_match(env: Env, project_id: u64, freelancer: Address) -> bool {
+    project::language_match(&env, project_id, &freelancer)
+  }
+
+  // Services
+  // Publishes a fixed-price service, listed under its category until it is deactivated
+  pub fn create_service(
+    env: Env,
+    from: Address, // Freelancer address
+    title: String,
+    description: String,
+    category: String,
+    price: i128,
+    asset: Address, // Token the price is denominated in
+    delivery_days: u32,
+  ) -> Result<u64, Error> {
+    service::create_service(&env, from, title, description, category, price, asset, delivery_days)
+  }
+
+  // Only reaches purchases made after the change
+  pub fn update_service(env: Env, from: Address, listing_id: u64, title: String, description: String, price: i128, delivery_days: u32) -> Result<(), Error> {
+    service::update_service(&env, from, listing_id, title, description, price, delivery_days)
+  }
+
+  pub fn deactivate_service(env: Env, from: Address, listing_id: u64) -> Result<(), Error> {
+    service::deactivate_service(&env, from, listing_id)
+  }
+
+  pub fn get_service(env: Env, listing_id: u64) -> Result<ServiceListing, Error> {
+    service::get_service(&env, listing_id)
+  }
+
+  // Active listing IDs in a category, oldest first
+  pub fn list_services(env: Env, category: String, cursor: u64, limit: u32) -> IdPage {
+    service::list_services(&env, category, cursor, limit)
+  }
+
+  // The client buys a listing at its current price. Returns the ID of the lump-sum escrow
+  // opened for it, already funded in full and due delivery_days from now.
+  pub fn purchase_service(env: Env, from: Address, listing_id: u64) -> Result<u64, Error> {
+    service::purchase_service(&env, from, listing_id)
+  }
+
+  // Subcontracting
+  // The client agrees to the freelancer subcontracting part of an unsettled milestone
+  pub fn consent_subcontract(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    subcontract::consent_subcontract(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn has_subcontract_consent(env: Env, escrow_id: u64, milestone_index: u32) -> bool {
+    subcontract::has_subcontract_consent(&env, escrow_id, milestone_index)
+  }
+
+  // The freelancer opens a child escrow for the subcontractor, with themselves as its client,
+  // for up to what is left of the milestone. Returns the child escrow's ID. The child is
+  // funded when the parent milestone pays out.
+  pub fn open_subcontract(
+    env: Env,
+    from: Address, // Freelancer of the parent escrow
+    escrow_id: u64,
+    milestone_index: u32,
+    subcontractor: Address,
+    amount: i128,
+    milestones: Vec<Milestone>, // The child's schedule; empty for a lump sum
+  ) -> Result<u64, Error> {
+    subcontract::open_subcontract(&env, from, escrow_id, milestone_index, subcontractor, amount, milestones)
+  }
+
+  pub fn get_subcontract(env: Env, child_id: u64) -> Option<Subcontract> {
+    subcontract::get_subcontract(&env, child_id)
+  }
+
+  pub fn list_subcontracts(env: Env, escrow_id: u64, milestone_index: u32, cursor: u64, limit: u32) -> IdPage {
+    subcontract::list_subcontracts(&env, escrow_id, milestone_index, cursor, limit)
+  }
+
+  // Escrow Management
+  pub fn initiate_escrow(
+    env: Env,
+    from: Address, // Client address
+    project_id: u64,
+    freelancer: Address, // Freelancer address
+  ) -> Result<u64, Error> {
+    escrow::initiate_escrow(&env, from, project_id, freelancer)
+  }
+
+  // The client accepts a proposal on their open project. The escrow takes the proposal's
+  // amount and schedule; a proposal without a schedule gets the project's, scaled to its
+  // amount.
+  pub fn accept_proposal(env: Env, from: Address, proposal_id: u64) -> Result<u64, Error> {
+    project::accept_proposal(&env, from, proposal_id)
+  }
+
+  // Escrows opened before sources were recorded came from their project
+  pub fn get_terms_source(env: Env, escrow_id: u64) -> TermsSource {
+    project::get_terms_source(&env, escrow_id)
+  }
+
+  // Caps how many active escrows one freelancer may have across the client's projects.
+  // In soft mode the escrow still opens but a wip_warn event is emitted.
+  pub fn set_wip_limit(env: Env, from: Address, max_active: u32, hard: bool) {
+    user::set_wip_limit(&env, from, max_active, hard)
+  }
+
+  pub fn get_wip_limit(env: Env, client: Address) -> WipLimit {
+    user::get_wip_limit(&env, client)
+  }
+
+  // Engagement
+  pub fn require_bond(env: Env, from: Address, escrow_id: u64, token: Address, amount: i128) -> Result<(), Error> {
+    escrow::require_bond(&env, from, escrow_id, token, amount)
+  }
+
+  pub fn get_bond(env: Env, escrow_id: u64) -> Option<Bond> {
+    escrow::get_bond(&env, escrow_id)
+  }
+
+  // Replaces the kickoff checklist. Until every item is checked off, milestone 0 can't be
+  // started or submitted. Like the bond, it is part of the terms, so it can only change
+  // before the freelancer accepts.
+  pub fn set_checklist(env: Env, from: Address, escrow_id: u64, items: Vec<ChecklistItem>) -> Result<(), Error> {
+    escrow::set_checklist(&env, from, escrow_id, items)
+  }
+
+  // Only the item's party can check it off. Checking an item twice keeps the first time.
+  pub fn complete_checklist_item(env: Env, from: Address, escrow_id: u64, item_index: u32) -> Result<(), Error> {
+    escrow::complete_checklist_item(&env, from, escrow_id, item_index)
+  }
+
+  pub fn get_checklist(env: Env, escrow_id: u64) -> Option<Checklist> {
+    escrow::get_checklist(&env, escrow_id)
+  }
+
+  // The client offers a bonus on a milestone with a deadline, before the first deposit and
+  // before the freelancer accepts. The bonus is added to the escrow total, so it is funded
+  // with the milestones. A zero amount withdraws the offer.
+  pub fn set_early_bonus(env: Env, from: Address, escrow_id: u64, milestone_index: u32, amount: i128, min_days_early: u32) -> Result<(), Error> {
+    escrow::set_early_bonus(&env, from, escrow_id, milestone_index, amount, min_days_early)
+  }
+
+  pub fn get_early_bonus(env: Env, escrow_id: u64, milestone_index: u32) -> Option<EarlyBonus> {
+    escrow::get_early_bonus(&env, escrow_id, milestone_index)
+  }
+
+  // The client sets the kill fees cancel_escrow pays, before the freelancer accepts. The
+  // tiers can't fall and none may pass MAX_CANCELLATION_FEE_BPS (InvalidSchedule). Like the
+  // bond, the schedule is part of the terms the freelancer accepts.
+  pub fn set_cancellation_schedule(env: Env, from: Address, escrow_id: u64, schedule: CancellationSchedule) -> Result<(), Error> {
+    escrow::set_cancellation_schedule(&env, from, escrow_id, schedule)
+  }
+
+  pub fn get_cancellation_schedule(env: Env, escrow_id: u64) -> Option<CancellationSchedule> {
+    escrow::get_cancellation_schedule(&env, escrow_id)
+  }
+
+  // Moves the required bond from the freelancer into the contract
+  pub fn post_bond(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::post_bond(&env, from, escrow_id)
+  }
+
+  // Hash of the escrow's economic terms, which the freelancer signs by passing it to
+  // accept_engagement. Any change to them gives a new digest.
+  pub fn terms_digest(env: Env, escrow_id: u64) -> Result<BytesN<32>, Error> {
+    escrow::terms_digest(&env, escrow_id)
+  }
+
+  // A required bond has to be posted first, and `digest` has to match the current terms
+  pub fn accept_engagement(env: Env, from: Address, escrow_id: u64, digest: BytesN<32>) -> Result<(), Error> {
+    escrow::accept_engagement(&env, from, escrow_id, digest)
+  }
+
+  // Onboarding in one call: posts the bond if one is required and not yet posted, accepts,
+  // and stores the notification preference. Nothing is kept if any step fails, including
+  // the bond transfer.
+  pub fn accept_engagement_bundle(env: Env, from: Address, escrow_id: u64, digest: BytesN<32>, pref: Option<BytesN<32>>) -> Result<(), Error> {
+    escrow::accept_engagement_bundle(&env, from, escrow_id, digest, pref)
+  }
+
+  // Invites
+  // The client of an escrow awaiting acceptance gets a random code for its freelancer. The
+  // code resolves for seven days unless revoked; a client can hold several for one escrow.
+  pub fn create_invite_code(env: Env, from: Address, escrow_id: u64) -> Result<BytesN<8>, Error> {
+    invite::create_invite_code(&env, from, escrow_id)
+  }
+
+  pub fn revoke_invite(env: Env, from: Address, code: BytesN<8>) -> Result<(), Error> {
+    invite::revoke_invite(&env, from, code)
+  }
+
+  pub fn resolve_invite(env: Env, code: BytesN<8>) -> Result<EngagementOffer, Error> {
+    invite::resolve_invite(&env, code)
+  }
+
+  // accept_engagement addressed by invite code, which is used up
+  pub fn accept_invite(env: Env, from: Address, code: BytesN<8>, digest: BytesN<32>) -> Result<(), Error> {
+    invite::accept_invite(&env, from, code, digest)
+  }
+
+  // Key recovery: the freelancer's recovery address asks for the escrow to move to
+  // `new_freelancer`, replacing any earlier request. The client confirms it, or the admin
+  // queues RecoverFreelancer when the client won't. The freelancer or the recovery address
+  // can cancel it until then.
+  pub fn request_recovery(env: Env, from: Address, escrow_id: u64, new_freelancer: Address) -> Result<(), Error> {
+    recovery::request_recovery(&env, from, escrow_id, new_freelancer)
+  }
+
+  pub fn confirm_recovery(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    recovery::confirm_recovery(&env, from, escrow_id)
+  }
+
+  pub fn cancel_recovery(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    recovery::cancel_recovery(&env, from, escrow_id)
+  }
+
+  pub fn get_recovery_request(env: Env, escrow_id: u64) -> Option<RecoveryRequest> {
+    recovery::get_recovery_request(&env, escrow_id)
+  }
+
+  // Each party of an accepted escrow agrees once, for good, to share contact details; the
+  // contact event fires when the second one does
+  pub fn consent_contact_exchange(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::consent_contact_exchange(&env, from, escrow_id)
+  }
+
+  pub fn get_contact_consent(env: Env, escrow_id: u64) -> Result<ContactConsent, Error> {
+    escrow::get_contact_consent(&env, escrow_id)
+  }
+
+  pub fn set_notification_pref(env: Env, from: Address, pref: BytesN<32>) {
+    user::set_notification_pref(&env, from, pref)
+  }
+
+  pub fn get_notification_pref(env: Env, user: Address) -> Option<BytesN<32>> {
+    user::get_notification_pref(&env, user)
+  }
+
+  // The optional memo lets support match deposits made from exchanges; it is only recorded
+  pub fn deposit_funds(env: Env, from: Address, escrow_id: u64, amount: i128, memo: Option<BytesN<32>>) -> Result<(), Error> {
+    escrow::deposit_funds(&env, from, escrow_id, amount, memo)
+  }
+
+  // Every money movement on the escrow as numbered lines, written as they happened. The
+  // Closed line carries the fee actually charged; payout lines show their share of it,
+  // rounded down.
+  pub fn export_ledger(env: Env, escrow_id: u64, cursor: u64, limit: u32) -> LedgerPage {
+    escrow::export_ledger(&env, escrow_id, cursor, limit)
+  }
+
+  pub fn get_deposits(env: Env, escrow_id: u64, cursor: u64, limit: u32) -> DepositPage {
+    escrow::get_deposits(&env, escrow_id, cursor, limit)
+  }
+
+  // First deposit recorded with this memo, if any
+  pub fn find_deposit_by_memo(env: Env, escrow_id: u64, memo: BytesN<32>) -> Option<Deposit> {
+    escrow::find_deposit_by_memo(&env, escrow_id, memo)
+  }
+
+  // Milestone review
+  pub fn submit_milestone(env: Env, from: Address, escrow_id: u64, milestone_index: u32, deliverable: BytesN<32>) -> Result<(), Error> {
+    escrow::submit_milestone(&env, from, escrow_id, milestone_index, deliverable)
+  }
+
+  // The client approves a submitted milestone, paying it out
+  pub fn release_funds(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    escrow::release_funds(&env, from, escrow_id, milestone_index)
+  }
+
+  // Within the config's revoke window, the client can take back an approval made with
+  // release_funds: the milestone returns to Submitted and the payout, bonus included, comes
+  // back into the escrow. An approval that completed the escrow is final, since closing it
+  // settles the payout, and so is one whose payout the freelancer withdrew; after that only a
+  // dispute on another milestone is left.
+  pub fn revoke_approval(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    escrow::revoke_approval(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn get_approval(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Approval> {
+    escrow::get_approval(&env, escrow_id, milestone_index)
+  }
+
+  // The freelancer withdraws what an approval paid, returning the amount. The approval can't
+  // be revoked after that. NotApproved while the milestone has no revocable approval.
+  pub fn withdraw_payout(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<i128, Error> {
+    escrow::withdraw_payout(&env, from, escrow_id, milestone_index)
+  }
+
+  // What the freelancer withdrew on a milestone, while its approval is on record
+  pub fn get_withdrawal(env: Env, escrow_id: u64, milestone_index: u32) -> Option<i128> {
+    escrow::get_withdrawal(&env, escrow_id, milestone_index)
+  }
+
+  // The freelancer drops a milestone they won't deliver. What it had not paid yet, its early
+  // bonus included, goes back to the client, and the escrow completes once nothing else is
+  // left open.
+  pub fn void_milestone(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    escrow::void_milestone(&env, from, escrow_id, milestone_index)
+  }
+
+  // Either party proposes a new amount for a Pending milestone and the other accepts it,
+  // naming the same amount. The total moves with it: an increase has to be deposited before
+  // more work is submitted, and a decrease returns the deposits it frees to the client.
+  pub fn propose_reprice(env: Env, from: Address, escrow_id: u64, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+    escrow::propose_reprice(&env, from, escrow_id, milestone_index, new_amount)
+  }
+
+  pub fn accept_reprice(env: Env, from: Address, escrow_id: u64, milestone_index: u32, new_amount: i128) -> Result<(), Error> {
+    escrow::accept_reprice(&env, from, escrow_id, milestone_index, new_amount)
+  }
+
+  pub fn get_reprice(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Reprice> {
+    escrow::get_reprice(&env, escrow_id, milestone_index)
+  }
+
+  // Milestone order
+  // In sequential mode each milestone waits until every earlier one is paid, ruled on or
+  // voided before it can be submitted or started (TooEarly). The client sets it before the
+  // freelancer accepts, and it is part of the terms digest.
+  pub fn set_sequential(env: Env, from: Address, escrow_id: u64, sequential: bool) -> Result<(), Error> {
+    milestone_schedule::set_sequential(&env, from, escrow_id, sequential)
+  }
+
+  // Both parties append a milestone to an active escrow, optionally waiting for the existing
+  // milestones in `depends_on`, and get its index. The total grows by its amount, which has to
+  // be deposited before more work is handed in on an escrow under way.
+  pub fn add_milestone(env: Env, client: Address, freelancer: Address, escrow_id: u64, milestone: Milestone, depends_on: Vec<u32>) -> Result<u32, Error> {
+    milestone_schedule::add_milestone(&env, client, freelancer, escrow_id, milestone, depends_on)
+  }
+
+  pub fn get_milestone_order(env: Env, escrow_id: u64) -> MilestoneOrder {
+    milestone_schedule::get_milestone_order(&env, escrow_id)
+  }
+
+  // Amendments
+  // Either party proposes a set of changes to a milestone escrow's terms: milestones added,
+  // voided or repriced, and milestone or project deadlines moved. Up to
+  // MAX_AMENDMENT_CHANGES per amendment and MAX_AMENDMENTS per escrow (LimitReached).
+  pub fn propose_amendment(env: Env, from: Address, escrow_id: u64, changes: Vec<AmendmentChange>) -> Result<u32, Error> {
+    amendment::propose_amendment(&env, from, escrow_id, changes)
+  }
+
+  // The other party applies every change in order, or none of them if one fails, and gets
+  // the new terms digest. Each change is checked as its single-change call checks it.
+  pub fn accept_amendment(env: Env, from: Address, escrow_id: u64, amendment_id: u32) -> Result<BytesN<32>, Error> {
+    amendment::accept_amendment(&env, from, escrow_id, amendment_id)
+  }
+
+  pub fn reject_amendment(env: Env, from: Address, escrow_id: u64, amendment_id: u32) -> Result<(), Error> {
+    amendment::reject_amendment(&env, from, escrow_id, amendment_id)
+  }
+
+  // Every amendment on the escrow in the order proposed, including the single changes made
+  // through accept_reprice, add_milestone and void_milestone
+  pub fn get_amendments(env: Env, escrow_id: u64, cursor: u64, limit: u32) -> AmendmentPage {
+    amendment::get_amendments(&env, escrow_id, cursor, limit)
+  }
+
+  // Epoch digests
+  // What the escrow counted so far in the epoch it was last touched in. Earlier epochs went
+  // out in rollover events.
+  pub fn get_epoch_activity(env: Env, escrow_id: u64) -> Option<EpochActivity> {
+    epoch::get_epoch_activity(&env, escrow_id)
+  }
+
+  // Release guard
+  // The client caps what one approval on the escrow pays out; 0 removes the cap. An approval
+  // over it (release_funds or approve_deliverable) pays nothing and raises a held event
+  // instead. Auto-releases are never held.
+  pub fn set_max_single_release(env: Env, client: Address, escrow_id: u64, max_single_release: i128) -> Result<(), Error> {
+    release_guard::set_max_single_release(&env, client, escrow_id, max_single_release)
+  }
+
+  pub fn get_max_single_release(env: Env, escrow_id: u64) -> i128 {
+    release_guard::get_max_single_release(&env, escrow_id)
+  }
+
+  // Pays a held approval, RELEASE_CONFIRM_SECS after it was held at the earliest
+  // (ReleaseTooEarly). Approving the milestone again does the same. A dispute on the
+  // milestone freezes it.
+  pub fn confirm_release(env: Env, client: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    release_guard::confirm_release(&env, client, escrow_id, milestone_index)
+  }
+
+  pub fn cancel_pending_release(env: Env, client: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    release_guard::cancel_pending_release(&env, client, escrow_id, milestone_index)
+  }
+
+  pub fn get_pending_release(env: Env, escrow_id: u64, milestone_index: u32) -> Option<PendingRelease> {
+    release_guard::get_pending_release(&env, escrow_id, milestone_index)
+  }
+
+  // Once the review period runs out, the freelancer can release a submitted milestone
+  pub fn auto_release(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    escrow::auto_release(&env, from, escrow_id, milestone_index)
+  }
+
+  // The client sends a submitted milestone back; the freelancer can submit it again
+  pub fn reject_milestone(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    escrow::reject_milestone(&env, from, escrow_id, milestone_index)
+  }
+
+  // Once the project deadline plus the bundle grace has passed without the client touching
+  // the escrow, the freelancer submits every unpaid milestone at once, one deliverable each,
+  // and a single review clock starts for all of them
+  pub fn submit_bundle(env: Env, from: Address, escrow_id: u64, deliverables: Vec<BytesN<32>>) -> Result<Bundle, Error> {
+    escrow::submit_bundle(&env, from, escrow_id, deliverables)
+  }
+
+  // Pays out every bundled milestone once the bundle's review clock has run out
+  pub fn release_bundle(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::release_bundle(&env, from, escrow_id)
+  }
+
+  pub fn get_bundle(env: Env, escrow_id: u64) -> Option<Bundle> {
+    escrow::get_bundle(&env, escrow_id)
+  }
+
+  // Empty for lump-sum escrows, whose single deliverable is read with get_deliverable
+  pub fn get_milestone_records(env: Env, escrow_id: u64) -> Result<Vec<MilestoneRecord>, Error> {
+    escrow::get_milestone_records(&env, escrow_id)
+  }
+
+  // The freelancer countersigns an approved milestone, recording its acceptance for both
+  // parties
+  pub fn countersign_acceptance(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<AcceptanceRecord, Error> {
+    escrow::countersign_acceptance(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn get_acceptance(env: Env, escrow_id: u64, milestone_index: u32) -> Option<AcceptanceRecord> {
+    escrow::get_acceptance(&env, escrow_id, milestone_index)
+  }
+
+  pub fn get_completion_certificate(env: Env, escrow_id: u64) -> Result<CompletionCertificate, Error> {
+    escrow::get_completion_certificate(&env, escrow_id)
+  }
+
+  // Kickoff payments
+  // Sets the upfront share of a milestone the freelancer gets when the client starts it; only
+  // until the freelancer accepts, since the shares are part of the terms
+  pub fn set_kickoff(env: Env, from: Address, escrow_id: u64, milestone_index: u32, kickoff_bps: u32) -> Result<(), Error> {
+    escrow::set_kickoff(&env, from, escrow_id, milestone_index, kickoff_bps)
+  }
+
+  // Releases the kickoff share of a pending milestone and returns it. Kickoff and final
+  // payment always add up to the milestone amount, since the final payment is whatever is
+  // left unpaid.
+  pub fn start_milestone(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<i128, Error> {
+    escrow::start_milestone(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn get_kickoff(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Kickoff> {
+    escrow::get_kickoff(&env, escrow_id, milestone_index)
+  }
+
+  // Disputes
+  // Either party can freeze an unpaid milestone of a funded escrow for the admin to settle
+  pub fn raise_dispute(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<Dispute, Error> {
+    dispute::raise_dispute(&env, from, escrow_id, milestone_index)
+  }
+
+  // Peremptory challenge: each party can strike the arbitrator assigned to their dispute
+  // once, before a ruling. Another arbitrator is drawn at once, leaving out everyone struck
+  // so far; without one the dispute goes back to the queue. Returns the new arbitrator.
+  pub fn strike_arbitrator(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<Option<Address>, Error> {
+    dispute::strike_arbitrator(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn get_dispute_strikes(env: Env, escrow_id: u64, milestone_index: u32) -> Map<Address, Address> {
+    dispute::get_dispute_strikes(&env, escrow_id, milestone_index)
+  }
+
+  // Splits the pot: `freelancer_amount` is released to the freelancer, the rest is returned
+  // to the client. The admin can settle any dispute, an arbitrator the ones assigned to them.
+  // A probationary arbitrator's first rulings only take effect once co-signed.
+  pub fn resolve_dispute(env: Env, from: Address, escrow_id: u64, milestone_index: u32, freelancer_amount: i128) -> Result<(), Error> {
+    dispute::resolve_dispute(&env, from, escrow_id, milestone_index, freelancer_amount)
+  }
+
+  pub fn get_dispute(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Dispute> {
+    dispute::get_dispute(&env, escrow_id, milestone_index)
+  }
+
+  // A full arbitrator co-signs a probationary arbitrator's ruling, which settles the dispute
+  pub fn cosign_ruling(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    dispute::cosign_ruling(&env, from, escrow_id, milestone_index)
+  }
+
+  pub fn get_pending_ruling(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Ruling> {
+    dispute::get_pending_ruling(&env, escrow_id, milestone_index)
+  }
+
+  // Arbitrators
+  pub fn get_random_assignment(env: Env) -> bool {
+    dispute::get_random_assignment(&env)
+  }
+
+  pub fn get_arbitrator(env: Env, arbitrator: Address) -> Option<Arbitrator> {
+    dispute::get_arbitrator(&env, arbitrator)
+  }
+
+  pub fn get_arbitrator_stats(env: Env, arbitrator: Address) -> ArbitratorStats {
+    dispute::get_arbitrator_stats(&env, arbitrator)
+  }
+
+  pub fn get_arbitration_policy(env: Env) -> ArbitrationPolicy {
+    dispute::get_arbitration_policy(&env)
+  }
+
+  // Assigns the arbitrator the oldest open dispute they may take: not one of their own
+  // escrows, not one a party struck them from, and for a probationary arbitrator not above
+  // the pot limit. Only the first
+  // page of the queue is searched.
+  pub fn next_dispute(env: Env, from: Address) -> Result<DisputeRef, Error> {
+    dispute::next_dispute(&env, from)
+  }
+
+  pub fn get_dispute_arbitrator(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Address> {
+    dispute::get_dispute_arbitrator(&env, escrow_id, milestone_index)
+  }
+
+  pub fn get_dispute_assignment(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Assignment> {
+    dispute::get_dispute_assignment(&env, escrow_id, milestone_index)
+  }
+
+  // Scope objections
+  // The client flags a submitted milestone as out of scope without opening a dispute. The
+  // review clock stops until the freelancer answers or the contest window runs out.
+  pub fn flag_out_of_scope(env: Env, from: Address, escrow_id: u64, milestone_index: u32, note: String) -> Result<Contest, Error> {
+    dispute::flag_out_of_scope(&env, from, escrow_id, milestone_index, note)
+  }
+
+  // Offers the freelancer part of a contested milestone; the rest goes back to the client
+  // if they accept. A new offer replaces the previous one.
+  pub fn offer_partial(env: Env, from: Address, escrow_id: u64, milestone_index: u32, amount: i128) -> Result<(), Error> {
+    dispute::offer_partial(&env, from, escrow_id, milestone_index, amount)
+  }
+
+  // The freelancer takes the client's partial offer, settling the milestone
+  pub fn accept_partial(env: Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    dispute::accept_partial(&env, from, escrow_id, milestone_index)
+  }
+
+  // Once the contest window has run out unanswered, anyone can send the milestone back to
+  // Pending
+  pub fn lapse_contest(env: Env, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+    dispute::lapse_contest(&env, escrow_id, milestone_index)
+  }
+
+  pub fn get_contest(env: Env, escrow_id: u64, milestone_index: u32) -> Option<Contest> {
+    dispute::get_contest(&env, escrow_id, milestone_index)
+  }
+
+  // Lump sum
+  // A project posted without milestones opens a lump-sum escrow: it is funded in full up
+  // front, then one deliverable, one approval (or auto-release) and one payment close it
+  pub fn submit_deliverable(env: Env, from: Address, escrow_id: u64, deliverable: BytesN<32>) -> Result<(), Error> {
+    escrow::submit_deliverable(&env, from, escrow_id, deliverable)
+  }
+
+  pub fn approve_deliverable(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::approve_deliverable(&env, from, escrow_id)
+  }
+
+  pub fn auto_release_deliverable(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::auto_release_deliverable(&env, from, escrow_id)
+  }
+
+  pub fn reject_deliverable(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::reject_deliverable(&env, from, escrow_id)
+  }
+
+  pub fn get_deliverable(env: Env, escrow_id: u64) -> Result<Option<BytesN<32>>, Error> {
+    escrow::get_deliverable(&env, escrow_id)
+  }
+
+  // Refunds an escrow nobody has started on. Fails with RefundNotAllowed once the escrow has
+  // a cancellation schedule, which cancel_escrow settles instead.
+  pub fn refund_funds(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::refund_funds(&env, from, escrow_id)
+  }
+
+  // The client ends an escrow with a cancellation schedule on their own, without a dispute.
+  // The freelancer gets the current tier's share of what the escrow still holds and the rest
+  // is refunded. Fails with MilestoneDisputed while a milestone is in dispute.
+  pub fn cancel_escrow(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::cancel_escrow(&env, from, escrow_id)
+  }
+
+  // Bounties
+  // Lets anyone fund the escrow. Has to be chosen before the first deposit.
+  pub fn open_bounty(env: Env, from: Address, escrow_id: u64, expires_at: u64) -> Result<(), Error> {
+    escrow::open_bounty(&env, from, escrow_id, expires_at)
+  }
+
+  // Past its expiry, anyone can end a bounty that has no milestone in review. What it still
+  // holds goes back to the sponsors pro rata.
+  pub fn expire_bounty(env: Env, escrow_id: u64) -> Result<(), Error> {
+    escrow::expire_bounty(&env, escrow_id)
+  }
+
+  pub fn get_bounty(env: Env, escrow_id: u64) -> Result<Bounty, Error> {
+    escrow::get_bounty(&env, escrow_id)
+  }
+
+  // Cleanup
+  // The freelancer turns down an escrow they have not accepted and nobody has funded. The
+  // project reopens for someone else.
+  pub fn decline_escrow(env: Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::decline_escrow(&env, from, escrow_id)
+  }
+
+  // Frees a completed or refunded escrow's payload, keeping its tombstone. Acceptance
+  // receipts and ratings stay, and still point at the escrow ID. A collected escrow leaves
+  // the per-state counts and indexes.
+  pub fn collect_escrow(env: Env, admin: Address, escrow_id: u64) -> Result<(), Error> {
+    escrow::collect_escrow(&env, admin, escrow_id)
+  }
+
+  pub fn get_escrow_tombstone(env: Env, escrow_id: u64) -> Option<Tombstone> {
+    escrow::get_escrow_tombstone(&env, escrow_id)
+  }
+
+  // Informational: escrow totals the client still has to deposit, and deposits still held,
+  // across their active escrows in one asset
+  pub fn get_commitments(env: Env, client: Address, asset: Address) -> Commitments {
+    escrow::get_commitments(&env, client, asset)
+  }
+
+  // The full escrow for its parties, its acknowledged observers, the arbitrator assigned to
+  // one of its open disputes and the admin; only the header for anyone else
+  pub fn get_escrow(env: Env, viewer: Address, escrow_id: u64) -> Result<EscrowView, Error> {
+    escrow::view_escrow(&env, viewer, escrow_id)
+  }
+
+  // Observers
+  // The client grants an address read access to the escrow: the full get_escrow view and an
+  // observed event for each of the escrow's events. It takes effect once the freelancer
+  // acknowledges it and gives no authority; every other call still takes a party. At most
+  // MAX_OBSERVERS per escrow (LimitReached), and neither party can be one (InvalidOwner).
+  pub fn add_observer(env: Env, client: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+    observer::add_observer(&env, client, escrow_id, observer)
+  }
+
+  pub fn acknowledge_observer(env: Env, freelancer: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+    observer::acknowledge_observer(&env, freelancer, escrow_id, observer)
+  }
+
+  // The client can take an observer off at any time, acknowledged or not
+  pub fn remove_observer(env: Env, client: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+    observer::remove_observer(&env, client, escrow_id, observer)
+  }
+
+  pub fn get_observers(env: Env, escrow_id: u64, cursor: u64, limit: u32) -> ObserverPage {
+    observer::get_observers(&env, escrow_id, cursor, limit)
+  }
+
+  // Keeps the caller's private note on the escrow, replacing any earlier one. Only the
+  // client and the freelancer keep notes, and each reads back only their own.
+  pub fn set_private_note(env: Env, from: Address, escrow_id: u64, note_hash: BytesN<32>) -> Result<(), Error> {
+    escrow::set_private_note(&env, from, escrow_id, note_hash)
+  }
+
+  pub fn get_private_note(env: Env, viewer: Address, escrow_id: u64) -> Result<Option<BytesN<32>>, Error> {
+    escrow::get_private_note(&env, viewer, escrow_id)
+  }
+
+  // The terms_digest the freelancer accepted, once accepted
+  pub fn get_accepted_terms(env: Env, escrow_id: u64) -> Option<BytesN<32>> {
+    escrow::get_accepted_terms(&env, escrow_id)
+  }
+
+  // The escrow's amounts in the reference unit, at the asset's posted rate. Fails with
+  // NotFound while the asset has no rate or only one older than the config allows.
+  pub fn get_escrow_in_reference(env: Env, escrow_id: u64) -> Result<ReferenceView, Error> {
+    escrow::get_escrow_in_reference(&env, escrow_id)
+  }
+
+  // Progress
+  pub fn time_status(env: Env, deadline: u64) -> TimeStatus {
+    escrow::time_status(&env, deadline)
+  }
+
+  pub fn get_progress(env: Env, escrow_id: u64) -> Result<ProgressView, Error> {
+    escrow::get_progress(&env, escrow_id)
+  }
+
+  // What the escrow's remaining milestones need beyond what it still holds. A running escrow
+  // publishes an underfund event each time this grows.
+  pub fn get_shortfall(env: Env, escrow_id: u64) -> Result<i128, Error> {
+    escrow::get_shortfall(&env, escrow_id)
+  }
+
+  // Delay the client caused on each milestone: waiting for funding and reviews past the
+  // review period
+  pub fn get_client_delays(env: Env, escrow_id: u64) -> Map<u32, u64> {
+    escrow::get_client_delays(&env, escrow_id)
+  }
+
+  // What `user` can do on the escrow now, each with the deadline it runs against. Empty for
+  // anyone but the client and the freelancer, and once the escrow has ended.
+  pub fn get_action_items(env: Env, escrow_id: u64, user: Address) -> Result<Vec<ActionItem>, Error> {
+    escrow::get_action_items(&env, escrow_id, user)
+  }
+
+  // The freelancer's hours per milestone, once, after accepting the engagement. A second call
+  // fails with AlreadyAccepted.
+  pub fn set_effort_estimates(env: Env, from: Address, escrow_id: u64, hours: Vec<u32>) -> Result<(), Error> {
+    progress::set_effort_estimates(&env, from, escrow_id, hours)
+  }
+
+  pub fn get_effort_estimates(env: Env, escrow_id: u64) -> Option<EffortEstimate> {
+    progress::get_effort_estimates(&env, escrow_id)
+  }
+
+  // How far a pending milestone has come, 0 to 100 percent, at most once per
+  // PROGRESS_REPORT_SECS per milestone (ReportTooSoon). Only the latest report is kept, and
+  // payouts never read it.
+  pub fn report_progress(env: Env, from: Address, escrow_id: u64, milestone_index: u32, percent: u32) -> Result<(), Error> {
+    progress::report_progress(&env, from, escrow_id, milestone_index, percent)
+  }
+
+  pub fn get_progress_reports(env: Env, escrow_id: u64) -> Map<u32, ProgressReport> {
+    progress::get_progress_reports(&env, escrow_id)
+  }
+
+  // Ratings
+  // `tags` are OutcomeTag registry IDs, at most MAX_RATING_TAGS and each once
+  pub fn rate_freelancer(env: Env, from: Address, escrow_id: u64, rating: u32, comment: String, tags: Vec<u32>) -> Result<(), Error> {
+    rating::rate_freelancer(&env, from, escrow_id, rating, comment, tags)
+  }
+
+  pub fn get_rating(env: Env, escrow_id: u64) -> Result<Rating, Error> {
+    rating::get_rating(&env, escrow_id)
+  }
+
+  // A freelancer's ratings, oldest first
+  pub fn get_ratings(env: Env, freelancer: Address, cursor: u64, limit: u32) -> RatingPage {
+    rating::get_ratings(&env, freelancer, cursor, limit)
+  }
+
+  pub fn get_rating_tags(env: Env, escrow_id: u64) -> Vec<u32> {
+    rating::get_rating_tags(&env, escrow_id)
+  }
+
+  pub fn get_rating_summary(env: Env, freelancer: Address) -> RatingSummary {
+    rating::get_rating_summary(&env, freelancer)
+  }
+
+  // Users
+  pub fn get_profile(env: Env, user: Address) -> Profile {
+    user::get_profile(&env, user)
+  }
+
+  // Working languages shown on the user's profile and matched against projects
+  pub fn set_languages(env: Env, from: Address, languages: Vec<Symbol>) -> Result<(), Error> {
+    user::set_languages(&env, from, languages)
+  }
+
+  pub fn is_allowlisted(env: Env, user: Address) -> bool {
+    admin::is_allowlisted(&env, user)
+  }
+
+  pub fn get_user_stats(env: Env, user: Address) -> UserStats {
+    user::get_user_stats(&env, user)
+  }
+
+  pub fn get_freelancer_stats(env: Env, freelancer: Address) -> FreelancerStats {
+    user::get_freelancer_stats(&env, freelancer)
+  }
+
+  // The freelancer's risk tier, its cap from the config and what their active escrows count
+  // for against it. Opening an escrow past the cap fails with LimitReached; this shows by how
+  // much.
+  pub fn get_risk_limit(env: Env, freelancer: Address) -> RiskLimit {
+    risk::get_risk_limit(&env, freelancer)
+  }
+
+  // Where a recovery request for the user's escrows has to come from. Replacing the address
+  // takes RECOVERY_DELAY_SECS; the old one stays in effect until then.
+  pub fn set_recovery_address(env: Env, from: Address, recovery: Address) -> Result<(), Error> {
+    user::set_recovery_address(&env, from, recovery)
+  }
+
+  pub fn get_recovery_setting(env: Env, user: Address) -> Option<RecoverySetting> {
+    user::get_recovery_setting(&env, user)
+  }
+
+  // Shown to clients comparing proposals; it does not stop anyone from opening an escrow
+  pub fn set_availability(env: Env, from: Address, available: bool) {
+    user::set_availability(&env, from, available)
+  }
+
+  // Review habits of a client over the escrows they completed. Auto-released milestones
+  // count at the full review period.
+  pub fn get_counterparty_risk(env: Env, client: Address) -> CounterpartyRisk {
+    user::get_counterparty_risk(&env, client)
+  }
+
+  // Monitoring
+  pub fn count_escrows_by_state(env: Env) -> EscrowStateCounts {
+    escrow::count_escrows_by_state(&env)
+  }
+
+  // Lists escrow IDs currently in a state, oldest transition first. The index is bounded, so
+  // once it is full the count can exceed what is listed.
+  pub fn list_escrows_by_state(env: Env, state: EscrowState, cursor: u64, limit: u32) -> IdPage {
+    escrow::list_escrows_by_state(&env, state, cursor, limit)
+  }
+
+  // Active escrows with no activity for at least `older_than_secs`, least recently active
+  // first. Escrows whose activity bucket was full are not listed. The total is not known
+  // without walking every bucket, so it is left out.
+  pub fn list_stale_escrows(env: Env, older_than_secs: u64, cursor: u64, limit: u32) -> IdPage {
+    escrow::list_stale_escrows(&env, older_than_secs, cursor, limit)
+  }
+
+  // Debug read: reports repeated IDs and IDs that no longer belong in one page of an index
+  pub fn check_index_integrity(env: Env, index: IndexKind, offset: u32) -> IndexReport {
+    admin::check_index_integrity(&env, index, offset)
+  }
+
+  // Monitoring canary, meant to be simulated: checks up to 20 escrows' accounting invariants
+  // and their assets' obligations without writing anything. Each failed check also raises a
+  // chk_fail event.
+  pub fn self_check(env: Env, sample: Vec<u64>) -> Result<Vec<CheckResult>, Error> {
+    admin::self_check(&env, sample)
+  }
+
+  // Legacy entry points
+  // post_project with the version 1 signature: u64 amounts, no asset and no locale. The
+  // project is in the legacy asset (InvalidConfig until the admin sets one).
+  pub fn post_project_v1(
+    env: Env,
+    from: Address, // Client address
+    title: String,
+    description: String,
+    category: String,
+    budget: u64,
+    deadline: u64, // Unix timestamp for deadline
+    milestones: Vec<LegacyMilestone>,
+  ) -> Result<u64, Error> {
+    legacy::post_project_v1(&env, from, title, description, category, budget, deadline, milestones)
+  }
+
+  // deposit_funds with the version 1 signature: a u64 amount and no memo
+  pub fn deposit_funds_v1(env: Env, from: Address, escrow_id: u64, amount: u64) -> Result<(), Error> {
+    legacy::deposit_funds_v1(&env, from, escrow_id, amount)
+  }
+}
@@ -0,0 +1,187 @@
+// Vec-based ID indexes. Every index is read and written through these helpers, so IDs stay
+// unique and in insertion order, and an emptied index leaves no entry behind. Each change is
+// written through before its helper returns, never buffered, so a listing later in the same
+// invocation, a contract that posts and then lists for one, sees it. Debug builds read the
+// index back after every write to hold that. Every write also extends the index's TTL by its
+// class.
+
+use soroban_sdk::{ contracttype, Address, Env, String, Vec };
+
+use crate::page::{ self, IdPage };
+use crate::escrow::is_active;
+use crate::ttl::TtlClass;
+use crate::{ storage, ttl, EscrowState, MilestoneStatus, ACTIVITY_BUCKET_SECS, MAX_LIST_LIMIT };
+
+// Upper bound on the number of ids kept in each per-state escrow index
+pub const MAX_STATE_INDEX_LEN: u32 = 1000;
+// Upper bound on the number of active escrows indexed per client/freelancer pair
+pub const MAX_PAIR_INDEX_LEN: u32 = 1000;
+// Upper bound on the number of proposals a project takes
+pub const MAX_PROPOSALS_PER_PROJECT: u32 = 100;
+// Upper bound on the number of disputes waiting for an arbitrator
+pub const MAX_OPEN_DISPUTES: u32 = 1000;
+// Upper bound on the number of escrows indexed per activity bucket
+pub const MAX_ACTIVITY_BUCKET_LEN: u32 = 1000;
+// Upper bound on the number of non-empty activity buckets. Buckets are a week wide, so this
+// covers far more history than any escrow lives.
+pub const MAX_ACTIVITY_BUCKETS: u32 = 10_000;
+// Upper bound on the number of ratings listed per freelancer
+pub const MAX_RATINGS_PER_FREELANCER: u32 = 1000;
+// Upper bound on the number of active service listings per category
+pub const MAX_SERVICES_PER_CATEGORY: u32 = 1000;
+// Upper bound on the number of subcontracts opened on one milestone
+pub const MAX_SUBCONTRACTS_PER_MILESTONE: u32 = 10;
+
+// Every index the contract keeps
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub enum IndexKind {
+  EscrowsByState(EscrowState),
+  PairEscrows(Address, Address), // Client, freelancer
+  ProjectProposals(u64), // Project ID
+  OpenDisputes, // Escrow ID and milestone index, packed
+  ActivityBucket(u64), // Bucket number (last activity time / bucket width)
+  ActivityBuckets, // Bucket numbers, oldest first
+  FreelancerRatings(Address), // Rated escrow IDs
+  CategoryServices(String), // Active service listing IDs in a category
+  Subcontracts(u64, u32), // Child escrow IDs, by parent escrow ID and milestone index
+}
+
+// What check_index_integrity found in one page of an index
+#[derive(Clone, Debug, PartialEq)]
+#[contracttype]
+pub struct IndexReport {
+  pub len: u32, // Total IDs in the index
+  pub scanned: u32, // IDs checked in this page
+  pub duplicates: Vec<u64>, // IDs that also appear earlier in the index
+  pub dangling: Vec<u64>, // IDs whose escrow is missing or no longer belongs in the index
+}
+
+// Packs two numbers into one ID, the first in the high 32 bits. Disputes (escrow ID and
+// milestone index) are indexed this way, and listing cursors (position and offset) use it.
+pub fn pack(high: u64, low: u32) -> u64 {
+  (high << 32) | low as u64
+}
+
+pub fn unpack(packed: u64) -> (u64, u32) {
+  (packed >> 32, packed as u32)
+}
+
+// Ratings and service listings are profile data; every other index lists live work
+fn ttl_class(index: &IndexKind) -> TtlClass {
+  match index {
+    IndexKind::FreelancerRatings(_) | IndexKind::CategoryServices(_) => TtlClass::Warm,
+    _ => TtlClass::Hot,
+  }
+}
+
+fn write(env: &Env, index: &IndexKind, ids: &Vec<u64>) {
+  let key = storage::index(index);
+  key.set(env, ids);
+  ttl::bump(env, &key, ttl_class(index));
+}
+
+// Every ID in the index. Callers bound the work by the index's own cap.
+pub fn read(env: &Env, index: &IndexKind) -> Vec<u64> {
+  storage::index(index).get(env).unwrap_or(Vec::new(env))
+}
+
+// Appends an ID unless it is already listed or the index holds `cap` IDs. Returns whether
+// the ID is listed afterwards.
+pub fn append_unique(env: &Env, index: &IndexKind, id: u64, cap: u32) -> bool {
+  let mut ids = read(env, index);
+  if ids.contains(id) {
+    return true;
+  }
+  if ids.len() >= cap {
+    return false;
+  }
+  ids.push_back(id);
+  write(env, index, &ids);
+  debug_assert_listed(env, index, id, true);
+  true
+}
+
+// Removes every occurrence of an ID. Returns whether it was listed.
+pub fn remove_value(env: &Env, index: &IndexKind, id: u64) -> bool {
+  let mut ids = read(env, index);
+  let mut removed = false;
+  while let Some(position) = ids.first_index_of(id) {
+    ids.remove(position);
+    removed = true;
+  }
+  if !removed {
+    return false;
+  }
+  if ids.is_empty() {
+    storage::index(index).remove(env);
+  } else {
+    write(env, index, &ids);
+  }
+  debug_assert_listed(env, index, id, false);
+  true
+}
+
+// A write a later read could miss fails here in tests instead of showing up as a stale listing
+fn debug_assert_listed(env: &Env, index: &IndexKind, id: u64, listed: bool) {
+  debug_assert!(read(env, index).contains(id) == listed, "index write not visible to a later read");
+}
+
+// One page of IDs from `cursor` on, cut by page::window
+pub fn paged_read(env: &Env, index: &IndexKind, cursor: u64, limit: u32) -> IdPage {
+  let ids = read(env, index);
+  let (positions, next_cursor) = page::window(ids.len(), cursor, limit);
+  IdPage { items: ids.slice(positions), next_cursor, total: Some(ids.len()) }
+}
+
+pub fn len(env: &Env, index: &IndexKind) -> u32 {
+  read(env, index).len()
+}
+
+// Checks one page of an index for repeated IDs and IDs that point at an entry which is
+// missing or no longer matches the index
+pub fn check_integrity(env: &Env, index: &IndexKind, offset: u32) -> IndexReport {
+  let ids = read(env, index);
+  let (positions, _) = page::window(ids.len(), offset as u64, MAX_LIST_LIMIT);
+  let mut report = IndexReport { len: ids.len(), scanned: positions.len() as u32, duplicates: Vec::new(env), dangling: Vec::new(env) };
+  for position in positions {
+    let id = ids.get(position).unwrap();
+    if ids.first_index_of(id) != Some(position) {
+      report.duplicates.push_back(id);
+    }
+    let escrow = || storage::escrows(id).get(env);
+    let belongs = match index {
+      IndexKind::EscrowsByState(state) => escrow().is_some_and(|escrow| escrow.state == *state),
+      IndexKind::PairEscrows(client, freelancer) => {
+        escrow().is_some_and(|escrow| escrow.client == *client && escrow.freelancer == *freelancer && is_active(&escrow))
+      }
+      IndexKind::ProjectProposals(project_id) => {
+        storage::proposals(id).get(env).is_some_and(|proposal| proposal.project_id == *project_id)
+      }
+      IndexKind::OpenDisputes => {
+        let (escrow_id, milestone_index) = unpack(id);
+        let records = storage::milestone_records(escrow_id).get(env);
+        let disputed = records.and_then(|records| records.get(milestone_index)).is_some_and(|record| record.status == MilestoneStatus::Disputed);
+        disputed && !storage::dispute_arbitrators(escrow_id, milestone_index).has(env)
+      }
+      IndexKind::ActivityBucket(bucket) => {
+        let last_activity = storage::escrow_activity(id).get(env);
+        escrow().is_some_and(|escrow| is_active(&escrow)) && last_activity.is_some_and(|last_activity| last_activity / ACTIVITY_BUCKET_SECS == *bucket)
+      }
+      IndexKind::ActivityBuckets => len(env, &IndexKind::ActivityBucket(id)) > 0,
+      IndexKind::FreelancerRatings(freelancer) => {
+        storage::ratings(id).get(env).is_some_and(|rating| rating.to == *freelancer)
+      }
+      IndexKind::Subcontracts(parent_id, milestone_index) => {
+        storage::subcontracts(id).get(env).is_some_and(|link| link.parent_id == *parent_id && link.milestone_index == *milestone_index)
+      }
+      IndexKind::CategoryServices(category) => {
+        storage::services(id).get(env).is_some_and(|listing| listing.active && listing.category == *category)
+      }
+    };
+    if !belongs {
+      report.dangling.push_back(id);
+    }
+  }
+  report
+}
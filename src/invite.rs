@@ -0,0 +1,124 @@
+// Invite codes: short random codes a client hands a freelancer, resolving to everything
+// needed to review and accept an engagement without knowing the escrow ID.
+
+use soroban_sdk::{ Address, BytesN, Env };
+
+use crate::escrow::{ accept_engagement, is_accepted, is_active, terms_digest };
+use crate::{ storage, EngagementOffer, Error, Invite, INVITE_SECS, MAX_INVITE_DRAWS };
+
+pub fn create_invite_code(env: &Env, from: Address, escrow_id: u64) -> Result<BytesN<8>, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+
+  let code = draw_code(env)?;
+  storage::invites(code.clone()).set(env, &Invite { escrow_id, expires_at: env.ledger().timestamp() + INVITE_SECS });
+  Ok(code)
+}
+
+pub fn revoke_invite(env: &Env, from: Address, code: BytesN<8>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let invite = storage::invites(code.clone()).get(env).ok_or(Error::NotFound)?;
+  let escrow = storage::escrows(invite.escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  storage::invites(code).remove(env);
+  Ok(())
+}
+
+pub fn resolve_invite(env: &Env, code: BytesN<8>) -> Result<EngagementOffer, Error> {
+  let invite = live_invite(env, &code)?;
+  let escrow = storage::escrows(invite.escrow_id).get(env).ok_or(Error::NotFound)?;
+  Ok(EngagementOffer {
+    escrow_id: invite.escrow_id,
+    project_id: escrow.project_id,
+    client: escrow.client,
+    freelancer: escrow.freelancer,
+    asset: escrow.asset,
+    total_amount: escrow.total_amount,
+    lump_sum: escrow.lump_sum,
+    milestones: escrow.milestones,
+    terms_digest: terms_digest(env, invite.escrow_id)?,
+    accept_by: invite.expires_at,
+  })
+}
+
+// accept_engagement by code. The code is used up.
+pub fn accept_invite(env: &Env, from: Address, code: BytesN<8>, digest: BytesN<32>) -> Result<(), Error> {
+  let invite = live_invite(env, &code)?;
+  accept_engagement(env, from, invite.escrow_id, digest)?;
+  storage::invites(code).remove(env);
+  Ok(())
+}
+
+// An invite that still resolves, up to and including its last second. Past that it is
+// NotFound, the same as a code that was revoked or never issued.
+fn live_invite(env: &Env, code: &BytesN<8>) -> Result<Invite, Error> {
+  storage::invites(code.clone()).get(env).filter(|invite| env.ledger().timestamp() <= invite.expires_at).ok_or(Error::NotFound)
+}
+
+// A code no invite uses yet, redrawn from the ledger PRNG on collision. Expired codes stay
+// taken, so a stale link never starts resolving to someone else's escrow. LimitReached when
+// every draw is taken.
+fn draw_code(env: &Env) -> Result<BytesN<8>, Error> {
+  (0..MAX_INVITE_DRAWS)
+    .map(|_| env.prng().gen::<BytesN<8>>())
+    .find(|code| !storage::invites(code.clone()).has(env))
+    .ok_or(Error::LimitReached)
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::{ Bytes, BytesN, Env, Vec };
+
+  use crate::invite::draw_code;
+  use crate::test::harness;
+  use crate::{ storage, Error, Invite, MAX_INVITE_DRAWS };
+
+  // The codes draw_code will try, in order, once the PRNG is seeded the same way
+  fn upcoming_codes(env: &Env) -> Vec<BytesN<8>> {
+    env.prng().seed(Bytes::from_array(env, &[7; 32]));
+    let mut codes = Vec::new(env);
+    for _ in 0..MAX_INVITE_DRAWS {
+      codes.push_back(env.prng().gen());
+    }
+    env.prng().seed(Bytes::from_array(env, &[7; 32]));
+    codes
+  }
+
+  #[test]
+  fn a_taken_code_is_redrawn() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      let codes = upcoming_codes(&env);
+      for code in codes.slice(0..MAX_INVITE_DRAWS - 1) {
+        storage::invites(code).set(&env, &Invite { escrow_id: 1, expires_at: 0 });
+      }
+      assert_eq!(draw_code(&env), Ok(codes.last().unwrap()));
+    });
+  }
+
+  #[test]
+  fn drawing_gives_up_once_every_draw_is_taken() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      for code in upcoming_codes(&env) {
+        storage::invites(code).set(&env, &Invite { escrow_id: 1, expires_at: 0 });
+      }
+      assert_eq!(draw_code(&env), Err(Error::LimitReached));
+    });
+  }
+}
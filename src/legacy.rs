@@ -0,0 +1,51 @@
+// The version 1 call surface, for callers built before amounts became i128 and posting took
+// an asset and a locale. Each wrapper converts its arguments and delegates to the current
+// call, so it leaves the same state behind. The admin turns the surface off through the
+// config's legacy_api switch once callers have moved; from then on each wrapper fails with
+// Deprecated.
+
+use soroban_sdk::{ Address, Env, String, Symbol, Vec };
+
+use crate::admin::config;
+use crate::{ escrow, project, storage, Error, LegacyMilestone, Locale, Milestone };
+
+pub fn post_project_v1(
+  env: &Env,
+  from: Address,
+  title: String,
+  description: String,
+  category: String,
+  budget: u64,
+  deadline: u64,
+  milestones: Vec<LegacyMilestone>,
+) -> Result<u64, Error> {
+  check_enabled(env)?;
+  // Version 1 projects did not name an asset; they are in the one set for legacy entries
+  let asset = storage::legacy_asset().get(env).ok_or(Error::InvalidConfig)?;
+  let mut converted = Vec::new(env);
+  for milestone in milestones {
+    converted.push_back(Milestone { description: milestone.description, amount: milestone.amount.into(), completed: milestone.completed, deadline: milestone.deadline });
+  }
+  project::post_project(env, from, title, description, category, budget.into(), asset, deadline, converted, Locale::default())
+}
+
+pub fn deposit_funds_v1(env: &Env, from: Address, escrow_id: u64, amount: u64) -> Result<(), Error> {
+  check_enabled(env)?;
+  escrow::deposit_funds(env, from, escrow_id, amount.into(), None)
+}
+
+// The wrappers describe() lists as deprecated while they still answer
+pub fn deprecated_calls(env: &Env) -> Vec<Symbol> {
+  if !config(env).legacy_api {
+    return Vec::new(env);
+  }
+  Vec::from_array(env, [Symbol::new(env, "post_project_v1"), Symbol::new(env, "deposit_funds_v1")])
+}
+
+fn check_enabled(env: &Env) -> Result<(), Error> {
+  if config(env).legacy_api {
+    Ok(())
+  } else {
+    Err(Error::Deprecated)
+  }
+}
@@ -0,0 +1,133 @@
+// Arithmetic on amounts and basis points. Nothing here reads or writes storage.
+
+use soroban_sdk::{ Env, Vec };
+
+use crate::{ Error, Milestone, MilestoneShare, BPS_DENOMINATOR, RATE_SCALE };
+
+// Each share is rounded down and any rounding dust goes to the last milestone,
+// so the expanded amounts always sum to the budget
+pub fn expand_milestone_shares(env: &Env, budget: i128, shares: &Vec<MilestoneShare>) -> Result<Vec<Milestone>, Error> {
+  if shares.is_empty() {
+    return Err(Error::InvalidShares);
+  }
+  let mut total_bps: u32 = 0;
+  for share in shares.iter() {
+    total_bps = total_bps.checked_add(share.bps).ok_or(Error::InvalidShares)?;
+  }
+  if total_bps != BPS_DENOMINATOR {
+    return Err(Error::InvalidShares);
+  }
+
+  let mut milestones = Vec::new(env);
+  let mut allocated: i128 = 0;
+  let last = shares.len() - 1;
+  for (i, share) in shares.iter().enumerate() {
+    let amount = if i as u32 == last {
+      budget - allocated
+    } else {
+      bps_of(budget, share.bps).ok_or(Error::InvalidAmount)?
+    };
+    allocated += amount;
+    milestones.push_back(Milestone {
+      description: share.description,
+      amount,
+      completed: false,
+      deadline: share.deadline,
+    });
+  }
+  Ok(milestones)
+}
+
+// Rescales a schedule summing to `from_total` so it sums to `to_total`. Each amount is rounded
+// down and the rounding dust goes to the last milestone.
+pub fn scale_milestones(env: &Env, milestones: &Vec<Milestone>, from_total: i128, to_total: i128) -> Result<Vec<Milestone>, Error> {
+  if milestones.is_empty() || from_total == to_total {
+    return Ok(milestones.clone());
+  }
+  if from_total <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let mut scaled = Vec::new(env);
+  let mut allocated: i128 = 0;
+  let last = milestones.len() - 1;
+  for (i, mut milestone) in milestones.iter().enumerate() {
+    milestone.amount = if i as u32 == last {
+      to_total - allocated
+    } else {
+      milestone.amount.checked_mul(to_total).ok_or(Error::InvalidAmount)? / from_total
+    };
+    allocated += milestone.amount;
+    scaled.push_back(milestone);
+  }
+  Ok(scaled)
+}
+
+// Rounds down, so the remainder always stays with the payer
+pub fn bps_of(amount: i128, bps: u32) -> Option<i128> {
+  Some(amount.checked_mul(bps as i128)? / BPS_DENOMINATOR as i128)
+}
+
+// An amount in the reference unit at a rate scaled by RATE_SCALE, rounded down
+pub fn to_reference(amount: i128, rate: i128) -> Option<i128> {
+  Some(amount.checked_mul(rate)? / RATE_SCALE)
+}
+
+#[cfg(test)]
+mod test {
+  extern crate std;
+
+  use soroban_sdk::{ vec, Env, String, Vec };
+
+  use crate::math::{ bps_of, expand_milestone_shares, scale_milestones, to_reference };
+  use crate::{ Error, Milestone, MilestoneShare };
+
+  fn amounts(milestones: &Vec<Milestone>) -> std::vec::Vec<i128> {
+    milestones.iter().map(|milestone| milestone.amount).collect()
+  }
+
+  fn share(env: &Env, bps: u32) -> MilestoneShare {
+    MilestoneShare { description: String::from_str(env, "Part"), bps, deadline: 0 }
+  }
+
+  fn milestone(env: &Env, amount: i128) -> Milestone {
+    Milestone { description: String::from_str(env, "Part"), amount, completed: false, deadline: 0 }
+  }
+
+  #[test]
+  fn rounding_dust_from_shares_goes_to_the_last_milestone() {
+    let env = Env::default();
+    let shares = vec![&env, share(&env, 3333), share(&env, 3333), share(&env, 3334)];
+    assert_eq!(amounts(&expand_milestone_shares(&env, 100, &shares).unwrap()), [33, 33, 34]);
+  }
+
+  #[test]
+  fn shares_must_cover_the_whole_budget() {
+    let env = Env::default();
+    assert_eq!(expand_milestone_shares(&env, 100, &vec![&env, share(&env, 5000)]).err(), Some(Error::InvalidShares));
+    assert_eq!(expand_milestone_shares(&env, 100, &Vec::new(&env)).err(), Some(Error::InvalidShares));
+    assert_eq!(expand_milestone_shares(&env, 100, &vec![&env, share(&env, u32::MAX), share(&env, 1)]).err(), Some(Error::InvalidShares));
+  }
+
+  #[test]
+  fn scaling_keeps_the_proportions_and_the_total() {
+    let env = Env::default();
+    let milestones = vec![&env, milestone(&env, 400), milestone(&env, 600)];
+    assert_eq!(amounts(&scale_milestones(&env, &milestones, 1000, 500).unwrap()), [200, 300]);
+    assert_eq!(amounts(&scale_milestones(&env, &milestones, 1000, 333).unwrap()), [133, 200]);
+    assert_eq!(scale_milestones(&env, &milestones, 0, 500).err(), Some(Error::InvalidAmount));
+  }
+
+  #[test]
+  fn basis_points_round_down_and_overflow_to_none() {
+    assert_eq!(bps_of(1000, 250), Some(25));
+    assert_eq!(bps_of(99, 100), Some(0));
+    assert_eq!(bps_of(i128::MAX, 2), None);
+  }
+
+  #[test]
+  fn reference_amounts_round_down_and_overflow_to_none() {
+    assert_eq!(to_reference(1000, 12_500_000), Some(1250));
+    assert_eq!(to_reference(3, 3_333_333), Some(0));
+    assert_eq!(to_reference(i128::MAX, 2), None);
+  }
+}
@@ -0,0 +1,683 @@
+// Storage layout upgrades. Every layout change bumps DATA_VERSION and adds a reader for the
+// layout it replaces; each reader rewrites entries of its version straight into the current
+// layout. `run` goes through a bounded number of IDs per call, so large deployments can
+// migrate over several transactions.
+
+use soroban_sdk::{ contracttype, Address, BytesN, Env, IntoVal, String, TryFromVal, Val, Vec };
+
+use crate::escrow::{ adjust_commitments, is_active };
+use crate::index::IndexKind;
+use crate::storage::Entry;
+use crate::ttl::TtlClass;
+use crate::{ storage, ttl, Config, Deposit, Error, Escrow, EscrowState, LaunchMode, Milestone, MilestoneRecord, MilestoneStatus, Project, ProjectStatus, RiskCaps, ACTIVITY_BUCKET_SECS };
+
+// Layout written by the current code
+pub const DATA_VERSION: u32 = 12;
+
+// Contracts deployed before the version was stored hold layout 1
+pub fn data_version(env: &Env) -> u32 {
+  storage::data_version().get(env).unwrap_or(1)
+}
+
+// Migrates up to `limit` IDs and returns the data version afterwards. Call again until it
+// returns DATA_VERSION.
+pub fn run(env: &Env, limit: u32) -> Result<u32, Error> {
+  if limit == 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let version = data_version(env);
+  if version >= DATA_VERSION {
+    return Ok(version);
+  }
+
+  // The config is a single entry, rewritten before the first batch
+  if !storage::migration_cursor().has(env) {
+    match version {
+      ..=4 => v4::migrate_config(env),
+      5 => v5::migrate_config(env),
+      6 => v6::migrate_config(env),
+      7..=8 => v8::migrate_config(env),
+      9 => v9::migrate_config(env),
+      10 => v10::migrate_config(env),
+      _ => {}
+    }
+    v11::migrate_shared(env);
+  }
+
+  // One reader per older layout, then every layout moves the batch out of the instance
+  let (start, end, done) = next_batch(env, limit);
+  match version {
+    1 => v1::migrate(env, start, end)?,
+    2 => v2::migrate(env, start, end)?,
+    3 => v3::migrate(env, start, end)?,
+    4..=7 => v7::migrate(env, start, end),
+    8..=11 => {}
+    _ => return Err(Error::InvalidConfig),
+  }
+  v11::migrate(env, start, end);
+  if !done {
+    return Ok(version);
+  }
+  storage::migration_cursor().remove(env);
+  storage::data_version().set(env, &DATA_VERSION);
+  Ok(DATA_VERSION)
+}
+
+// Layouts before 4 did not name an asset; the admin sets the one their amounts are in
+fn legacy_asset(env: &Env) -> Result<Address, Error> {
+  storage::legacy_asset().get(env).ok_or(Error::InvalidConfig)
+}
+
+// Stores a migrated escrow and counts it towards its client's commitments, which layouts
+// before 4 did not track
+fn put_migrated_escrow(env: &Env, escrow_id: u64, escrow: &Escrow, accepted_terms: Option<BytesN<32>>) {
+  adjust_commitments(env, None, Some(escrow));
+  storage::escrows(escrow_id).set(env, escrow);
+  put_accepted_terms(env, escrow_id, accepted_terms);
+}
+
+// Layouts before 8 kept the accepted digest inside the escrow
+fn put_accepted_terms(env: &Env, escrow_id: u64, accepted_terms: Option<BytesN<32>>) {
+  if let Some(digest) = accepted_terms {
+    storage::accepted_terms(escrow_id).set(env, &digest);
+  }
+}
+
+// Advances the shared ID cursor over projects, escrows and service listings. Returns the IDs
+// to migrate in this call and whether they finish the step.
+fn next_batch(env: &Env, limit: u32) -> (u64, u64, bool) {
+  let last_project = storage::project_count().get(env).unwrap_or(0);
+  let last_escrow = storage::escrow_count().get(env).unwrap_or(0);
+  let last_service = storage::service_count().get(env).unwrap_or(0);
+  let last = last_project.max(last_escrow).max(last_service);
+  let start = storage::migration_cursor().get(env).unwrap_or(1);
+  let end = last.min(start + limit as u64 - 1);
+  storage::migration_cursor().set(env, &(end + 1));
+  (start, end, end >= last)
+}
+
+// Layout 1: amounts were u64 and escrows did not record when they were opened
+pub(crate) mod v1 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct MilestoneV1 {
+    pub description: String,
+    pub amount: u64,
+    pub completed: bool,
+    pub deadline: u64,
+  }
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ProjectV1 {
+    pub id: u64,
+    pub client: Address,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub budget: u64,
+    pub deadline: u64,
+    pub milestones: Vec<MilestoneV1>,
+    pub status: ProjectStatus,
+  }
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct EscrowV1 {
+    pub project_id: u64,
+    pub client: Address,
+    pub freelancer: Address,
+    pub total_amount: u64,
+    pub milestones: Vec<MilestoneV1>,
+    pub deposited_amount: u64,
+    pub released_amount: u64,
+    pub state: EscrowState,
+    pub client_active_at: u64,
+    pub lump_sum: bool,
+    pub accepted_terms: Option<BytesN<32>>,
+  }
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct MilestoneRecordV1 {
+    pub status: MilestoneStatus,
+    pub deliverable: Option<BytesN<32>>,
+    pub submitted_at: u64,
+    pub paid: u64,
+  }
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct DepositV1 {
+    pub depositor: Address,
+    pub amount: u64,
+    pub memo: Option<BytesN<32>>,
+    pub timestamp: u64,
+  }
+
+  pub fn migrate(env: &Env, start: u64, end: u64) -> Result<(), Error> {
+    let asset = legacy_asset(env)?;
+    for id in start..=end {
+      migrate_project(env, id, &asset);
+      migrate_escrow(env, id, &asset);
+    }
+    Ok(())
+  }
+
+  fn migrate_milestones(env: &Env, milestones: Vec<MilestoneV1>) -> Vec<Milestone> {
+    let mut migrated = Vec::new(env);
+    for milestone in milestones.iter() {
+      migrated.push_back(Milestone {
+        description: milestone.description,
+        amount: milestone.amount as i128,
+        completed: milestone.completed,
+        deadline: milestone.deadline,
+      });
+    }
+    migrated
+  }
+
+  fn migrate_project(env: &Env, project_id: u64, asset: &Address) {
+    let key = storage::projects(project_id);
+    let Some(project) = key.as_layout::<ProjectV1>().get(env) else {
+      return;
+    };
+    key.set(env, &Project {
+      id: project.id,
+      client: project.client,
+      title: project.title,
+      description: project.description,
+      category: project.category,
+      budget: project.budget as i128,
+      asset: asset.clone(),
+      deadline: project.deadline,
+      milestones: migrate_milestones(env, project.milestones),
+      status: project.status,
+    });
+  }
+
+  fn migrate_escrow(env: &Env, escrow_id: u64, asset: &Address) {
+    let key = storage::escrows(escrow_id);
+    let Some(escrow) = key.as_layout::<EscrowV1>().get(env) else {
+      return;
+    };
+
+    let deposits_key = storage::deposits(escrow_id);
+    let mut deposits = Vec::new(env);
+    for deposit in deposits_key.as_layout::<Vec<DepositV1>>().get(env).unwrap_or(Vec::new(env)).iter() {
+      deposits.push_back(Deposit {
+        depositor: deposit.depositor,
+        amount: deposit.amount as i128,
+        memo: deposit.memo,
+        timestamp: deposit.timestamp,
+      });
+    }
+    if !deposits.is_empty() {
+      deposits_key.set(env, &deposits);
+    }
+
+    let records_key = storage::milestone_records(escrow_id);
+    let mut records = Vec::new(env);
+    for record in records_key.as_layout::<Vec<MilestoneRecordV1>>().get(env).unwrap_or(Vec::new(env)).iter() {
+      records.push_back(MilestoneRecord {
+        status: record.status,
+        deliverable: record.deliverable,
+        submitted_at: record.submitted_at,
+        paid: record.paid as i128,
+      });
+    }
+    records_key.set(env, &records);
+
+    // Layout 1 did not record when the escrow was opened. The earliest time it did record,
+    // the first deposit or the client's last activity, stands in for it.
+    let created_at = deposits.first().map_or(escrow.client_active_at, |deposit| deposit.timestamp.min(escrow.client_active_at));
+    put_migrated_escrow(env, escrow_id, &Escrow {
+      project_id: escrow.project_id,
+      client: escrow.client,
+      freelancer: escrow.freelancer,
+      total_amount: escrow.total_amount as i128,
+      asset: asset.clone(),
+      milestones: migrate_milestones(env, escrow.milestones),
+      deposited_amount: escrow.deposited_amount as i128,
+      released_amount: escrow.released_amount as i128,
+      refunded_amount: 0,
+      state: escrow.state,
+      created_at,
+      client_active_at: escrow.client_active_at,
+      lump_sum: escrow.lump_sum,
+    }, escrow.accepted_terms);
+  }
+}
+
+// Layout 2: escrows did not track funds returned to the client
+pub(crate) mod v2 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct EscrowV2 {
+    pub project_id: u64,
+    pub client: Address,
+    pub freelancer: Address,
+    pub total_amount: i128,
+    pub milestones: Vec<Milestone>,
+    pub deposited_amount: i128,
+    pub released_amount: i128,
+    pub state: EscrowState,
+    pub created_at: u64,
+    pub client_active_at: u64,
+    pub lump_sum: bool,
+    pub accepted_terms: Option<BytesN<32>>,
+  }
+
+  pub fn migrate(env: &Env, start: u64, end: u64) -> Result<(), Error> {
+    let asset = legacy_asset(env)?;
+    for id in start..=end {
+      v3::migrate_project(env, id, &asset);
+      let Some(escrow) = storage::escrows(id).as_layout::<EscrowV2>().get(env) else {
+        continue;
+      };
+      put_migrated_escrow(env, id, &Escrow {
+        project_id: escrow.project_id,
+        client: escrow.client,
+        freelancer: escrow.freelancer,
+        total_amount: escrow.total_amount,
+        asset: asset.clone(),
+        milestones: escrow.milestones,
+        deposited_amount: escrow.deposited_amount,
+        released_amount: escrow.released_amount,
+        refunded_amount: 0,
+        state: escrow.state,
+        created_at: escrow.created_at,
+        client_active_at: escrow.client_active_at,
+        lump_sum: escrow.lump_sum,
+      }, escrow.accepted_terms);
+    }
+    Ok(())
+  }
+}
+
+// Layout 3: projects and escrows did not name the asset their amounts are in
+pub(crate) mod v3 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ProjectV3 {
+    pub id: u64,
+    pub client: Address,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    pub budget: i128,
+    pub deadline: u64,
+    pub milestones: Vec<Milestone>,
+    pub status: ProjectStatus,
+  }
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct EscrowV3 {
+    pub project_id: u64,
+    pub client: Address,
+    pub freelancer: Address,
+    pub total_amount: i128,
+    pub milestones: Vec<Milestone>,
+    pub deposited_amount: i128,
+    pub released_amount: i128,
+    pub refunded_amount: i128,
+    pub state: EscrowState,
+    pub created_at: u64,
+    pub client_active_at: u64,
+    pub lump_sum: bool,
+    pub accepted_terms: Option<BytesN<32>>,
+  }
+
+  pub fn migrate(env: &Env, start: u64, end: u64) -> Result<(), Error> {
+    let asset = legacy_asset(env)?;
+    for id in start..=end {
+      migrate_project(env, id, &asset);
+      let Some(escrow) = storage::escrows(id).as_layout::<EscrowV3>().get(env) else {
+        continue;
+      };
+      put_migrated_escrow(env, id, &Escrow {
+        project_id: escrow.project_id,
+        client: escrow.client,
+        freelancer: escrow.freelancer,
+        total_amount: escrow.total_amount,
+        asset: asset.clone(),
+        milestones: escrow.milestones,
+        deposited_amount: escrow.deposited_amount,
+        released_amount: escrow.released_amount,
+        refunded_amount: escrow.refunded_amount,
+        state: escrow.state,
+        created_at: escrow.created_at,
+        client_active_at: escrow.client_active_at,
+        lump_sum: escrow.lump_sum,
+      }, escrow.accepted_terms);
+    }
+    Ok(())
+  }
+
+  // Projects kept the same layout from 2 to 3
+  pub fn migrate_project(env: &Env, project_id: u64, asset: &Address) {
+    let key = storage::projects(project_id);
+    let Some(project) = key.as_layout::<ProjectV3>().get(env) else {
+      return;
+    };
+    key.set(env, &Project {
+      id: project.id,
+      client: project.client,
+      title: project.title,
+      description: project.description,
+      category: project.category,
+      budget: project.budget,
+      asset: asset.clone(),
+      deadline: project.deadline,
+      milestones: project.milestones,
+      status: project.status,
+    });
+  }
+}
+
+// Layout 4: the config had no contest window. Every earlier layout stored the same config.
+pub(crate) mod v4 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV4 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+  }
+
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV4>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: Config::default_values().contest_window_secs,
+      revoke_window_secs: Config::default_values().revoke_window_secs,
+      rate_max_age_secs: Config::default_values().rate_max_age_secs,
+      launch_mode: Config::default_values().launch_mode,
+      risk_caps: Config::default_values().risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 5: the config had no revoke window
+pub(crate) mod v5 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV5 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+    pub contest_window_secs: u64,
+  }
+
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV5>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: config.contest_window_secs,
+      revoke_window_secs: Config::default_values().revoke_window_secs,
+      rate_max_age_secs: Config::default_values().rate_max_age_secs,
+      launch_mode: Config::default_values().launch_mode,
+      risk_caps: Config::default_values().risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 6: the config had no maximum reference rate age
+pub(crate) mod v6 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV6 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+    pub contest_window_secs: u64,
+    pub revoke_window_secs: u64,
+  }
+
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV6>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: config.contest_window_secs,
+      revoke_window_secs: config.revoke_window_secs,
+      rate_max_age_secs: Config::default_values().rate_max_age_secs,
+      launch_mode: Config::default_values().launch_mode,
+      risk_caps: Config::default_values().risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 7: escrows kept the digest the freelancer accepted inside. Every layout from 4 on
+// stored escrows this way.
+pub(crate) mod v7 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct EscrowV7 {
+    pub project_id: u64,
+    pub client: Address,
+    pub freelancer: Address,
+    pub total_amount: i128,
+    pub asset: Address,
+    pub milestones: Vec<Milestone>,
+    pub deposited_amount: i128,
+    pub released_amount: i128,
+    pub refunded_amount: i128,
+    pub state: EscrowState,
+    pub created_at: u64,
+    pub client_active_at: u64,
+    pub lump_sum: bool,
+    pub accepted_terms: Option<BytesN<32>>,
+  }
+
+  // Commitments were already tracked, so the escrow is rewritten in place
+  pub fn migrate(env: &Env, start: u64, end: u64) {
+    for id in start..=end {
+      let key = storage::escrows(id);
+      let Some(escrow) = key.as_layout::<EscrowV7>().get(env) else {
+        continue;
+      };
+      key.set(env, &Escrow {
+        project_id: escrow.project_id,
+        client: escrow.client,
+        freelancer: escrow.freelancer,
+        total_amount: escrow.total_amount,
+        asset: escrow.asset,
+        milestones: escrow.milestones,
+        deposited_amount: escrow.deposited_amount,
+        released_amount: escrow.released_amount,
+        refunded_amount: escrow.refunded_amount,
+        state: escrow.state,
+        created_at: escrow.created_at,
+        client_active_at: escrow.client_active_at,
+        lump_sum: escrow.lump_sum,
+      });
+      put_accepted_terms(env, id, escrow.accepted_terms);
+    }
+  }
+}
+
+// Layout 8: the config had no launch mode. Layout 7 stored it the same way.
+pub(crate) mod v8 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV8 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+    pub contest_window_secs: u64,
+    pub revoke_window_secs: u64,
+    pub rate_max_age_secs: u64,
+  }
+
+  // Deployed marketplaces were open to everyone, and stay that way
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV8>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: config.contest_window_secs,
+      revoke_window_secs: config.revoke_window_secs,
+      rate_max_age_secs: config.rate_max_age_secs,
+      launch_mode: LaunchMode::Open,
+      risk_caps: Config::default_values().risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 9: the config had no risk-tier caps
+pub(crate) mod v9 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV9 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+    pub contest_window_secs: u64,
+    pub revoke_window_secs: u64,
+    pub rate_max_age_secs: u64,
+    pub launch_mode: LaunchMode,
+  }
+
+  // Deployed marketplaces start uncapped, as they were
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV9>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: config.contest_window_secs,
+      revoke_window_secs: config.revoke_window_secs,
+      rate_max_age_secs: config.rate_max_age_secs,
+      launch_mode: config.launch_mode,
+      risk_caps: Config::default_values().risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 10: the config had no legacy_api switch
+pub(crate) mod v10 {
+  use super::*;
+
+  #[derive(Clone)]
+  #[contracttype(export = false)]
+  pub struct ConfigV10 {
+    pub review_period_secs: u64,
+    pub bundle_grace_secs: u64,
+    pub contest_window_secs: u64,
+    pub revoke_window_secs: u64,
+    pub rate_max_age_secs: u64,
+    pub launch_mode: LaunchMode,
+    pub risk_caps: RiskCaps,
+  }
+
+  // Deployed marketplaces keep answering their version 1 callers
+  pub fn migrate_config(env: &Env) {
+    let Some(config) = storage::config().as_layout::<ConfigV10>().get(env) else {
+      return;
+    };
+    storage::config().set(env, &Config {
+      review_period_secs: config.review_period_secs,
+      bundle_grace_secs: config.bundle_grace_secs,
+      contest_window_secs: config.contest_window_secs,
+      revoke_window_secs: config.revoke_window_secs,
+      rate_max_age_secs: config.rate_max_age_secs,
+      launch_mode: config.launch_mode,
+      risk_caps: config.risk_caps,
+      legacy_api: Config::default_values().legacy_api,
+    });
+  }
+}
+
+// Layout 11: projects, escrows, accepted digests, indexes and tombstones were kept in the
+// instance with everything else. Each moves to a persistent entry of its own, extended by the
+// class it is bumped with from then on.
+pub(crate) mod v11 {
+  use super::*;
+
+  // Indexes that no ID leads to, moved before the first batch
+  pub fn migrate_shared(env: &Env) {
+    for state in [EscrowState::Created, EscrowState::InProgress, EscrowState::Completed, EscrowState::Refunded] {
+      move_entry(env, &storage::index(&IndexKind::EscrowsByState(state)), TtlClass::Hot);
+    }
+    move_entry(env, &storage::index(&IndexKind::OpenDisputes), TtlClass::Hot);
+    move_entry(env, &storage::index(&IndexKind::ActivityBuckets), TtlClass::Hot);
+  }
+
+  pub fn migrate(env: &Env, start: u64, end: u64) {
+    for id in start..=end {
+      move_entry(env, &storage::projects(id), TtlClass::Hot);
+      move_entry(env, &storage::index(&IndexKind::ProjectProposals(id)), TtlClass::Hot);
+      move_entry(env, &storage::project_tombstones(id), TtlClass::Cold);
+      move_entry(env, &storage::escrow_tombstones(id), TtlClass::Cold);
+      if let Some(rating) = storage::ratings(id).get(env) {
+        move_entry(env, &storage::index(&IndexKind::FreelancerRatings(rating.to)), TtlClass::Warm);
+      }
+      if let Some(listing) = storage::services(id).get(env) {
+        move_entry(env, &storage::index(&IndexKind::CategoryServices(listing.category)), TtlClass::Warm);
+      }
+      if let Some(last_activity) = storage::escrow_activity(id).get(env) {
+        move_entry(env, &storage::index(&IndexKind::ActivityBucket(last_activity / ACTIVITY_BUCKET_SECS)), TtlClass::Hot);
+      }
+      let key = storage::escrows(id);
+      let Some(escrow) = key.get(env).or_else(|| key.as_layout::<Escrow>().get(env)) else {
+        continue;
+      };
+      let class = if is_active(&escrow) { TtlClass::Hot } else { TtlClass::Cold };
+      move_entry(env, &key, class);
+      let accepted = storage::accepted_terms(id);
+      move_entry(env, &accepted, class);
+      // A layout 7 escrow migrated in this batch had its digest written straight to its entry
+      if accepted.has(env) {
+        ttl::bump(env, &accepted, class);
+      }
+      move_entry(env, &storage::index(&IndexKind::PairEscrows(escrow.client, escrow.freelancer)), TtlClass::Hot);
+      for milestone_index in 0..escrow.milestones.len() {
+        move_entry(env, &storage::index(&IndexKind::Subcontracts(id, milestone_index)), TtlClass::Hot);
+      }
+    }
+  }
+
+  fn move_entry<V: IntoVal<Env, Val> + TryFromVal<Env, Val>>(env: &Env, entry: &Entry<V>, class: TtlClass) {
+    let stored = entry.as_layout::<V>();
+    if !stored.has(env) {
+      return;
+    }
+    // Where a reader wrote the current entry, the instance copy is still in the old layout
+    if !entry.has(env) {
+      entry.set(env, &stored.get(env).unwrap());
+    }
+    stored.remove(env);
+    ttl::bump(env, entry, class);
+  }
+}
@@ -0,0 +1,294 @@
+// The order an escrow's milestones are worked in, and how milestones added after opening fit
+// into it. Whether a milestone's turn has come is decided here for every call that starts or
+// hands in work, so sequential mode, dependencies and the funding an added milestone needs
+// can't drift apart:
+//
+//   funding       once under way, an escrow whose total outgrew its deposits (an added or
+//                 repriced milestone) takes no more work until the client tops it up
+//   sequential    a milestone waits until every earlier one is settled
+//   dependencies  an added milestone also waits for the milestones it names
+//
+// A milestone is settled once it is paid, ruled on or voided, so voiding one never blocks the
+// milestones after it. Bundles are the one exception: once the client has gone silent past
+// the project deadline, everything left open is handed in together regardless of order.
+
+use soroban_sdk::{ Address, Env, Map, Vec };
+
+use crate::escrow::{ check_reentry, is_accepted, load_escrow_in_mode, milestone_records, save_escrow };
+use crate::risk::take_exposure;
+use crate::{ amendment, events, storage, AmendmentChange, Error, Escrow, EscrowState, Milestone, MilestoneOrder, MilestoneRecord, MilestoneStatus, MAX_MILESTONES };
+
+// Only the client chooses, and only while the terms are still open: before the freelancer
+// accepts and before any work is handed in
+pub fn set_sequential(env: &Env, from: Address, escrow_id: u64, sequential: bool) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if is_accepted(env, escrow_id) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if milestone_records(env, escrow_id).iter().any(|record| record.status != MilestoneStatus::Pending) {
+    return Err(Error::WorkUnderReview);
+  }
+  let mut order = get_milestone_order(env, escrow_id);
+  order.sequential = sequential;
+  storage::milestone_orders(escrow_id).set(env, &order);
+  Ok(())
+}
+
+pub fn get_milestone_order(env: &Env, escrow_id: u64) -> MilestoneOrder {
+  storage::milestone_orders(escrow_id).get(env).unwrap_or(MilestoneOrder { sequential: false, depends_on: Map::new(env) })
+}
+
+// Appends a milestone both parties agreed on and returns its index. The total grows by its
+// amount, which counts against the freelancer's risk-tier cap like the rest of the escrow.
+pub fn add_milestone(env: &Env, client: Address, freelancer: Address, escrow_id: u64, milestone: Milestone, depends_on: Vec<u32>) -> Result<u32, Error> {
+  // Ensure both parties authorized this call
+  client.require_auth();
+  freelancer.require_auth();
+  check_reentry(env)?;
+
+  let mut escrow = load_escrow_in_mode(env, escrow_id, false)?;
+  if escrow.client != client || escrow.freelancer != freelancer {
+    return Err(Error::Unauthorized);
+  }
+  let milestone_index = append_milestone(env, escrow_id, &mut escrow, milestone.clone(), depends_on.clone())?;
+  amendment::record_change(env, escrow_id, &escrow, client, AmendmentChange::AddMilestone(milestone, depends_on))?;
+  Ok(milestone_index)
+}
+
+// Appends the milestone, checked against the escrow, and saves the escrow
+pub fn append_milestone(env: &Env, escrow_id: u64, escrow: &mut Escrow, milestone: Milestone, depends_on: Vec<u32>) -> Result<u32, Error> {
+  let project = storage::projects(escrow.project_id).get(env).ok_or(Error::NotFound)?;
+  let mut records = milestone_records(env, escrow_id);
+  check_addition(env.ledger().timestamp(), project.deadline, &escrow.milestones, &records, &milestone, &depends_on)?;
+  take_exposure(env, escrow_id, escrow, milestone.amount)?;
+
+  let milestone_index = records.len();
+  let amount = milestone.amount;
+  escrow.total_amount = escrow.total_amount.checked_add(amount).ok_or(Error::InvalidAmount)?;
+  escrow.milestones.push_back(Milestone { completed: false, ..milestone });
+  records.push_back(MilestoneRecord { status: MilestoneStatus::Pending, deliverable: None, submitted_at: 0, paid: 0 });
+  if !depends_on.is_empty() {
+    let mut order = get_milestone_order(env, escrow_id);
+    order.depends_on.set(milestone_index, depends_on);
+    storage::milestone_orders(escrow_id).set(env, &order);
+  }
+  save_escrow(env, escrow_id, escrow, &records);
+  events::milestone_added(env, escrow_id, escrow, milestone_index, amount);
+  Ok(milestone_index)
+}
+
+// Fails with NotFullyFunded while an escrow under way waits for a top-up, and with TooEarly
+// while the milestone waits for others
+pub fn check_turn(env: &Env, escrow_id: u64, escrow: &Escrow, records: &Vec<MilestoneRecord>, milestone_index: u32) -> Result<(), Error> {
+  if escrow.state == EscrowState::InProgress && escrow.deposited_amount < escrow.total_amount {
+    return Err(Error::NotFullyFunded);
+  }
+  check_order(&get_milestone_order(env, escrow_id), records, milestone_index)
+}
+
+fn check_order(order: &MilestoneOrder, records: &Vec<MilestoneRecord>, milestone_index: u32) -> Result<(), Error> {
+  if order.sequential && (0..milestone_index).any(|i| !is_settled(records, i)) {
+    return Err(Error::TooEarly);
+  }
+  let depends_on = order.depends_on.get(milestone_index).unwrap_or(Vec::new(records.env()));
+  if depends_on.iter().any(|i| !is_settled(records, i)) {
+    return Err(Error::TooEarly);
+  }
+  Ok(())
+}
+
+fn is_settled(records: &Vec<MilestoneRecord>, milestone_index: u32) -> bool {
+  records.get(milestone_index).is_some_and(|record| matches!(record.status, MilestoneStatus::Released | MilestoneStatus::Resolved | MilestoneStatus::Voided))
+}
+
+// An added milestone has a positive amount and names each dependency once, none of them
+// voided. Its deadline, its own or the project's, can't have passed, can't be later than
+// the project's and can't come before a dependency's.
+fn check_addition(now: u64, project_deadline: u64, milestones: &Vec<Milestone>, records: &Vec<MilestoneRecord>, milestone: &Milestone, depends_on: &Vec<u32>) -> Result<(), Error> {
+  if records.len() >= MAX_MILESTONES {
+    return Err(Error::LimitReached);
+  }
+  if milestone.amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  for (position, dependency) in depends_on.iter().enumerate() {
+    let record = records.get(dependency).ok_or(Error::InvalidMilestoneIndex)?;
+    if record.status == MilestoneStatus::Voided || depends_on.first_index_of(dependency) != Some(position as u32) {
+      return Err(Error::InvalidMilestoneIndex);
+    }
+  }
+
+  let due = if milestone.deadline == 0 { project_deadline } else { milestone.deadline };
+  if due != 0 && due <= now {
+    return Err(Error::InvalidSchedule);
+  }
+  if project_deadline != 0 && due > project_deadline {
+    return Err(Error::InvalidSchedule);
+  }
+  for dependency in depends_on.iter() {
+    let dependency_due = match milestones.get(dependency) {
+      Some(earlier) if earlier.deadline != 0 => earlier.deadline,
+      _ => project_deadline,
+    };
+    if latest_first(due) < latest_first(dependency_due) {
+      return Err(Error::InvalidSchedule);
+    }
+  }
+  Ok(())
+}
+
+// A deadline of 0 means none, which comes after any date
+fn latest_first(deadline: u64) -> u64 {
+  if deadline == 0 { u64::MAX } else { deadline }
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::{ vec, Env, Map, String, Vec };
+
+  use crate::milestone_schedule::{ check_addition, check_order };
+  use crate::{ Error, Milestone, MilestoneOrder, MilestoneRecord, MilestoneStatus, MAX_MILESTONES };
+  use MilestoneStatus::{ Disputed, Pending, Released, Resolved, Submitted, Voided };
+
+  fn records(env: &Env, statuses: &[MilestoneStatus]) -> Vec<MilestoneRecord> {
+    let mut records = Vec::new(env);
+    for status in statuses {
+      records.push_back(MilestoneRecord { status: *status, deliverable: None, submitted_at: 0, paid: 0 });
+    }
+    records
+  }
+
+  fn order(env: &Env, sequential: bool, depends_on: &[(u32, &[u32])]) -> MilestoneOrder {
+    let mut map = Map::new(env);
+    for (milestone_index, dependencies) in depends_on {
+      map.set(*milestone_index, Vec::from_slice(env, dependencies));
+    }
+    MilestoneOrder { sequential, depends_on: map }
+  }
+
+  fn milestone(env: &Env, amount: i128, deadline: u64) -> Milestone {
+    Milestone { description: String::from_str(env, "Extra"), amount, completed: false, deadline }
+  }
+
+  #[test]
+  fn without_an_order_any_milestone_can_go_first() {
+    let env = Env::default();
+    let records = records(&env, &[Pending, Pending, Pending]);
+    for milestone_index in 0..3 {
+      assert_eq!(check_order(&order(&env, false, &[]), &records, milestone_index), Ok(()));
+    }
+  }
+
+  #[test]
+  fn sequential_mode_waits_for_every_earlier_milestone_to_settle() {
+    let env = Env::default();
+    let sequential = order(&env, true, &[]);
+    assert_eq!(check_order(&sequential, &records(&env, &[Pending, Pending]), 0), Ok(()));
+    assert_eq!(check_order(&sequential, &records(&env, &[Pending, Pending]), 1), Err(Error::TooEarly));
+    assert_eq!(check_order(&sequential, &records(&env, &[Submitted, Pending]), 1), Err(Error::TooEarly));
+    assert_eq!(check_order(&sequential, &records(&env, &[Disputed, Pending]), 1), Err(Error::TooEarly));
+    assert_eq!(check_order(&sequential, &records(&env, &[Released, Pending]), 1), Ok(()));
+    assert_eq!(check_order(&sequential, &records(&env, &[Resolved, Pending]), 1), Ok(()));
+  }
+
+  #[test]
+  fn a_voided_milestone_never_blocks_the_ones_after_it() {
+    let env = Env::default();
+    // Sequential mode skips it
+    assert_eq!(check_order(&order(&env, true, &[]), &records(&env, &[Voided, Released, Pending]), 2), Ok(()));
+    assert_eq!(check_order(&order(&env, true, &[]), &records(&env, &[Voided, Pending, Pending]), 2), Err(Error::TooEarly));
+    // So does a dependency on it, voided after the dependent milestone was added
+    assert_eq!(check_order(&order(&env, false, &[(2, &[0])]), &records(&env, &[Voided, Pending, Pending]), 2), Ok(()));
+  }
+
+  #[test]
+  fn a_milestone_added_in_sequential_mode_goes_last() {
+    let env = Env::default();
+    let sequential = order(&env, true, &[]);
+    assert_eq!(check_order(&sequential, &records(&env, &[Released, Pending, Pending]), 2), Err(Error::TooEarly));
+    assert_eq!(check_order(&sequential, &records(&env, &[Released, Released, Pending]), 2), Ok(()));
+  }
+
+  #[test]
+  fn dependencies_only_wait_for_the_milestones_they_name() {
+    let env = Env::default();
+    let depends = order(&env, false, &[(3, &[1])]);
+    assert_eq!(check_order(&depends, &records(&env, &[Pending, Pending, Pending, Pending]), 3), Err(Error::TooEarly));
+    assert_eq!(check_order(&depends, &records(&env, &[Pending, Released, Pending, Pending]), 3), Ok(()));
+    // Milestones without dependencies are unaffected
+    assert_eq!(check_order(&depends, &records(&env, &[Pending, Pending, Pending, Pending]), 2), Ok(()));
+  }
+
+  #[test]
+  fn dependencies_and_sequential_mode_both_have_to_clear() {
+    let env = Env::default();
+    // Sequential mode blocks even though the dependency is settled
+    let both = order(&env, true, &[(2, &[0])]);
+    assert_eq!(check_order(&both, &records(&env, &[Released, Pending, Pending]), 2), Err(Error::TooEarly));
+    assert_eq!(check_order(&both, &records(&env, &[Released, Released, Pending]), 2), Ok(()));
+  }
+
+  #[test]
+  fn an_added_milestone_needs_a_positive_amount_and_room() {
+    let env = Env::default();
+    let existing = vec![&env, milestone(&env, 100, 0)];
+    let pending = records(&env, &[Pending]);
+    assert_eq!(check_addition(0, 1_000, &existing, &pending, &milestone(&env, 0, 0), &vec![&env]), Err(Error::InvalidAmount));
+    assert_eq!(check_addition(0, 1_000, &existing, &pending, &milestone(&env, 50, 0), &vec![&env]), Ok(()));
+
+    let mut full = Vec::new(&env);
+    for _ in 0..MAX_MILESTONES {
+      full.push_back(MilestoneRecord { status: Pending, deliverable: None, submitted_at: 0, paid: 0 });
+    }
+    assert_eq!(check_addition(0, 1_000, &existing, &full, &milestone(&env, 50, 0), &vec![&env]), Err(Error::LimitReached));
+  }
+
+  #[test]
+  fn dependencies_name_existing_unvoided_milestones_once() {
+    let env = Env::default();
+    let existing = vec![&env, milestone(&env, 100, 0), milestone(&env, 100, 0)];
+    let statuses = records(&env, &[Voided, Pending]);
+    let extra = milestone(&env, 50, 0);
+    assert_eq!(check_addition(0, 1_000, &existing, &statuses, &extra, &vec![&env, 1]), Ok(()));
+    assert_eq!(check_addition(0, 1_000, &existing, &statuses, &extra, &vec![&env, 2]), Err(Error::InvalidMilestoneIndex));
+    assert_eq!(check_addition(0, 1_000, &existing, &statuses, &extra, &vec![&env, 0]), Err(Error::InvalidMilestoneIndex));
+    assert_eq!(check_addition(0, 1_000, &existing, &statuses, &extra, &vec![&env, 1, 1]), Err(Error::InvalidMilestoneIndex));
+  }
+
+  #[test]
+  fn an_added_deadline_fits_between_now_its_dependencies_and_the_project() {
+    let env = Env::default();
+    let existing = vec![&env, milestone(&env, 100, 400), milestone(&env, 100, 0)];
+    let pending = records(&env, &[Pending, Pending]);
+    let add = |now: u64, deadline: u64, depends_on: Vec<u32>| check_addition(now, 1_000, &existing, &pending, &milestone(&env, 50, deadline), &depends_on);
+    // Already past, or past the project deadline
+    assert_eq!(add(500, 500, vec![&env]), Err(Error::InvalidSchedule));
+    assert_eq!(add(500, 1_001, vec![&env]), Err(Error::InvalidSchedule));
+    assert_eq!(add(500, 0, vec![&env]), Ok(()));
+    assert_eq!(add(1_000, 0, vec![&env]), Err(Error::InvalidSchedule));
+    // Before its dependency; milestone 1 falls due with the project
+    assert_eq!(add(0, 300, vec![&env, 0]), Err(Error::InvalidSchedule));
+    assert_eq!(add(0, 400, vec![&env, 0]), Ok(()));
+    assert_eq!(add(0, 900, vec![&env, 1]), Err(Error::InvalidSchedule));
+    assert_eq!(add(0, 0, vec![&env, 1]), Ok(()));
+  }
+
+  #[test]
+  fn without_a_project_deadline_an_added_milestone_can_only_be_dated_after_dated_dependencies() {
+    let env = Env::default();
+    let existing = vec![&env, milestone(&env, 100, 400), milestone(&env, 100, 0)];
+    let pending = records(&env, &[Pending, Pending]);
+    let add = |deadline: u64, depends_on: Vec<u32>| check_addition(0, 0, &existing, &pending, &milestone(&env, 50, deadline), &depends_on);
+    assert_eq!(add(5_000, vec![&env, 0]), Ok(()));
+    assert_eq!(add(0, vec![&env, 0]), Ok(()));
+    // Milestone 1 has no deadline at all, so nothing dated can follow it
+    assert_eq!(add(5_000, vec![&env, 1]), Err(Error::InvalidSchedule));
+    assert_eq!(add(0, vec![&env, 1]), Ok(()));
+  }
+}
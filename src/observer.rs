@@ -0,0 +1,101 @@
+// Read-only observers: third parties such as auditors or grant program managers the client
+// lets follow an escrow. An acknowledged observer reads the full escrow and gets an observed
+// event for each of its events, and holds no authority at all; the calls that change an
+// escrow check for its parties, the admin or an assigned arbitrator, never for an observer.
+
+use soroban_sdk::{ Address, Env, Vec };
+
+use crate::page::{ self, ObserverPage };
+use crate::{ events, storage, Error, Escrow, Observer, MAX_OBSERVERS };
+
+pub fn add_observer(env: &Env, client: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  client.require_auth();
+
+  let escrow = load_escrow(env, escrow_id)?;
+  if escrow.client != client {
+    return Err(Error::Unauthorized);
+  }
+  if observer == escrow.client || observer == escrow.freelancer {
+    return Err(Error::InvalidOwner);
+  }
+  let mut observers = stored(env, escrow_id);
+  // Granting an observer again changes nothing
+  if observers.iter().any(|existing| existing.address == observer) {
+    return Ok(());
+  }
+  if observers.len() >= MAX_OBSERVERS {
+    return Err(Error::LimitReached);
+  }
+  observers.push_back(Observer { address: observer, acknowledged: false });
+  storage::observers(escrow_id).set(env, &observers);
+  Ok(())
+}
+
+pub fn acknowledge_observer(env: &Env, freelancer: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  freelancer.require_auth();
+
+  let escrow = load_escrow(env, escrow_id)?;
+  if escrow.freelancer != freelancer {
+    return Err(Error::Unauthorized);
+  }
+  let mut observers = stored(env, escrow_id);
+  let position = observers.iter().position(|existing| existing.address == observer).ok_or(Error::NotFound)? as u32;
+  if observers.get(position).unwrap().acknowledged {
+    return Ok(());
+  }
+  observers.set(position, Observer { address: observer.clone(), acknowledged: true });
+  storage::observers(escrow_id).set(env, &observers);
+  events::observer(env, escrow_id, &escrow, &observer, true);
+  Ok(())
+}
+
+pub fn remove_observer(env: &Env, client: Address, escrow_id: u64, observer: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  client.require_auth();
+
+  let escrow = load_escrow(env, escrow_id)?;
+  if escrow.client != client {
+    return Err(Error::Unauthorized);
+  }
+  let mut observers = stored(env, escrow_id);
+  let position = observers.iter().position(|existing| existing.address == observer).ok_or(Error::NotFound)? as u32;
+  let acknowledged = observers.get(position).unwrap().acknowledged;
+  observers.remove(position);
+  if observers.is_empty() {
+    storage::observers(escrow_id).remove(env);
+  } else {
+    storage::observers(escrow_id).set(env, &observers);
+  }
+  // Only an observer who was following the escrow has anything to leave
+  if acknowledged {
+    events::observer(env, escrow_id, &escrow, &observer, false);
+  }
+  Ok(())
+}
+
+pub fn get_observers(env: &Env, escrow_id: u64, cursor: u64, limit: u32) -> ObserverPage {
+  let observers = stored(env, escrow_id);
+  let (items, next_cursor) = page::fill(env, observers.len(), cursor, limit, |position| observers.get(position));
+  ObserverPage { items, next_cursor, total: Some(observers.len()) }
+}
+
+// The observers the freelancer acknowledged; a grant still waiting on them gives no access
+pub fn observing(env: &Env, escrow_id: u64) -> Vec<Address> {
+  let mut addresses = Vec::new(env);
+  for observer in stored(env, escrow_id) {
+    if observer.acknowledged {
+      addresses.push_back(observer.address);
+    }
+  }
+  addresses
+}
+
+fn stored(env: &Env, escrow_id: u64) -> Vec<Observer> {
+  storage::observers(escrow_id).get(env).unwrap_or(Vec::new(env))
+}
+
+fn load_escrow(env: &Env, escrow_id: u64) -> Result<Escrow, Error> {
+  storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)
+}
@@ -0,0 +1,75 @@
+// Paging shared by every list getter. Contract types can't be generic, so each getter returns
+// its own page type, all with the same fields and all cut by `window`. A cursor is where the
+// next page starts; 0 starts from the beginning.
+
+use core::ops::Range;
+
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{ contracttype, Address, Env, IntoVal, TryFromVal, Val, Vec };
+
+use crate::{ Amendment, CategoryTemplate, Deposit, LedgerLine, Observer, ProjectSummary, ProposalListing, Rating, RegistryListing, MAX_LIST_LIMIT };
+
+// Upper bound on the encoded size of the items on one page, well inside the return value
+// limit. Pages of large items come back shorter instead of failing.
+pub const MAX_PAGE_BYTES: u32 = 32 * 1024;
+
+macro_rules! page_type {
+  ($name:ident, $item:ident) => {
+    #[derive(Clone, Debug, PartialEq)]
+    #[contracttype]
+    pub struct $name {
+      pub items: Vec<$item>,
+      pub next_cursor: Option<u64>, // None on the last page
+      pub total: Option<u32>, // Items in the whole list, when known without reading all of it
+    }
+  };
+}
+
+page_type!(IdPage, u64);
+page_type!(AddressPage, Address);
+page_type!(AmendmentPage, Amendment);
+page_type!(DepositPage, Deposit);
+page_type!(LedgerPage, LedgerLine);
+page_type!(ObserverPage, Observer);
+page_type!(ProjectPage, ProjectSummary);
+page_type!(ProposalPage, ProposalListing);
+page_type!(RatingPage, Rating);
+page_type!(RegistryPage, RegistryListing);
+page_type!(TemplatePage, CategoryTemplate);
+
+// Items one page may hold. A zero limit still reads one item, so a caller always makes progress.
+pub fn limit(limit: u32) -> u32 {
+  limit.clamp(1, MAX_LIST_LIMIT)
+}
+
+// Positions of a list of `len` items that a page from `cursor` covers, and the cursor of the
+// page after it. A cursor past the end gives an empty last page.
+pub fn window(len: u32, cursor: u64, limit: u32) -> (Range<u32>, Option<u64>) {
+  let start = cursor.min(len as u64) as u32;
+  let end = start + self::limit(limit).min(len - start);
+  (start..end, (end < len).then_some(end as u64))
+}
+
+// One page of a list of `len` items, loading each position's item with `load` (None skips
+// it). Items are added until the next one would take the page past MAX_PAGE_BYTES; the page
+// then ends early and its cursor points at that item. The first item always goes in.
+pub fn fill<T>(env: &Env, len: u32, cursor: u64, limit: u32, mut load: impl FnMut(u32) -> Option<T>) -> (Vec<T>, Option<u64>)
+where
+  T: Clone + IntoVal<Env, Val> + TryFromVal<Env, Val>,
+{
+  let (positions, next_cursor) = window(len, cursor, limit);
+  let mut items = Vec::new(env);
+  let mut bytes = 0;
+  for position in positions {
+    let Some(item) = load(position) else {
+      continue;
+    };
+    let size = item.clone().to_xdr(env).len();
+    if !items.is_empty() && bytes + size > MAX_PAGE_BYTES {
+      return (items, Some(position as u64));
+    }
+    bytes += size;
+    items.push_back(item);
+  }
+  (items, next_cursor)
+}
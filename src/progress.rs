@@ -0,0 +1,96 @@
+// Effort estimates and progress reports: the freelancer's own account of how far along the
+// work is, between submissions. Informational only; nothing that moves funds reads it.
+
+use soroban_sdk::{ Address, Env, Map, Vec };
+
+use crate::escrow::{ is_accepted, is_active, milestone_records };
+use crate::{ epoch, storage, EffortEstimate, Error, Escrow, MilestoneRecord, MilestoneStatus, ProgressReport, MAX_EFFORT_HOURS, PROGRESS_REPORT_SECS, PROGRESS_STALE_SECS };
+
+pub fn set_effort_estimates(env: &Env, from: Address, escrow_id: u64, hours: Vec<u32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  load_tracked_escrow(env, &from, escrow_id)?;
+  // Recorded once, against the terms as accepted
+  if storage::effort_estimates(escrow_id).has(env) {
+    return Err(Error::AlreadyAccepted);
+  }
+  if hours.len() != milestone_records(env, escrow_id).len() {
+    return Err(Error::InvalidMilestoneIndex);
+  }
+  if hours.iter().any(|milestone_hours| milestone_hours > MAX_EFFORT_HOURS) {
+    return Err(Error::InvalidAmount);
+  }
+  storage::effort_estimates(escrow_id).set(env, &EffortEstimate { hours, set_at: env.ledger().timestamp() });
+  Ok(())
+}
+
+pub fn get_effort_estimates(env: &Env, escrow_id: u64) -> Option<EffortEstimate> {
+  storage::effort_estimates(escrow_id).get(env)
+}
+
+pub fn report_progress(env: &Env, from: Address, escrow_id: u64, milestone_index: u32, percent: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = load_tracked_escrow(env, &from, escrow_id)?;
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Pending => {}
+    MilestoneStatus::Released | MilestoneStatus::Resolved | MilestoneStatus::Voided => return Err(Error::AlreadyReleased),
+    _ => return Err(Error::WorkUnderReview),
+  }
+  if percent > 100 {
+    return Err(Error::InvalidAmount);
+  }
+
+  let now = env.ledger().timestamp();
+  let key = storage::progress_reports(escrow_id);
+  let mut reports = key.get(env).unwrap_or(Map::new(env));
+  if reports.get(milestone_index).is_some_and(|last| now < last.reported_at + PROGRESS_REPORT_SECS) {
+    return Err(Error::ReportTooSoon);
+  }
+  reports.set(milestone_index, ProgressReport { percent, reported_at: now });
+  key.set(env, &reports);
+  epoch::record_message(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn get_progress_reports(env: &Env, escrow_id: u64) -> Map<u32, ProgressReport> {
+  storage::progress_reports(escrow_id).get(env).unwrap_or(Map::new(env))
+}
+
+// How much of a milestone is done, in percent: finished work counts in full, work still
+// pending counts for what the freelancer last reported
+pub fn percent_done(record: &MilestoneRecord, report: Option<&ProgressReport>) -> u32 {
+  match record.status {
+    MilestoneStatus::Pending | MilestoneStatus::Contested | MilestoneStatus::PartialOffered | MilestoneStatus::Disputed => report.map_or(0, |report| report.percent),
+    MilestoneStatus::Submitted | MilestoneStatus::Released | MilestoneStatus::Resolved | MilestoneStatus::Voided => 100,
+  }
+}
+
+// When progress on a pending milestone went stale: PROGRESS_STALE_SECS after its last report,
+// or after the estimates if it has none. Escrows whose freelancer never estimated nor reported
+// anything don't track progress and never go stale.
+pub fn stale_since(escrow: &Escrow, record: &MilestoneRecord, estimate: Option<&EffortEstimate>, report: Option<&ProgressReport>, now: u64) -> Option<u64> {
+  if !is_active(escrow) || record.status != MilestoneStatus::Pending {
+    return None;
+  }
+  let since = report.map(|report| report.reported_at).or(estimate.map(|estimate| estimate.set_at))? + PROGRESS_STALE_SECS;
+  (now >= since).then_some(since)
+}
+
+// Loads an escrow the caller is the freelancer on, accepted and still running
+fn load_tracked_escrow(env: &Env, from: &Address, escrow_id: u64) -> Result<Escrow, Error> {
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if !is_accepted(env, escrow_id) {
+    return Err(Error::NotAcknowledged);
+  }
+  Ok(escrow)
+}
@@ -0,0 +1,527 @@
+// Projects from posting to handoff: proposals, listings, locale hints, cancellation and
+// ownership transfer. Opening an escrow on a project is left to the escrow module.
+
+use soroban_sdk::{ Address, BytesN, Env, String, Vec };
+
+use crate::admin::require_launch_access;
+use crate::escrow::{ is_active, missing, note_client_activity, open_escrow, put_escrow };
+use crate::index::{ IndexKind, MAX_PAIR_INDEX_LEN, MAX_PROPOSALS_PER_PROJECT };
+use crate::math::{ expand_milestone_shares, scale_milestones };
+use crate::page::{ AddressPage, ProjectPage, ProposalPage };
+use crate::template::expand_template;
+use crate::ttl::TtlClass;
+use crate::user::user_languages;
+use crate::{ events, index, page, storage, ttl, Ending, Error, Escrow, InviteOutcome, IssueCode, Locale, Milestone, MilestoneShare, Project, ProjectStatus, ProjectSummary, ProjectTransfer, Proposal, ProposalListing, TermsSource, Tombstone, ValidationIssue, ValidationReport, MAX_INVITES_PER_CALL, MAX_INVITES_PER_PROJECT, MAX_MILESTONES, MAX_TZ_OFFSET_MINS, MIN_TZ_OFFSET_MINS };
+
+pub fn post_project(
+  env: &Env,
+  from: Address, // Client address
+  title: String,
+  description: String,
+  category: String,
+  budget: i128,
+  asset: Address, // Token the budget is denominated in
+  deadline: u64, // Unix timestamp for deadline
+  milestones: Vec<Milestone>,
+  locale: Locale,
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  require_launch_access(env, &from)?;
+  create_project(env, from, title, description, category, budget, asset, deadline, milestones, locale)
+}
+
+pub fn post_project_with_shares(
+  env: &Env,
+  from: Address, // Client address
+  title: String,
+  description: String,
+  category: String,
+  budget: i128,
+  asset: Address, // Token the budget is denominated in
+  deadline: u64, // Unix timestamp for deadline
+  shares: Vec<MilestoneShare>,
+  locale: Locale,
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  require_launch_access(env, &from)?;
+  let milestones = expand_milestone_shares(env, budget, &shares)?;
+  create_project(env, from, title, description, category, budget, asset, deadline, milestones, locale)
+}
+
+pub fn post_project_from_template(
+  env: &Env,
+  from: Address, // Client address
+  title: String,
+  description: String,
+  category_id: u32,
+  template_id: u32,
+  budget: i128,
+  asset: Address, // Token the budget is denominated in
+  deadline: u64, // Unix timestamp for deadline
+  locale: Locale,
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  require_launch_access(env, &from)?;
+  let (category, milestones, template) = expand_template(env, category_id, template_id, budget)?;
+  let project_id = create_project(env, from, title, description, category, budget, asset, deadline, milestones, locale)?;
+  storage::project_templates(project_id).set(env, &template);
+  Ok(project_id)
+}
+
+pub fn submit_proposal(env: &Env, from: Address, project_id: u64, amount: i128, milestones: Vec<Milestone>, cover: BytesN<32>) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  require_launch_access(env, &from)?;
+
+  let project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  if project.status != ProjectStatus::Open {
+    return Err(Error::ProjectNotOpen);
+  }
+  if project.client == from {
+    return Err(Error::Unauthorized);
+  }
+  if amount <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  if !milestones.is_empty() {
+    let mut sum: i128 = 0;
+    for milestone in milestones.iter() {
+      if milestone.amount < 0 {
+        return Err(Error::InvalidAmount);
+      }
+      sum = sum.checked_add(milestone.amount).ok_or(Error::InvalidAmount)?;
+    }
+    if sum != amount {
+      return Err(Error::InvalidAmount);
+    }
+  }
+
+  let proposal_id = storage::proposal_count().get(env).unwrap_or(0) + 1;
+  if !index::append_unique(env, &IndexKind::ProjectProposals(project_id), proposal_id, MAX_PROPOSALS_PER_PROJECT) {
+    return Err(Error::LimitReached);
+  }
+  let proposal = Proposal { id: proposal_id, project_id, freelancer: from, amount, milestones, cover, submitted_at: env.ledger().timestamp() };
+  storage::proposals(proposal_id).set(env, &proposal);
+  storage::proposal_count().set(env, &proposal_id);
+  Ok(proposal_id)
+}
+
+pub fn invite_many(env: &Env, from: Address, project_id: u64, freelancers: Vec<Address>, message: String) -> Result<Vec<InviteOutcome>, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  if project.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if project.status != ProjectStatus::Open {
+    return Err(Error::ProjectNotOpen);
+  }
+  if freelancers.len() > MAX_INVITES_PER_CALL {
+    return Err(Error::LimitReached);
+  }
+
+  let mut invited = storage::project_invites(project_id).get(env).unwrap_or(Vec::new(env));
+  let mut outcomes = Vec::new(env);
+  for freelancer in freelancers.iter() {
+    let outcome = if let Some(reason) = ineligibility(env, &project, &freelancer) {
+      reason
+    } else if invited.contains(&freelancer) {
+      InviteOutcome::AlreadyInvited
+    } else if invited.len() >= MAX_INVITES_PER_PROJECT {
+      InviteOutcome::ListFull
+    } else {
+      events::invited(env, &from, &freelancer, project_id, &message);
+      invited.push_back(freelancer);
+      InviteOutcome::Invited
+    };
+    outcomes.push_back(outcome);
+  }
+  let key = storage::project_invites(project_id);
+  key.set(env, &invited);
+  ttl::bump(env, &key, TtlClass::Warm);
+  Ok(outcomes)
+}
+
+pub fn get_project_invites(env: &Env, project_id: u64, cursor: u64, limit: u32) -> AddressPage {
+  let invited = storage::project_invites(project_id).get(env).unwrap_or(Vec::new(env));
+  let (items, next_cursor) = page::fill(env, invited.len(), cursor, limit, |position| invited.get(position));
+  AddressPage { items, next_cursor, total: Some(invited.len()) }
+}
+
+// Why a freelancer can't be invited to the project, if they can't
+fn ineligibility(env: &Env, project: &Project, freelancer: &Address) -> Option<InviteOutcome> {
+  if project.client == *freelancer {
+    Some(InviteOutcome::Client)
+  } else if storage::deactivated(freelancer.clone()).has(env) {
+    Some(InviteOutcome::Deactivated)
+  } else if storage::unavailable(freelancer.clone()).has(env) {
+    Some(InviteOutcome::Unavailable)
+  } else {
+    None
+  }
+}
+
+pub fn get_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, Error> {
+  storage::proposals(proposal_id).get(env).ok_or(Error::NotFound)
+}
+
+pub fn list_proposals(env: &Env, project_id: u64, cursor: u64, limit: u32, enriched: bool) -> ProposalPage {
+  let ids = index::read(env, &IndexKind::ProjectProposals(project_id));
+  let (listings, next_cursor) = page::fill(env, ids.len(), cursor, limit, |position| {
+    let proposal = storage::proposals(ids.get(position).unwrap()).get(env)?;
+    Some(proposal_listing(env, proposal, enriched))
+  });
+  ProposalPage { items: listings, next_cursor, total: Some(ids.len()) }
+}
+
+pub fn list_proposals_sorted(env: &Env, project_id: u64, cursor: u64, limit: u32, enriched: bool) -> ProposalPage {
+  let mut sorted: Vec<Proposal> = Vec::new(env);
+  // Bounded by MAX_PROPOSALS_PER_PROJECT
+  for proposal_id in index::read(env, &IndexKind::ProjectProposals(project_id)).iter() {
+    let Some(proposal) = storage::proposals(proposal_id).get(env) else {
+      continue;
+    };
+    // Insertion sort; the list is bounded and ties keep submission order
+    let position = sorted.iter().position(|listed| listed.amount > proposal.amount).unwrap_or(sorted.len() as usize);
+    sorted.insert(position as u32, proposal);
+  }
+
+  let (listings, next_cursor) = page::fill(env, sorted.len(), cursor, limit, |position| Some(proposal_listing(env, sorted.get(position).unwrap(), enriched)));
+  ProposalPage { items: listings, next_cursor, total: Some(sorted.len()) }
+}
+
+pub fn list_projects(env: &Env, cursor: u64, limit: u32) -> ProjectPage {
+  let count = storage::project_count().get(env).unwrap_or(0).min(u32::MAX as u64) as u32;
+  let (summaries, next_cursor) = page::fill(env, count, cursor, limit, |position| {
+    // IDs start at 1
+    let project = storage::projects(position as u64 + 1).get(env)?;
+    Some(ProjectSummary {
+      id: project.id,
+      title: project.title,
+      client: project.client,
+      budget: project.budget,
+      asset: project.asset,
+      status: project.status,
+      deadline: project.deadline,
+      category: project.category,
+    })
+  });
+  ProjectPage { items: summaries, next_cursor, total: Some(count) }
+}
+
+pub fn get_project(env: &Env, project_id: u64) -> Result<Project, Error> {
+  storage::projects(project_id).get(env).ok_or_else(|| missing(env, storage::project_tombstones(project_id)))
+}
+
+pub fn cancel_project(env: &Env, from: Address, project_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  if project.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if project.status != ProjectStatus::Open {
+    return Err(Error::ProjectNotOpen);
+  }
+  storage::projects(project_id).remove(env);
+  storage::project_transfers(project_id).remove(env);
+  storage::project_invites(project_id).remove(env);
+  storage::project_templates(project_id).remove(env);
+  let tombstone = Tombstone { id: project_id, ending: Ending::ProjectCancelled, closed_at: env.ledger().timestamp() };
+  let key = storage::project_tombstones(project_id);
+  key.set(env, &tombstone);
+  ttl::bump(env, &key, TtlClass::Cold);
+  Ok(())
+}
+
+pub fn offer_project_transfer(env: &Env, from: Address, project_id: u64, new_owner: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  if project.client != from {
+    return Err(Error::Unauthorized);
+  }
+  // The freelancer can't end up on both sides of their own escrow
+  let freelancer = active_project_escrow(env, &project)?.map(|(_, escrow)| escrow.freelancer);
+  if new_owner == from || freelancer == Some(new_owner.clone()) {
+    return Err(Error::InvalidOwner);
+  }
+  storage::project_transfers(project_id).set(env, &ProjectTransfer { new_owner, freelancer_ack: false });
+  Ok(())
+}
+
+pub fn acknowledge_project_transfer(env: &Env, from: Address, project_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let key = storage::project_transfers(project_id);
+  let mut transfer = key.get(env).ok_or(Error::NotFound)?;
+  let project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  let (_, escrow) = active_project_escrow(env, &project)?.ok_or(Error::EscrowNotActive)?;
+  if escrow.freelancer != from {
+    return Err(Error::Unauthorized);
+  }
+  transfer.freelancer_ack = true;
+  key.set(env, &transfer);
+  Ok(())
+}
+
+pub fn accept_project_transfer(env: &Env, from: Address, project_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let transfer = storage::project_transfers(project_id).get(env).ok_or(Error::NotFound)?;
+  if transfer.new_owner != from {
+    return Err(Error::Unauthorized);
+  }
+  let mut project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  if let Some((escrow_id, mut escrow)) = active_project_escrow(env, &project)? {
+    if !transfer.freelancer_ack {
+      return Err(Error::NotAcknowledged);
+    }
+    index::remove_value(env, &IndexKind::PairEscrows(escrow.client.clone(), escrow.freelancer.clone()), escrow_id);
+    if !index::append_unique(env, &IndexKind::PairEscrows(from.clone(), escrow.freelancer.clone()), escrow_id, MAX_PAIR_INDEX_LEN) {
+      return Err(Error::LimitReached);
+    }
+    escrow.client = from.clone();
+    note_client_activity(env, escrow_id, &mut escrow);
+    put_escrow(env, escrow_id, &escrow);
+  }
+
+  project.client = from;
+  let key = storage::projects(project_id);
+  key.set(env, &project);
+  storage::project_transfers(project_id).remove(env);
+  ttl::bump(env, &key, TtlClass::Hot);
+  Ok(())
+}
+
+pub fn get_project_transfer(env: &Env, project_id: u64) -> Option<ProjectTransfer> {
+  storage::project_transfers(project_id).get(env)
+}
+
+pub fn get_project_tombstone(env: &Env, project_id: u64) -> Option<Tombstone> {
+  storage::project_tombstones(project_id).get(env)
+}
+
+pub fn get_project_locale(env: &Env, project_id: u64) -> Locale {
+  storage::project_locales(project_id).get(env).unwrap_or_default()
+}
+
+pub fn accept_proposal(env: &Env, from: Address, proposal_id: u64) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let proposal = storage::proposals(proposal_id).get(env).ok_or(Error::NotFound)?;
+  let mut project = storage::projects(proposal.project_id).get(env).ok_or(Error::NotFound)?;
+  if project.client != from {
+    return Err(Error::Unauthorized);
+  }
+  let milestones = if proposal.milestones.is_empty() {
+    scale_milestones(env, &project.milestones, project.budget, proposal.amount)?
+  } else {
+    proposal.milestones
+  };
+  open_escrow(env, &mut project, proposal.freelancer, proposal.amount, milestones, TermsSource::Proposal(proposal_id))
+}
+
+pub fn get_terms_source(env: &Env, escrow_id: u64) -> TermsSource {
+  storage::terms_sources(escrow_id).get(env).unwrap_or(TermsSource::Project)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_project(
+  env: &Env,
+  from: Address,
+  title: String,
+  description: String,
+  category: String,
+  budget: i128,
+  asset: Address,
+  deadline: u64,
+  milestones: Vec<Milestone>,
+  locale: Locale,
+) -> Result<u64, Error> {
+  // Negative amounts would let a payout grow the escrow's remaining funds
+  if budget < 0 {
+    return Err(Error::InvalidAmount);
+  }
+  check_milestones(env, &milestones, budget, deadline)?;
+  if locale.tz_offset_mins.is_some_and(|offset| !(MIN_TZ_OFFSET_MINS..=MAX_TZ_OFFSET_MINS).contains(&offset)) {
+    return Err(Error::InvalidConfig);
+  }
+
+  let project_count = storage::project_count().get(env).unwrap_or(0);
+  let project = Project {
+    id: project_count + 1,
+    client: from,
+    title,
+    description,
+    category,
+    budget,
+    asset,
+    deadline,
+    milestones,
+    status: ProjectStatus::Open,
+  };
+  // Store project details in separate storage (consider database)
+  let key = storage::projects(project_count + 1);
+  key.set(env, &project);
+  storage::project_count().set(env, &(project_count + 1));
+  if locale != Locale::default() {
+    storage::project_locales(project_count + 1).set(env, &locale);
+  }
+  ttl::bump(env, &key, TtlClass::Hot);
+  Ok(project_count + 1)
+}
+
+pub fn validate_milestones(env: &Env, milestones: &Vec<Milestone>, budget: i128, project_deadline: u64) -> ValidationReport {
+  let mut issues = Vec::new(env);
+  let mut issue = |code, index, other| issues.push_back(ValidationIssue { code, index, other });
+  if milestones.len() > MAX_MILESTONES {
+    issue(IssueCode::TooMany, MAX_MILESTONES, MAX_MILESTONES);
+  }
+  // Milestones past the cap are still checked, but only against the first MAX_MILESTONES for
+  // duplicates, which bounds the pairwise work
+  let mut sum: Option<i128> = Some(0);
+  let mut last_dated: Option<(u32, u64)> = None;
+  for (index, milestone) in milestones.iter().enumerate() {
+    let index = index as u32;
+    if milestone.amount < 0 {
+      issue(IssueCode::NegativeAmount, index, index);
+    }
+    if milestone.completed {
+      issue(IssueCode::Completed, index, index);
+    }
+    let earlier = milestones.slice(0..index.min(MAX_MILESTONES)).iter().position(|other| {
+      other.description == milestone.description && other.amount == milestone.amount && other.deadline == milestone.deadline
+    });
+    if let Some(other) = earlier {
+      issue(IssueCode::Duplicate, index, other as u32);
+    }
+    if milestone.deadline != 0 {
+      if let Some((other, _)) = last_dated.filter(|(_, deadline)| milestone.deadline < *deadline) {
+        issue(IssueCode::DeadlineOrder, index, other);
+      }
+      if project_deadline != 0 && milestone.deadline > project_deadline {
+        issue(IssueCode::PastProjectDeadline, index, index);
+      }
+      last_dated = Some((index, milestone.deadline));
+    }
+    sum = sum.and_then(|sum| sum.checked_add(milestone.amount));
+  }
+  if !milestones.is_empty() && sum != Some(budget) {
+    let last = milestones.len() - 1;
+    issue(IssueCode::BudgetMismatch, last, last);
+  }
+  ValidationReport { valid: issues.is_empty(), issues }
+}
+
+// Fails on the first issue validate_milestones finds
+pub fn check_milestones(env: &Env, milestones: &Vec<Milestone>, budget: i128, project_deadline: u64) -> Result<(), Error> {
+  let Some(issue) = validate_milestones(env, milestones, budget, project_deadline).issues.first() else {
+    return Ok(());
+  };
+  Err(match issue.code {
+    IssueCode::TooMany => Error::LimitReached,
+    IssueCode::NegativeAmount | IssueCode::BudgetMismatch => Error::InvalidAmount,
+    IssueCode::Completed => Error::InvalidConfig,
+    IssueCode::Duplicate | IssueCode::DeadlineOrder | IssueCode::PastProjectDeadline => Error::InvalidSchedule,
+  })
+}
+
+// The project's escrow while it is active. A project that went in progress before escrows
+// were recorded per project can't be told apart from one whose escrow is still running, so
+// it reports NotFound rather than skip the freelancer's acknowledgement.
+pub fn active_project_escrow(env: &Env, project: &Project) -> Result<Option<(u64, Escrow)>, Error> {
+  let Some(escrow_id) = storage::project_escrows(project.id).get(env) else {
+    return if project.status == ProjectStatus::Open { Ok(None) } else { Err(Error::NotFound) };
+  };
+  let escrow = storage::escrows(escrow_id).get(env);
+  Ok(escrow.filter(is_active).map(|escrow| (escrow_id, escrow)))
+}
+
+pub fn proposal_listing(env: &Env, proposal: Proposal, enriched: bool) -> ProposalListing {
+  if !enriched {
+    return ProposalListing { proposal, enriched, active_escrows: 0, available: false, avg_response_secs: 0, language_match: false };
+  }
+  let stats = storage::freelancer_stats(proposal.freelancer.clone()).get(env).unwrap_or_default();
+  let available = !storage::unavailable(proposal.freelancer.clone()).has(env);
+  let language_match = language_match(env, proposal.project_id, &proposal.freelancer);
+  ProposalListing {
+    language_match,
+    proposal,
+    enriched,
+    active_escrows: stats.active_escrows,
+    available,
+    avg_response_secs: if stats.responses == 0 { 0 } else { stats.response_secs / stats.responses as u64 },
+  }
+}
+
+pub fn language_match(env: &Env, project_id: u64, freelancer: &Address) -> bool {
+  let Some(language) = storage::project_locales(project_id).get(env).and_then(|locale| locale.language) else {
+    return true;
+  };
+  user_languages(env, freelancer).contains(language)
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::Address as _;
+  use soroban_sdk::{ symbol_short, vec, Address, Env, String, Vec };
+
+  use crate::project::{ create_project, language_match };
+  use crate::test::harness;
+  use crate::{ storage, Error, Locale, ProjectStatus };
+
+  fn post(env: &Env, client: &Address, budget: i128, locale: Locale) -> Result<u64, Error> {
+    let text = String::from_str(env, "Site");
+    create_project(env, client.clone(), text.clone(), text.clone(), text, budget, client.clone(), 0, Vec::new(env), locale)
+  }
+
+  #[test]
+  fn projects_are_numbered_from_one_and_stored_open() {
+    let (env, contract) = harness();
+    let client = Address::generate(&env);
+    env.as_contract(&contract, || {
+      assert_eq!(post(&env, &client, 100, Locale::default()), Ok(1));
+      assert_eq!(post(&env, &client, 200, Locale::default()), Ok(2));
+      let project = storage::projects(2).get(&env).unwrap();
+      assert_eq!((project.budget, project.status), (200, ProjectStatus::Open));
+      assert_eq!(storage::project_count().get(&env), Some(2));
+      // Only a locale that says something is stored
+      assert!(!storage::project_locales(1).has(&env));
+    });
+  }
+
+  #[test]
+  fn a_negative_budget_or_an_unreal_offset_stores_nothing() {
+    let (env, contract) = harness();
+    let client = Address::generate(&env);
+    env.as_contract(&contract, || {
+      assert_eq!(post(&env, &client, -1, Locale::default()), Err(Error::InvalidAmount));
+      assert_eq!(post(&env, &client, 100, Locale { language: None, tz_offset_mins: Some(15 * 60) }), Err(Error::InvalidConfig));
+      assert!(!storage::project_count().has(&env));
+    });
+  }
+
+  #[test]
+  fn a_project_language_matches_only_freelancers_who_declared_it() {
+    let (env, contract) = harness();
+    let (client, freelancer) = (Address::generate(&env), Address::generate(&env));
+    env.as_contract(&contract, || {
+      let open = post(&env, &client, 100, Locale::default()).unwrap();
+      let spanish = post(&env, &client, 100, Locale { language: Some(symbol_short!("es")), tz_offset_mins: None }).unwrap();
+      assert!(language_match(&env, open, &freelancer));
+      assert!(!language_match(&env, spanish, &freelancer));
+      storage::user_languages(freelancer.clone()).set(&env, &vec![&env, symbol_short!("en"), symbol_short!("es")]);
+      assert!(language_match(&env, spanish, &freelancer));
+    });
+  }
+}
@@ -0,0 +1,120 @@
+// Ratings clients leave on completed escrows, and the per-freelancer summaries built from them
+
+use soroban_sdk::{ Address, Env, Map, String, Vec };
+
+use crate::index::{ IndexKind, MAX_RATINGS_PER_FREELANCER };
+use crate::page::RatingPage;
+use crate::ttl::TtlClass;
+use crate::{ index, page, storage, ttl, Error, EscrowState, Rating, RatingSummary, RegistryKind, MAX_RATING_TAGS };
+
+pub fn rate_freelancer(env: &Env, from: Address, escrow_id: u64, rating: u32, comment: String, tags: Vec<u32>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if escrow.state != EscrowState::Completed {
+    return Err(Error::EscrowNotCompleted);
+  }
+  if !(1..=5).contains(&rating) {
+    return Err(Error::InvalidRating);
+  }
+  if storage::ratings(escrow_id).has(env) {
+    return Err(Error::AlreadyRated);
+  }
+  check_rating_tags(env, &tags)?;
+
+  let record = Rating { escrow_id, from, to: escrow.freelancer.clone(), rating, comment, rated_at: env.ledger().timestamp() };
+  storage::ratings(escrow_id).set(env, &record);
+  let summary_key = storage::rating_summaries(escrow.freelancer.clone());
+  let mut summary = summary_key.get(env).unwrap_or_default();
+  summary.count += 1;
+  summary.stars += rating;
+  summary_key.set(env, &summary);
+  if !tags.is_empty() {
+    let counts_key = storage::tag_counts(escrow.freelancer.clone());
+    let mut counts = counts_key.get(env).unwrap_or(Map::new(env));
+    for tag in tags.iter() {
+      counts.set(tag, counts.get(tag).unwrap_or(0) + 1);
+    }
+    counts_key.set(env, &counts);
+    storage::rating_tags(escrow_id).set(env, &tags);
+  }
+  // Past the cap the rating still counts, it just is not listed
+  index::append_unique(env, &IndexKind::FreelancerRatings(escrow.freelancer), escrow_id, MAX_RATINGS_PER_FREELANCER);
+  ttl::bump(env, &summary_key, TtlClass::Warm);
+  Ok(())
+}
+
+pub fn get_rating(env: &Env, escrow_id: u64) -> Result<Rating, Error> {
+  storage::ratings(escrow_id).get(env).ok_or(Error::NotFound)
+}
+
+pub fn get_ratings(env: &Env, freelancer: Address, cursor: u64, limit: u32) -> RatingPage {
+  let ids = index::read(env, &IndexKind::FreelancerRatings(freelancer));
+  let (ratings, next_cursor) = page::fill(env, ids.len(), cursor, limit, |position| storage::ratings(ids.get(position).unwrap()).get(env));
+  RatingPage { items: ratings, next_cursor, total: Some(ids.len()) }
+}
+
+pub fn get_rating_tags(env: &Env, escrow_id: u64) -> Vec<u32> {
+  storage::rating_tags(escrow_id).get(env).unwrap_or(Vec::new(env))
+}
+
+pub fn get_rating_summary(env: &Env, freelancer: Address) -> RatingSummary {
+  storage::rating_summaries(freelancer).get(env).unwrap_or_default()
+}
+
+pub fn check_rating_tags(env: &Env, tags: &Vec<u32>) -> Result<(), Error> {
+  if tags.len() > MAX_RATING_TAGS {
+    return Err(Error::LimitReached);
+  }
+  let registered = storage::registry_count(RegistryKind::OutcomeTag).get(env).unwrap_or(0);
+  for (position, tag) in tags.iter().enumerate() {
+    if !(1..=registered).contains(&tag) {
+      return Err(Error::UnknownTag);
+    }
+    if tags.first_index_of(tag) != Some(position as u32) {
+      return Err(Error::InvalidRating);
+    }
+  }
+  Ok(())
+}
+
+// Average stars x100, starting from two neutral 3-star ratings so the first few reviews
+// cannot swing it to either end
+pub fn reputation(summary: &RatingSummary) -> u32 {
+  (summary.stars + 2 * 3) * 100 / (summary.count + 2)
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::{ vec, Vec };
+
+  use crate::rating::{ check_rating_tags, reputation };
+  use crate::test::harness;
+  use crate::{ storage, Error, RatingSummary, RegistryKind };
+
+  #[test]
+  fn reputation_starts_neutral_and_moves_slowly() {
+    assert_eq!(reputation(&RatingSummary { count: 0, stars: 0 }), 300);
+    assert_eq!(reputation(&RatingSummary { count: 1, stars: 5 }), 366);
+    assert_eq!(reputation(&RatingSummary { count: 10, stars: 10 }), 133);
+  }
+
+  #[test]
+  fn tags_must_be_registered_distinct_and_few() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      assert_eq!(check_rating_tags(&env, &Vec::new(&env)), Ok(()));
+      assert_eq!(check_rating_tags(&env, &vec![&env, 1]), Err(Error::UnknownTag));
+      storage::registry_count(RegistryKind::OutcomeTag).set(&env, &6);
+      assert_eq!(check_rating_tags(&env, &vec![&env, 2, 1]), Ok(()));
+      assert_eq!(check_rating_tags(&env, &vec![&env, 7]), Err(Error::UnknownTag));
+      assert_eq!(check_rating_tags(&env, &vec![&env, 0]), Err(Error::UnknownTag));
+      assert_eq!(check_rating_tags(&env, &vec![&env, 2, 2]), Err(Error::InvalidRating));
+      assert_eq!(check_rating_tags(&env, &vec![&env, 1, 2, 3, 4, 5, 6]), Err(Error::LimitReached));
+    });
+  }
+}
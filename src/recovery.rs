@@ -0,0 +1,101 @@
+// Recovery of a freelancer's lost key mid-escrow. The recovery address on the freelancer's
+// profile asks for the escrow to move to a new address, and the client confirms it. A client
+// who won't confirm can be overruled by the admin, through the queue, on RECOVERY_DELAY_SECS
+// notice, during which the freelancer can still withdraw the request if the key isn't lost.
+
+use soroban_sdk::{ Address, Env };
+
+use crate::escrow::{ is_active, ledger_line, put_escrow, record_line };
+use crate::index::{ IndexKind, MAX_PAIR_INDEX_LEN };
+use crate::risk::move_exposure;
+use crate::user::recovery_address;
+use crate::{ events, index, storage, Error, LedgerKind, RecoveryRequest };
+
+pub fn request_recovery(env: &Env, from: Address, escrow_id: u64, new_freelancer: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if recovery_address(env, &escrow.freelancer) != Some(from.clone()) {
+    return Err(Error::Unauthorized);
+  }
+  // The client can't end up on both sides of their own escrow
+  if new_freelancer == escrow.freelancer || new_freelancer == escrow.client {
+    return Err(Error::InvalidOwner);
+  }
+  storage::recovery_requests(escrow_id).set(env, &RecoveryRequest { recovery: from, new_freelancer, requested_at: env.ledger().timestamp() });
+  Ok(())
+}
+
+pub fn confirm_recovery(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  let request = storage::recovery_requests(escrow_id).get(env).ok_or(Error::NotFound)?;
+  recover(env, escrow_id, request.new_freelancer)
+}
+
+// The freelancer, or the recovery address, withdraws a request
+pub fn cancel_recovery(env: &Env, from: Address, escrow_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  let request = storage::recovery_requests(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.freelancer != from && request.recovery != from {
+    return Err(Error::Unauthorized);
+  }
+  storage::recovery_requests(escrow_id).remove(env);
+  Ok(())
+}
+
+pub fn get_recovery_request(env: &Env, escrow_id: u64) -> Option<RecoveryRequest> {
+  storage::recovery_requests(escrow_id).get(env)
+}
+
+// The pending request for exactly this address. Checked when the admin queues the fallback,
+// and again when it runs.
+pub fn check_recovery(env: &Env, escrow_id: u64, new_freelancer: &Address) -> Result<RecoveryRequest, Error> {
+  let request = storage::recovery_requests(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if request.new_freelancer != *new_freelancer {
+    return Err(Error::NotFound);
+  }
+  Ok(request)
+}
+
+// Moves the escrow to the requested address: its payouts, bond and refund rights from here on.
+// The request must still come from the freelancer's recovery address in effect.
+pub fn recover(env: &Env, escrow_id: u64, new_freelancer: Address) -> Result<(), Error> {
+  let request = check_recovery(env, escrow_id, &new_freelancer)?;
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if recovery_address(env, &escrow.freelancer) != Some(request.recovery) {
+    return Err(Error::Unauthorized);
+  }
+
+  let old = escrow.freelancer.clone();
+  index::remove_value(env, &IndexKind::PairEscrows(escrow.client.clone(), old.clone()), escrow_id);
+  if !index::append_unique(env, &IndexKind::PairEscrows(escrow.client.clone(), new_freelancer.clone()), escrow_id, MAX_PAIR_INDEX_LEN) {
+    return Err(Error::LimitReached);
+  }
+  move_exposure(env, escrow_id, &old, &new_freelancer);
+  if let Some(note_hash) = storage::private_notes(escrow_id, old.clone()).get(env) {
+    storage::private_notes(escrow_id, old.clone()).remove(env);
+    storage::private_notes(escrow_id, new_freelancer.clone()).set(env, &note_hash);
+  }
+  escrow.freelancer = new_freelancer;
+  put_escrow(env, escrow_id, &escrow);
+  storage::recovery_requests(escrow_id).remove(env);
+  record_line(env, escrow_id, ledger_line(env, LedgerKind::Recovered, &old, &escrow.asset, None, 0));
+  events::recovered(env, escrow_id, &escrow, &old);
+  Ok(())
+}
@@ -0,0 +1,100 @@
+// Fat-finger guard on payouts. A client can cap what one approval pays out on an escrow; an
+// approval above the cap is held instead of paid, and pays only when the client confirms it
+// in a separate call at least RELEASE_CONFIRM_SECS later. Until then the client can cancel it,
+// and a dispute on the milestone freezes it like any other payout. Auto-releases are the
+// freelancer's and never wait.
+
+use soroban_sdk::{ Address, Env };
+
+use crate::escrow::{ approve_work, check_reentry, is_active, note_client_activity, put_escrow };
+use crate::{ events, storage, Error, Escrow, MilestoneRecord, MilestoneStatus, PendingRelease, RELEASE_CONFIRM_SECS };
+
+pub fn set_max_single_release(env: &Env, client: Address, escrow_id: u64, max_single_release: i128) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  client.require_auth();
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != client {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  if max_single_release < 0 {
+    return Err(Error::InvalidAmount);
+  }
+  let key = storage::release_guards(escrow_id);
+  if max_single_release == 0 {
+    key.remove(env);
+  } else {
+    key.set(env, &max_single_release);
+  }
+  note_client_activity(env, escrow_id, &mut escrow);
+  put_escrow(env, escrow_id, &escrow);
+  Ok(())
+}
+
+// 0 when the escrow has no cap
+pub fn get_max_single_release(env: &Env, escrow_id: u64) -> i128 {
+  storage::release_guards(escrow_id).get(env).unwrap_or(0)
+}
+
+pub fn confirm_release(env: &Env, client: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  client.require_auth();
+  check_reentry(env)?;
+
+  if !storage::pending_releases(escrow_id, milestone_index).has(env) {
+    return Err(Error::NotFound);
+  }
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  approve_work(env, &client, escrow_id, escrow.lump_sum, milestone_index)
+}
+
+pub fn cancel_pending_release(env: &Env, client: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  client.require_auth();
+
+  let mut escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != client {
+    return Err(Error::Unauthorized);
+  }
+  let key = storage::pending_releases(escrow_id, milestone_index);
+  if !key.has(env) {
+    return Err(Error::NotFound);
+  }
+  key.remove(env);
+  note_client_activity(env, escrow_id, &mut escrow);
+  put_escrow(env, escrow_id, &escrow);
+  Ok(())
+}
+
+pub fn get_pending_release(env: &Env, escrow_id: u64, milestone_index: u32) -> Option<PendingRelease> {
+  storage::pending_releases(escrow_id, milestone_index).get(env)
+}
+
+// Runs before an approval pays `amount`, and returns true when it is held instead: the
+// amount is over the cap and nothing matching it is held yet. Approving a held milestone
+// again confirms it, failing with ReleaseTooEarly before its time; the payout itself clears
+// the hold.
+pub fn hold(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, record: &MilestoneRecord, amount: i128) -> Result<bool, Error> {
+  let key = storage::pending_releases(escrow_id, milestone_index);
+  if let Some(pending) = key.get(env) {
+    if pending.amount == amount {
+      if env.ledger().timestamp() < pending.confirmable_at {
+        return Err(Error::ReleaseTooEarly);
+      }
+      return Ok(false);
+    }
+    // Otherwise the payout changed since the client was asked to confirm it
+  }
+  let max_single_release = get_max_single_release(env, escrow_id);
+  // Only a payable submission is held; anything else fails in the payout as usual
+  if max_single_release == 0 || amount <= max_single_release || record.status != MilestoneStatus::Submitted {
+    return Ok(false);
+  }
+  let pending = PendingRelease { amount, confirmable_at: env.ledger().timestamp() + RELEASE_CONFIRM_SECS };
+  key.set(env, &pending);
+  events::release_held(env, escrow_id, escrow, milestone_index, &pending);
+  Ok(true)
+}
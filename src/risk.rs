@@ -0,0 +1,96 @@
+// Risk-tier caps: the value a freelancer can have in active escrows, in reference units, grows
+// with their track record. Each escrow counts at its asset's reference rate when it opens and
+// stops counting when it closes, so completions free room under the cap.
+
+use soroban_sdk::{ Address, Env };
+
+use crate::admin::{ config, fresh_reference_rate };
+use crate::math::to_reference;
+use crate::{ storage, Error, Escrow, EscrowState, Exposure, RiskLimit, RiskTier };
+
+// Applied through the admin queue
+pub fn set_verified(env: &Env, freelancer: Address, verified: bool) {
+  let key = storage::verified(freelancer);
+  if verified {
+    key.set(env, &true);
+  } else {
+    key.remove(env);
+  }
+}
+
+pub fn get_risk_limit(env: &Env, freelancer: Address) -> RiskLimit {
+  let exposure = storage::exposures(freelancer.clone()).get(env).unwrap_or(Exposure { usage: 0, completed: 0 });
+  let (tier, cap) = tier_cap(env, &freelancer, &exposure);
+  RiskLimit { tier, cap, usage: exposure.usage, completed: exposure.completed }
+}
+
+// Counts `amount` of an escrow, all of a new one or what an added milestone grows it by,
+// against its freelancer, failing with LimitReached when it would take them past their
+// tier's cap. Escrows in an asset without a fresh reference rate can't be measured: they
+// count for nothing where the tier is uncapped and fail with NotFound where it is capped.
+pub fn take_exposure(env: &Env, escrow_id: u64, escrow: &Escrow, amount: i128) -> Result<(), Error> {
+  let key = storage::exposures(escrow.freelancer.clone());
+  let mut exposure = key.get(env).unwrap_or(Exposure { usage: 0, completed: 0 });
+  let (_, cap) = tier_cap(env, &escrow.freelancer, &exposure);
+  let value = match fresh_reference_rate(env, escrow.asset.clone()) {
+    Ok(rate) => to_reference(amount, rate.rate).ok_or(Error::InvalidAmount)?,
+    Err(error) if cap > 0 => return Err(error),
+    Err(_) => 0,
+  };
+  if cap > 0 && exposure.usage + value > cap {
+    return Err(Error::LimitReached);
+  }
+  if value > 0 {
+    exposure.usage += value;
+    key.set(env, &exposure);
+    let escrow_key = storage::escrow_exposures(escrow_id);
+    escrow_key.set(env, &(escrow_key.get(env).unwrap_or(0) + value));
+  }
+  Ok(())
+}
+
+// Stops counting a closed or declined escrow; a completed one goes on the track record
+pub fn release_exposure(env: &Env, escrow_id: u64, escrow: &Escrow) {
+  let value = storage::escrow_exposures(escrow_id).get(env).unwrap_or(0);
+  let completed = escrow.state == EscrowState::Completed;
+  if value == 0 && !completed {
+    return;
+  }
+  let key = storage::exposures(escrow.freelancer.clone());
+  let mut exposure = key.get(env).unwrap_or(Exposure { usage: 0, completed: 0 });
+  exposure.usage = (exposure.usage - value).max(0);
+  if completed {
+    exposure.completed += 1;
+  }
+  key.set(env, &exposure);
+  storage::escrow_exposures(escrow_id).remove(env);
+}
+
+// An escrow recovered to a new address counts against that address from then on
+pub fn move_exposure(env: &Env, escrow_id: u64, old: &Address, new: &Address) {
+  let value = storage::escrow_exposures(escrow_id).get(env).unwrap_or(0);
+  if value == 0 {
+    return;
+  }
+  let old_key = storage::exposures(old.clone());
+  if let Some(mut exposure) = old_key.get(env) {
+    exposure.usage = (exposure.usage - value).max(0);
+    old_key.set(env, &exposure);
+  }
+  let new_key = storage::exposures(new.clone());
+  let mut exposure = new_key.get(env).unwrap_or(Exposure { usage: 0, completed: 0 });
+  exposure.usage += value;
+  new_key.set(env, &exposure);
+}
+
+// The freelancer's tier and its cap; a cap of 0 means uncapped
+fn tier_cap(env: &Env, freelancer: &Address, exposure: &Exposure) -> (RiskTier, i128) {
+  let caps = config(env).risk_caps;
+  if storage::verified(freelancer.clone()).has(env) {
+    (RiskTier::Verified, 0)
+  } else if caps.established_after > 0 && exposure.completed >= caps.established_after {
+    (RiskTier::Established, caps.established_cap)
+  } else {
+    (RiskTier::New, caps.new_cap)
+  }
+}
@@ -0,0 +1,170 @@
+// Fixed-price services freelancers publish, and buying one outright: the purchase posts a
+// project from the listing and opens a lump-sum escrow on it, funded by the buyer.
+
+use soroban_sdk::{ Address, Env, String, Vec };
+
+use crate::admin::require_launch_access;
+use crate::escrow::{ check_reentry, open_escrow, record_deposit };
+use crate::index::{ IndexKind, MAX_SERVICES_PER_CATEGORY };
+use crate::page::IdPage;
+use crate::project::create_project;
+use crate::ttl::TtlClass;
+use crate::{ index, storage, ttl, Error, Locale, ServiceListing, TermsSource, DAY_SECS };
+
+pub fn create_service(
+  env: &Env,
+  from: Address, // Freelancer address
+  title: String,
+  description: String,
+  category: String,
+  price: i128,
+  asset: Address,
+  delivery_days: u32,
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  require_launch_access(env, &from)?;
+  check_terms(price, delivery_days)?;
+
+  let listing_id = storage::service_count().get(env).unwrap_or(0) + 1;
+  if !index::append_unique(env, &IndexKind::CategoryServices(category.clone()), listing_id, MAX_SERVICES_PER_CATEGORY) {
+    return Err(Error::LimitReached);
+  }
+  let listing = ServiceListing { freelancer: from, title, description, category, price, asset, delivery_days, active: true };
+  let key = storage::services(listing_id);
+  key.set(env, &listing);
+  storage::service_count().set(env, &listing_id);
+  ttl::bump(env, &key, TtlClass::Warm);
+  Ok(listing_id)
+}
+
+// Purchases already made keep the price and deadline they were bought at
+pub fn update_service(env: &Env, from: Address, listing_id: u64, title: String, description: String, price: i128, delivery_days: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  let mut listing = own_listing(env, &from, listing_id)?;
+  check_terms(price, delivery_days)?;
+
+  listing.title = title;
+  listing.description = description;
+  listing.price = price;
+  listing.delivery_days = delivery_days;
+  storage::services(listing_id).set(env, &listing);
+  Ok(())
+}
+
+pub fn deactivate_service(env: &Env, from: Address, listing_id: u64) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  let mut listing = own_listing(env, &from, listing_id)?;
+
+  listing.active = false;
+  storage::services(listing_id).set(env, &listing);
+  index::remove_value(env, &IndexKind::CategoryServices(listing.category), listing_id);
+  Ok(())
+}
+
+pub fn get_service(env: &Env, listing_id: u64) -> Result<ServiceListing, Error> {
+  storage::services(listing_id).get(env).ok_or(Error::NotFound)
+}
+
+pub fn list_services(env: &Env, category: String, cursor: u64, limit: u32) -> IdPage {
+  index::paged_read(env, &IndexKind::CategoryServices(category), cursor, limit)
+}
+
+pub fn purchase_service(env: &Env, from: Address, listing_id: u64) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  check_reentry(env)?;
+
+  let listing = get_service(env, listing_id)?;
+  // A listing taken down is closed to buyers, like a project that is no longer open
+  if !listing.active {
+    return Err(Error::ProjectNotOpen);
+  }
+  if listing.freelancer == from {
+    return Err(Error::Unauthorized);
+  }
+
+  let deadline = env.ledger().timestamp() + listing.delivery_days as u64 * DAY_SECS;
+  let project_id = create_project(
+    env,
+    from.clone(),
+    listing.title,
+    listing.description,
+    listing.category,
+    listing.price,
+    listing.asset,
+    deadline,
+    Vec::new(env),
+    Locale::default(),
+  )?;
+  let mut project = storage::projects(project_id).get(env).ok_or(Error::NotFound)?;
+  let escrow_id = open_escrow(env, &mut project, listing.freelancer, listing.price, Vec::new(env), TermsSource::Service(listing_id))?;
+  record_deposit(env, from, escrow_id, listing.price, None)?;
+  Ok(escrow_id)
+}
+
+// Loads a listing the caller published
+fn own_listing(env: &Env, from: &Address, listing_id: u64) -> Result<ServiceListing, Error> {
+  let listing = get_service(env, listing_id)?;
+  if listing.freelancer != *from {
+    return Err(Error::Unauthorized);
+  }
+  Ok(listing)
+}
+
+fn check_terms(price: i128, delivery_days: u32) -> Result<(), Error> {
+  if price <= 0 {
+    return Err(Error::InvalidAmount);
+  }
+  // A purchase due the moment it is made would start out overdue
+  if delivery_days == 0 {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::Address as _;
+  use soroban_sdk::{ Address, String, Vec };
+
+  use crate::index::{ IndexKind, MAX_SERVICES_PER_CATEGORY };
+  use crate::service::{ check_terms, create_service };
+  use crate::test::harness;
+  use crate::{ storage, Error };
+
+  #[test]
+  fn a_listing_needs_a_price_and_at_least_a_day_to_deliver() {
+    assert_eq!(check_terms(0, 3), Err(Error::InvalidAmount));
+    assert_eq!(check_terms(-1, 3), Err(Error::InvalidAmount));
+    assert_eq!(check_terms(100, 0), Err(Error::InvalidConfig));
+    assert_eq!(check_terms(100, 1), Ok(()));
+  }
+
+  #[test]
+  fn a_full_category_takes_no_more_listings() {
+    let (env, contract) = harness();
+    let freelancer = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let category = String::from_str(&env, "Design");
+    env.as_contract(&contract, || {
+      // Filled in one write; appending a thousand times would outrun the test budget
+      let mut listed = Vec::new(&env);
+      for id in 1..=MAX_SERVICES_PER_CATEGORY as u64 {
+        listed.push_back(id + 100);
+      }
+      storage::index(&IndexKind::CategoryServices(category.clone())).set(&env, &listed);
+      let text = String::from_str(&env, "Logo");
+      let result = create_service(&env, freelancer.clone(), text.clone(), text, category.clone(), 100, asset.clone(), 3);
+      assert_eq!(result, Err(Error::LimitReached));
+      assert!(!storage::service_count().has(&env));
+    });
+    env.as_contract(&contract, || {
+      let text = String::from_str(&env, "Logo");
+      let other = String::from_str(&env, "Writing");
+      assert_eq!(create_service(&env, freelancer, text.clone(), text, other, 100, asset, 3), Ok(1));
+    });
+  }
+}
@@ -0,0 +1,88 @@
+#![cfg(test)]
+
+// Test-only capture of a contract's stored state in a stable text form, and re-loading of
+// that state into a contract running the current code. Migration tests keep fixtures of
+// older storage layouts in this form.
+//
+// Each line is `<storage> <key> <value>`, with the key and value as base64 ScVal XDR and
+// the lines sorted, so the same state always captures to the same text.
+
+extern crate std;
+
+use std::string::{ String, ToString };
+use std::vec::Vec;
+
+use soroban_sdk::xdr::{ ContractDataDurability, LedgerEntryData, LedgerKey, Limits, ReadXdr, ScAddress, ScVal, WriteXdr };
+use soroban_sdk::{ Address, Env, TryFromVal, Val };
+
+pub fn capture(env: &Env, contract: &Address) -> String {
+  let mut lines: Vec<String> = entries(env, contract)
+    .into_iter()
+    .map(|(storage, key, value)| std::format!("{} {} {}", storage, encode(&key), encode(&value)))
+    .collect();
+  lines.sort();
+  lines.join("\n") + "\n"
+}
+
+// Replaces everything the contract has stored with the captured state
+pub fn restore(env: &Env, contract: &Address, snapshot: &str) {
+  let current = entries(env, contract);
+  env.as_contract(contract, || {
+    for (storage, key, _) in current {
+      let key = Val::try_from_val(env, &key).unwrap();
+      match storage {
+        "instance" => env.storage().instance().remove(&key),
+        "persistent" => env.storage().persistent().remove(&key),
+        _ => env.storage().temporary().remove(&key),
+      }
+    }
+    for line in snapshot.lines().filter(|line| !line.is_empty()) {
+      let mut parts = line.split(' ');
+      let storage = parts.next().unwrap();
+      let key = Val::try_from_val(env, &decode(parts.next().unwrap())).unwrap();
+      let value = Val::try_from_val(env, &decode(parts.next().unwrap())).unwrap();
+      match storage {
+        "instance" => env.storage().instance().set(&key, &value),
+        "persistent" => env.storage().persistent().set(&key, &value),
+        _ => env.storage().temporary().set(&key, &value),
+      }
+    }
+  });
+}
+
+// Every stored entry of the contract: its instance storage map, then its own ledger entries
+fn entries(env: &Env, contract: &Address) -> Vec<(&'static str, ScVal, ScVal)> {
+  let contract: ScAddress = contract.into();
+  let mut entries = Vec::new();
+  for (key, (entry, _)) in env.to_ledger_snapshot().entries() {
+    let (LedgerKey::ContractData(key), LedgerEntryData::ContractData(data)) = (key.as_ref(), &entry.data) else {
+      continue;
+    };
+    if key.contract != contract {
+      continue;
+    }
+    match (&data.key, &data.val) {
+      (ScVal::LedgerKeyContractInstance, ScVal::ContractInstance(instance)) => {
+        for item in instance.storage.iter().flat_map(|storage| storage.iter()) {
+          entries.push(("instance", item.key.clone(), item.val.clone()));
+        }
+      }
+      _ => {
+        let storage = match data.durability {
+          ContractDataDurability::Persistent => "persistent",
+          ContractDataDurability::Temporary => "temporary",
+        };
+        entries.push((storage, data.key.clone(), data.val.clone()));
+      }
+    }
+  }
+  entries
+}
+
+fn encode(value: &ScVal) -> String {
+  value.to_xdr_base64(Limits::none()).unwrap().to_string()
+}
+
+fn decode(value: &str) -> ScVal {
+  ScVal::from_xdr_base64(value, Limits::none()).unwrap()
+}
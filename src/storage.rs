@@ -0,0 +1,717 @@
+// Every key the contract stores under, and a typed handle per key. Only this module builds
+// keys; the rest of the contract reads and writes through the handles, so the value type
+// stored under a key is fixed in one place.
+
+use core::marker::PhantomData;
+
+use soroban_sdk::{ contracttype, Address, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec };
+
+use crate::index::IndexKind;
+use crate::ttl::{ TtlClass, TtlPolicy };
+use crate::{ AcceptanceRecord, Amendment, Approval, ArbitrationPolicy, Arbitrator, ArbitratorTally, Bond, Bounty, Bundle, CancellationSchedule, CategoryTemplate, Checklist, Commitments, Config, Contest, Deposit, Dispute, EarlyBonus, EffortEstimate, EpochActivity, Escrow, EscrowState, Exposure, FeeTerms, FreelancerStats, Invite, Kickoff, LedgerLine, Locale, MilestoneOrder, MilestoneRecord, Observer, PendingRelease, PlatformStats, ProgressReport, Project, ProjectTransfer, Proposal, QueuedAction, Rating, RatingSummary, RecoveryRequest, RecoverySetting, ReferenceRate, RegistryEntry, RegistryKind, Reprice, ReviewTally, Ruling, ServiceListing, Subcontract, TemplateRef, TermsSource, Tombstone, UserStats, WipLimit };
+
+// Only ever used as a storage key, never passed across the interface, so it stays out of the
+// contract spec (which also caps a union at 50 cases)
+#[derive(Clone)]
+#[contracttype(export = false)]
+pub enum StorageKey {
+  ProjectCount,
+  UserCount, // Removed as user data is not stored
+  Projects(u64), // Key for each project by ID
+  Escrows(u64),  // Key for each escrow by ID
+  ProjectTombstones(u64),
+  EscrowTombstones(u64),
+  EscrowCount,
+  Admin,
+  RegistryCount(RegistryKind), // Number of entries registered in each registry
+  Registry(RegistryKind, u32), // Key for each registry entry by kind and ID
+  Config,
+  TtlPolicies(TtlClass), // Admin-set storage lifetime of each data class
+  MilestoneRecords(u64), // Review state of each milestone, by escrow ID
+  Bundles(u64), // Running bundle release, by escrow ID
+  EscrowStateCount(EscrowState), // Number of escrows currently in each state
+  EscrowsByState(EscrowState), // Bounded list of escrow IDs currently in each state
+  Deposits(u64), // Deposit log, by escrow ID
+  Bounties(u64), // Crowdfunding of an escrow in bounty mode, by escrow ID
+  ReviewTallies(u64), // Review times so far, by escrow ID
+  LedgerLines(u64), // Money movements of an escrow, oldest first
+  UserStats(Address),
+  UserLanguages(Address), // Working languages a user declared
+  ProjectLocales(u64), // Locale hint of a project that was posted with one
+  ProjectEscrows(u64), // Latest escrow opened on a project, by project ID
+  ProjectTransfers(u64), // Pending ownership transfer, by project ID
+  Ratings(u64), // Rating given on an escrow, by escrow ID
+  RatingSummaries(Address),
+  FreelancerRatings(Address), // Rated escrow IDs of a freelancer, oldest first
+  Deactivated(Address),
+  Checklists(u64), // Kickoff checklist of an escrow that has one
+  ClientDelays(u64), // Client-caused delay in seconds, by milestone index
+  EarlyBonuses(u64), // Early-completion bonuses of an escrow, by milestone index
+  ArbitratorTallies(Address), // Outcome counters of each arbitrator
+  RatingTags(u64), // Outcome tags of the rating left on an escrow
+  TagCounts(Address), // Outcome tag counters of each rated freelancer
+  Bonds(u64), // Bond required from the freelancer, by escrow ID
+  NotificationPrefs(Address), // Hash of the user's off-chain notification settings
+  PairEscrows(Address, Address), // Active escrow IDs between a client and a freelancer
+  WipLimits(Address), // Per-freelancer concurrency preference set by a client
+  DataVersion, // Storage layout version of the stored entries
+  MigrationCursor, // Next ID the running layout migration will rewrite
+  Kickoffs(u64, u32), // Kickoff terms of a milestone, by escrow ID and milestone index
+  Disputes(u64, u32), // Dispute on a milestone, by escrow ID and milestone index
+  Commitments(Address, Address), // Committed funds of a client, by client and asset
+  LegacyAsset, // Asset of entries stored before projects and escrows named one
+  Contests(u64, u32), // Out-of-scope flag on a milestone, by escrow ID and milestone index
+  PlatformFee, // Basis points charged on escrows opened from now on
+  Nonprofits(Address), // Present for clients the admin flagged as nonprofits
+  FeeTerms(u64), // Fee snapshot taken when the escrow was opened, by escrow ID
+  PlatformStats,
+  FeePool(Address), // Fees accrued in an asset and not withdrawn yet
+  TransferGuard, // Set while a token transfer is in flight
+  Obligations(Address), // Funds the contract holds for others in an asset: escrow deposits and posted bonds
+  Acceptances(u64, u32), // Countersigned acceptance, by escrow ID and milestone index
+  Approvals(u64, u32), // Latest client approval, by escrow ID and milestone index
+  ProposalCount,
+  Proposals(u64), // Key for each proposal by ID
+  ProjectProposals(u64), // Bounded list of proposal IDs, by project ID
+  FreelancerStats(Address),
+  Unavailable(Address), // Present for freelancers who are not taking new work
+  TermsSources(u64), // Where the escrow's schedule came from, by escrow ID
+  Arbitrators(Address),
+  ArbitratorRoster, // Every arbitrator added, in the order they were added
+  RandomAssignment, // Present while disputes get a random arbitrator when raised
+  DisputeStrikes(u64, u32), // Arbitrator each party struck, by escrow ID and milestone index
+  ArbitrationPolicy,
+  OpenDisputes, // Bounded queue of unassigned disputes, oldest first
+  DisputeArbitrators(u64, u32), // Arbitrator assigned to a dispute, by escrow ID and milestone index
+  PendingRulings(u64, u32), // Ruling waiting for a co-signature, by escrow ID and milestone index
+  EscrowActivity(u64), // Ledger timestamp of the escrow's last activity, by escrow ID
+  ActivityBucket(u64), // Active escrow IDs whose last activity falls in a time bucket
+  ActivityBuckets, // Non-empty activity buckets, oldest first
+  ServiceCount,
+  Services(u64), // Key for each service listing by ID
+  CategoryServices(String), // Bounded list of active service listing IDs, by category
+  Invites(BytesN<8>), // Escrow an invite code stands for, by code
+  SubcontractConsents(u64, u32), // Present once the client agreed to subcontracting a milestone
+  Subcontracts(u64), // Parent milestone of a child escrow, by child escrow ID
+  MilestoneSubcontracts(u64, u32), // Bounded list of child escrow IDs, by parent escrow ID and milestone index
+  ContactConsents(u64, Address), // Present once the party agreed to exchange contact details on the escrow
+  ReferenceRates(Address), // Latest reference rate the admin posted, by asset
+  Reprices(u64, u32), // Open proposal to reprice a milestone, by escrow ID and milestone index
+  AdminActionCount,
+  AdminActions(u64), // Admin change waiting in the queue, by action ID
+  AcceptedTerms(u64), // terms_digest the freelancer accepted, by escrow ID
+  ProjectInvites(u64), // Freelancers the client invited, by project ID
+  AssetMigrations(Address), // Asset replacing an old one, by the old asset
+  MigrationPools(Address), // New-asset funds the admin set aside for escrows moving to it
+  CancellationSchedules(u64), // Kill fees the client agreed to, by escrow ID
+  Allowlisted(Address), // Addresses let in while the marketplace is in allowlist mode
+  EffortEstimates(u64), // Hours the freelancer estimated per milestone, by escrow ID
+  ProgressReports(u64), // Latest progress report per milestone index, by escrow ID
+  RecoveryAddresses(Address), // Recovery address setting, by user
+  RecoveryRequests(u64), // Pending key recovery, by escrow ID
+  TemplateCount,
+  Templates(u32), // Milestone template by ID
+  CategoryTemplates(u32), // Template IDs in the order they were added, by category registry ID
+  ProjectTemplates(u64), // Template a project's milestones were expanded from, by project ID
+  Verified(Address), // Present for freelancers the admin verified
+  Exposures(Address), // Value counted against a freelancer's risk-tier cap, and their completions
+  EscrowExposures(u64), // What an active escrow counts for against its freelancer's cap, by escrow ID
+  PrivateNotes(u64, Address), // Note hash one party keeps on an escrow, by escrow ID and party
+  MilestoneOrders(u64), // Sequential mode and milestone dependencies, by escrow ID
+  Observers(u64), // Read-only observers granted on an escrow, by escrow ID
+  ReleaseGuards(u64), // Most one approval pays without a confirmation, by escrow ID
+  PendingReleases(u64, u32), // Approval held for the client's confirmation, by escrow ID and milestone index
+  Amendments(u64), // Amendment history, by escrow ID
+  DepositIncrements(Address), // Smallest deposit the admin set for an asset, by asset
+  EpochActivity(u64), // Activity counted in the escrow's current epoch, by escrow ID
+  Withdrawals(u64, u32), // Approved payout the freelancer drew, by escrow ID and milestone index
+}
+
+// Where an entry lives. Projects, escrows, accepted digests, indexes and tombstones are
+// persistent entries with a TTL of their own; settings, counters, profiles and an escrow's
+// other side entries stay in the instance and share its TTL.
+#[derive(Clone, Copy, PartialEq)]
+enum Durability {
+  Instance,
+  Persistent,
+}
+
+// One stored entry and the type stored under it
+pub struct Entry<V> {
+  key: StorageKey,
+  durability: Durability,
+  value: PhantomData<V>,
+}
+
+impl<V> Entry<V> {
+  fn new(key: StorageKey) -> Self {
+    Entry { key, durability: Durability::Instance, value: PhantomData }
+  }
+
+  fn persistent(key: StorageKey) -> Self {
+    Entry { key, durability: Durability::Persistent, value: PhantomData }
+  }
+
+  pub fn has(&self, env: &Env) -> bool {
+    match self.durability {
+      Durability::Instance => env.storage().instance().has(&self.key),
+      Durability::Persistent => env.storage().persistent().has(&self.key),
+    }
+  }
+
+  pub fn remove(&self, env: &Env) {
+    match self.durability {
+      Durability::Instance => env.storage().instance().remove(&self.key),
+      Durability::Persistent => env.storage().persistent().remove(&self.key),
+    }
+  }
+
+  pub fn is_persistent(&self) -> bool {
+    self.durability == Durability::Persistent
+  }
+
+  // Extends a persistent entry, which has to exist. An instance entry lives as long as the
+  // instance does.
+  pub fn extend_ttl(&self, env: &Env, threshold: u32, extend_to: u32) {
+    if self.is_persistent() {
+      env.storage().persistent().extend_ttl(&self.key, threshold, extend_to);
+    }
+  }
+
+  // The same entry as an older storage layout stored it, for migrations. Layouts before 12
+  // kept every entry in the instance.
+  pub fn as_layout<W>(&self) -> Entry<W> {
+    Entry::new(self.key.clone())
+  }
+}
+
+impl<V: IntoVal<Env, Val> + TryFromVal<Env, Val>> Entry<V> {
+  pub fn get(&self, env: &Env) -> Option<V> {
+    match self.durability {
+      Durability::Instance => env.storage().instance().get(&self.key),
+      Durability::Persistent => env.storage().persistent().get(&self.key),
+    }
+  }
+
+  pub fn set(&self, env: &Env, value: &V) {
+    match self.durability {
+      Durability::Instance => env.storage().instance().set(&self.key, value),
+      Durability::Persistent => env.storage().persistent().set(&self.key, value),
+    }
+  }
+}
+
+pub fn project_count() -> Entry<u64> {
+  Entry::new(StorageKey::ProjectCount)
+}
+
+pub fn projects(project_id: u64) -> Entry<Project> {
+  Entry::persistent(StorageKey::Projects(project_id))
+}
+
+pub fn escrows(escrow_id: u64) -> Entry<Escrow> {
+  Entry::persistent(StorageKey::Escrows(escrow_id))
+}
+
+pub fn project_tombstones(project_id: u64) -> Entry<Tombstone> {
+  Entry::persistent(StorageKey::ProjectTombstones(project_id))
+}
+
+pub fn escrow_tombstones(escrow_id: u64) -> Entry<Tombstone> {
+  Entry::persistent(StorageKey::EscrowTombstones(escrow_id))
+}
+
+pub fn escrow_count() -> Entry<u64> {
+  Entry::new(StorageKey::EscrowCount)
+}
+
+pub fn admin() -> Entry<Address> {
+  Entry::new(StorageKey::Admin)
+}
+
+pub fn registry_count(registry: RegistryKind) -> Entry<u32> {
+  Entry::new(StorageKey::RegistryCount(registry))
+}
+
+pub fn registry(registry: RegistryKind, id: u32) -> Entry<RegistryEntry> {
+  Entry::new(StorageKey::Registry(registry, id))
+}
+
+pub fn config() -> Entry<Config> {
+  Entry::new(StorageKey::Config)
+}
+
+pub fn ttl_policies(class: TtlClass) -> Entry<TtlPolicy> {
+  Entry::new(StorageKey::TtlPolicies(class))
+}
+
+pub fn milestone_records(escrow_id: u64) -> Entry<Vec<MilestoneRecord>> {
+  Entry::new(StorageKey::MilestoneRecords(escrow_id))
+}
+
+pub fn bundles(escrow_id: u64) -> Entry<Bundle> {
+  Entry::new(StorageKey::Bundles(escrow_id))
+}
+
+pub fn escrow_state_count(state: EscrowState) -> Entry<u32> {
+  Entry::new(StorageKey::EscrowStateCount(state))
+}
+
+pub fn deposits(escrow_id: u64) -> Entry<Vec<Deposit>> {
+  Entry::new(StorageKey::Deposits(escrow_id))
+}
+
+pub fn bounties(escrow_id: u64) -> Entry<Bounty> {
+  Entry::new(StorageKey::Bounties(escrow_id))
+}
+
+pub fn review_tallies(escrow_id: u64) -> Entry<ReviewTally> {
+  Entry::new(StorageKey::ReviewTallies(escrow_id))
+}
+
+pub fn ledger_lines(escrow_id: u64) -> Entry<Vec<LedgerLine>> {
+  Entry::new(StorageKey::LedgerLines(escrow_id))
+}
+
+pub fn user_stats(user: Address) -> Entry<UserStats> {
+  Entry::new(StorageKey::UserStats(user))
+}
+
+pub fn user_languages(user: Address) -> Entry<Vec<Symbol>> {
+  Entry::new(StorageKey::UserLanguages(user))
+}
+
+pub fn project_locales(project_id: u64) -> Entry<Locale> {
+  Entry::new(StorageKey::ProjectLocales(project_id))
+}
+
+pub fn project_escrows(project_id: u64) -> Entry<u64> {
+  Entry::new(StorageKey::ProjectEscrows(project_id))
+}
+
+pub fn project_transfers(project_id: u64) -> Entry<ProjectTransfer> {
+  Entry::new(StorageKey::ProjectTransfers(project_id))
+}
+
+pub fn ratings(escrow_id: u64) -> Entry<Rating> {
+  Entry::new(StorageKey::Ratings(escrow_id))
+}
+
+pub fn rating_summaries(freelancer: Address) -> Entry<RatingSummary> {
+  Entry::new(StorageKey::RatingSummaries(freelancer))
+}
+
+pub fn deactivated(user: Address) -> Entry<bool> {
+  Entry::new(StorageKey::Deactivated(user))
+}
+
+pub fn checklists(escrow_id: u64) -> Entry<Checklist> {
+  Entry::new(StorageKey::Checklists(escrow_id))
+}
+
+pub fn client_delays(escrow_id: u64) -> Entry<Map<u32, u64>> {
+  Entry::new(StorageKey::ClientDelays(escrow_id))
+}
+
+pub fn early_bonuses(escrow_id: u64) -> Entry<Map<u32, EarlyBonus>> {
+  Entry::new(StorageKey::EarlyBonuses(escrow_id))
+}
+
+pub fn arbitrator_tallies(arbitrator: Address) -> Entry<ArbitratorTally> {
+  Entry::new(StorageKey::ArbitratorTallies(arbitrator))
+}
+
+pub fn rating_tags(escrow_id: u64) -> Entry<Vec<u32>> {
+  Entry::new(StorageKey::RatingTags(escrow_id))
+}
+
+pub fn tag_counts(freelancer: Address) -> Entry<Map<u32, u32>> {
+  Entry::new(StorageKey::TagCounts(freelancer))
+}
+
+pub fn bonds(escrow_id: u64) -> Entry<Bond> {
+  Entry::new(StorageKey::Bonds(escrow_id))
+}
+
+pub fn notification_prefs(user: Address) -> Entry<BytesN<32>> {
+  Entry::new(StorageKey::NotificationPrefs(user))
+}
+
+pub fn wip_limits(client: Address) -> Entry<WipLimit> {
+  Entry::new(StorageKey::WipLimits(client))
+}
+
+pub fn data_version() -> Entry<u32> {
+  Entry::new(StorageKey::DataVersion)
+}
+
+pub fn migration_cursor() -> Entry<u64> {
+  Entry::new(StorageKey::MigrationCursor)
+}
+
+pub fn kickoffs(escrow_id: u64, milestone_index: u32) -> Entry<Kickoff> {
+  Entry::new(StorageKey::Kickoffs(escrow_id, milestone_index))
+}
+
+pub fn disputes(escrow_id: u64, milestone_index: u32) -> Entry<Dispute> {
+  Entry::new(StorageKey::Disputes(escrow_id, milestone_index))
+}
+
+pub fn commitments(client: Address, asset: Address) -> Entry<Commitments> {
+  Entry::new(StorageKey::Commitments(client, asset))
+}
+
+pub fn legacy_asset() -> Entry<Address> {
+  Entry::new(StorageKey::LegacyAsset)
+}
+
+pub fn contests(escrow_id: u64, milestone_index: u32) -> Entry<Contest> {
+  Entry::new(StorageKey::Contests(escrow_id, milestone_index))
+}
+
+pub fn platform_fee() -> Entry<u32> {
+  Entry::new(StorageKey::PlatformFee)
+}
+
+pub fn nonprofits(client: Address) -> Entry<bool> {
+  Entry::new(StorageKey::Nonprofits(client))
+}
+
+pub fn fee_terms(escrow_id: u64) -> Entry<FeeTerms> {
+  Entry::new(StorageKey::FeeTerms(escrow_id))
+}
+
+pub fn platform_stats() -> Entry<PlatformStats> {
+  Entry::new(StorageKey::PlatformStats)
+}
+
+pub fn fee_pool(asset: Address) -> Entry<i128> {
+  Entry::new(StorageKey::FeePool(asset))
+}
+
+pub fn transfer_guard() -> Entry<bool> {
+  Entry::new(StorageKey::TransferGuard)
+}
+
+pub fn obligations(asset: Address) -> Entry<i128> {
+  Entry::new(StorageKey::Obligations(asset))
+}
+
+pub fn acceptances(escrow_id: u64, milestone_index: u32) -> Entry<AcceptanceRecord> {
+  Entry::new(StorageKey::Acceptances(escrow_id, milestone_index))
+}
+
+pub fn approvals(escrow_id: u64, milestone_index: u32) -> Entry<Approval> {
+  Entry::new(StorageKey::Approvals(escrow_id, milestone_index))
+}
+
+pub fn proposal_count() -> Entry<u64> {
+  Entry::new(StorageKey::ProposalCount)
+}
+
+pub fn proposals(proposal_id: u64) -> Entry<Proposal> {
+  Entry::new(StorageKey::Proposals(proposal_id))
+}
+
+pub fn freelancer_stats(freelancer: Address) -> Entry<FreelancerStats> {
+  Entry::new(StorageKey::FreelancerStats(freelancer))
+}
+
+pub fn unavailable(freelancer: Address) -> Entry<bool> {
+  Entry::new(StorageKey::Unavailable(freelancer))
+}
+
+pub fn terms_sources(escrow_id: u64) -> Entry<TermsSource> {
+  Entry::new(StorageKey::TermsSources(escrow_id))
+}
+
+pub fn arbitrators(arbitrator: Address) -> Entry<Arbitrator> {
+  Entry::new(StorageKey::Arbitrators(arbitrator))
+}
+
+pub fn arbitrator_roster() -> Entry<Vec<Address>> {
+  Entry::new(StorageKey::ArbitratorRoster)
+}
+
+pub fn random_assignment() -> Entry<()> {
+  Entry::new(StorageKey::RandomAssignment)
+}
+
+pub fn dispute_strikes(escrow_id: u64, milestone_index: u32) -> Entry<Map<Address, Address>> {
+  Entry::new(StorageKey::DisputeStrikes(escrow_id, milestone_index))
+}
+
+pub fn arbitration_policy() -> Entry<ArbitrationPolicy> {
+  Entry::new(StorageKey::ArbitrationPolicy)
+}
+
+pub fn dispute_arbitrators(escrow_id: u64, milestone_index: u32) -> Entry<Address> {
+  Entry::new(StorageKey::DisputeArbitrators(escrow_id, milestone_index))
+}
+
+pub fn pending_rulings(escrow_id: u64, milestone_index: u32) -> Entry<Ruling> {
+  Entry::new(StorageKey::PendingRulings(escrow_id, milestone_index))
+}
+
+pub fn escrow_activity(escrow_id: u64) -> Entry<u64> {
+  Entry::new(StorageKey::EscrowActivity(escrow_id))
+}
+
+pub fn service_count() -> Entry<u64> {
+  Entry::new(StorageKey::ServiceCount)
+}
+
+pub fn services(listing_id: u64) -> Entry<ServiceListing> {
+  Entry::new(StorageKey::Services(listing_id))
+}
+
+pub fn invites(code: BytesN<8>) -> Entry<Invite> {
+  Entry::new(StorageKey::Invites(code))
+}
+
+pub fn subcontract_consents(escrow_id: u64, milestone_index: u32) -> Entry<()> {
+  Entry::new(StorageKey::SubcontractConsents(escrow_id, milestone_index))
+}
+
+pub fn subcontracts(child_id: u64) -> Entry<Subcontract> {
+  Entry::new(StorageKey::Subcontracts(child_id))
+}
+
+pub fn contact_consents(escrow_id: u64, party: Address) -> Entry<()> {
+  Entry::new(StorageKey::ContactConsents(escrow_id, party))
+}
+
+pub fn reference_rates(asset: Address) -> Entry<ReferenceRate> {
+  Entry::new(StorageKey::ReferenceRates(asset))
+}
+
+pub fn reprices(escrow_id: u64, milestone_index: u32) -> Entry<Reprice> {
+  Entry::new(StorageKey::Reprices(escrow_id, milestone_index))
+}
+
+pub fn admin_action_count() -> Entry<u64> {
+  Entry::new(StorageKey::AdminActionCount)
+}
+
+pub fn admin_actions(action_id: u64) -> Entry<QueuedAction> {
+  Entry::new(StorageKey::AdminActions(action_id))
+}
+
+pub fn accepted_terms(escrow_id: u64) -> Entry<BytesN<32>> {
+  Entry::persistent(StorageKey::AcceptedTerms(escrow_id))
+}
+
+pub fn project_invites(project_id: u64) -> Entry<Vec<Address>> {
+  Entry::new(StorageKey::ProjectInvites(project_id))
+}
+
+pub fn asset_migrations(asset: Address) -> Entry<Address> {
+  Entry::new(StorageKey::AssetMigrations(asset))
+}
+
+pub fn migration_pools(asset: Address) -> Entry<i128> {
+  Entry::new(StorageKey::MigrationPools(asset))
+}
+
+pub fn cancellation_schedules(escrow_id: u64) -> Entry<CancellationSchedule> {
+  Entry::new(StorageKey::CancellationSchedules(escrow_id))
+}
+
+pub fn allowlisted(user: Address) -> Entry<bool> {
+  Entry::new(StorageKey::Allowlisted(user))
+}
+
+pub fn effort_estimates(escrow_id: u64) -> Entry<EffortEstimate> {
+  Entry::new(StorageKey::EffortEstimates(escrow_id))
+}
+
+pub fn progress_reports(escrow_id: u64) -> Entry<Map<u32, ProgressReport>> {
+  Entry::new(StorageKey::ProgressReports(escrow_id))
+}
+
+pub fn recovery_addresses(user: Address) -> Entry<RecoverySetting> {
+  Entry::new(StorageKey::RecoveryAddresses(user))
+}
+
+pub fn recovery_requests(escrow_id: u64) -> Entry<RecoveryRequest> {
+  Entry::new(StorageKey::RecoveryRequests(escrow_id))
+}
+
+pub fn template_count() -> Entry<u32> {
+  Entry::new(StorageKey::TemplateCount)
+}
+
+pub fn templates(template_id: u32) -> Entry<CategoryTemplate> {
+  Entry::new(StorageKey::Templates(template_id))
+}
+
+pub fn category_templates(category_id: u32) -> Entry<Vec<u32>> {
+  Entry::new(StorageKey::CategoryTemplates(category_id))
+}
+
+pub fn project_templates(project_id: u64) -> Entry<TemplateRef> {
+  Entry::new(StorageKey::ProjectTemplates(project_id))
+}
+
+pub fn verified(freelancer: Address) -> Entry<bool> {
+  Entry::new(StorageKey::Verified(freelancer))
+}
+
+pub fn exposures(freelancer: Address) -> Entry<Exposure> {
+  Entry::new(StorageKey::Exposures(freelancer))
+}
+
+pub fn escrow_exposures(escrow_id: u64) -> Entry<i128> {
+  Entry::new(StorageKey::EscrowExposures(escrow_id))
+}
+
+pub fn private_notes(escrow_id: u64, party: Address) -> Entry<BytesN<32>> {
+  Entry::new(StorageKey::PrivateNotes(escrow_id, party))
+}
+
+pub fn milestone_orders(escrow_id: u64) -> Entry<MilestoneOrder> {
+  Entry::new(StorageKey::MilestoneOrders(escrow_id))
+}
+
+pub fn observers(escrow_id: u64) -> Entry<Vec<Observer>> {
+  Entry::new(StorageKey::Observers(escrow_id))
+}
+
+pub fn release_guards(escrow_id: u64) -> Entry<i128> {
+  Entry::new(StorageKey::ReleaseGuards(escrow_id))
+}
+
+pub fn pending_releases(escrow_id: u64, milestone_index: u32) -> Entry<PendingRelease> {
+  Entry::new(StorageKey::PendingReleases(escrow_id, milestone_index))
+}
+
+pub fn amendments(escrow_id: u64) -> Entry<Vec<Amendment>> {
+  Entry::new(StorageKey::Amendments(escrow_id))
+}
+
+pub fn deposit_increments(asset: Address) -> Entry<i128> {
+  Entry::new(StorageKey::DepositIncrements(asset))
+}
+
+pub fn epoch_activity(escrow_id: u64) -> Entry<EpochActivity> {
+  Entry::new(StorageKey::EpochActivity(escrow_id))
+}
+
+pub fn withdrawals(escrow_id: u64, milestone_index: u32) -> Entry<i128> {
+  Entry::new(StorageKey::Withdrawals(escrow_id, milestone_index))
+}
+
+// The ID list behind each index
+pub fn index(index: &IndexKind) -> Entry<Vec<u64>> {
+  Entry::persistent(match index {
+    IndexKind::EscrowsByState(state) => StorageKey::EscrowsByState(*state),
+    IndexKind::PairEscrows(client, freelancer) => StorageKey::PairEscrows(client.clone(), freelancer.clone()),
+    IndexKind::ProjectProposals(project_id) => StorageKey::ProjectProposals(*project_id),
+    IndexKind::OpenDisputes => StorageKey::OpenDisputes,
+    IndexKind::ActivityBucket(bucket) => StorageKey::ActivityBucket(*bucket),
+    IndexKind::ActivityBuckets => StorageKey::ActivityBuckets,
+    IndexKind::FreelancerRatings(freelancer) => StorageKey::FreelancerRatings(freelancer.clone()),
+    IndexKind::CategoryServices(category) => StorageKey::CategoryServices(category.clone()),
+    IndexKind::Subcontracts(parent_id, milestone_index) => StorageKey::MilestoneSubcontracts(*parent_id, *milestone_index),
+  })
+}
+
+// Removes everything stored under an escrow's own ID
+pub fn remove_escrow_payload(env: &Env, escrow_id: u64) {
+  escrows(escrow_id).remove(env);
+  accepted_terms(escrow_id).remove(env);
+  for key in [
+    StorageKey::MilestoneRecords(escrow_id),
+    StorageKey::Deposits(escrow_id),
+    StorageKey::LedgerLines(escrow_id),
+    StorageKey::Bonds(escrow_id),
+    StorageKey::Bundles(escrow_id),
+    StorageKey::Bounties(escrow_id),
+    StorageKey::Checklists(escrow_id),
+    StorageKey::EarlyBonuses(escrow_id),
+    StorageKey::FeeTerms(escrow_id),
+    StorageKey::TermsSources(escrow_id),
+    StorageKey::ReviewTallies(escrow_id),
+    StorageKey::EscrowActivity(escrow_id),
+    StorageKey::CancellationSchedules(escrow_id),
+    StorageKey::EffortEstimates(escrow_id),
+    StorageKey::ProgressReports(escrow_id),
+    StorageKey::RecoveryRequests(escrow_id),
+    StorageKey::EscrowExposures(escrow_id),
+    StorageKey::MilestoneOrders(escrow_id),
+    StorageKey::Observers(escrow_id),
+    StorageKey::ReleaseGuards(escrow_id),
+    StorageKey::Amendments(escrow_id),
+    StorageKey::EpochActivity(escrow_id),
+  ] {
+    env.storage().instance().remove(&key);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::Address as _;
+  use soroban_sdk::{ Address, Vec };
+
+  use crate::migration::v5::ConfigV5;
+  use crate::storage;
+  use crate::test::harness;
+  use crate::FeeTerms;
+
+  #[test]
+  fn an_entry_reads_back_what_was_set_until_it_is_removed() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      let count = storage::escrow_count();
+      assert_eq!(count.get(&env), None);
+      count.set(&env, &7);
+      assert!(count.has(&env));
+      assert_eq!(count.get(&env), Some(7));
+      count.remove(&env);
+      assert!(!count.has(&env));
+    });
+  }
+
+  #[test]
+  fn entries_of_one_kind_are_kept_apart_by_their_arguments() {
+    let (env, contract) = harness();
+    let (first, second) = (Address::generate(&env), Address::generate(&env));
+    env.as_contract(&contract, || {
+      storage::fee_pool(first.clone()).set(&env, &10);
+      storage::fee_pool(second.clone()).set(&env, &20);
+      storage::obligations(first.clone()).set(&env, &30);
+      assert_eq!(storage::fee_pool(first.clone()).get(&env), Some(10));
+      assert_eq!(storage::fee_pool(second).get(&env), Some(20));
+      assert_eq!(storage::obligations(first).get(&env), Some(30));
+    });
+  }
+
+  #[test]
+  fn an_older_layout_is_read_and_written_under_the_same_key() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      let old = ConfigV5 { review_period_secs: 1, bundle_grace_secs: 2, contest_window_secs: 3 };
+      storage::config().as_layout::<ConfigV5>().set(&env, &old);
+      assert!(storage::config().has(&env));
+      assert_eq!(storage::config().as_layout::<ConfigV5>().get(&env).map(|config| config.contest_window_secs), Some(3));
+    });
+  }
+
+  #[test]
+  fn removing_an_escrow_payload_leaves_other_escrows_alone() {
+    let (env, contract) = harness();
+    env.as_contract(&contract, || {
+      for escrow_id in [1, 2] {
+        storage::escrow_activity(escrow_id).set(&env, &100);
+        storage::fee_terms(escrow_id).set(&env, &FeeTerms::default());
+        storage::ledger_lines(escrow_id).set(&env, &Vec::new(&env));
+      }
+      storage::remove_escrow_payload(&env, 1);
+      assert!(!storage::escrow_activity(1).has(&env));
+      assert!(!storage::fee_terms(1).has(&env));
+      assert!(!storage::ledger_lines(1).has(&env));
+      assert_eq!(storage::escrow_activity(2).get(&env), Some(100));
+      assert!(storage::fee_terms(2).has(&env));
+    });
+  }
+}
@@ -0,0 +1,162 @@
+// Subcontracting: with the client's consent, a freelancer opens a child escrow on part of a
+// milestone, acting as the child's client. The parent milestone's payout funds the child.
+
+use soroban_sdk::{ Address, Env, Vec };
+
+use crate::escrow::{ is_active, milestone_amount, milestone_deadline, milestone_records, open_escrow, record_deposit };
+use crate::index::{ IndexKind, MAX_SUBCONTRACTS_PER_MILESTONE };
+use crate::page::IdPage;
+use crate::project::create_project;
+use crate::{ index, storage, Error, Escrow, Locale, Milestone, MilestoneStatus, Subcontract, TermsSource };
+
+pub fn consent_subcontract(env: &Env, from: Address, escrow_id: u64, milestone_index: u32) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let escrow = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if escrow.client != from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&escrow) {
+    return Err(Error::EscrowNotActive);
+  }
+  check_unsettled(env, escrow_id, milestone_index)?;
+  storage::subcontract_consents(escrow_id, milestone_index).set(env, &());
+  Ok(())
+}
+
+pub fn has_subcontract_consent(env: &Env, escrow_id: u64, milestone_index: u32) -> bool {
+  storage::subcontract_consents(escrow_id, milestone_index).has(env)
+}
+
+pub fn open_subcontract(
+  env: &Env,
+  from: Address, // Freelancer of the parent escrow
+  escrow_id: u64,
+  milestone_index: u32,
+  subcontractor: Address,
+  amount: i128,
+  milestones: Vec<Milestone>,
+) -> Result<u64, Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+
+  let parent = storage::escrows(escrow_id).get(env).ok_or(Error::NotFound)?;
+  if parent.freelancer != from || subcontractor == from {
+    return Err(Error::Unauthorized);
+  }
+  if !is_active(&parent) {
+    return Err(Error::EscrowNotActive);
+  }
+  if !has_subcontract_consent(env, escrow_id, milestone_index) {
+    return Err(Error::Unauthorized);
+  }
+  let paid = check_unsettled(env, escrow_id, milestone_index)?;
+  if amount <= 0 || !milestones.is_empty() && schedule_total(&milestones) != Some(amount) {
+    return Err(Error::InvalidAmount);
+  }
+  // Children share what is left of the milestone
+  if subcontracted(env, escrow_id, milestone_index) + amount > milestone_amount(&parent, milestone_index) - paid {
+    return Err(Error::InvalidAmount);
+  }
+
+  let project = storage::projects(parent.project_id).get(env).ok_or(Error::NotFound)?;
+  let description = parent.milestones.get(milestone_index).map_or(project.description.clone(), |milestone| milestone.description);
+  let deadline = milestone_deadline(&parent, &project, milestone_index);
+  let child_project_id = create_project(env, from, project.title, description, project.category, amount, parent.asset, deadline, milestones.clone(), Locale::default())?;
+  let mut child_project = storage::projects(child_project_id).get(env).ok_or(Error::NotFound)?;
+  let child_id = open_escrow(env, &mut child_project, subcontractor, amount, milestones, TermsSource::Project)?;
+  if !index::append_unique(env, &IndexKind::Subcontracts(escrow_id, milestone_index), child_id, MAX_SUBCONTRACTS_PER_MILESTONE) {
+    return Err(Error::LimitReached);
+  }
+  storage::subcontracts(child_id).set(env, &Subcontract { parent_id: escrow_id, milestone_index, amount, credited: 0 });
+  Ok(child_id)
+}
+
+pub fn get_subcontract(env: &Env, child_id: u64) -> Option<Subcontract> {
+  storage::subcontracts(child_id).get(env)
+}
+
+pub fn list_subcontracts(env: &Env, escrow_id: u64, milestone_index: u32, cursor: u64, limit: u32) -> IdPage {
+  index::paged_read(env, &IndexKind::Subcontracts(escrow_id, milestone_index), cursor, limit)
+}
+
+// Credits each child of a settled milestone with its share of what the milestone paid the
+// freelancer, in total. A full release funds the children in full; a dispute or partial
+// offer settled for part of the milestone funds them in the same proportion, rounded down.
+pub fn fund_subcontracts(env: &Env, escrow_id: u64, escrow: &Escrow, milestone_index: u32, paid: i128) -> Result<(), Error> {
+  let amount = milestone_amount(escrow, milestone_index);
+  if amount <= 0 {
+    return Ok(());
+  }
+  for child_id in index::read(env, &IndexKind::Subcontracts(escrow_id, milestone_index)) {
+    let key = storage::subcontracts(child_id);
+    let (Some(mut link), Some(child)) = (key.get(env), storage::escrows(child_id).get(env)) else {
+      continue;
+    };
+    if !is_active(&child) {
+      continue;
+    }
+    let share = link.amount.checked_mul(paid).ok_or(Error::InvalidAmount)? / amount;
+    let credit = (share - link.credited).min(child.total_amount - child.deposited_amount);
+    if credit <= 0 {
+      continue;
+    }
+    record_deposit(env, escrow.freelancer.clone(), child_id, credit, None)?;
+    link.credited += credit;
+    key.set(env, &link);
+  }
+  Ok(())
+}
+
+// Child payouts wait with MilestoneDisputed while the parent milestone funding them is in
+// dispute
+pub fn check_parent_dispute(env: &Env, child_id: u64) -> Result<(), Error> {
+  let Some(link) = storage::subcontracts(child_id).get(env) else {
+    return Ok(());
+  };
+  let record = milestone_records(env, link.parent_id).get(link.milestone_index);
+  if record.is_some_and(|record| record.status == MilestoneStatus::Disputed) {
+    return Err(Error::MilestoneDisputed);
+  }
+  Ok(())
+}
+
+// What the milestone's children were promised in total; a declined child gives its part back
+pub fn subcontracted(env: &Env, escrow_id: u64, milestone_index: u32) -> i128 {
+  let mut taken: i128 = 0;
+  for child_id in index::read(env, &IndexKind::Subcontracts(escrow_id, milestone_index)) {
+    if storage::escrows(child_id).has(env) {
+      taken += storage::subcontracts(child_id).get(env).map_or(0, |link| link.amount);
+    }
+  }
+  taken
+}
+
+// Whether any child of the milestone has been credited out of its payout
+pub fn funded_subcontracts(env: &Env, escrow_id: u64, milestone_index: u32) -> bool {
+  index::read(env, &IndexKind::Subcontracts(escrow_id, milestone_index))
+    .iter()
+    .any(|child_id| storage::subcontracts(child_id).get(env).is_some_and(|link| link.credited > 0))
+}
+
+// What the milestone has paid so far, provided it is still open to work
+fn check_unsettled(env: &Env, escrow_id: u64, milestone_index: u32) -> Result<i128, Error> {
+  let record = milestone_records(env, escrow_id).get(milestone_index).ok_or(Error::InvalidMilestoneIndex)?;
+  match record.status {
+    MilestoneStatus::Released | MilestoneStatus::Voided => Err(Error::AlreadyReleased),
+    MilestoneStatus::Disputed | MilestoneStatus::Resolved => Err(Error::MilestoneDisputed),
+    _ => Ok(record.paid),
+  }
+}
+
+fn schedule_total(milestones: &Vec<Milestone>) -> Option<i128> {
+  let mut total: i128 = 0;
+  for milestone in milestones.iter() {
+    if milestone.amount < 0 {
+      return None;
+    }
+    total = total.checked_add(milestone.amount)?;
+  }
+  Some(total)
+}
@@ -0,0 +1,102 @@
+// Milestone templates the admin curates per category: a layout of basis-point shares a client
+// can post a project with instead of working out a schedule. Posting expands the template
+// against the budget, so later edits never reach projects already posted.
+
+use soroban_sdk::{ Env, String, Vec };
+
+use crate::admin::display_label;
+use crate::math::expand_milestone_shares;
+use crate::page::TemplatePage;
+use crate::{ page, storage, CategoryTemplate, Error, Milestone, MilestoneShare, RegistryKind, TemplateRef, BPS_DENOMINATOR, MAX_TEMPLATES_PER_CATEGORY };
+
+// Applied through the admin queue. The template takes the next template ID, starting at 1.
+pub fn add_category_template(env: &Env, category_id: u32, name: String, shares: Vec<MilestoneShare>) -> Result<(), Error> {
+  check_new_template(env, category_id, &shares)?;
+  let mut template_ids = storage::category_templates(category_id).get(env).unwrap_or(Vec::new(env));
+  let template_id = storage::template_count().get(env).unwrap_or(0) + 1;
+  storage::templates(template_id).set(env, &CategoryTemplate { id: template_id, category_id, name, shares, version: 1 });
+  storage::template_count().set(env, &template_id);
+  template_ids.push_back(template_id);
+  storage::category_templates(category_id).set(env, &template_ids);
+  Ok(())
+}
+
+pub fn check_new_template(env: &Env, category_id: u32, shares: &Vec<MilestoneShare>) -> Result<(), Error> {
+  if !storage::registry(RegistryKind::Category, category_id).has(env) {
+    return Err(Error::NotFound);
+  }
+  check_shares(env, shares)?;
+  if storage::category_templates(category_id).get(env).is_some_and(|template_ids| template_ids.len() >= MAX_TEMPLATES_PER_CATEGORY) {
+    return Err(Error::LimitReached);
+  }
+  Ok(())
+}
+
+// Applied through the admin queue. Replaces a template's shares and bumps its version.
+pub fn update_category_template(env: &Env, template_id: u32, shares: Vec<MilestoneShare>) -> Result<(), Error> {
+  check_template_update(env, template_id, &shares)?;
+  let mut template = storage::templates(template_id).get(env).unwrap();
+  template.shares = shares;
+  template.version += 1;
+  storage::templates(template_id).set(env, &template);
+  Ok(())
+}
+
+pub fn check_template_update(env: &Env, template_id: u32, shares: &Vec<MilestoneShare>) -> Result<(), Error> {
+  if !storage::templates(template_id).has(env) {
+    return Err(Error::NotFound);
+  }
+  check_shares(env, shares)
+}
+
+pub fn get_category_templates(env: &Env, category_id: u32, cursor: u64, limit: u32) -> TemplatePage {
+  let template_ids = storage::category_templates(category_id).get(env).unwrap_or(Vec::new(env));
+  let (items, next_cursor) = page::fill(env, template_ids.len(), cursor, limit, |position| storage::templates(template_ids.get(position).unwrap()).get(env));
+  TemplatePage { items, next_cursor, total: Some(template_ids.len()) }
+}
+
+pub fn get_project_template(env: &Env, project_id: u64) -> Option<TemplateRef> {
+  storage::project_templates(project_id).get(env)
+}
+
+// The category label a templated project is filed under, its milestones expanded against the
+// budget, and the template version they came from. A template is only found within its own
+// category.
+pub fn expand_template(env: &Env, category_id: u32, template_id: u32, budget: i128) -> Result<(String, Vec<Milestone>, TemplateRef), Error> {
+  let template = storage::templates(template_id).get(env).ok_or(Error::NotFound)?;
+  if template.category_id != category_id {
+    return Err(Error::NotFound);
+  }
+  let category = display_label(env, RegistryKind::Category, category_id)?;
+  let milestones = expand_milestone_shares(env, budget, &template.shares)?;
+  Ok((category, milestones, TemplateRef { template_id, version: template.version }))
+}
+
+// Shares have to sum to the whole budget. Template milestones carry no deadline of their own,
+// as a fixed date can't suit every project; they fall due by the project deadline.
+fn check_shares(env: &Env, shares: &Vec<MilestoneShare>) -> Result<(), Error> {
+  if shares.iter().any(|share| share.deadline != 0) {
+    return Err(Error::InvalidShares);
+  }
+  expand_milestone_shares(env, BPS_DENOMINATOR as i128, shares)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::{ vec, String };
+
+  use crate::template::check_shares;
+  use crate::test::harness;
+  use crate::{ Error, MilestoneShare };
+
+  #[test]
+  fn template_shares_cover_the_budget_and_carry_no_deadline() {
+    let (env, _) = harness();
+    let share = |bps: u32, deadline: u64| MilestoneShare { description: String::from_str(&env, "Part"), bps, deadline };
+    assert_eq!(check_shares(&env, &vec![&env, share(3_000, 0), share(7_000, 0)]), Ok(()));
+    assert_eq!(check_shares(&env, &vec![&env, share(3_000, 0), share(6_000, 0)]), Err(Error::InvalidShares));
+    assert_eq!(check_shares(&env, &vec![&env, share(3_000, 0), share(7_000, 100)]), Err(Error::InvalidShares));
+    assert_eq!(check_shares(&env, &vec![&env]), Err(Error::InvalidShares));
+  }
+}
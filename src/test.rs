@@ -0,0 +1,5851 @@
+#![cfg(test)]
+
+extern crate std;
+
+use core::ops::Deref;
+
+use soroban_sdk::testutils::storage::{ Instance as _, Persistent as _ };
+use soroban_sdk::testutils::{ Address as _, Events as _, Ledger as _ };
+use soroban_sdk::xdr::{ LedgerKey, Limits, ScAddress, ScVal, ToXdr, WriteXdr };
+use soroban_sdk::{ contract, contractimpl, contracttype, symbol_short, token, vec, Address, BytesN, Env, FromVal, IntoVal, TryFromVal, String, Symbol, Val, Vec };
+
+use crate::migration::v1::{ DepositV1, EscrowV1, MilestoneRecordV1, MilestoneV1, ProjectV1 };
+use crate::migration::v2::EscrowV2;
+use crate::migration::v3::{ EscrowV3, ProjectV3 };
+use crate::migration::v4::ConfigV4;
+use crate::migration::v5::ConfigV5;
+use crate::migration::v6::ConfigV6;
+use crate::migration::v7::EscrowV7;
+use crate::migration::v8::ConfigV8;
+use crate::migration::v9::ConfigV9;
+use crate::migration::v10::ConfigV10;
+use crate::index::{ self, IndexKind };
+use crate::math::bps_of;
+use crate::page;
+use crate::testutils::{ self, EscrowBuilder, Marketplace, ProjectBuilder, BUDGET, PROJECT_DEADLINE };
+use crate::storage::StorageKey;
+use crate::ttl::{ TtlClass, TtlPolicy, DAY_IN_LEDGERS };
+use crate::{ migration, snapshot, storage };
+use crate::{ AcceptanceRecord, ActionKind, AdminAction, AmendmentChange, AmendmentStatus, ArbitrationPolicy, Arbitrator, ArbitratorTier, CancellationSchedule, CategoryTemplate, ChecklistItem, ClosingReport, Commitments, Config, ContactConsent, Deposit, Ending, DisputeRef, EpochActivity, Error, Escrow, EscrowHeader, EscrowServiceContractClient, EscrowState, EscrowView, FeeTerms, Invariant, InviteOutcome, IssueCode, LaunchMode, LedgerKind, LegacyMilestone, Milestone, MilestoneRecord, MilestoneShare, MilestoneStatus, Locale, Observer, PendingRelease, PlatformStats, Project, ProjectStatus, RegistryKind, RiskCaps, RiskLimit, RiskTier, Ruling, TemplateRef, TermsSource, Tombstone, UserType, ValidationIssue, MAX_CANCELLATION_FEE_BPS, MAX_INVITES_PER_CALL, MAX_INVITES_PER_PROJECT, MAX_LIST_LIMIT, MAX_MILESTONES, MAX_OBSERVERS, EPOCH_SECS, RELEASE_CONFIRM_SECS };
+
+const DAY: u64 = 86_400;
+const BOND: i128 = 100;
+
+// A contract with no calls of its own. Module unit tests run module functions inside it with
+// `env.as_contract`, against empty storage and without registering the marketplace.
+#[contract]
+pub struct Harness;
+
+pub fn harness() -> (Env, Address) {
+  let env = Env::default();
+  env.mock_all_auths();
+  let contract = env.register(Harness, ());
+  (env, contract)
+}
+
+// The testutils marketplace (env, admin, contract, asset) and one client and freelancer
+struct Fixture<'a> {
+  market: Marketplace<'a>,
+  client: Address,
+  freelancer: Address,
+}
+
+impl<'a> Deref for Fixture<'a> {
+  type Target = Marketplace<'a>;
+
+  fn deref(&self) -> &Marketplace<'a> {
+    &self.market
+  }
+}
+
+impl Fixture<'_> {
+  fn new() -> Self {
+    let market = Marketplace::new();
+    let client = Address::generate(&market.env);
+    let freelancer = Address::generate(&market.env);
+    Fixture { market, client, freelancer }
+  }
+
+  fn text(&self, value: &str) -> String {
+    String::from_str(&self.env, value)
+  }
+
+  fn milestone(&self, description: &str, amount: i128) -> Milestone {
+    testutils::milestone(&self.env, description, amount)
+  }
+
+  // Posts the default two-milestone project for the whole budget
+  fn post_project(&self) -> u64 {
+    ProjectBuilder::new(&self.market, &self.client).post()
+  }
+
+  fn escrow_builder(&self) -> EscrowBuilder<'_, '_> {
+    EscrowBuilder::new(&self.market, &self.client, &self.freelancer)
+  }
+
+  fn open_escrow(&self) -> u64 {
+    self.escrow_builder().open()
+  }
+
+  fn funded_escrow(&self) -> u64 {
+    self.escrow_builder().funded().open()
+  }
+
+  // A project without milestones opens a lump-sum escrow
+  fn lump_sum_escrow(&self) -> u64 {
+    self.escrow_builder().project(|project| project.title("Logo").description("A logo").category("design").lump_sum(BUDGET)).open()
+  }
+
+  // Queues an admin action and executes it once its delay has passed, then sets the clock
+  // back
+  fn enact(&self, action: AdminAction) {
+    let now = self.env.ledger().timestamp();
+    let action_id = self.contract.queue_admin_action(&self.admin, &action);
+    self.set_time(self.contract.get_admin_action(&action_id).unwrap().execute_after);
+    self.contract.execute_admin_action(&action_id);
+    self.set_time(now);
+  }
+
+  // The full escrow, read as the admin
+  fn escrow(&self, escrow_id: u64) -> Escrow {
+    match self.contract.get_escrow(&self.admin, &escrow_id) {
+      EscrowView::Full(escrow) => escrow,
+      EscrowView::Header(_) => panic!("the admin sees the full escrow"),
+    }
+  }
+
+  fn digest(&self, escrow_id: u64) -> BytesN<32> {
+    self.contract.terms_digest(&escrow_id)
+  }
+
+  // Ledgers left before the contract instance expires
+  fn instance_ttl(&self) -> u32 {
+    self.env.as_contract(&self.contract.address, || self.env.storage().instance().get_ttl())
+  }
+
+  // Ledgers left before a persistent entry expires
+  fn entry_ttl(&self, key: &StorageKey) -> u32 {
+    self.env.as_contract(&self.contract.address, || self.env.storage().persistent().get_ttl(key))
+  }
+
+  // Ledger bytes of a persistent entry, which is what a write of it costs in write bytes
+  fn entry_bytes(&self, key: &StorageKey) -> u64 {
+    let key = ScVal::try_from_val(&self.env, &IntoVal::<Env, Val>::into_val(key, &self.env)).unwrap();
+    let contract = ScAddress::from(&self.contract.address);
+    let snapshot = self.env.to_ledger_snapshot();
+    let (_, (entry, _)) = snapshot.ledger_entries.iter()
+      .find(|(ledger_key, _)| matches!(ledger_key.as_ref(), LedgerKey::ContractData(data) if data.contract == contract && data.key == key))
+      .unwrap();
+    entry.to_xdr(Limits::none()).unwrap().len() as u64
+  }
+
+  fn advance_ledgers(&self, ledgers: u32) {
+    self.env.ledger().with_mut(|ledger| ledger.sequence_number += ledgers);
+  }
+
+  // Opens a funded escrow whose kickoff checklist has an NDA for the freelancer and a kickoff
+  // call for the client
+  fn checklist_escrow(&self) -> u64 {
+    let escrow_id = self.open_escrow();
+    let items = vec![
+      &self.env,
+      ChecklistItem { label: self.text("NDA signed"), party: UserType::Freelancer },
+      ChecklistItem { label: self.text("Kickoff call"), party: UserType::Client },
+    ];
+    self.contract.set_checklist(&self.client, &escrow_id, &items);
+    self.contract.deposit_funds(&self.client, &escrow_id, &BUDGET, &None);
+    escrow_id
+  }
+
+  // A funded escrow whose milestones are due on days 10 and 20, with a bonus of 100 on the
+  // first one for approval at least two days early
+  fn bonus_escrow(&self) -> u64 {
+    let mut milestones = vec![&self.env, self.milestone("Design", 400), self.milestone("Build", 600)];
+    milestones.set(0, Milestone { deadline: 10 * DAY, ..milestones.get(0).unwrap() });
+    milestones.set(1, Milestone { deadline: 20 * DAY, ..milestones.get(1).unwrap() });
+    let project_id = self.contract.post_project(&self.client, &self.text("Site"), &self.text("A website"), &self.text("web"), &BUDGET, &self.asset, &(30 * DAY), &milestones, &Locale::default());
+    let escrow_id = self.contract.initiate_escrow(&self.client, &project_id, &self.freelancer);
+    self.contract.set_early_bonus(&self.client, &escrow_id, &0, &100, &2);
+    self.contract.deposit_funds(&self.client, &escrow_id, &(BUDGET + 100), &None);
+    escrow_id
+  }
+
+  fn hash(&self, byte: u8) -> BytesN<32> {
+    BytesN::from_array(&self.env, &[byte; 32])
+  }
+
+  fn submit(&self, escrow_id: u64, milestone_index: u32) {
+    self.contract.submit_milestone(&self.freelancer, &escrow_id, &milestone_index, &self.hash(milestone_index as u8 + 1));
+  }
+
+  fn set_time(&self, timestamp: u64) {
+    self.env.ledger().with_mut(|ledger| ledger.timestamp = timestamp);
+  }
+
+  fn status(&self, escrow_id: u64, milestone_index: u32) -> MilestoneStatus {
+    self.contract.get_milestone_records(&escrow_id).get(milestone_index).unwrap().status
+  }
+
+  fn release_all(&self, escrow_id: u64) {
+    testutils::run_to_completed(&self.market, escrow_id);
+  }
+
+  fn post_with_shares(&self, budget: i128, bps: &[u32]) -> Result<u64, Error> {
+    let mut shares = Vec::new(&self.env);
+    // Numbered, so equal shares are not duplicate milestones
+    for (phase, share) in bps.iter().enumerate() {
+      shares.push_back(MilestoneShare { description: self.text(&std::format!("Phase {}", phase + 1)), bps: *share, deadline: 0 });
+    }
+    match self.contract.try_post_project_with_shares(&self.client, &self.text("Site"), &self.text("A website"), &self.text("web"), &budget, &self.asset, &PROJECT_DEADLINE, &shares, &Locale::default()) {
+      Ok(project_id) => Ok(project_id.unwrap()),
+      Err(error) => Err(error.unwrap()),
+    }
+  }
+
+  fn post_with_locale(&self, language: Option<Symbol>, tz_offset_mins: Option<i32>) -> Result<u64, Error> {
+    let milestones = vec![&self.env, self.milestone("Design", 400), self.milestone("Build", 600)];
+    let locale = Locale { language, tz_offset_mins };
+    match self.contract.try_post_project(&self.client, &self.text("Site"), &self.text("A website"), &self.text("web"), &BUDGET, &self.asset, &PROJECT_DEADLINE, &milestones, &locale) {
+      Ok(project_id) => Ok(project_id.unwrap()),
+      Err(error) => Err(error.unwrap()),
+    }
+  }
+
+  fn milestone_amounts(&self, project_id: u64) -> Vec<i128> {
+    let mut amounts = Vec::new(&self.env);
+    for milestone in self.contract.get_project(&project_id).milestones.iter() {
+      amounts.push_back(milestone.amount);
+    }
+    amounts
+  }
+
+  // Registers a token, funds the freelancer with it and makes the escrow require a bond of 100
+  fn require_bond(&self, escrow_id: u64, freelancer_balance: i128) -> token::Client<'_> {
+    let token = self.env.register_stellar_asset_contract_v2(self.admin.clone()).address();
+    token::StellarAssetClient::new(&self.env, &token).mint(&self.freelancer, &freelancer_balance);
+    self.contract.require_bond(&self.client, &escrow_id, &token, &BOND);
+    token::Client::new(&self.env, &token)
+  }
+
+  // Gives the contract a balance in the fixture asset, standing in for what deposits would hold
+  fn fund_contract(&self, amount: i128) {
+    token::StellarAssetClient::new(&self.env, &self.asset).mint(&self.contract.address, &amount);
+  }
+
+  // Milestones as the layout fixtures store them
+  fn layout_milestones(&self) -> Vec<Milestone> {
+    vec![&self.env, self.milestone("Design", 400), self.milestone("Build", 600)]
+  }
+
+  // Submits the Build milestone at 1_000 and flags it out of scope at 2_000
+  fn contested_escrow(&self) -> u64 {
+    let escrow_id = self.funded_escrow();
+    self.set_time(1_000);
+    self.submit(escrow_id, 1);
+    self.set_time(2_000);
+    self.contract.flag_out_of_scope(&self.client, &escrow_id, &1, &self.text("Not in the brief"));
+    escrow_id
+  }
+
+  // Funds an escrow and disputes its Build milestone (pot 600)
+  fn disputed_escrow(&self) -> u64 {
+    let escrow_id = self.funded_escrow();
+    self.submit(escrow_id, 1);
+    self.contract.raise_dispute(&self.client, &escrow_id, &1);
+    escrow_id
+  }
+
+  // Topics and data of the latest event named `name`
+  fn last_event(&self, name: Symbol) -> (Vec<Val>, Val) {
+    let name: Val = name.into_val(&self.env);
+    let (_, topics, data) = self.env.events().all().iter().filter(|(_, topics, _)| topics.get(0).is_some_and(|topic| topic.shallow_eq(&name))).last().unwrap();
+    (topics, data)
+  }
+
+  // Topics of an escrow event between the fixture's client and freelancer
+  fn escrow_topics(&self, name: &str, id: u64) -> Vec<Val> {
+    (Symbol::new(&self.env, name), self.client.clone(), self.freelancer.clone(), id).into_val(&self.env)
+  }
+
+  // Completes an escrow and rates its freelancer
+  fn rated_escrow(&self, stars: u32) -> u64 {
+    let escrow_id = self.funded_escrow();
+    self.release_all(escrow_id);
+    self.contract.rate_freelancer(&self.client, &escrow_id, &stars, &self.text("Thanks"), &Vec::new(&self.env));
+    escrow_id
+  }
+
+  // Opens an escrow in bounty mode, expiring at `expires_at`, and has each sponsor put in
+  // their amount
+  fn bounty(&self, expires_at: u64, amounts: &[i128]) -> (u64, std::vec::Vec<Address>) {
+    let escrow_id = self.open_escrow();
+    self.contract.open_bounty(&self.client, &escrow_id, &expires_at);
+    let sponsors: std::vec::Vec<Address> = amounts.iter().map(|_| Address::generate(&self.env)).collect();
+    for (sponsor, amount) in sponsors.iter().zip(amounts) {
+      self.contract.deposit_funds(sponsor, &escrow_id, amount, &None);
+    }
+    (escrow_id, sponsors)
+  }
+
+  // Enacts a registry entry and returns its ID, the registry's count once it is in
+  fn add_registry_entry(&self, registry: RegistryKind, lang: Symbol, label: &str) -> u32 {
+    self.enact(AdminAction::AddRegistryEntry(registry, lang, self.text(label)));
+    self.contract.list_registry(&registry, &0, &1).total.unwrap()
+  }
+
+  fn add_category(&self, lang: Symbol, label: &str) -> u32 {
+    self.add_registry_entry(RegistryKind::Category, lang, label)
+  }
+
+  // Enacts a template for `category_id` and returns its ID, the last one the category lists
+  fn add_template(&self, category_id: u32, name: &str, shares: &Vec<MilestoneShare>) -> u32 {
+    self.enact(AdminAction::AddCategoryTemplate(category_id, self.text(name), shares.clone()));
+    self.contract.get_category_templates(&category_id, &0, &MAX_LIST_LIMIT).items.last().unwrap().id
+  }
+}
+
+// Milestone shares
+#[test]
+fn thirds_expand_with_dust_on_the_last_milestone() {
+  let fixture = Fixture::new();
+
+  let project_id = fixture.post_with_shares(BUDGET, &[3333, 3333, 3334]).unwrap();
+
+  assert_eq!(fixture.milestone_amounts(project_id), vec![&fixture.env, 333, 333, 334]);
+}
+
+#[test]
+fn rounding_dust_goes_to_the_last_milestone() {
+  let fixture = Fixture::new();
+
+  // 30% of 101 rounds down to 30, twice; the last milestone takes the remaining 41
+  let project_id = fixture.post_with_shares(101, &[3000, 3000, 4000]).unwrap();
+
+  assert_eq!(fixture.milestone_amounts(project_id), vec![&fixture.env, 30, 30, 41]);
+  assert!(fixture.contract.get_project(&project_id).milestones.iter().all(|milestone| !milestone.completed));
+}
+
+#[test]
+fn negative_amounts_are_rejected() {
+  let fixture = Fixture::new();
+  let milestones = vec![&fixture.env, fixture.milestone("Design", -400), fixture.milestone("Build", 1400)];
+
+  let result = fixture.contract.try_post_project(&fixture.client, &fixture.text("Site"), &fixture.text("A website"), &fixture.text("web"), &BUDGET, &fixture.asset, &PROJECT_DEADLINE, &milestones, &Locale::default());
+
+  assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+  let escrow_id = fixture.open_escrow();
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &-1, &None), Err(Ok(Error::InvalidAmount)));
+}
+
+#[test]
+fn shares_not_summing_to_10000_are_rejected() {
+  let fixture = Fixture::new();
+
+  assert_eq!(fixture.post_with_shares(BUDGET, &[3000, 3000, 3000]), Err(Error::InvalidShares));
+  assert_eq!(fixture.post_with_shares(BUDGET, &[6000, 6000]), Err(Error::InvalidShares));
+  assert_eq!(fixture.post_with_shares(BUDGET, &[]), Err(Error::InvalidShares));
+  assert_eq!(fixture.post_with_shares(BUDGET, &[u32::MAX, 10_001]), Err(Error::InvalidShares));
+}
+
+// Milestone validation
+fn dated(fixture: &Fixture, description: &str, amount: i128, deadline: u64) -> Milestone {
+  Milestone { deadline, ..fixture.milestone(description, amount) }
+}
+
+fn issue(code: IssueCode, index: u32, other: u32) -> ValidationIssue {
+  ValidationIssue { code, index, other }
+}
+
+#[test]
+fn the_report_lists_every_issue_with_its_indices() {
+  let fixture = Fixture::new();
+  let milestones = vec![
+    &fixture.env,
+    dated(&fixture, "Design", 300, 5_000),
+    dated(&fixture, "Design", 300, 5_000),
+    dated(&fixture, "Build", 300, 4_000),
+    Milestone { completed: true, ..dated(&fixture, "Launch", -100, PROJECT_DEADLINE + 1) },
+  ];
+
+  let report = fixture.contract.validate_milestones(&milestones, &BUDGET, &PROJECT_DEADLINE);
+
+  assert!(!report.valid);
+  assert_eq!(report.issues, vec![
+    &fixture.env,
+    issue(IssueCode::Duplicate, 1, 0),
+    issue(IssueCode::DeadlineOrder, 2, 1),
+    issue(IssueCode::NegativeAmount, 3, 3),
+    issue(IssueCode::Completed, 3, 3),
+    issue(IssueCode::PastProjectDeadline, 3, 3),
+    issue(IssueCode::BudgetMismatch, 3, 3),
+  ]);
+  // Unset deadlines are never out of order, and a lump sum has nothing to check
+  let undated = vec![&fixture.env, dated(&fixture, "Design", 400, 5_000), fixture.milestone("Build", 600)];
+  assert!(fixture.contract.validate_milestones(&undated, &BUDGET, &PROJECT_DEADLINE).valid);
+  assert!(fixture.contract.validate_milestones(&vec![&fixture.env], &BUDGET, &PROJECT_DEADLINE).valid);
+}
+
+#[test]
+fn too_many_milestones_are_reported_once() {
+  let fixture = Fixture::new();
+  let mut milestones = Vec::new(&fixture.env);
+  for phase in 0..=MAX_MILESTONES {
+    milestones.push_back(fixture.milestone(&std::format!("Phase {phase}"), 10));
+  }
+
+  let report = fixture.contract.validate_milestones(&milestones, &(10 * (MAX_MILESTONES as i128 + 1)), &0);
+
+  assert_eq!(report.issues, vec![&fixture.env, issue(IssueCode::TooMany, MAX_MILESTONES, MAX_MILESTONES)]);
+}
+
+#[test]
+fn posting_fails_where_the_dry_run_reports_an_issue() {
+  let fixture = Fixture::new();
+  let design = fixture.milestone("Design", 400);
+  let build = fixture.milestone("Build", 600);
+  let mut many = Vec::new(&fixture.env);
+  for phase in 0..=MAX_MILESTONES {
+    many.push_back(fixture.milestone(&std::format!("Phase {phase}"), 10));
+  }
+  let cases = [
+    (vec![&fixture.env, design.clone(), build.clone()], BUDGET, None),
+    (many, 10 * (MAX_MILESTONES as i128 + 1), Some(Error::LimitReached)),
+    (vec![&fixture.env, fixture.milestone("Design", -400), fixture.milestone("Build", 1_400)], BUDGET, Some(Error::InvalidAmount)),
+    (vec![&fixture.env, Milestone { completed: true, ..design.clone() }, build.clone()], BUDGET, Some(Error::InvalidConfig)),
+    (vec![&fixture.env, fixture.milestone("Design", 500), fixture.milestone("Design", 500)], BUDGET, Some(Error::InvalidSchedule)),
+    (vec![&fixture.env, dated(&fixture, "Design", 400, 6_000), dated(&fixture, "Build", 600, 5_000)], BUDGET, Some(Error::InvalidSchedule)),
+    (vec![&fixture.env, design.clone(), dated(&fixture, "Build", 600, PROJECT_DEADLINE + 1)], BUDGET, Some(Error::InvalidSchedule)),
+    (vec![&fixture.env, design, build], BUDGET + 1, Some(Error::InvalidAmount)),
+  ];
+
+  for (milestones, budget, error) in cases {
+    let report = fixture.contract.validate_milestones(&milestones, &budget, &PROJECT_DEADLINE);
+    let result = fixture.contract.try_post_project(&fixture.client, &fixture.text("Site"), &fixture.text("A website"), &fixture.text("web"), &budget, &fixture.asset, &PROJECT_DEADLINE, &milestones, &Locale::default());
+    assert_eq!(report.valid, error.is_none());
+    assert_eq!(result.err(), error.map(Ok));
+  }
+}
+
+#[test]
+fn opening_checks_a_project_posted_before_validation() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let mut project = fixture.contract.get_project(&project_id);
+  project.milestones = vec![&fixture.env, fixture.milestone("Design", 500), fixture.milestone("Design", 500)];
+  fixture.env.as_contract(&fixture.contract.address, || storage::projects(project_id).set(&fixture.env, &project));
+
+  let report = fixture.contract.validate_milestones(&project.milestones, &project.budget, &project.deadline);
+  assert_eq!(report.issues, vec![&fixture.env, issue(IssueCode::Duplicate, 1, 0)]);
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::InvalidSchedule)));
+}
+
+#[test]
+fn only_a_payout_completes_a_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  assert!(fixture.escrow(escrow_id).milestones.iter().all(|milestone| !milestone.completed));
+  fixture.submit(escrow_id, 0);
+
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let completed: std::vec::Vec<bool> = fixture.escrow(escrow_id).milestones.iter().map(|milestone| milestone.completed).collect();
+  assert_eq!(completed, [true, false]);
+}
+
+// Proposals
+#[test]
+fn enriched_listing_reads_the_bidders_current_stats() {
+  let fixture = Fixture::new();
+  fixture.set_time(1_000);
+  let escrow_id = fixture.open_escrow();
+  fixture.set_time(1_300);
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  fixture.contract.set_availability(&fixture.freelancer, &false);
+  let project_id = fixture.post_project();
+  fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+
+  let listing = fixture.contract.list_proposals(&project_id, &0, &10, &true).items.get(0).unwrap();
+
+  let stats = fixture.contract.get_freelancer_stats(&fixture.freelancer);
+  assert_eq!((stats.active_escrows, stats.responses, stats.response_secs), (1, 1, 300));
+  assert_eq!((listing.enriched, listing.active_escrows, listing.available, listing.avg_response_secs), (true, 1, false, 300));
+
+  // Closing the escrow frees the bidder up
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+  fixture.contract.set_availability(&fixture.freelancer, &true);
+  let listing = fixture.contract.list_proposals(&project_id, &0, &10, &true).items.get(0).unwrap();
+  assert_eq!((listing.active_escrows, listing.available), (0, true));
+}
+
+#[test]
+fn plain_listing_carries_only_the_proposal() {
+  let fixture = Fixture::new();
+  fixture.open_escrow();
+  let project_id = fixture.post_project();
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+
+  let listing = fixture.contract.list_proposals(&project_id, &0, &10, &false).items.get(0).unwrap();
+
+  assert_eq!(listing.proposal, fixture.contract.get_proposal(&proposal_id));
+  assert_eq!((listing.enriched, listing.active_escrows, listing.available, listing.avg_response_secs), (false, 0, false, 0));
+}
+
+#[test]
+fn sorted_listing_puts_the_cheapest_bid_first() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  for amount in [900, 700, 950, 700] {
+    fixture.contract.submit_proposal(&Address::generate(&fixture.env), &project_id, &amount, &Vec::new(&fixture.env), &fixture.hash(1));
+  }
+
+  let listings = fixture.contract.list_proposals_sorted(&project_id, &1, &2, &false);
+
+  assert_eq!((listings.next_cursor, listings.total), (Some(3), Some(4)));
+  let ids: std::vec::Vec<u64> = listings.items.iter().map(|listing| listing.proposal.id).collect();
+  assert_eq!(ids, [4, 1]);
+}
+
+#[test]
+fn proposed_schedules_must_add_up_to_the_amount() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let schedule = vec![&fixture.env, fixture.milestone("Design", 300), fixture.milestone("Build", 500)];
+
+  assert_eq!(fixture.contract.try_submit_proposal(&fixture.freelancer, &project_id, &900, &schedule, &fixture.hash(1)), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(fixture.contract.try_submit_proposal(&fixture.client, &project_id, &800, &schedule, &fixture.hash(1)), Err(Ok(Error::Unauthorized)));
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &800, &schedule, &fixture.hash(1));
+  assert_eq!(fixture.contract.get_proposal(&proposal_id).milestones, schedule);
+}
+
+#[test]
+fn accepted_proposal_schedule_replaces_the_projects() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let schedule = vec![&fixture.env, fixture.milestone("Prototype", 200), fixture.milestone("Design", 300), fixture.milestone("Build", 300)];
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &800, &schedule, &fixture.hash(1));
+
+  let escrow_id = fixture.contract.accept_proposal(&fixture.client, &proposal_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.milestones.clone()), (800, schedule));
+  assert_eq!(fixture.contract.get_milestone_records(&escrow_id).len(), 3);
+  assert_eq!(fixture.contract.get_terms_source(&escrow_id), TermsSource::Proposal(proposal_id));
+  assert_eq!(fixture.contract.get_project(&project_id).status, ProjectStatus::InProgress);
+  assert_eq!(fixture.contract.try_accept_proposal(&fixture.client, &proposal_id), Err(Ok(Error::ProjectNotOpen)));
+}
+
+#[test]
+fn proposal_without_a_schedule_scales_the_projects_to_its_amount() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &901, &Vec::new(&fixture.env), &fixture.hash(1));
+
+  let escrow_id = fixture.contract.accept_proposal(&fixture.client, &proposal_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.total_amount, 901);
+  assert_eq!((escrow.milestones.get(0).unwrap().amount, escrow.milestones.get(1).unwrap().amount), (360, 541));
+  assert_eq!(fixture.contract.get_terms_source(&fixture.open_escrow()), TermsSource::Project);
+}
+
+#[test]
+fn accepting_a_proposal_respects_the_wip_limit() {
+  let fixture = Fixture::new();
+  fixture.contract.set_wip_limit(&fixture.client, &1, &true);
+  fixture.open_escrow();
+  let project_id = fixture.post_project();
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+
+  assert_eq!(fixture.contract.try_accept_proposal(&fixture.freelancer, &proposal_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_accept_proposal(&fixture.client, &proposal_id), Err(Ok(Error::WipLimitReached)));
+}
+
+// Shortlist invitations
+#[test]
+fn a_shortlist_skips_and_reports_what_it_cannot_invite() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let [banned, resting, returning, fresh] = core::array::from_fn(|_| Address::generate(&fixture.env));
+  fixture.enact(AdminAction::SetDeactivated(banned.clone(), true));
+  fixture.contract.set_availability(&resting, &false);
+  fixture.contract.invite_many(&fixture.client, &project_id, &vec![&fixture.env, returning.clone()], &fixture.text("Hi"));
+
+  let shortlist = vec![&fixture.env, banned, returning.clone(), fixture.client.clone(), resting, fresh.clone(), fresh.clone()];
+  let outcomes = fixture.contract.invite_many(&fixture.client, &project_id, &shortlist, &fixture.text("Join us"));
+
+  let expected = [
+    InviteOutcome::Deactivated,
+    InviteOutcome::AlreadyInvited,
+    InviteOutcome::Client,
+    InviteOutcome::Unavailable,
+    InviteOutcome::Invited,
+    InviteOutcome::AlreadyInvited,
+  ];
+  assert_eq!(outcomes, Vec::from_slice(&fixture.env, &expected));
+  // One event per invitation made, none for the skipped addresses
+  let invited: Val = symbol_short!("invited").into_val(&fixture.env);
+  let events = fixture.env.events().all().iter().filter(|(_, topics, _)| topics.get(0).unwrap().shallow_eq(&invited)).count();
+  assert_eq!(events, 1);
+  let (topics, data) = fixture.last_event(symbol_short!("invited"));
+  assert_eq!(topics, (symbol_short!("invited"), fixture.client.clone(), fresh.clone(), project_id).into_val(&fixture.env));
+  assert_eq!(String::from_val(&fixture.env, &data), fixture.text("Join us"));
+  assert_eq!(fixture.contract.get_project_invites(&project_id, &0, &MAX_LIST_LIMIT).items, vec![&fixture.env, returning, fresh]);
+}
+
+#[test]
+fn a_shortlist_is_capped_per_call_and_per_project() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let batch = |len: u32| {
+    let mut freelancers = Vec::new(&fixture.env);
+    for _ in 0..len {
+      freelancers.push_back(Address::generate(&fixture.env));
+    }
+    freelancers
+  };
+
+  let too_many = batch(MAX_INVITES_PER_CALL + 1);
+  assert_eq!(fixture.contract.try_invite_many(&fixture.client, &project_id, &too_many, &fixture.text("Hi")), Err(Ok(Error::LimitReached)));
+  assert!(fixture.contract.get_project_invites(&project_id, &0, &MAX_LIST_LIMIT).items.is_empty());
+  for _ in 0..MAX_INVITES_PER_PROJECT / MAX_INVITES_PER_CALL {
+    let outcomes = fixture.contract.invite_many(&fixture.client, &project_id, &batch(MAX_INVITES_PER_CALL), &fixture.text("Hi"));
+    assert!(outcomes.iter().all(|outcome| outcome == InviteOutcome::Invited));
+  }
+
+  let outcomes = fixture.contract.invite_many(&fixture.client, &project_id, &batch(1), &fixture.text("Hi"));
+  assert_eq!(outcomes, vec![&fixture.env, InviteOutcome::ListFull]);
+  assert_eq!(fixture.contract.get_project_invites(&project_id, &0, &MAX_LIST_LIMIT).items.len(), MAX_INVITES_PER_PROJECT);
+}
+
+#[test]
+fn only_the_client_invites_and_only_while_the_project_is_open() {
+  let fixture = Fixture::new();
+  let shortlist = vec![&fixture.env, Address::generate(&fixture.env)];
+  let project_id = fixture.post_project();
+
+  assert_eq!(fixture.contract.try_invite_many(&fixture.freelancer, &project_id, &shortlist, &fixture.text("Hi")), Err(Ok(Error::Unauthorized)));
+  fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+  assert_eq!(fixture.contract.try_invite_many(&fixture.client, &project_id, &shortlist, &fixture.text("Hi")), Err(Ok(Error::ProjectNotOpen)));
+}
+
+// Escrow lifecycle
+#[test]
+fn initiating_an_escrow_moves_the_project_in_progress() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+
+  let escrow_id = fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+
+  assert_eq!(escrow_id, 1);
+  assert_eq!(fixture.contract.get_project(&project_id).status, ProjectStatus::InProgress);
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::ProjectNotOpen)));
+}
+
+#[test]
+fn only_the_project_owner_can_initiate_an_escrow() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+
+  let result = fixture.contract.try_initiate_escrow(&fixture.freelancer, &project_id, &fixture.freelancer);
+
+  assert_eq!(result, Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn full_deposit_moves_escrow_in_progress() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &(BUDGET - 1), &None);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Created);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &1, &None);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.state, EscrowState::InProgress);
+  assert_eq!(escrow.deposited_amount, BUDGET);
+  assert_eq!(escrow.released_amount, 0);
+}
+
+#[test]
+fn refund_is_only_allowed_before_full_funding() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+
+  let result = fixture.contract.try_refund_funds(&fixture.client, &escrow_id);
+
+  assert_eq!(result, Err(Ok(Error::RefundNotAllowed)));
+}
+
+#[test]
+fn refunding_an_escrow_cancels_its_project() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &500, &None);
+
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).status, ProjectStatus::Cancelled);
+}
+
+// Commitments
+#[test]
+fn commitments_fall_as_escrows_are_funded_and_released() {
+  let fixture = Fixture::new();
+  let first = fixture.open_escrow();
+  let second = fixture.open_escrow();
+  let current = || {
+    let commitments = fixture.contract.get_commitments(&fixture.client, &fixture.asset);
+    (commitments.unfunded, commitments.held)
+  };
+  assert_eq!(current(), (2000, 0));
+
+  fixture.contract.deposit_funds(&fixture.client, &first, &BUDGET, &None);
+  fixture.contract.deposit_funds(&fixture.client, &second, &300, &None);
+  assert_eq!(current(), (700, 1300));
+
+  fixture.submit(first, 0);
+  fixture.contract.release_funds(&fixture.client, &first, &0);
+  assert_eq!(current(), (700, 900));
+
+  // Finishing or refunding an escrow drops it from the commitments
+  fixture.submit(first, 1);
+  fixture.contract.release_funds(&fixture.client, &first, &1);
+  assert_eq!(current(), (700, 300));
+  fixture.contract.refund_funds(&fixture.client, &second);
+  assert_eq!(current(), (0, 0));
+  assert_eq!(fixture.escrow(second).refunded_amount, 300);
+
+  // Other assets are counted separately
+  assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.admin).unfunded, 0);
+}
+
+// Platform fees
+#[test]
+fn nonprofit_escrows_carry_no_fee_and_count_as_public_goods() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  fixture.enact(AdminAction::SetNonprofit(fixture.client.clone(), true));
+
+  for _ in 0..2 {
+    let escrow_id = fixture.open_escrow();
+    let (_, data) = fixture.last_event(symbol_short!("opened"));
+    let data: (u32, bool) = data.into_val(&fixture.env);
+    assert_eq!(data, (0, true));
+    assert_eq!(fixture.contract.get_fee_terms(&escrow_id), FeeTerms { bps: 0, public_goods: true });
+    fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+
+    fixture.release_all(escrow_id);
+    let (topics, report) = fixture.last_event(symbol_short!("closed"));
+    assert_eq!(topics, fixture.escrow_topics("closed", escrow_id));
+    let report: ClosingReport = report.into_val(&fixture.env);
+    assert_eq!(report, ClosingReport { state: EscrowState::Completed, released: BUDGET, refunded: 0, fee: 0, public_goods: true });
+  }
+
+  assert_eq!(fixture.contract.get_platform_stats(), PlatformStats { volume: 2 * BUDGET, fees: 0, public_goods_volume: 2 * BUDGET });
+}
+
+#[test]
+fn regular_escrows_pay_the_fee_on_what_they_release() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let escrow_id = fixture.funded_escrow();
+
+  fixture.release_all(escrow_id);
+
+  let (_, report) = fixture.last_event(symbol_short!("closed"));
+  let report: ClosingReport = report.into_val(&fixture.env);
+  assert_eq!((report.fee, report.public_goods), (25, false));
+  assert_eq!(fixture.contract.get_platform_stats(), PlatformStats { volume: BUDGET, fees: 25, public_goods_volume: 0 });
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::SetPlatformFee(10_001)), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::SetNonprofit(fixture.client.clone(), true)), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn removing_the_flag_leaves_open_escrows_on_their_snapshot() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  fixture.enact(AdminAction::SetNonprofit(fixture.client.clone(), true));
+  let in_flight = fixture.funded_escrow();
+
+  fixture.enact(AdminAction::SetNonprofit(fixture.client.clone(), false));
+  let later = fixture.funded_escrow();
+
+  assert!(!fixture.contract.is_nonprofit(&fixture.client));
+  assert_eq!(fixture.contract.get_fee_terms(&later), FeeTerms { bps: 250, public_goods: false });
+  fixture.release_all(in_flight);
+  assert_eq!(fixture.contract.get_platform_stats(), PlatformStats { volume: BUDGET, fees: 0, public_goods_volume: BUDGET });
+  fixture.release_all(later);
+  assert_eq!(fixture.contract.get_platform_stats(), PlatformStats { volume: 2 * BUDGET, fees: 25, public_goods_volume: BUDGET });
+}
+
+#[test]
+fn fee_withdrawal_is_capped_at_the_free_surplus() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let closed = fixture.funded_escrow();
+  fixture.release_all(closed);
+  fixture.funded_escrow();
+  // The balance backs the live escrow with only 10 to spare, less than the 25 in fees
+  fixture.fund_contract(BUDGET + 10);
+  let treasury = Address::generate(&fixture.env);
+
+  assert_eq!((fixture.contract.get_fee_pool(&fixture.asset), fixture.contract.get_obligations(&fixture.asset)), (25, BUDGET));
+  assert_eq!(fixture.contract.get_withdrawable_fees(&fixture.asset), 10);
+  assert_eq!(fixture.contract.try_withdraw_fees(&fixture.admin, &fixture.asset, &treasury, &11), Err(Ok(Error::ExceedsSurplus)));
+  fixture.contract.withdraw_fees(&fixture.admin, &fixture.asset, &treasury, &10);
+
+  assert_eq!(token::Client::new(&fixture.env, &fixture.asset).balance(&treasury), 10);
+  assert_eq!((fixture.contract.get_fee_pool(&fixture.asset), fixture.contract.get_withdrawable_fees(&fixture.asset)), (15, 0));
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), BUDGET);
+  assert_eq!(fixture.contract.try_withdraw_fees(&fixture.client, &fixture.asset, &treasury, &1), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_withdraw_fees(&fixture.admin, &fixture.asset, &treasury, &0), Err(Ok(Error::InvalidAmount)));
+}
+
+#[test]
+fn completing_escrows_moves_the_withdrawal_cap() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let first = fixture.funded_escrow();
+  let second = fixture.funded_escrow();
+  fixture.fund_contract(2 * BUDGET);
+  assert_eq!(fixture.contract.get_withdrawable_fees(&fixture.asset), 0);
+
+  fixture.release_all(first);
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), BUDGET);
+  assert_eq!(fixture.contract.get_withdrawable_fees(&fixture.asset), 25);
+
+  fixture.release_all(second);
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), 0);
+  assert_eq!(fixture.contract.get_withdrawable_fees(&fixture.asset), 50);
+}
+
+#[test]
+fn posted_bonds_count_as_obligations_until_returned() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let bond_token = fixture.require_bond(escrow_id, BOND);
+
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &None);
+  assert_eq!(fixture.contract.get_obligations(&bond_token.address), BOND);
+
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+  assert_eq!(fixture.contract.get_obligations(&bond_token.address), 0);
+}
+
+// Asset migration
+// Declares a replacement for the fixture's asset and returns it, with `pool` of it put in
+// the migration pool
+fn replacement_asset(fixture: &Fixture, pool: i128) -> Address {
+  let new = fixture.env.register_stellar_asset_contract_v2(fixture.admin.clone()).address();
+  fixture.enact(AdminAction::SetAssetMigration(fixture.asset.clone(), new.clone()));
+  if pool > 0 {
+    token::StellarAssetClient::new(&fixture.env, &new).mint(&fixture.admin, &pool);
+    fixture.contract.fund_migration_pool(&fixture.admin, &new, &pool);
+  }
+  new
+}
+
+#[test]
+fn a_migrated_escrow_completes_in_the_new_asset() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let escrow_id = fixture.funded_escrow();
+  fixture.fund_contract(BUDGET);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  let new = replacement_asset(&fixture, BUDGET);
+
+  fixture.contract.migrate_escrow_asset(&fixture.client, &fixture.freelancer, &escrow_id);
+
+  let (topics, data) = fixture.last_event(symbol_short!("migrated"));
+  assert_eq!(topics, fixture.escrow_topics("migrated", escrow_id));
+  let data: (Address, Address, i128) = data.into_val(&fixture.env);
+  assert_eq!(data, (fixture.asset.clone(), new.clone(), 600));
+  assert_eq!(fixture.escrow(escrow_id).asset, new);
+  assert_eq!(fixture.contract.get_project(&fixture.escrow(escrow_id).project_id).asset, new);
+  // The 600 still held now counts against the new asset and comes out of the pool; no old
+  // asset leaves the contract
+  assert_eq!((fixture.contract.get_obligations(&fixture.asset), fixture.contract.get_obligations(&new)), (0, 600));
+  assert_eq!(fixture.contract.get_commitments(&fixture.client, &new).held, 600);
+  assert_eq!(fixture.contract.get_migration_pool(&new), 400);
+  assert_eq!(token::Client::new(&fixture.env, &fixture.asset).balance(&fixture.admin), 0);
+  assert_eq!(token::Client::new(&fixture.env, &fixture.asset).balance(&fixture.contract.address), BUDGET);
+
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+  assert_eq!(fixture.contract.get_obligations(&new), 0);
+  // The fee is counted in the asset the escrow closed in
+  assert_eq!((fixture.contract.get_fee_pool(&fixture.asset), fixture.contract.get_fee_pool(&new)), (0, 25));
+  assert_eq!(fixture.contract.get_withdrawable_fees(&new), 25);
+}
+
+#[test]
+fn a_migration_leaves_a_bond_in_the_old_asset_alone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  // The bond is the only old asset the contract holds
+  token::StellarAssetClient::new(&fixture.env, &fixture.asset).mint(&fixture.freelancer, &BOND);
+  fixture.contract.require_bond(&fixture.client, &escrow_id, &fixture.asset, &BOND);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &None);
+  let new = replacement_asset(&fixture, BUDGET);
+
+  fixture.contract.migrate_escrow_asset(&fixture.client, &fixture.freelancer, &escrow_id);
+
+  let old = token::Client::new(&fixture.env, &fixture.asset);
+  assert_eq!((old.balance(&fixture.contract.address), old.balance(&fixture.admin)), (BOND, 0));
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), BOND);
+  assert_eq!(fixture.contract.get_migration_pool(&new), 0);
+
+  // The bond still goes back in its own token when the escrow completes
+  for milestone_index in 0..2 {
+    fixture.submit(escrow_id, milestone_index);
+    fixture.contract.release_funds(&fixture.client, &escrow_id, &milestone_index);
+  }
+  assert_eq!((old.balance(&fixture.contract.address), old.balance(&fixture.freelancer)), (0, BOND));
+}
+
+#[test]
+fn a_migration_the_pool_cannot_cover_changes_nothing() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.fund_contract(BUDGET);
+  let stranger = Address::generate(&fixture.env);
+
+  assert_eq!(fixture.contract.try_migrate_escrow_asset(&fixture.client, &fixture.freelancer, &escrow_id), Err(Ok(Error::NotFound)));
+  let new = replacement_asset(&fixture, BUDGET - 1);
+  assert_eq!(fixture.contract.try_migrate_escrow_asset(&fixture.client, &stranger, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_migrate_escrow_asset(&fixture.client, &fixture.freelancer, &escrow_id), Err(Ok(Error::InsufficientFunds)));
+
+  assert_eq!(fixture.escrow(escrow_id).asset, fixture.asset);
+  assert_eq!((fixture.contract.get_obligations(&fixture.asset), fixture.contract.get_obligations(&new)), (BUDGET, 0));
+  assert_eq!(fixture.contract.get_migration_pool(&new), BUDGET - 1);
+  assert_eq!(token::Client::new(&fixture.env, &fixture.asset).balance(&fixture.contract.address), BUDGET);
+  let same = AdminAction::SetAssetMigration(fixture.asset.clone(), fixture.asset.clone());
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &same), Err(Ok(Error::InvalidConfig)));
+}
+
+// Reference rates
+#[test]
+fn escrow_amounts_convert_at_the_reference_rate() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(10_000);
+  assert_eq!(fixture.contract.try_get_escrow_in_reference(&escrow_id), Err(Ok(Error::NotFound)));
+  fixture.enact(AdminAction::SetReferenceRate(fixture.asset.clone(), 12_500_000, 9_000));
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let view = fixture.contract.get_escrow_in_reference(&escrow_id);
+
+  assert_eq!((view.rate, view.rate_timestamp), (12_500_000, 9_000));
+  assert_eq!((view.total, view.deposited, view.released, view.refunded), (1250, 1250, 500, 0));
+  assert_eq!(view.milestones, vec![&fixture.env, 500, 750]);
+}
+
+#[test]
+fn stale_reference_rates_are_refused() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(10 * DAY);
+  let report = |rate: i128, timestamp: u64| AdminAction::SetReferenceRate(fixture.asset.clone(), rate, timestamp);
+  let rate = |timestamp: u64| fixture.contract.try_queue_admin_action(&fixture.admin, &report(10_000_000, timestamp));
+
+  assert_eq!(rate(10 * DAY + 1), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(rate(9 * DAY - 1), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &report(0, 10 * DAY)), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &report(10_000_000, 10 * DAY)), Err(Ok(Error::Unauthorized)));
+  fixture.enact(report(10_000_000, 9 * DAY + 100));
+  // A late report of an older price doesn't replace the newer one
+  assert_eq!(rate(9 * DAY + 99), Err(Ok(Error::InvalidConfig)));
+
+  // The rate converts up to and including the last second of its maximum age
+  fixture.set_time(10 * DAY + 100);
+  assert_eq!(fixture.contract.get_escrow_in_reference(&escrow_id).total, BUDGET);
+  fixture.set_time(10 * DAY + 101);
+  assert_eq!(fixture.contract.try_get_escrow_in_reference(&escrow_id), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn a_new_rate_changes_the_view_but_not_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(1_000);
+  fixture.enact(AdminAction::SetReferenceRate(fixture.asset.clone(), 5_000_000, 1_000));
+  assert_eq!(fixture.contract.get_escrow_in_reference(&escrow_id).total, 500);
+
+  fixture.set_time(2_000);
+  fixture.enact(AdminAction::SetReferenceRate(fixture.asset.clone(), 20_000_000, 2_000));
+
+  let view = fixture.contract.get_escrow_in_reference(&escrow_id);
+  assert_eq!((view.total, view.rate_timestamp), (2000, 2_000));
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.deposited_amount), (BUDGET, BUDGET));
+}
+
+// Risk-tier caps
+// Caps the fixture's marketplace with the asset at par, posted at DAY, and moves the clock there
+fn cap_risk(fixture: &Fixture, new_cap: i128, established_cap: i128, established_after: u32) {
+  fixture.set_time(DAY);
+  let risk_caps = RiskCaps { new_cap, established_cap, established_after };
+  fixture.enact(AdminAction::SetConfig(Config { risk_caps, ..fixture.contract.get_config() }));
+  fixture.enact(AdminAction::SetReferenceRate(fixture.asset.clone(), 10_000_000, DAY));
+}
+
+#[test]
+fn a_new_freelancer_is_stopped_at_the_cap() {
+  let fixture = Fixture::new();
+  cap_risk(&fixture, 1_500, 10_000, 1);
+  fixture.open_escrow();
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer), RiskLimit { tier: RiskTier::New, cap: 1_500, usage: BUDGET, completed: 0 });
+
+  // Another 1000 through a proposal would take the freelancer to 2000
+  let project_id = fixture.post_project();
+  let proposal_id = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &BUDGET, &Vec::new(&fixture.env), &fixture.hash(0));
+  assert_eq!(fixture.contract.try_accept_proposal(&fixture.client, &proposal_id), Err(Ok(Error::LimitReached)));
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::LimitReached)));
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer).usage, BUDGET);
+  // 500 still fits
+  let smaller = fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &500, &Vec::new(&fixture.env), &fixture.hash(0));
+  fixture.contract.accept_proposal(&fixture.client, &smaller);
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer).usage, 1_500);
+
+  // A capped tier can't be measured without a fresh rate
+  fixture.set_time(3 * DAY);
+  let project_id = ProjectBuilder::new(&fixture, &fixture.client).lump_sum(1).post();
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn completions_free_room_and_lift_the_cap() {
+  let fixture = Fixture::new();
+  cap_risk(&fixture, 1_000, 2_500, 1);
+  let first = fixture.funded_escrow();
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &fixture.post_project(), &fixture.freelancer), Err(Ok(Error::LimitReached)));
+
+  fixture.release_all(first);
+  let limit = fixture.contract.get_risk_limit(&fixture.freelancer);
+  assert_eq!(limit, RiskLimit { tier: RiskTier::Established, cap: 2_500, usage: 0, completed: 1 });
+  fixture.open_escrow();
+  let declined = fixture.open_escrow();
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer).usage, 2 * BUDGET);
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &fixture.post_project(), &fixture.freelancer), Err(Ok(Error::LimitReached)));
+
+  // A declined escrow stops counting too, but only completions build the track record
+  fixture.contract.decline_escrow(&fixture.freelancer, &declined);
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer), RiskLimit { tier: RiskTier::Established, cap: 2_500, usage: BUDGET, completed: 1 });
+}
+
+#[test]
+fn verified_freelancers_bypass_the_caps() {
+  let fixture = Fixture::new();
+  cap_risk(&fixture, 1, 1, 0);
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &fixture.post_project(), &fixture.freelancer), Err(Ok(Error::LimitReached)));
+
+  fixture.enact(AdminAction::SetVerified(fixture.freelancer.clone(), true));
+  fixture.open_escrow();
+  fixture.open_escrow();
+  let limit = fixture.contract.get_risk_limit(&fixture.freelancer);
+  assert_eq!((limit.tier, limit.cap, limit.usage), (RiskTier::Verified, 0, 2 * BUDGET));
+  // Not even a missing rate stops them
+  fixture.set_time(3 * DAY);
+  fixture.open_escrow();
+
+  fixture.enact(AdminAction::SetVerified(fixture.freelancer.clone(), false));
+  assert_eq!(fixture.contract.get_risk_limit(&fixture.freelancer).tier, RiskTier::New);
+}
+
+#[test]
+fn negative_caps_are_refused() {
+  let fixture = Fixture::new();
+  let risk_caps = RiskCaps { new_cap: -1, established_cap: 0, established_after: 0 };
+  let result = fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::SetConfig(Config { risk_caps, ..fixture.contract.get_config() }));
+  assert_eq!(result, Err(Ok(Error::InvalidConfig)));
+}
+
+// Deposits
+#[test]
+fn deposit_memo_round_trips() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let memo = fixture.hash(7);
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &300, &Some(memo.clone()));
+  let (_, topics, data) = fixture.env.events().all().last().unwrap();
+  assert_eq!(topics, fixture.escrow_topics("deposit", escrow_id));
+  let data: (Address, i128, Option<BytesN<32>>) = data.into_val(&fixture.env);
+  assert_eq!(data, (fixture.client.clone(), 300, Some(memo.clone())));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &200, &None);
+
+  let found = fixture.contract.find_deposit_by_memo(&escrow_id, &memo).unwrap();
+  assert_eq!(found.amount, 300);
+  assert_eq!(found.memo, Some(memo));
+  assert!(fixture.contract.find_deposit_by_memo(&escrow_id, &fixture.hash(8)).is_none());
+  // Memos never affect accounting
+  assert_eq!(fixture.escrow(escrow_id).deposited_amount, 500);
+}
+
+#[test]
+fn each_deposit_records_its_depositor() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &600, &None);
+  fixture.contract.deposit_funds(&fixture.freelancer, &escrow_id, &400, &None);
+
+  let deposits = fixture.contract.get_deposits(&escrow_id, &0, &MAX_LIST_LIMIT).items;
+  assert_eq!(deposits.len(), 2);
+  assert_eq!((deposits.get(0).unwrap().depositor, deposits.get(0).unwrap().amount), (fixture.client.clone(), 600));
+  assert_eq!((deposits.get(1).unwrap().depositor, deposits.get(1).unwrap().amount), (fixture.freelancer.clone(), 400));
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::InProgress);
+}
+
+#[test]
+fn the_deposit_log_comes_back_a_page_at_a_time() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  for amount in [100, 200, 300] {
+    fixture.contract.deposit_funds(&fixture.client, &escrow_id, &amount, &None);
+  }
+
+  let first = fixture.contract.get_deposits(&escrow_id, &0, &2);
+  assert_eq!(first.items.iter().map(|deposit| deposit.amount).collect::<std::vec::Vec<_>>(), [100, 200]);
+  assert_eq!((first.next_cursor, first.total), (Some(2), Some(3)));
+  let last = fixture.contract.get_deposits(&escrow_id, &2, &2);
+  assert_eq!(last.items.get(0).unwrap().amount, 300);
+  assert_eq!(last.next_cursor, None);
+}
+
+// Ledger export
+#[test]
+fn the_ledger_export_reconciles_with_the_closing_report() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let escrow_id = fixture.open_escrow();
+  let bond_token = fixture.require_bond(escrow_id, BOND);
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &2000);
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &None);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &600, &Some(fixture.hash(3)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &400, &None);
+  // A kickoff clawed back by a dispute, settled in part for the freelancer
+  fixture.contract.start_milestone(&fixture.client, &escrow_id, &0);
+  fixture.contract.raise_dispute(&fixture.client, &escrow_id, &0);
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &0, &300);
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  let (_, report) = fixture.last_event(symbol_short!("closed"));
+  let report: ClosingReport = report.into_val(&fixture.env);
+
+  // Read back in pages of three
+  let mut lines = std::vec::Vec::new();
+  let mut cursor = Some(0);
+  while let Some(next) = cursor {
+    let page = fixture.contract.export_ledger(&escrow_id, &next, &3);
+    assert_eq!(page.total, Some(10));
+    lines.extend(page.items.iter());
+    cursor = page.next_cursor;
+  }
+
+  let kinds: std::vec::Vec<LedgerKind> = lines.iter().map(|line| line.kind).collect();
+  assert_eq!(kinds, [
+    LedgerKind::BondPosted,
+    LedgerKind::Deposit,
+    LedgerKind::Deposit,
+    LedgerKind::Kickoff,
+    LedgerKind::Clawback,
+    LedgerKind::DisputeRelease,
+    LedgerKind::Refund,
+    LedgerKind::Release,
+    LedgerKind::Closed,
+    LedgerKind::BondReturned,
+  ]);
+  assert!(lines.iter().enumerate().all(|(position, line)| line.line == position as u32 + 1));
+  let sum = |kinds: &[LedgerKind]| lines.iter().filter(|line| kinds.contains(&line.kind)).map(|line| line.gross).sum::<i128>();
+  let payouts = [LedgerKind::Kickoff, LedgerKind::Release, LedgerKind::PartialRelease, LedgerKind::DisputeRelease, LedgerKind::Bonus];
+  assert_eq!(sum(&[LedgerKind::Deposit]), BUDGET);
+  assert_eq!(sum(&payouts) - sum(&[LedgerKind::Clawback]), report.released);
+  assert_eq!(sum(&[LedgerKind::Refund]), report.refunded);
+  let closed = &lines[8];
+  assert_eq!((closed.gross, closed.fee, closed.net), (report.released, report.fee, report.released - report.fee));
+  assert_eq!((lines[1].party.clone(), lines[1].memo.clone()), (fixture.client.clone(), Some(fixture.hash(3))));
+  assert_eq!((lines[7].milestone_index, lines[7].fee, lines[7].net), (Some(1), 15, 585));
+  assert_eq!((lines[9].asset.clone(), lines[9].gross), (bond_token.address.clone(), BOND));
+}
+
+#[test]
+fn bounty_refunds_export_one_line_per_sponsor() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.open_bounty(&fixture.client, &escrow_id, &DAY);
+  let sponsor = Address::generate(&fixture.env);
+  fixture.contract.deposit_funds(&sponsor, &escrow_id, &300, &None);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+
+  let page = fixture.contract.export_ledger(&escrow_id, &0, &10);
+  let refunds: std::vec::Vec<(Address, i128)> = page.items.iter().filter(|line| line.kind == LedgerKind::Refund).map(|line| (line.party, line.gross)).collect();
+  assert_eq!(refunds, [(sponsor, 300), (fixture.client.clone(), 100)]);
+  assert_eq!(page.items.last().unwrap().kind, LedgerKind::Closed);
+}
+
+// Bounties
+#[test]
+fn cancelling_a_bounty_returns_each_contribution() {
+  let fixture = Fixture::new();
+  let (escrow_id, sponsors) = fixture.bounty(DAY, &[300, 200, 100]);
+  fixture.contract.deposit_funds(&sponsors[0], &escrow_id, &50, &None);
+
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+
+  let refund_events = fixture.env.events().all().iter().filter(|(_, topics, _)| topics.get(0).unwrap().shallow_eq(&symbol_short!("refund").to_val())).count();
+  assert_eq!(refund_events, 3);
+  let refunded: std::vec::Vec<(i128, i128)> = fixture.contract.get_bounty(&escrow_id).contributions.iter().map(|c| (c.amount, c.refunded)).collect();
+  assert_eq!(refunded, [(350, 350), (200, 200), (100, 100)]);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Refunded);
+}
+
+#[test]
+fn an_expired_bounty_splits_what_is_left_pro_rata() {
+  let fixture = Fixture::new();
+  let (escrow_id, _) = fixture.bounty(DAY, &[500, 300, 200]);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.submit(escrow_id, 1);
+
+  assert_eq!(fixture.contract.try_expire_bounty(&escrow_id), Err(Ok(Error::TooEarly)));
+  fixture.set_time(DAY);
+  assert_eq!(fixture.contract.try_expire_bounty(&escrow_id), Err(Ok(Error::WorkUnderReview)));
+  fixture.contract.reject_milestone(&fixture.client, &escrow_id, &1);
+  fixture.contract.expire_bounty(&escrow_id);
+
+  let refunded: std::vec::Vec<i128> = fixture.contract.get_bounty(&escrow_id).contributions.iter().map(|c| c.refunded).collect();
+  assert_eq!(refunded, [300, 180, 120]);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Refunded, 400, 600));
+}
+
+#[test]
+fn rounding_dust_goes_to_the_largest_sponsor() {
+  let fixture = Fixture::new();
+  let (escrow_id, _) = fixture.bounty(DAY, &[333, 334, 333]);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.set_time(DAY);
+
+  fixture.contract.expire_bounty(&escrow_id);
+
+  // 600 split 333/334/333 rounds down to 199/200/199
+  let refunded: std::vec::Vec<i128> = fixture.contract.get_bounty(&escrow_id).contributions.iter().map(|c| c.refunded).collect();
+  assert_eq!(refunded, [199, 202, 199]);
+}
+
+#[test]
+fn a_bounty_takes_a_bounded_number_of_sponsors() {
+  let fixture = Fixture::new();
+  let (escrow_id, sponsors) = fixture.bounty(DAY, &[10; 20]);
+
+  let late = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_deposit_funds(&late, &escrow_id, &10, &None), Err(Ok(Error::LimitReached)));
+  // Sponsors already listed can keep contributing
+  fixture.contract.deposit_funds(&sponsors[0], &escrow_id, &10, &None);
+  assert_eq!(fixture.contract.get_bounty(&escrow_id).contributions.get(0).unwrap().amount, 20);
+}
+
+#[test]
+fn bounty_mode_is_chosen_before_funding() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let stranger = Address::generate(&fixture.env);
+
+  assert_eq!(fixture.contract.try_deposit_funds(&stranger, &escrow_id, &10, &None), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_open_bounty(&fixture.freelancer, &escrow_id, &DAY), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_open_bounty(&fixture.client, &escrow_id, &0), Err(Ok(Error::InvalidConfig)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &10, &None);
+  assert_eq!(fixture.contract.try_open_bounty(&fixture.client, &escrow_id, &DAY), Err(Ok(Error::HasDeposits)));
+  assert_eq!(fixture.contract.try_expire_bounty(&escrow_id), Err(Ok(Error::NotBounty)));
+}
+
+// Project handoff
+#[test]
+fn an_open_project_passes_to_the_new_owner() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let new_owner = Address::generate(&fixture.env);
+  let stranger = Address::generate(&fixture.env);
+
+  assert_eq!(fixture.contract.try_accept_project_transfer(&new_owner, &project_id), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_offer_project_transfer(&stranger, &project_id, &new_owner), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_offer_project_transfer(&fixture.client, &project_id, &fixture.client), Err(Ok(Error::InvalidOwner)));
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &new_owner);
+  assert_eq!(fixture.contract.try_accept_project_transfer(&stranger, &project_id), Err(Ok(Error::Unauthorized)));
+  // Nobody works on an open project, so there is nothing to acknowledge
+  assert_eq!(fixture.contract.try_acknowledge_project_transfer(&fixture.freelancer, &project_id), Err(Ok(Error::EscrowNotActive)));
+
+  fixture.contract.accept_project_transfer(&new_owner, &project_id);
+
+  assert_eq!(fixture.contract.get_project(&project_id).client, new_owner);
+  assert_eq!(fixture.contract.get_project_transfer(&project_id), None);
+  assert_eq!(fixture.contract.try_cancel_project(&fixture.client, &project_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.initiate_escrow(&new_owner, &project_id, &fixture.freelancer);
+}
+
+#[test]
+fn an_active_escrow_moves_once_the_freelancer_acknowledges() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let project_id = fixture.escrow(escrow_id).project_id;
+  let new_owner = Address::generate(&fixture.env);
+  let pair_len = |client: &Address| fixture.contract.check_index_integrity(&IndexKind::PairEscrows(client.clone(), fixture.freelancer.clone()), &0).len;
+
+  assert_eq!(fixture.contract.try_offer_project_transfer(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::InvalidOwner)));
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &new_owner);
+  assert_eq!(fixture.contract.try_accept_project_transfer(&new_owner, &project_id), Err(Ok(Error::NotAcknowledged)));
+  assert_eq!(fixture.contract.try_acknowledge_project_transfer(&new_owner, &project_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.acknowledge_project_transfer(&fixture.freelancer, &project_id);
+
+  fixture.contract.accept_project_transfer(&new_owner, &project_id);
+
+  assert_eq!(fixture.escrow(escrow_id).client, new_owner);
+  assert_eq!((pair_len(&fixture.client), pair_len(&new_owner)), (0, 1));
+  assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.asset), Commitments::default());
+  assert_eq!(fixture.contract.get_commitments(&new_owner, &fixture.asset).held, BUDGET);
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), BUDGET);
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+  fixture.contract.release_funds(&new_owner, &escrow_id, &0);
+}
+
+#[test]
+fn a_new_offer_needs_a_new_acknowledgement() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let project_id = fixture.escrow(escrow_id).project_id;
+  let new_owner = Address::generate(&fixture.env);
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &Address::generate(&fixture.env));
+  fixture.contract.acknowledge_project_transfer(&fixture.freelancer, &project_id);
+
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &new_owner);
+
+  assert!(!fixture.contract.get_project_transfer(&project_id).unwrap().freelancer_ack);
+  assert_eq!(fixture.contract.try_accept_project_transfer(&new_owner, &project_id), Err(Ok(Error::NotAcknowledged)));
+}
+
+#[test]
+fn finished_escrows_stay_with_the_original_client() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let project_id = fixture.escrow(escrow_id).project_id;
+  fixture.release_all(escrow_id);
+  let new_owner = Address::generate(&fixture.env);
+
+  // The freelancer is free to take over once their escrow is done
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &fixture.freelancer);
+  fixture.contract.offer_project_transfer(&fixture.client, &project_id, &new_owner);
+  fixture.contract.accept_project_transfer(&new_owner, &project_id);
+
+  assert_eq!(fixture.contract.get_project(&project_id).client, new_owner);
+  assert_eq!(fixture.escrow(escrow_id).client, fixture.client);
+  assert_eq!(fixture.contract.get_user_stats(&fixture.client).completed_as_client, 1);
+}
+
+// Key recovery
+// The fixture's funded escrow with `recovery` on the freelancer's profile, and a request from
+// it to move the escrow to a fresh address, which it returns
+fn recovery_requested(fixture: &Fixture, escrow_id: u64, recovery: &Address) -> Address {
+  fixture.contract.set_recovery_address(&fixture.freelancer, recovery);
+  let new_freelancer = Address::generate(&fixture.env);
+  fixture.contract.request_recovery(recovery, &escrow_id, &new_freelancer);
+  new_freelancer
+}
+
+#[test]
+fn the_recovery_address_and_the_client_move_an_escrow_to_a_new_key() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let recovery = Address::generate(&fixture.env);
+  let stranger = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_request_recovery(&recovery, &escrow_id, &stranger), Err(Ok(Error::Unauthorized)));
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  let new_freelancer = recovery_requested(&fixture, escrow_id, &recovery);
+  assert_eq!(fixture.contract.try_request_recovery(&recovery, &escrow_id, &fixture.client), Err(Ok(Error::InvalidOwner)));
+  assert_eq!(fixture.contract.try_confirm_recovery(&stranger, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_confirm_recovery(&recovery, &escrow_id), Err(Ok(Error::Unauthorized)));
+
+  fixture.contract.confirm_recovery(&fixture.client, &escrow_id);
+  let (topics, data) = fixture.last_event(Symbol::new(&fixture.env, "recovered"));
+  assert_eq!(topics, (Symbol::new(&fixture.env, "recovered"), fixture.client.clone(), new_freelancer.clone(), escrow_id).into_val(&fixture.env));
+  assert_eq!(Address::from_val(&fixture.env, &data), fixture.freelancer);
+  assert_eq!(fixture.escrow(escrow_id).freelancer, new_freelancer);
+  assert_eq!(fixture.contract.get_recovery_request(&escrow_id), None);
+  let lines = fixture.contract.export_ledger(&escrow_id, &0, &MAX_LIST_LIMIT).items;
+  assert!(lines.iter().any(|line| line.kind == LedgerKind::Recovered && line.party == fixture.freelancer));
+
+  // The lost key is out; the rest of the escrow pays the new one
+  let deliverable = fixture.hash(2);
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &1, &deliverable), Err(Ok(Error::Unauthorized)));
+  fixture.contract.submit_milestone(&new_freelancer, &escrow_id, &1, &deliverable);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  let releases = fixture.contract.export_ledger(&escrow_id, &0, &MAX_LIST_LIMIT).items.iter().filter(|line| line.kind == LedgerKind::Release).map(|line| (line.party, line.gross)).collect::<std::vec::Vec<_>>();
+  assert_eq!(releases, [(fixture.freelancer.clone(), 400), (new_freelancer, 600)]);
+}
+
+#[test]
+fn the_admin_completes_a_recovery_the_client_ignores_on_thirty_days_notice() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let recovery = Address::generate(&fixture.env);
+  fixture.set_time(1_000);
+  let new_freelancer = recovery_requested(&fixture, escrow_id, &recovery);
+  let other = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::RecoverFreelancer(escrow_id, other)), Err(Ok(Error::NotFound)));
+
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::RecoverFreelancer(escrow_id, new_freelancer.clone()));
+  fixture.set_time(1_000 + 30 * DAY - 1);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::TooEarly)));
+  fixture.set_time(1_000 + 30 * DAY);
+  fixture.contract.execute_admin_action(&action_id);
+  assert_eq!(fixture.escrow(escrow_id).freelancer, new_freelancer);
+}
+
+#[test]
+fn a_freelancer_who_still_has_the_key_can_stop_the_admin_fallback() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let recovery = Address::generate(&fixture.env);
+  let new_freelancer = recovery_requested(&fixture, escrow_id, &recovery);
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::RecoverFreelancer(escrow_id, new_freelancer));
+
+  fixture.contract.cancel_recovery(&fixture.freelancer, &escrow_id);
+  fixture.set_time(30 * DAY);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.escrow(escrow_id).freelancer, fixture.freelancer);
+}
+
+#[test]
+fn replacing_a_recovery_address_waits_thirty_days() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let (first, second) = (Address::generate(&fixture.env), Address::generate(&fixture.env));
+  assert_eq!(fixture.contract.try_set_recovery_address(&fixture.freelancer, &fixture.freelancer), Err(Ok(Error::InvalidConfig)));
+  // The first one applies at once
+  fixture.contract.set_recovery_address(&fixture.freelancer, &first);
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).recovery, Some(first.clone()));
+
+  fixture.set_time(1_000);
+  fixture.contract.set_recovery_address(&fixture.freelancer, &second);
+  let new_freelancer = Address::generate(&fixture.env);
+  fixture.contract.request_recovery(&first, &escrow_id, &new_freelancer);
+  fixture.set_time(1_000 + 30 * DAY - 1);
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).recovery, Some(first.clone()));
+  assert_eq!(fixture.contract.try_request_recovery(&second, &escrow_id, &new_freelancer), Err(Ok(Error::Unauthorized)));
+
+  fixture.set_time(1_000 + 30 * DAY);
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).recovery, Some(second.clone()));
+  // A request from the replaced address can no longer go through
+  assert_eq!(fixture.contract.try_confirm_recovery(&fixture.client, &escrow_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.request_recovery(&second, &escrow_id, &new_freelancer);
+  fixture.contract.confirm_recovery(&fixture.client, &escrow_id);
+  assert_eq!(fixture.escrow(escrow_id).freelancer, new_freelancer);
+}
+
+// Engagement
+#[test]
+fn bundle_posts_the_bond_accepts_and_stores_the_pref() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let token = fixture.require_bond(escrow_id, BOND);
+
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &Some(fixture.hash(9)));
+
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), Some(fixture.digest(escrow_id)));
+  assert!(fixture.contract.get_bond(&escrow_id).unwrap().posted);
+  assert_eq!(token.balance(&fixture.freelancer), 0);
+  assert_eq!(token.balance(&fixture.contract.address), BOND);
+  assert_eq!(fixture.contract.get_notification_pref(&fixture.freelancer), Some(fixture.hash(9)));
+
+  // The bond comes back once the escrow completes
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.release_all(escrow_id);
+  assert_eq!(token.balance(&fixture.freelancer), BOND);
+  assert_eq!(fixture.contract.get_bond(&escrow_id), None);
+}
+
+#[test]
+fn bundle_fails_as_a_unit_on_insufficient_bond_balance() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let token = fixture.require_bond(escrow_id, BOND - 1);
+
+  let result = fixture.contract.try_accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &Some(fixture.hash(9)));
+
+  assert!(result.is_err());
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), None);
+  assert!(!fixture.contract.get_bond(&escrow_id).unwrap().posted);
+  assert_eq!(token.balance(&fixture.freelancer), BOND - 1);
+  assert_eq!(fixture.contract.get_notification_pref(&fixture.freelancer), None);
+}
+
+#[test]
+fn bundle_without_a_bond_requirement_accepts() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &None);
+
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), Some(fixture.digest(escrow_id)));
+  assert_eq!(fixture.contract.get_notification_pref(&fixture.freelancer), None);
+  assert_eq!(fixture.contract.try_accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id)), Err(Ok(Error::AlreadyAccepted)));
+}
+
+#[test]
+fn granular_acceptance_needs_the_bond_posted_first() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let token = fixture.require_bond(escrow_id, BOND);
+
+  assert_eq!(fixture.contract.try_accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id)), Err(Ok(Error::BondNotPosted)));
+  fixture.contract.post_bond(&fixture.freelancer, &escrow_id);
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  fixture.contract.set_notification_pref(&fixture.freelancer, &fixture.hash(9));
+
+  assert_eq!(token.balance(&fixture.contract.address), BOND);
+  assert_eq!(fixture.contract.get_notification_pref(&fixture.freelancer), Some(fixture.hash(9)));
+  assert_eq!(fixture.contract.try_require_bond(&fixture.client, &escrow_id, &token.address, &BOND), Err(Ok(Error::AlreadyAccepted)));
+}
+
+#[test]
+fn the_terms_digest_ignores_everything_but_the_terms() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let digest = fixture.digest(escrow_id);
+
+  fixture.contract.set_notification_pref(&fixture.freelancer, &fixture.hash(9));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+  fixture.set_time(DAY);
+
+  assert_eq!(fixture.digest(escrow_id), digest);
+  // The same terms on another escrow hash the same way
+  assert_eq!(fixture.digest(fixture.open_escrow()), digest);
+}
+
+#[test]
+fn the_terms_digest_changes_with_the_terms() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let plain = fixture.digest(escrow_id);
+
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &2000);
+  let with_kickoff = fixture.digest(escrow_id);
+  fixture.require_bond(escrow_id, BOND);
+  let with_bond = fixture.digest(escrow_id);
+
+  assert_ne!(with_kickoff, plain);
+  assert_ne!(with_bond, with_kickoff);
+  // Posting the bond it already names leaves the digest alone
+  fixture.contract.post_bond(&fixture.freelancer, &escrow_id);
+  assert_eq!(fixture.digest(escrow_id), with_bond);
+}
+
+#[test]
+fn a_stale_digest_cannot_accept() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let stale = fixture.digest(escrow_id);
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &1, &5000);
+
+  assert_eq!(fixture.contract.try_accept_engagement(&fixture.freelancer, &escrow_id, &stale), Err(Ok(Error::DigestMismatch)));
+  assert_eq!(fixture.contract.try_accept_engagement_bundle(&fixture.freelancer, &escrow_id, &stale, &None), Err(Ok(Error::DigestMismatch)));
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), None);
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), Some(fixture.digest(escrow_id)));
+}
+
+#[test]
+fn contact_exchange_needs_an_accepted_engagement() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+
+  assert_eq!(fixture.contract.try_consent_contact_exchange(&fixture.client, &escrow_id), Err(Ok(Error::NotAcknowledged)));
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  let stranger = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_consent_contact_exchange(&stranger, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.get_contact_consent(&escrow_id), ContactConsent::None);
+}
+
+#[test]
+fn contact_exchange_opens_once_both_parties_consent() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  let contact_events = || fixture.env.events().all().iter().filter(|(_, topics, _)| topics.get(0).unwrap().shallow_eq(&symbol_short!("contact").to_val())).count();
+
+  // Each call's events are read before the next call replaces them
+  fixture.contract.consent_contact_exchange(&fixture.client, &escrow_id);
+  assert_eq!(contact_events(), 0);
+  fixture.contract.consent_contact_exchange(&fixture.client, &escrow_id);
+  assert_eq!(contact_events(), 0);
+  assert_eq!(fixture.contract.get_contact_consent(&escrow_id), ContactConsent::One);
+
+  fixture.contract.consent_contact_exchange(&fixture.freelancer, &escrow_id);
+  assert_eq!(contact_events(), 1);
+  assert_eq!(fixture.last_event(symbol_short!("contact")).0, fixture.escrow_topics("contact", escrow_id));
+  assert_eq!(fixture.contract.get_contact_consent(&escrow_id), ContactConsent::Both);
+
+  // Consenting again neither undoes anything nor raises the event a second time
+  fixture.contract.consent_contact_exchange(&fixture.freelancer, &escrow_id);
+  assert_eq!(contact_events(), 0);
+  fixture.contract.consent_contact_exchange(&fixture.client, &escrow_id);
+  assert_eq!(contact_events(), 0);
+  assert_eq!(fixture.contract.get_contact_consent(&escrow_id), ContactConsent::Both);
+}
+
+// Invites
+#[test]
+fn invite_code_resolves_to_the_engagement_offer() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.set_time(1_000);
+  assert_eq!(fixture.contract.try_create_invite_code(&fixture.freelancer, &escrow_id), Err(Ok(Error::Unauthorized)));
+  let code = fixture.contract.create_invite_code(&fixture.client, &escrow_id);
+  assert_ne!(fixture.contract.create_invite_code(&fixture.client, &escrow_id), code);
+
+  let offer = fixture.contract.resolve_invite(&code);
+  assert_eq!((offer.escrow_id, offer.client, offer.freelancer), (escrow_id, fixture.client.clone(), fixture.freelancer.clone()));
+  assert_eq!((offer.asset, offer.total_amount, offer.lump_sum), (fixture.asset.clone(), BUDGET, false));
+  assert_eq!(offer.milestones.iter().map(|milestone| milestone.amount).collect::<std::vec::Vec<_>>(), [400, 600]);
+  assert_eq!(offer.terms_digest, fixture.digest(escrow_id));
+  assert_eq!(offer.accept_by, 1_000 + 7 * DAY);
+
+  // Only the escrow's freelancer accepts through it, and the code is used up
+  assert_eq!(fixture.contract.try_accept_invite(&fixture.client, &code, &offer.terms_digest), Err(Ok(Error::Unauthorized)));
+  fixture.contract.accept_invite(&fixture.freelancer, &code, &offer.terms_digest);
+  assert_eq!(fixture.contract.get_accepted_terms(&escrow_id), Some(offer.terms_digest));
+  assert!(fixture.contract.try_resolve_invite(&code).err() == Some(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_create_invite_code(&fixture.client, &escrow_id), Err(Ok(Error::AlreadyAccepted)));
+}
+
+#[test]
+fn invite_code_expires_after_seven_days() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.set_time(1_000);
+  let code = fixture.contract.create_invite_code(&fixture.client, &escrow_id);
+  let digest = fixture.digest(escrow_id);
+
+  fixture.set_time(1_000 + 7 * DAY);
+  assert_eq!(fixture.contract.resolve_invite(&code).escrow_id, escrow_id);
+  fixture.set_time(1_000 + 7 * DAY + 1);
+  assert!(fixture.contract.try_resolve_invite(&code).err() == Some(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_accept_invite(&fixture.freelancer, &code, &digest), Err(Ok(Error::NotFound)));
+
+  // The escrow itself can still be accepted by ID
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &digest);
+}
+
+#[test]
+fn revoked_invite_code_no_longer_resolves() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let code = fixture.contract.create_invite_code(&fixture.client, &escrow_id);
+  let other = fixture.contract.create_invite_code(&fixture.client, &escrow_id);
+
+  assert_eq!(fixture.contract.try_revoke_invite(&fixture.freelancer, &code), Err(Ok(Error::Unauthorized)));
+  fixture.contract.revoke_invite(&fixture.client, &code);
+  assert!(fixture.contract.try_resolve_invite(&code).err() == Some(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_accept_invite(&fixture.freelancer, &code, &fixture.digest(escrow_id)), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_revoke_invite(&fixture.client, &code), Err(Ok(Error::NotFound)));
+
+  // Other codes for the same escrow are unaffected
+  assert_eq!(fixture.contract.resolve_invite(&other).escrow_id, escrow_id);
+}
+
+// Reentrancy
+// A token whose transfer calls back into deposit_funds, standing in for a token contract
+// with callback-like behaviour. It records whether the nested call went through.
+#[contract]
+struct CallbackToken;
+
+#[contracttype]
+enum CallbackKey {
+  Target, // Escrow contract and escrow ID to call back into
+  Reentered,
+}
+
+#[contractimpl]
+impl CallbackToken {
+  pub fn set_target(env: Env, escrow_contract: Address, escrow_id: u64) {
+    env.storage().instance().set(&CallbackKey::Target, &(escrow_contract, escrow_id));
+  }
+
+  pub fn transfer(env: Env, from: Address, _to: Address, amount: i128) {
+    let (escrow_contract, escrow_id): (Address, u64) = env.storage().instance().get(&CallbackKey::Target).unwrap();
+    let result = EscrowServiceContractClient::new(&env, &escrow_contract).try_deposit_funds(&from, &escrow_id, &amount, &None);
+    env.storage().instance().set(&CallbackKey::Reentered, &matches!(result, Ok(Ok(()))));
+  }
+
+  pub fn reentered(env: Env) -> bool {
+    env.storage().instance().get(&CallbackKey::Reentered).unwrap_or(false)
+  }
+}
+
+#[test]
+fn a_token_calling_back_during_a_transfer_cannot_reenter() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let token = fixture.env.register(CallbackToken, ());
+  let token_client = CallbackTokenClient::new(&fixture.env, &token);
+  token_client.set_target(&fixture.contract.address, &escrow_id);
+  fixture.contract.require_bond(&fixture.client, &escrow_id, &token, &BOND);
+
+  fixture.contract.post_bond(&fixture.freelancer, &escrow_id);
+
+  assert!(!token_client.reentered());
+  assert!(fixture.contract.get_bond(&escrow_id).unwrap().posted);
+  assert_eq!(fixture.escrow(escrow_id).deposited_amount, 0);
+  assert_eq!(fixture.contract.get_deposits(&escrow_id, &0, &MAX_LIST_LIMIT).items.len(), 0);
+  assert_eq!(fixture.contract.get_obligations(&token), BOND);
+}
+
+#[test]
+fn money_calls_fail_fast_while_a_transfer_is_in_flight() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.env.as_contract(&fixture.contract.address, || fixture.env.storage().instance().set(&StorageKey::TransferGuard, &true));
+
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &1, &None), Err(Ok(Error::Reentrant)));
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0), Err(Ok(Error::Reentrant)));
+  assert_eq!(fixture.contract.try_refund_funds(&fixture.client, &escrow_id), Err(Ok(Error::Reentrant)));
+  assert_eq!(fixture.contract.try_withdraw_fees(&fixture.admin, &fixture.asset, &fixture.admin, &1), Err(Ok(Error::Reentrant)));
+  // Calls without a check of their own still cannot write the escrow
+  fixture.set_time(8 * DAY);
+  assert_eq!(fixture.contract.try_auto_release(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::Reentrant)));
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Submitted);
+}
+
+#[test]
+fn the_guard_is_cleared_after_every_transfer() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.require_bond(escrow_id, BOND);
+
+  fixture.contract.accept_engagement_bundle(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id), &None);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.release_all(escrow_id);
+
+  let guarded = fixture.env.as_contract(&fixture.contract.address, || fixture.env.storage().instance().has(&StorageKey::TransferGuard));
+  assert!(!guarded);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+}
+
+// Kickoff checklist
+#[test]
+fn an_open_checklist_blocks_the_first_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.checklist_escrow();
+  let submit = |milestone_index: u32| fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &milestone_index, &fixture.hash(1));
+
+  assert_eq!(submit(0), Err(Ok(Error::ChecklistPending)));
+  assert_eq!(fixture.contract.try_start_milestone(&fixture.client, &escrow_id, &0), Err(Ok(Error::ChecklistPending)));
+  // Later milestones are not gated
+  assert_eq!(submit(1), Ok(Ok(())));
+
+  fixture.contract.complete_checklist_item(&fixture.freelancer, &escrow_id, &0);
+  assert_eq!(submit(0), Err(Ok(Error::ChecklistPending)));
+  fixture.set_time(500);
+  fixture.contract.complete_checklist_item(&fixture.client, &escrow_id, &1);
+  assert_eq!(submit(0), Ok(Ok(())));
+  let checklist = fixture.contract.get_checklist(&escrow_id).unwrap();
+  assert_eq!(checklist.checked_at, vec![&fixture.env, Some(0), Some(500)]);
+}
+
+#[test]
+fn only_the_items_party_checks_it_off() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.checklist_escrow();
+  let complete = |from: &Address, item_index: u32| fixture.contract.try_complete_checklist_item(from, &escrow_id, &item_index);
+
+  assert_eq!(complete(&fixture.client, 0), Err(Ok(Error::Unauthorized)));
+  assert_eq!(complete(&fixture.freelancer, 1), Err(Ok(Error::Unauthorized)));
+  assert_eq!(complete(&Address::generate(&fixture.env), 0), Err(Ok(Error::Unauthorized)));
+  assert_eq!(complete(&fixture.freelancer, 2), Err(Ok(Error::NotFound)));
+
+  let kinds = |user: &Address| fixture.contract.get_action_items(&escrow_id, user).iter().map(|item| (item.kind, item.milestone_index)).collect::<std::vec::Vec<_>>();
+  assert!(kinds(&fixture.freelancer).contains(&(ActionKind::CheckItem, 0)));
+  assert!(kinds(&fixture.client).contains(&(ActionKind::CheckItem, 1)));
+  assert_eq!(complete(&fixture.freelancer, 0), Ok(Ok(())));
+  assert!(!kinds(&fixture.freelancer).contains(&(ActionKind::CheckItem, 0)));
+}
+
+#[test]
+fn the_checklist_is_part_of_the_accepted_terms() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let item = ChecklistItem { label: fixture.text("Repo access"), party: UserType::Client };
+  let plain = fixture.digest(escrow_id);
+
+  fixture.contract.set_checklist(&fixture.client, &escrow_id, &vec![&fixture.env, item.clone()]);
+  assert_ne!(fixture.digest(escrow_id), plain);
+  let too_many = Vec::from_array(&fixture.env, [item.clone(), item.clone(), item.clone(), item.clone(), item.clone(), item.clone()]);
+  assert_eq!(fixture.contract.try_set_checklist(&fixture.client, &escrow_id, &too_many), Err(Ok(Error::LimitReached)));
+
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  assert_eq!(fixture.contract.try_set_checklist(&fixture.client, &escrow_id, &Vec::new(&fixture.env)), Err(Ok(Error::AlreadyAccepted)));
+}
+
+#[test]
+fn an_escrow_without_a_checklist_is_not_gated() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+
+  assert_eq!(fixture.contract.get_checklist(&escrow_id), None);
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Submitted);
+}
+
+// Early bonuses
+#[test]
+fn a_bonus_is_funded_with_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let set = |milestone_index: u32, amount: i128| fixture.contract.try_set_early_bonus(&fixture.client, &escrow_id, &milestone_index, &amount, &1);
+
+  assert_eq!(set(0, 100), Ok(Ok(())));
+  assert_eq!(set(1, 50), Ok(Ok(())));
+  assert_eq!(set(0, 0), Ok(Ok(())));
+  assert_eq!(set(2, 50), Err(Ok(Error::InvalidMilestoneIndex)));
+  assert_eq!(set(1, -1), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(fixture.contract.get_early_bonus(&escrow_id, &0), None);
+  assert_eq!(fixture.escrow(escrow_id).total_amount, BUDGET + 50);
+
+  // Only the bonus-free budget deposited: not funded yet
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Created);
+  assert_eq!(set(1, 60), Err(Ok(Error::HasDeposits)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &50, &None);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::InProgress);
+
+  // A project without any deadline has nothing to beat
+  let undated = fixture.contract.post_project(&fixture.client, &fixture.text("Site"), &fixture.text("A website"), &fixture.text("web"), &BUDGET, &fixture.asset, &0, &vec![&fixture.env, fixture.milestone("Design", 1000)], &Locale::default());
+  let undated_escrow = fixture.contract.initiate_escrow(&fixture.client, &undated, &fixture.freelancer);
+  assert_eq!(fixture.contract.try_set_early_bonus(&fixture.client, &undated_escrow, &0, &100, &1), Err(Ok(Error::InvalidConfig)));
+}
+
+#[test]
+fn approval_early_enough_pays_the_bonus() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.bonus_escrow();
+  fixture.set_time(7 * DAY);
+  fixture.submit(escrow_id, 0);
+
+  // Exactly two days before the deadline still counts
+  fixture.set_time(8 * DAY);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (500, 0));
+  assert_eq!(fixture.contract.get_early_bonus(&escrow_id, &0).unwrap().earned, Some(true));
+}
+
+#[test]
+fn approval_one_second_late_returns_the_bonus() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.bonus_escrow();
+  fixture.set_time(7 * DAY);
+  fixture.submit(escrow_id, 0);
+
+  fixture.set_time(8 * DAY + 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (400, 100));
+  assert_eq!(fixture.contract.get_early_bonus(&escrow_id, &0).unwrap().earned, Some(false));
+}
+
+#[test]
+fn unearned_bonuses_are_refunded_by_completion() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.bonus_escrow();
+  fixture.set_time(9 * DAY);
+
+  fixture.release_all(escrow_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Completed, 1000, 100));
+  assert_eq!(escrow.deposited_amount - escrow.released_amount - escrow.refunded_amount, 0);
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), 0);
+}
+
+#[test]
+fn a_disputed_milestone_forfeits_its_bonus() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.bonus_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.raise_dispute(&fixture.client, &escrow_id, &0);
+
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &0, &300);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (300, 200));
+  assert_eq!(fixture.contract.get_early_bonus(&escrow_id, &0).unwrap().earned, Some(false));
+}
+
+// Kickoff payments
+#[test]
+fn starting_a_milestone_releases_its_kickoff() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &2000);
+
+  assert_eq!(fixture.contract.start_milestone(&fixture.client, &escrow_id, &0), 80);
+
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 80);
+  assert_eq!(fixture.contract.get_milestone_records(&escrow_id).get(0).unwrap().paid, 80);
+  assert_eq!(fixture.contract.try_start_milestone(&fixture.client, &escrow_id, &0), Err(Ok(Error::AlreadyStarted)));
+  assert_eq!(fixture.contract.try_set_kickoff(&fixture.client, &escrow_id, &0, &1000), Err(Ok(Error::AlreadyStarted)));
+}
+
+#[test]
+fn final_release_pays_the_rest_of_the_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  // A third of 400 rounds down to 133, so the final payment is 267
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &3333);
+  assert_eq!(fixture.contract.start_milestone(&fixture.client, &escrow_id, &0), 133);
+
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(fixture.contract.get_milestone_records(&escrow_id).get(0).unwrap().paid, 400);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 400);
+}
+
+#[test]
+fn kickoff_share_above_10000_is_rejected() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+
+  assert_eq!(fixture.contract.try_set_kickoff(&fixture.client, &escrow_id, &0, &10_001), Err(Ok(Error::InvalidShares)));
+  assert_eq!(fixture.contract.try_set_kickoff(&fixture.freelancer, &escrow_id, &0, &2000), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn kickoff_shares_are_fixed_once_the_terms_are_accepted() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let digest = fixture.contract.get_accepted_terms(&escrow_id).unwrap();
+
+  assert_eq!(fixture.contract.try_set_kickoff(&fixture.client, &escrow_id, &0, &2000), Err(Ok(Error::AlreadyAccepted)));
+  // The signed terms still hash the same
+  assert_eq!(fixture.contract.terms_digest(&escrow_id), digest);
+}
+
+// Cancellation fees
+const KILL_FEES: CancellationSchedule = CancellationSchedule { before_acceptance_bps: 500, after_acceptance_bps: 1_000, after_submission_bps: 2_500 };
+
+// Opens an escrow under KILL_FEES and funds it, after the freelancer's acceptance if `accept`
+fn scheduled_escrow(fixture: &Fixture, accept: bool) -> u64 {
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.set_cancellation_schedule(&fixture.client, &escrow_id, &KILL_FEES);
+  if accept {
+    fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  }
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  escrow_id
+}
+
+#[test]
+fn cancelling_pays_the_tier_the_engagement_reached() {
+  let fixture = Fixture::new();
+  let unaccepted = scheduled_escrow(&fixture, false);
+  let accepted = scheduled_escrow(&fixture, true);
+  let submitted = scheduled_escrow(&fixture, true);
+  fixture.submit(submitted, 0);
+
+  for (escrow_id, bps, fee) in [(unaccepted, 500, 50), (accepted, 1_000, 100), (submitted, 2_500, 250)] {
+    fixture.contract.cancel_escrow(&fixture.client, &escrow_id);
+
+    let (topics, data) = fixture.last_event(symbol_short!("kill_fee"));
+    assert_eq!(topics, fixture.escrow_topics("kill_fee", escrow_id));
+    let data: (u32, i128) = data.into_val(&fixture.env);
+    assert_eq!(data, (bps, fee));
+    let escrow = fixture.escrow(escrow_id);
+    assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Refunded, fee, BUDGET - fee));
+    let lines = fixture.contract.export_ledger(&escrow_id, &0, &MAX_LIST_LIMIT).items;
+    assert!(lines.iter().any(|line| line.kind == LedgerKind::KillFee && line.gross == fee && line.party == fixture.freelancer));
+  }
+}
+
+#[test]
+fn cancelling_after_a_release_splits_only_what_is_left() {
+  let fixture = Fixture::new();
+  let escrow_id = scheduled_escrow(&fixture, true);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  fixture.contract.cancel_escrow(&fixture.client, &escrow_id);
+
+  // 25% of the 600 still held; the 400 already paid stays paid
+  let (_, report) = fixture.last_event(symbol_short!("closed"));
+  let report: ClosingReport = report.into_val(&fixture.env);
+  assert_eq!((report.state, report.released, report.refunded), (EscrowState::Refunded, 400 + 150, 450));
+  assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.asset), Commitments::default());
+  assert_eq!(fixture.contract.try_cancel_escrow(&fixture.client, &escrow_id), Err(Ok(Error::EscrowNotActive)));
+}
+
+#[test]
+fn a_cancellation_schedule_must_rise_and_stay_under_its_cap() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let schedule = |before_acceptance_bps, after_acceptance_bps, after_submission_bps| CancellationSchedule { before_acceptance_bps, after_acceptance_bps, after_submission_bps };
+
+  for invalid in [schedule(0, 0, 10_001), schedule(0, 0, MAX_CANCELLATION_FEE_BPS + 1), schedule(1_000, 500, 2_000), schedule(0, 2_000, 1_000)] {
+    assert_eq!(fixture.contract.try_set_cancellation_schedule(&fixture.client, &escrow_id, &invalid), Err(Ok(Error::InvalidSchedule)));
+  }
+  assert_eq!(fixture.contract.get_cancellation_schedule(&escrow_id), None);
+  assert_eq!(fixture.contract.try_set_cancellation_schedule(&fixture.freelancer, &escrow_id, &KILL_FEES), Err(Ok(Error::Unauthorized)));
+
+  // The schedule is part of the terms, so setting it changes the digest
+  let digest = fixture.digest(escrow_id);
+  fixture.contract.set_cancellation_schedule(&fixture.client, &escrow_id, &schedule(0, 0, MAX_CANCELLATION_FEE_BPS));
+  assert_ne!(fixture.digest(escrow_id), digest);
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  assert_eq!(fixture.contract.try_set_cancellation_schedule(&fixture.client, &escrow_id, &KILL_FEES), Err(Ok(Error::AlreadyAccepted)));
+}
+
+#[test]
+fn a_scheduled_escrow_can_only_be_left_through_cancel_escrow() {
+  let fixture = Fixture::new();
+  let plain = fixture.funded_escrow();
+  let scheduled = scheduled_escrow(&fixture, false);
+  let disputed = scheduled_escrow(&fixture, true);
+  fixture.submit(disputed, 1);
+  fixture.contract.raise_dispute(&fixture.client, &disputed, &1);
+
+  assert_eq!(fixture.contract.try_refund_funds(&fixture.client, &scheduled), Err(Ok(Error::RefundNotAllowed)));
+  assert_eq!(fixture.contract.try_cancel_escrow(&fixture.freelancer, &scheduled), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_cancel_escrow(&fixture.client, &plain), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_cancel_escrow(&fixture.client, &disputed), Err(Ok(Error::MilestoneDisputed)));
+}
+
+// Disputes
+#[test]
+fn dispute_before_submission_claws_back_the_kickoff() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &2000);
+  fixture.contract.start_milestone(&fixture.client, &escrow_id, &0);
+
+  let dispute = fixture.contract.raise_dispute(&fixture.client, &escrow_id, &0);
+
+  assert_eq!((dispute.clawback, dispute.pot), (80, 400));
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Disputed);
+
+  // The admin splits the whole milestone, then the escrow completes with the other one
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &0, &150);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (150, 250));
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Resolved);
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount), (EscrowState::Completed, 750));
+}
+
+#[test]
+fn dispute_after_submission_keeps_the_kickoff() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &0, &2000);
+  fixture.contract.start_milestone(&fixture.client, &escrow_id, &0);
+  fixture.submit(escrow_id, 0);
+
+  let dispute = fixture.contract.raise_dispute(&fixture.freelancer, &escrow_id, &0);
+
+  assert_eq!((dispute.clawback, dispute.pot), (0, 320));
+  assert_eq!(fixture.contract.try_resolve_dispute(&fixture.admin, &escrow_id, &0, &321), Err(Ok(Error::InvalidAmount)));
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &0, &320);
+  assert_eq!(fixture.contract.get_milestone_records(&escrow_id).get(0).unwrap().paid, 400);
+}
+
+#[test]
+fn disputed_milestones_are_frozen() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.raise_dispute(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0), Err(Ok(Error::MilestoneDisputed)));
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &0, &fixture.hash(1)), Err(Ok(Error::MilestoneDisputed)));
+  assert_eq!(fixture.contract.try_raise_dispute(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::MilestoneDisputed)));
+  assert_eq!(fixture.contract.try_resolve_dispute(&fixture.admin, &escrow_id, &1, &0), Err(Ok(Error::NoDispute)));
+  assert_eq!(fixture.contract.try_resolve_dispute(&fixture.client, &escrow_id, &0, &0), Err(Ok(Error::Unauthorized)));
+}
+
+// Scope objections
+#[test]
+fn flagging_out_of_scope_pauses_the_review_clock() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+
+  let contest = fixture.contract.get_contest(&escrow_id, &1).unwrap();
+  assert_eq!((contest.flagged_at, contest.respond_by, contest.offer), (2_000, 2_000 + 5 * DAY, 0));
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Contested);
+
+  fixture.set_time(1_000 + 7 * DAY);
+  assert_eq!(fixture.contract.try_auto_release(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::MilestoneContested)));
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &1), Err(Ok(Error::MilestoneContested)));
+}
+
+#[test]
+fn only_the_client_can_flag_a_submitted_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let note = fixture.text("Not in the brief");
+
+  assert_eq!(fixture.contract.try_flag_out_of_scope(&fixture.client, &escrow_id, &0, &note), Err(Ok(Error::MilestoneNotCompleted)));
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.contract.try_flag_out_of_scope(&fixture.freelancer, &escrow_id, &0, &note), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn resubmitting_ends_the_contest_and_restarts_the_clock() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+
+  fixture.set_time(3_000);
+  fixture.contract.submit_milestone(&fixture.freelancer, &escrow_id, &1, &fixture.hash(9));
+
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Submitted);
+  assert_eq!(fixture.contract.get_contest(&escrow_id, &1), None);
+  fixture.set_time(3_000 + 7 * DAY - 1);
+  assert_eq!(fixture.contract.try_auto_release(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::TooEarly)));
+  fixture.set_time(3_000 + 7 * DAY);
+  fixture.contract.auto_release(&fixture.freelancer, &escrow_id, &1);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Released);
+}
+
+#[test]
+fn accepting_a_partial_offer_settles_the_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+  assert_eq!(fixture.contract.try_accept_partial(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::NoPartialOffer)));
+  assert_eq!(fixture.contract.try_offer_partial(&fixture.client, &escrow_id, &1, &600), Err(Ok(Error::InvalidAmount)));
+
+  fixture.contract.offer_partial(&fixture.client, &escrow_id, &1, &250);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::PartialOffered);
+  fixture.contract.accept_partial(&fixture.freelancer, &escrow_id, &1);
+
+  let record = fixture.contract.get_milestone_records(&escrow_id).get(1).unwrap();
+  assert_eq!((record.status, record.paid), (MilestoneStatus::Released, 250));
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (250, 350));
+  assert_eq!(fixture.contract.get_contest(&escrow_id, &1), None);
+
+  // The other milestone still completes the escrow
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+}
+
+#[test]
+fn escalating_a_contest_opens_a_dispute() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+  fixture.contract.offer_partial(&fixture.client, &escrow_id, &1, &250);
+
+  let dispute = fixture.contract.raise_dispute(&fixture.freelancer, &escrow_id, &1);
+
+  assert_eq!(dispute.pot, 600);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Disputed);
+  assert_eq!(fixture.contract.get_contest(&escrow_id, &1), None);
+}
+
+#[test]
+fn unanswered_contest_lapses_back_to_pending() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+  fixture.contract.offer_partial(&fixture.client, &escrow_id, &1, &250);
+
+  fixture.set_time(2_000 + 5 * DAY);
+  assert_eq!(fixture.contract.try_lapse_contest(&escrow_id, &1), Err(Ok(Error::TooEarly)));
+
+  fixture.set_time(2_000 + 5 * DAY + 1);
+  assert_eq!(fixture.contract.try_accept_partial(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::ContestLapsed)));
+  assert_eq!(fixture.contract.try_raise_dispute(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::ContestLapsed)));
+  fixture.contract.lapse_contest(&escrow_id, &1);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Pending);
+  assert_eq!(fixture.contract.get_contest(&escrow_id, &1), None);
+}
+
+// Arbitrators
+#[test]
+fn probationary_arbitrators_only_get_disputes_under_the_limit() {
+  let fixture = Fixture::new();
+  let arbitrator = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 500, probation_cosigned: 1 }));
+  let large = fixture.disputed_escrow();
+  let small = fixture.funded_escrow();
+  fixture.contract.raise_dispute(&fixture.client, &small, &0);
+
+  assert_eq!(fixture.contract.next_dispute(&arbitrator), DisputeRef { escrow_id: small, milestone_index: 0 });
+  assert_eq!(fixture.contract.try_next_dispute(&arbitrator), Err(Ok(Error::NotFound)));
+
+  // The larger dispute waits for a full arbitrator
+  let full = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(full.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(full.clone()));
+  assert_eq!(fixture.contract.next_dispute(&full), DisputeRef { escrow_id: large, milestone_index: 1 });
+  assert_eq!(fixture.contract.get_dispute_arbitrator(&large, &1), Some(full));
+}
+
+#[test]
+fn probationary_rulings_need_a_full_cosignature() {
+  let fixture = Fixture::new();
+  let arbitrator = Address::generate(&fixture.env);
+  let full = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+  fixture.enact(AdminAction::AddArbitrator(full.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(full.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 1000, probation_cosigned: 1 }));
+  let first = fixture.disputed_escrow();
+  let second = fixture.disputed_escrow();
+
+  fixture.contract.next_dispute(&arbitrator);
+  assert_eq!(fixture.contract.try_resolve_dispute(&full, &first, &1, &200), Err(Ok(Error::Unauthorized)));
+  fixture.contract.resolve_dispute(&arbitrator, &first, &1, &200);
+  assert_eq!(fixture.status(first, 1), MilestoneStatus::Disputed);
+  assert_eq!(fixture.contract.get_pending_ruling(&first, &1), Some(Ruling { arbitrator: arbitrator.clone(), freelancer_amount: 200 }));
+  assert_eq!(fixture.contract.try_cosign_ruling(&arbitrator, &first, &1), Err(Ok(Error::Unauthorized)));
+
+  fixture.contract.cosign_ruling(&full, &first, &1);
+  assert_eq!(fixture.status(first, 1), MilestoneStatus::Resolved);
+  assert_eq!(fixture.escrow(first).refunded_amount, 400);
+  assert_eq!(fixture.contract.get_arbitrator(&arbitrator).unwrap().rulings, 1);
+
+  // Past the co-signed rulings, a probationary ruling applies directly
+  fixture.contract.next_dispute(&arbitrator);
+  fixture.contract.resolve_dispute(&arbitrator, &second, &1, &600);
+  assert_eq!(fixture.status(second, 1), MilestoneStatus::Resolved);
+}
+
+#[test]
+fn promotion_lifts_the_limit_and_the_cosignature() {
+  let fixture = Fixture::new();
+  let arbitrator = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 500, probation_cosigned: 5 }));
+  let escrow_id = fixture.disputed_escrow();
+  assert_eq!(fixture.contract.try_next_dispute(&arbitrator), Err(Ok(Error::NotFound)));
+
+  fixture.enact(AdminAction::PromoteArbitrator(arbitrator.clone()));
+
+  fixture.contract.next_dispute(&arbitrator);
+  fixture.contract.resolve_dispute(&arbitrator, &escrow_id, &1, &300);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Resolved);
+  assert_eq!(fixture.contract.get_arbitrator(&arbitrator).unwrap(), Arbitrator { tier: ArbitratorTier::Full, rulings: 1 });
+}
+
+#[test]
+fn arbitrators_are_never_assigned_their_own_escrows() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::AddArbitrator(fixture.freelancer.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(fixture.freelancer.clone()));
+  fixture.disputed_escrow();
+
+  assert_eq!(fixture.contract.try_next_dispute(&fixture.freelancer), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_next_dispute(&fixture.client), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn arbitrator_stats_follow_resolutions_and_overturned_appeals() {
+  let fixture = Fixture::new();
+  let full = Address::generate(&fixture.env);
+  let probationary = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(full.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(full.clone()));
+  fixture.enact(AdminAction::AddArbitrator(probationary.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 1000, probation_cosigned: 1 }));
+
+  let first = fixture.disputed_escrow();
+  fixture.contract.next_dispute(&full);
+  fixture.set_time(1_000);
+  fixture.contract.resolve_dispute(&full, &first, &1, &150);
+  let second = fixture.disputed_escrow();
+  fixture.contract.next_dispute(&full);
+  fixture.set_time(4_000);
+  fixture.contract.resolve_dispute(&full, &second, &1, &600);
+
+  let stats = fixture.contract.get_arbitrator_stats(&full);
+  assert_eq!((stats.resolved, stats.avg_resolution_secs, stats.avg_client_share_bps), (2, 2_000, 3_750));
+  assert_eq!((stats.overturned, stats.credibility_bps), (0, 10_000));
+
+  // The admin overturns the probationary arbitrator's ruling on appeal
+  let third = fixture.disputed_escrow();
+  fixture.contract.next_dispute(&probationary);
+  fixture.contract.resolve_dispute(&probationary, &third, &1, &0);
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::OverturnRuling(third, 1, 300)), Err(Ok(Error::Unauthorized)));
+  fixture.enact(AdminAction::OverturnRuling(third, 1, 300));
+
+  assert_eq!(fixture.status(third, 1), MilestoneStatus::Resolved);
+  assert_eq!(fixture.escrow(third).refunded_amount, 300);
+  let stats = fixture.contract.get_arbitrator_stats(&probationary);
+  assert_eq!((stats.resolved, stats.overturned, stats.credibility_bps), (0, 1, 0));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::OverturnRuling(third, 1, 300)), Err(Ok(Error::NotFound)));
+  let assignment = fixture.contract.get_dispute_assignment(&first, &1).unwrap();
+  assert_eq!((assignment.arbitrator, assignment.stats.resolved), (full, 2));
+}
+
+// Adds full arbitrators and turns random assignment on
+fn random_arbitrators(fixture: &Fixture, count: usize) -> std::vec::Vec<Address> {
+  fixture.enact(AdminAction::SetRandomAssignment(true));
+  (0..count)
+    .map(|_| {
+      let arbitrator = Address::generate(&fixture.env);
+      fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+      fixture.enact(AdminAction::PromoteArbitrator(arbitrator.clone()));
+      arbitrator
+    })
+    .collect()
+}
+
+#[test]
+fn random_draws_respect_conflicts_and_the_probation_limit() {
+  let fixture = Fixture::new();
+  let full = random_arbitrators(&fixture, 2);
+  // The client can't judge their own dispute, and the probationary arbitrator only gets
+  // pots up to 500
+  fixture.enact(AdminAction::AddArbitrator(fixture.client.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(fixture.client.clone()));
+  let probationary = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(probationary.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 500, probation_cosigned: 0 }));
+
+  let mut drawn = std::vec::Vec::new();
+  for _ in 0..20 {
+    // Milestone 1 carries a pot of 600
+    let escrow_id = fixture.disputed_escrow();
+    let arbitrator = fixture.contract.get_dispute_arbitrator(&escrow_id, &1).unwrap();
+    assert!(full.contains(&arbitrator));
+    drawn.push(arbitrator);
+  }
+
+  assert!(full.iter().all(|arbitrator| drawn.contains(arbitrator)));
+  assert_eq!(fixture.contract.try_next_dispute(&probationary), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn a_random_assignment_is_recorded_on_the_dispute() {
+  let fixture = Fixture::new();
+  let arbitrator = random_arbitrators(&fixture, 1).pop().unwrap();
+
+  let escrow_id = fixture.disputed_escrow();
+
+  let (topics, _) = fixture.last_event(symbol_short!("assigned"));
+  assert_eq!(topics, (symbol_short!("assigned"), arbitrator.clone(), escrow_id).into_val(&fixture.env));
+  assert_eq!(fixture.contract.get_dispute_assignment(&escrow_id, &1).unwrap().arbitrator, arbitrator);
+  // Taken off the queue, so nobody else pulls it
+  let other = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(other.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(other.clone()));
+  assert_eq!(fixture.contract.try_next_dispute(&other), Err(Ok(Error::NotFound)));
+  fixture.contract.resolve_dispute(&arbitrator, &escrow_id, &1, &300);
+}
+
+#[test]
+fn each_party_strikes_one_arbitrator() {
+  let fixture = Fixture::new();
+  let arbitrators = random_arbitrators(&fixture, 2);
+  let escrow_id = fixture.disputed_escrow();
+  let first = fixture.contract.get_dispute_arbitrator(&escrow_id, &1).unwrap();
+  let second = arbitrators.iter().find(|arbitrator| **arbitrator != first).unwrap().clone();
+  let strike = |from: &Address| fixture.contract.try_strike_arbitrator(from, &escrow_id, &1);
+
+  assert_eq!(strike(&Address::generate(&fixture.env)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(strike(&fixture.client), Ok(Ok(Some(second.clone()))));
+  assert_eq!(strike(&fixture.client), Err(Ok(Error::StrikeUsed)));
+  assert_eq!(fixture.contract.try_resolve_dispute(&first, &escrow_id, &1, &300), Err(Ok(Error::Unauthorized)));
+
+  // With both struck nobody is left to draw, and the struck ones can't pull it either
+  assert_eq!(strike(&fixture.freelancer), Ok(Ok(None)));
+  assert_eq!(fixture.contract.get_dispute_arbitrator(&escrow_id, &1), None);
+  assert_eq!(fixture.contract.get_dispute_strikes(&escrow_id, &1).len(), 2);
+  assert_eq!(fixture.contract.try_next_dispute(&first), Err(Ok(Error::NotFound)));
+  let third = random_arbitrators(&fixture, 1).pop().unwrap();
+  assert_eq!(fixture.contract.next_dispute(&third), DisputeRef { escrow_id, milestone_index: 1 });
+}
+
+#[test]
+fn a_pending_ruling_cant_be_struck() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetRandomAssignment(true));
+  let arbitrator = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+  fixture.enact(AdminAction::SetArbitrationPolicy(ArbitrationPolicy { probation_max_pot: 1000, probation_cosigned: 1 }));
+  let escrow_id = fixture.disputed_escrow();
+  fixture.contract.resolve_dispute(&arbitrator, &escrow_id, &1, &300);
+
+  assert_eq!(fixture.contract.try_strike_arbitrator(&fixture.client, &escrow_id, &1), Err(Ok(Error::RulingPending)));
+}
+
+// Work-in-progress limit
+#[test]
+fn hard_wip_limit_blocks_the_escrow_at_the_limit() {
+  let fixture = Fixture::new();
+  fixture.contract.set_wip_limit(&fixture.client, &2, &true);
+  fixture.open_escrow();
+  let second = fixture.open_escrow();
+
+  let project_id = fixture.post_project();
+  assert_eq!(fixture.contract.try_initiate_escrow(&fixture.client, &project_id, &fixture.freelancer), Err(Ok(Error::WipLimitReached)));
+  // Other freelancers are not affected
+  fixture.contract.initiate_escrow(&fixture.client, &project_id, &Address::generate(&fixture.env));
+
+  // A finished escrow frees a slot
+  fixture.contract.refund_funds(&fixture.client, &second);
+  let project_id = fixture.post_project();
+  fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+}
+
+#[test]
+fn soft_wip_limit_opens_the_escrow_with_a_warning() {
+  let fixture = Fixture::new();
+  fixture.contract.set_wip_limit(&fixture.client, &1, &false);
+  fixture.open_escrow();
+  let project_id = fixture.post_project();
+
+  let escrow_id = fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+
+  assert_eq!(escrow_id, 2);
+  let (topics, data) = fixture.last_event(symbol_short!("wip_warn"));
+  assert_eq!(topics, (symbol_short!("wip_warn"), fixture.client.clone(), fixture.freelancer.clone(), project_id).into_val(&fixture.env));
+  let data: (u32, u32) = data.into_val(&fixture.env);
+  assert_eq!(data, (1, 1));
+}
+
+// Approval revocation
+#[test]
+fn an_approval_can_be_revoked_inside_the_window() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.set_time(1000);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.contract.countersign_acceptance(&fixture.freelancer, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+
+  fixture.set_time(1300);
+  fixture.contract.revoke_approval(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Submitted);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+  assert_eq!(fixture.contract.get_milestone_records(&escrow_id).get(0).unwrap().paid, 0);
+  assert_eq!(fixture.contract.get_acceptance(&escrow_id, &0), None);
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &escrow_id, &0), Err(Ok(Error::NotApproved)));
+  // Approving again pays it once
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 400);
+}
+
+#[test]
+fn the_revoke_window_ends_on_its_last_second() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.submit(escrow_id, 1);
+  fixture.set_time(1000);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  // The second approval completed the escrow, which settles both payouts
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &escrow_id, &1), Err(Ok(Error::EscrowNotActive)));
+
+  let other = fixture.funded_escrow();
+  fixture.submit(other, 0);
+  fixture.contract.release_funds(&fixture.client, &other, &0);
+  fixture.set_time(1601);
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &other, &0), Err(Ok(Error::RevokeWindowClosed)));
+  fixture.set_time(1600);
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &other, &0), Ok(Ok(())));
+}
+
+#[test]
+fn a_withdrawn_payout_can_no_longer_be_revoked() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.set_time(1000);
+  assert_eq!(fixture.contract.try_withdraw_payout(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::NotApproved)));
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_withdraw_payout(&fixture.client, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+
+  fixture.set_time(1100);
+  assert_eq!(fixture.contract.withdraw_payout(&fixture.freelancer, &escrow_id, &0), 400);
+  let (topics, data) = fixture.last_event(symbol_short!("withdrawn"));
+  assert_eq!(topics, fixture.escrow_topics("withdrawn", escrow_id));
+  let data: (u32, i128) = data.into_val(&fixture.env);
+  assert_eq!(data, (0, 400));
+
+  // Still inside the window, but the payout is the freelancer's now
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &escrow_id, &0), Err(Ok(Error::RevokeWindowClosed)));
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Released);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 400);
+  assert_eq!(fixture.contract.withdraw_payout(&fixture.freelancer, &escrow_id, &0), 400);
+  assert_eq!(fixture.contract.get_withdrawal(&escrow_id, &0), Some(400));
+}
+
+#[test]
+fn revoking_takes_back_an_earned_bonus() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.bonus_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 500);
+
+  fixture.contract.revoke_approval(&fixture.client, &escrow_id, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.released_amount, escrow.refunded_amount), (0, 0));
+  assert_eq!(fixture.contract.get_early_bonus(&escrow_id, &0).unwrap().earned, None);
+  let revoked: i128 = fixture.contract.export_ledger(&escrow_id, &0, &10).items.iter().filter(|line| line.kind == LedgerKind::Revoked).map(|line| line.gross).sum();
+  assert_eq!(revoked, 500);
+}
+
+// Milestone review
+#[test]
+fn release_requires_a_submitted_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+
+  let result = fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(result, Err(Ok(Error::MilestoneNotCompleted)));
+}
+
+#[test]
+fn releasing_every_milestone_completes_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+
+  // Milestones are paid independently of each other
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 600);
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &1), Err(Ok(Error::AlreadyReleased)));
+
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.released_amount, BUDGET);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert!(escrow.milestones.iter().all(|milestone| milestone.completed));
+}
+
+#[test]
+fn release_needs_the_milestone_covered_by_deposits() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &500, &None);
+  fixture.submit(escrow_id, 1);
+
+  let result = fixture.contract.try_release_funds(&fixture.client, &escrow_id, &1);
+
+  assert_eq!(result, Err(Ok(Error::InsufficientFunds)));
+}
+
+#[test]
+fn freelancer_can_release_once_the_review_period_runs_out() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(1_000);
+  fixture.submit(escrow_id, 0);
+
+  fixture.set_time(1_000 + 7 * DAY - 1);
+  assert_eq!(fixture.contract.try_auto_release(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::TooEarly)));
+
+  fixture.set_time(1_000 + 7 * DAY);
+  fixture.contract.auto_release(&fixture.freelancer, &escrow_id, &0);
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Released);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 400);
+}
+
+#[test]
+fn rejected_milestone_goes_back_to_the_freelancer() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+
+  fixture.contract.reject_milestone(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(fixture.status(escrow_id, 0), MilestoneStatus::Pending);
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0), Err(Ok(Error::MilestoneNotCompleted)));
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+}
+
+// Escrow completion
+#[test]
+fn voiding_the_last_milestone_completes_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_void_milestone(&fixture.client, &escrow_id, &1), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_void_milestone(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::AlreadyReleased)));
+
+  fixture.contract.void_milestone(&fixture.freelancer, &escrow_id, &1);
+
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Voided);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Completed, 400, 600));
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).status, ProjectStatus::Completed);
+}
+
+#[test]
+fn partial_payout_on_the_last_milestone_completes_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.contested_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.contract.offer_partial(&fixture.client, &escrow_id, &1, &250);
+
+  fixture.contract.accept_partial(&fixture.freelancer, &escrow_id, &1);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Completed, 650, 350));
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).status, ProjectStatus::Completed);
+}
+
+#[test]
+fn dispute_on_the_last_milestone_completes_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.disputed_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::InProgress);
+
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &1, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Completed, 400, 600));
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).status, ProjectStatus::Completed);
+}
+
+#[test]
+fn voiding_needs_the_remainder_covered_by_deposits() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &500, &None);
+
+  assert_eq!(fixture.contract.try_void_milestone(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::InsufficientFunds)));
+  fixture.contract.void_milestone(&fixture.freelancer, &escrow_id, &0);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.refunded_amount), (EscrowState::Created, 400));
+}
+
+// Repricing
+#[test]
+fn raising_a_milestone_needs_a_top_up_before_more_work() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &1, &800);
+  assert_eq!(fixture.contract.try_accept_reprice(&fixture.freelancer, &escrow_id, &1, &800), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_accept_reprice(&fixture.client, &escrow_id, &1, &700), Err(Ok(Error::DigestMismatch)));
+
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &1, &800);
+
+  let (topics, data) = fixture.last_event(symbol_short!("reprice"));
+  assert_eq!(topics, fixture.escrow_topics("reprice", escrow_id));
+  assert_eq!(<(u32, i128, i128)>::from_val(&fixture.env, &data), (1, 600, 800));
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.milestones.get(1).unwrap().amount), (1200, 800));
+  assert_eq!(fixture.contract.get_reprice(&escrow_id, &1), None);
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &1, &fixture.hash(2)), Err(Ok(Error::NotFullyFunded)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &200, &None);
+  fixture.release_all(escrow_id);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount), (EscrowState::Completed, 1200));
+}
+
+#[test]
+fn lowering_a_milestone_returns_the_freed_deposits() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.propose_reprice(&fixture.client, &escrow_id, &0, &300);
+
+  fixture.contract.accept_reprice(&fixture.freelancer, &escrow_id, &0, &300);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.refunded_amount), (900, 100));
+  let commitments = fixture.contract.get_commitments(&fixture.client, &fixture.asset);
+  assert_eq!((commitments.unfunded, commitments.held), (0, 900));
+  let refund = fixture.contract.export_ledger(&escrow_id, &0, &10).items.last().unwrap();
+  assert_eq!((refund.kind, refund.party, refund.gross), (LedgerKind::Refund, fixture.client.clone(), 100));
+  fixture.release_all(escrow_id);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount, escrow.refunded_amount), (EscrowState::Completed, 900, 100));
+}
+
+#[test]
+fn only_pending_milestones_can_be_repriced() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.propose_reprice(&fixture.client, &escrow_id, &0, &300);
+  fixture.submit(escrow_id, 0);
+
+  assert_eq!(fixture.contract.try_accept_reprice(&fixture.freelancer, &escrow_id, &0, &300), Err(Ok(Error::WorkUnderReview)));
+  assert_eq!(fixture.contract.try_propose_reprice(&fixture.freelancer, &escrow_id, &0, &500), Err(Ok(Error::WorkUnderReview)));
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_propose_reprice(&fixture.freelancer, &escrow_id, &0, &500), Err(Ok(Error::AlreadyReleased)));
+  assert_eq!(fixture.contract.try_propose_reprice(&fixture.freelancer, &escrow_id, &1, &0), Err(Ok(Error::InvalidAmount)));
+  let stranger = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_propose_reprice(&stranger, &escrow_id, &1, &500), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn repricing_changes_the_terms_digest() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &900, &None);
+  let digest = fixture.digest(escrow_id);
+
+  fixture.contract.propose_reprice(&fixture.client, &escrow_id, &1, &500);
+  fixture.contract.accept_reprice(&fixture.freelancer, &escrow_id, &1, &500);
+
+  // The lower total is covered, so the escrow is under way
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::InProgress);
+  assert_eq!(fixture.contract.try_accept_engagement(&fixture.freelancer, &escrow_id, &digest), Err(Ok(Error::DigestMismatch)));
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+}
+
+// Milestone order
+#[test]
+fn sequential_mode_holds_each_milestone_for_the_one_before() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let digest = fixture.digest(escrow_id);
+  assert_eq!(fixture.contract.try_set_sequential(&fixture.freelancer, &escrow_id, &true), Err(Ok(Error::Unauthorized)));
+  fixture.contract.set_sequential(&fixture.client, &escrow_id, &true);
+  // The order is part of the terms
+  assert_ne!(fixture.digest(escrow_id), digest);
+  fixture.contract.set_kickoff(&fixture.client, &escrow_id, &1, &2000);
+  testutils::fund_and_accept(&fixture.market, escrow_id);
+  assert_eq!(fixture.contract.try_set_sequential(&fixture.client, &escrow_id, &false), Err(Ok(Error::AlreadyAccepted)));
+
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &1, &fixture.hash(2)), Err(Ok(Error::TooEarly)));
+  assert_eq!(fixture.contract.try_start_milestone(&fixture.client, &escrow_id, &1), Err(Ok(Error::TooEarly)));
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &1, &fixture.hash(2)), Err(Ok(Error::TooEarly)));
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  assert_eq!(fixture.contract.start_milestone(&fixture.client, &escrow_id, &1), 120);
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+}
+
+#[test]
+fn an_added_milestone_waits_for_its_top_up_and_its_dependencies() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let extra = fixture.milestone("Launch", 300);
+
+  assert_eq!(fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &extra, &vec![&fixture.env, 0]), 2);
+
+  let (topics, data) = fixture.last_event(symbol_short!("ms_added"));
+  assert_eq!(topics, fixture.escrow_topics("ms_added", escrow_id));
+  assert_eq!(<(u32, i128)>::from_val(&fixture.env, &data), (2, 300));
+  assert_eq!(fixture.escrow(escrow_id).total_amount, 1300);
+  assert_eq!(fixture.contract.get_milestone_order(&escrow_id).depends_on.get(2), Some(vec![&fixture.env, 0]));
+  // Nothing more is handed in until the client covers the new total
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &1, &fixture.hash(2)), Err(Ok(Error::NotFullyFunded)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &300, &None);
+  fixture.submit(escrow_id, 1);
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &2, &fixture.hash(3)), Err(Ok(Error::TooEarly)));
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.submit(escrow_id, 2);
+  fixture.release_all(escrow_id);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount), (EscrowState::Completed, 1300));
+}
+
+#[test]
+fn added_milestones_are_checked_against_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let add = |milestone: &Milestone, depends_on: Vec<u32>| fixture.contract.try_add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, milestone, &depends_on);
+  let extra = fixture.milestone("Launch", 300);
+
+  assert_eq!(add(&fixture.milestone("Launch", 0), vec![&fixture.env]), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(add(&extra, vec![&fixture.env, 2]), Err(Ok(Error::InvalidMilestoneIndex)));
+  assert_eq!(add(&Milestone { deadline: PROJECT_DEADLINE + 1, ..extra.clone() }, vec![&fixture.env]), Err(Ok(Error::InvalidSchedule)));
+  let stranger = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_add_milestone(&fixture.client, &stranger, &escrow_id, &extra, &vec![&fixture.env]), Err(Ok(Error::Unauthorized)));
+  let lump_sum_id = fixture.lump_sum_escrow();
+  assert_eq!(fixture.contract.try_add_milestone(&fixture.client, &fixture.freelancer, &lump_sum_id, &extra, &vec![&fixture.env]), Err(Ok(Error::ModeMismatch)));
+  // A voided milestone frees what depends on it, and can't be depended on afterwards
+  assert_eq!(add(&extra, vec![&fixture.env, 1]), Ok(Ok(2)));
+  fixture.contract.void_milestone(&fixture.freelancer, &escrow_id, &1);
+  assert_eq!(add(&extra, vec![&fixture.env, 1]), Err(Ok(Error::InvalidMilestoneIndex)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &300, &None);
+  fixture.submit(escrow_id, 2);
+}
+
+#[test]
+fn an_added_milestone_can_be_repriced_like_the_others() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &fixture.milestone("Launch", 300), &vec![&fixture.env]);
+
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &2, &500);
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &2, &500);
+
+  assert_eq!(fixture.escrow(escrow_id).total_amount, 1500);
+  fixture.release_all(escrow_id);
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released_amount), (EscrowState::Completed, 1500));
+}
+
+#[test]
+fn a_bundle_hands_in_everything_left_regardless_of_order() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.set_sequential(&fixture.client, &escrow_id, &true);
+  testutils::fund_and_accept(&fixture.market, escrow_id);
+
+  fixture.set_time(PROJECT_DEADLINE + 30 * DAY);
+  fixture.contract.submit_bundle(&fixture.freelancer, &escrow_id, &vec![&fixture.env, fixture.hash(1), fixture.hash(2)]);
+
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Submitted);
+}
+
+// Shortfall
+// The underfund amounts the last call published
+fn underfunds(fixture: &Fixture) -> std::vec::Vec<i128> {
+  let name: Val = symbol_short!("underfund").into_val(&fixture.env);
+  fixture.env.events().all().iter()
+    .filter(|(_, topics, _)| topics.get(0).is_some_and(|topic| topic.shallow_eq(&name)))
+    .map(|(_, _, data)| i128::from_val(&fixture.env, &data))
+    .collect()
+}
+
+#[test]
+fn a_charge_added_to_a_running_escrow_raises_one_underfund_event() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  assert_eq!(fixture.contract.get_shortfall(&escrow_id), 0);
+
+  fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &fixture.milestone("Launch", 300), &vec![&fixture.env]);
+
+  assert_eq!(underfunds(&fixture), [300]);
+  let (topics, _) = fixture.last_event(symbol_short!("underfund"));
+  assert_eq!(topics, fixture.escrow_topics("underfund", escrow_id));
+  assert_eq!(fixture.contract.get_shortfall(&escrow_id), 300);
+  assert_eq!(fixture.contract.get_progress(&escrow_id).shortfall, 300);
+  let items = fixture.contract.get_action_items(&escrow_id, &fixture.client);
+  assert_eq!(items.get(0).unwrap().kind, ActionKind::CoverShortfall);
+  // The added milestone can't be worked on until the gap is covered
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &2, &fixture.hash(3)), Err(Ok(Error::NotFullyFunded)));
+}
+
+#[test]
+fn the_underfund_event_fires_only_when_the_gap_grows() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &fixture.milestone("Launch", 300), &vec![&fixture.env]);
+
+  // A partial top-up shrinks the gap quietly
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+  assert!(underfunds(&fixture).is_empty());
+  assert_eq!(fixture.contract.get_shortfall(&escrow_id), 200);
+
+  // A reprice that adds to the pending milestone grows it again
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &2, &500);
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &2, &500);
+  assert_eq!(underfunds(&fixture), [400]);
+
+  // A cut that leaves it short but smaller is no news either
+  fixture.contract.propose_reprice(&fixture.client, &escrow_id, &2, &450);
+  fixture.contract.accept_reprice(&fixture.freelancer, &escrow_id, &2, &450);
+  assert!(underfunds(&fixture).is_empty());
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &350, &None);
+  assert_eq!(fixture.contract.get_shortfall(&escrow_id), 0);
+  let items = fixture.contract.get_action_items(&escrow_id, &fixture.client);
+  assert!(items.iter().all(|item| item.kind != ActionKind::CoverShortfall));
+}
+
+#[test]
+fn an_escrow_still_being_funded_raises_no_underfund_event() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &400, &None);
+
+  fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &fixture.milestone("Launch", 300), &vec![&fixture.env]);
+
+  assert!(underfunds(&fixture).is_empty());
+  assert_eq!(fixture.contract.get_shortfall(&escrow_id), BUDGET + 300 - 400);
+  let items = fixture.contract.get_action_items(&escrow_id, &fixture.client);
+  assert_eq!(items.get(0).unwrap().kind, ActionKind::Deposit);
+}
+
+// Release guard
+// An accepted, funded escrow capped at `max`, with milestone 1 (600) submitted
+fn guarded_escrow(fixture: &Fixture, max: i128) -> u64 {
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  fixture.contract.set_max_single_release(&fixture.client, &escrow_id, &max);
+  fixture.submit(escrow_id, 1);
+  escrow_id
+}
+
+#[test]
+fn an_approval_over_the_cap_waits_for_confirmation() {
+  let fixture = Fixture::new();
+  let escrow_id = guarded_escrow(&fixture, 500);
+
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+
+  let (topics, data) = fixture.last_event(symbol_short!("held"));
+  assert_eq!(topics, fixture.escrow_topics("held", escrow_id));
+  assert_eq!(<(u32, i128, u64)>::from_val(&fixture.env, &data), (1, 600, RELEASE_CONFIRM_SECS));
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Submitted);
+  assert_eq!(fixture.contract.get_pending_release(&escrow_id, &1), Some(PendingRelease { amount: 600, confirmable_at: RELEASE_CONFIRM_SECS }));
+
+  fixture.set_time(RELEASE_CONFIRM_SECS - 1);
+  assert_eq!(fixture.contract.try_confirm_release(&fixture.client, &escrow_id, &1), Err(Ok(Error::ReleaseTooEarly)));
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &1), Err(Ok(Error::ReleaseTooEarly)));
+  fixture.set_time(RELEASE_CONFIRM_SECS);
+  fixture.contract.confirm_release(&fixture.client, &escrow_id, &1);
+
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 600);
+  assert_eq!(fixture.contract.get_pending_release(&escrow_id, &1), None);
+}
+
+#[test]
+fn an_approval_at_the_cap_pays_at_once() {
+  let fixture = Fixture::new();
+  let escrow_id = guarded_escrow(&fixture, 600);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 600);
+
+  let escrow_id = guarded_escrow(&fixture, 599);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+  // The freelancer's auto-release is not the client's to hold
+  fixture.set_time(7 * DAY);
+  fixture.contract.auto_release(&fixture.freelancer, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 600);
+  assert_eq!(fixture.contract.get_pending_release(&escrow_id, &1), None);
+}
+
+#[test]
+fn a_cancelled_hold_pays_nothing() {
+  let fixture = Fixture::new();
+  let escrow_id = guarded_escrow(&fixture, 500);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.contract.try_cancel_pending_release(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::Unauthorized)));
+
+  fixture.contract.cancel_pending_release(&fixture.client, &escrow_id, &1);
+
+  assert_eq!(fixture.contract.get_pending_release(&escrow_id, &1), None);
+  fixture.set_time(RELEASE_CONFIRM_SECS);
+  assert_eq!(fixture.contract.try_confirm_release(&fixture.client, &escrow_id, &1), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_cancel_pending_release(&fixture.client, &escrow_id, &1), Err(Ok(Error::NotFound)));
+  // Approving again starts a new wait
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.contract.get_pending_release(&escrow_id, &1).unwrap().confirmable_at, 2 * RELEASE_CONFIRM_SECS);
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+}
+
+#[test]
+fn a_dispute_freezes_a_held_release() {
+  let fixture = Fixture::new();
+  let escrow_id = guarded_escrow(&fixture, 500);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+
+  fixture.contract.raise_dispute(&fixture.freelancer, &escrow_id, &1);
+  fixture.set_time(RELEASE_CONFIRM_SECS);
+
+  assert_eq!(fixture.contract.try_confirm_release(&fixture.client, &escrow_id, &1), Err(Ok(Error::MilestoneDisputed)));
+  assert!(fixture.contract.get_pending_release(&escrow_id, &1).is_some());
+  assert_eq!(fixture.escrow(escrow_id).released_amount, 0);
+}
+
+#[test]
+fn setting_the_cap_is_client_activity() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  assert_eq!(fixture.contract.try_set_max_single_release(&fixture.freelancer, &escrow_id, &500), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_set_max_single_release(&fixture.client, &escrow_id, &-1), Err(Ok(Error::InvalidAmount)));
+  fixture.set_time(5_000);
+
+  fixture.contract.set_max_single_release(&fixture.client, &escrow_id, &500);
+
+  assert_eq!(fixture.contract.get_max_single_release(&escrow_id), 500);
+  assert_eq!(fixture.contract.get_progress(&escrow_id).last_activity_at, 5_000);
+  assert_eq!(fixture.escrow(escrow_id).client_active_at, 5_000);
+  fixture.contract.set_max_single_release(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.contract.get_max_single_release(&escrow_id), 0);
+}
+
+// Amendments
+#[test]
+fn an_accepted_amendment_applies_every_change_at_once() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let before = fixture.contract.terms_digest(&escrow_id);
+  let changes = vec![
+    &fixture.env,
+    AmendmentChange::Reprice(0, 500),
+    AmendmentChange::AddMilestone(fixture.milestone("Launch", 300), vec![&fixture.env, 1]),
+    AmendmentChange::MilestoneDeadline(1, PROJECT_DEADLINE - 1_000),
+  ];
+  let amendment_id = fixture.contract.propose_amendment(&fixture.freelancer, &escrow_id, &changes);
+  // Nothing moves until the client accepts
+  assert_eq!(fixture.escrow(escrow_id).total_amount, BUDGET);
+
+  let digest = fixture.contract.accept_amendment(&fixture.client, &escrow_id, &amendment_id);
+
+  let (topics, data) = fixture.last_event(symbol_short!("amended"));
+  assert_eq!(topics, fixture.escrow_topics("amended", escrow_id));
+  assert_eq!(<(u32, BytesN<32>)>::from_val(&fixture.env, &data), (amendment_id, digest.clone()));
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.total_amount, BUDGET + 100 + 300);
+  assert_eq!(escrow.milestones.get(0).unwrap().amount, 500);
+  assert_eq!(escrow.milestones.get(1).unwrap().deadline, PROJECT_DEADLINE - 1_000);
+  assert_eq!(fixture.status(escrow_id, 2), MilestoneStatus::Pending);
+  assert_ne!(digest, before);
+  assert_eq!(fixture.contract.terms_digest(&escrow_id), digest);
+  let amendment = fixture.contract.get_amendments(&escrow_id, &0, &MAX_LIST_LIMIT).items.get(amendment_id).unwrap();
+  assert_eq!((amendment.status, amendment.changes, amendment.terms_digest), (AmendmentStatus::Accepted, changes, Some(digest)));
+  assert_eq!(fixture.contract.try_accept_amendment(&fixture.client, &escrow_id, &amendment_id), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn one_failing_change_rolls_back_the_whole_amendment() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let before = fixture.contract.terms_digest(&escrow_id);
+  let changes = vec![
+    &fixture.env,
+    AmendmentChange::Reprice(0, 300),
+    AmendmentChange::ProjectDeadline(PROJECT_DEADLINE + 30 * DAY),
+    // Past the project deadline it just moved, so still invalid
+    AmendmentChange::MilestoneDeadline(0, PROJECT_DEADLINE + 31 * DAY),
+  ];
+  let amendment_id = fixture.contract.propose_amendment(&fixture.client, &escrow_id, &changes);
+
+  assert_eq!(fixture.contract.try_accept_amendment(&fixture.freelancer, &escrow_id, &amendment_id), Err(Ok(Error::InvalidSchedule)));
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.refunded_amount), (BUDGET, 0));
+  assert_eq!(escrow.milestones.get(0).unwrap().amount, 400);
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).deadline, PROJECT_DEADLINE);
+  assert_eq!(fixture.contract.terms_digest(&escrow_id), before);
+  assert_eq!(fixture.contract.get_amendments(&escrow_id, &0, &MAX_LIST_LIMIT).items.get(amendment_id).unwrap().status, AmendmentStatus::Proposed);
+}
+
+#[test]
+fn a_rejected_amendment_leaves_only_its_record() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let digest = fixture.contract.terms_digest(&escrow_id);
+  let amendment_id = fixture.contract.propose_amendment(&fixture.freelancer, &escrow_id, &vec![&fixture.env, AmendmentChange::VoidMilestone(1)]);
+  fixture.set_time(5_000);
+
+  fixture.contract.reject_amendment(&fixture.client, &escrow_id, &amendment_id);
+
+  assert!(fixture.env.events().all().is_empty());
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.refunded_amount, escrow.client_active_at), (BUDGET, 0, 0));
+  assert_eq!(fixture.contract.terms_digest(&escrow_id), digest);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Pending);
+  let amendment = fixture.contract.get_amendments(&escrow_id, &0, &MAX_LIST_LIMIT).items.get(amendment_id).unwrap();
+  assert_eq!((amendment.status, amendment.decided_at, amendment.terms_digest), (AmendmentStatus::Rejected, 5_000, None));
+  assert_eq!(fixture.contract.try_accept_amendment(&fixture.client, &escrow_id, &amendment_id), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn single_changes_are_recorded_as_accepted_amendments() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &0, &500);
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &0, &500);
+  let (_, data) = fixture.last_event(symbol_short!("amended"));
+  assert_eq!(<(u32, BytesN<32>)>::from_val(&fixture.env, &data), (0, fixture.contract.terms_digest(&escrow_id)));
+  let launch = fixture.milestone("Launch", 300);
+  fixture.contract.add_milestone(&fixture.client, &fixture.freelancer, &escrow_id, &launch, &vec![&fixture.env]);
+  fixture.contract.void_milestone(&fixture.freelancer, &escrow_id, &1);
+
+  let amendments = fixture.contract.get_amendments(&escrow_id, &0, &MAX_LIST_LIMIT).items;
+  let recorded: std::vec::Vec<(u32, Address, Vec<AmendmentChange>, AmendmentStatus)> =
+    amendments.iter().map(|amendment| (amendment.id, amendment.proposer, amendment.changes, amendment.status)).collect();
+  assert_eq!(recorded, [
+    (0, fixture.freelancer.clone(), vec![&fixture.env, AmendmentChange::Reprice(0, 500)], AmendmentStatus::Accepted),
+    (1, fixture.client.clone(), vec![&fixture.env, AmendmentChange::AddMilestone(launch, vec![&fixture.env])], AmendmentStatus::Accepted),
+    (2, fixture.freelancer.clone(), vec![&fixture.env, AmendmentChange::VoidMilestone(1)], AmendmentStatus::Accepted),
+  ]);
+  assert_eq!(amendments.get(2).unwrap().terms_digest, Some(fixture.contract.terms_digest(&escrow_id)));
+}
+
+#[test]
+fn only_the_other_party_decides_an_amendment() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.escrow_builder().funded().accepted().open();
+  let outsider = Address::generate(&fixture.env);
+  let changes = vec![&fixture.env, AmendmentChange::ProjectDeadline(PROJECT_DEADLINE + DAY)];
+  assert_eq!(fixture.contract.try_propose_amendment(&outsider, &escrow_id, &changes), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_propose_amendment(&fixture.client, &escrow_id, &vec![&fixture.env]), Err(Ok(Error::InvalidConfig)));
+  let amendment_id = fixture.contract.propose_amendment(&fixture.client, &escrow_id, &changes);
+
+  assert_eq!(fixture.contract.try_accept_amendment(&fixture.client, &escrow_id, &amendment_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_reject_amendment(&fixture.client, &escrow_id, &amendment_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_accept_amendment(&outsider, &escrow_id, &amendment_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.accept_amendment(&fixture.freelancer, &escrow_id, &amendment_id);
+  assert_eq!(fixture.contract.get_project(&fixture.escrow(escrow_id).project_id).deadline, PROJECT_DEADLINE + DAY);
+}
+
+// Deposit increments
+// A token that only reports its decimals, for assets coarser than Stellar's 7
+#[contract]
+struct CoarseToken;
+
+#[contractimpl]
+impl CoarseToken {
+  pub fn __constructor(env: Env, decimals: u32) {
+    env.storage().instance().set(&symbol_short!("decimals"), &decimals);
+  }
+
+  pub fn decimals(env: Env) -> u32 {
+    env.storage().instance().get(&symbol_short!("decimals")).unwrap()
+  }
+}
+
+#[test]
+fn a_deposit_under_the_increment_is_refused() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  assert_eq!(fixture.contract.get_min_deposit_increment(&fixture.asset), 1);
+  let increment = |increment: i128| AdminAction::SetMinDepositIncrement(fixture.asset.clone(), Some(increment));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &increment(300)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &increment(0)), Err(Ok(Error::InvalidAmount)));
+  fixture.enact(AdminAction::SetMinDepositIncrement(fixture.asset.clone(), Some(300)));
+
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &299, &None), Err(Ok(Error::InvalidAmount)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &300, &None);
+
+  assert_eq!(fixture.contract.get_deposits(&escrow_id, &0, &MAX_LIST_LIMIT).items.len(), 1);
+  assert_eq!(fixture.escrow(escrow_id).deposited_amount, 300);
+}
+
+#[test]
+fn the_top_up_that_funds_the_escrow_may_be_any_size() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.enact(AdminAction::SetMinDepositIncrement(fixture.asset.clone(), Some(300)));
+  for _ in 0..3 {
+    fixture.contract.deposit_funds(&fixture.client, &escrow_id, &300, &None);
+  }
+  // Short of the remaining 100 is still too small, and so is past it
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &50, &None), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &150, &None), Err(Ok(Error::InvalidAmount)));
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.deposited_amount, escrow.state), (BUDGET, EscrowState::InProgress));
+}
+
+#[test]
+fn the_default_increment_is_one_whole_token() {
+  let fixture = Fixture::new();
+  let whole = fixture.env.register(CoarseToken, (0u32,));
+  let cents = fixture.env.register(CoarseToken, (2u32,));
+
+  fixture.enact(AdminAction::SetMinDepositIncrement(whole.clone(), None));
+  fixture.enact(AdminAction::SetMinDepositIncrement(cents.clone(), None));
+  fixture.enact(AdminAction::SetMinDepositIncrement(fixture.asset.clone(), None));
+
+  assert_eq!(fixture.contract.get_min_deposit_increment(&whole), 1);
+  assert_eq!(fixture.contract.get_min_deposit_increment(&cents), 100);
+  assert_eq!(fixture.contract.get_min_deposit_increment(&fixture.asset), 10_000_000);
+}
+
+#[test]
+fn a_queued_increment_waits_out_the_settings_delay() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::SetMinDepositIncrement(fixture.asset.clone(), Some(300)));
+
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::TooEarly)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+
+  fixture.set_time(fixture.contract.get_admin_action(&action_id).unwrap().execute_after);
+  fixture.contract.execute_admin_action(&action_id);
+  assert_eq!(fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &100, &None), Err(Ok(Error::InvalidAmount)));
+}
+
+// Epoch digests
+// The rollover counts the last call published
+fn rollovers(fixture: &Fixture) -> std::vec::Vec<(u64, u32, u32, u32, u32)> {
+  let name: Val = symbol_short!("rollover").into_val(&fixture.env);
+  fixture.env.events().all().iter()
+    .filter(|(_, topics, _)| topics.get(0).is_some_and(|topic| topic.shallow_eq(&name)))
+    .map(|(_, _, data)| <(u64, u32, u32, u32, u32)>::from_val(&fixture.env, &data))
+    .collect()
+}
+
+// An escrow opened, funded in two deposits, accepted, with milestone 0 paid and a progress
+// report on milestone 1, all in epoch 0
+fn busy_escrow(fixture: &Fixture) -> u64 {
+  let escrow_id = fixture.escrow_builder().deposit(400).accepted().open();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &600, &None);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &1, &50);
+  escrow_id
+}
+
+#[test]
+fn activity_adds_up_within_an_epoch() {
+  let fixture = Fixture::new();
+  let escrow_id = busy_escrow(&fixture);
+
+  assert!(rollovers(&fixture).is_empty());
+  // Opening and starting are the state changes
+  let activity = EpochActivity { epoch: 0, deposits: 2, releases: 1, messages: 1, state_changes: 2 };
+  assert_eq!(fixture.contract.get_epoch_activity(&escrow_id), Some(activity));
+}
+
+#[test]
+fn the_first_touch_of_an_epoch_publishes_the_last_one() {
+  let fixture = Fixture::new();
+  let escrow_id = busy_escrow(&fixture);
+  fixture.set_time(EPOCH_SECS + 5);
+
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &1, &80);
+
+  assert_eq!(rollovers(&fixture), [(0, 2, 1, 1, 2)]);
+  let (topics, _) = fixture.last_event(symbol_short!("rollover"));
+  assert_eq!(topics, fixture.escrow_topics("rollover", escrow_id));
+  let activity = EpochActivity { epoch: 1, deposits: 0, releases: 0, messages: 1, state_changes: 0 };
+  assert_eq!(fixture.contract.get_epoch_activity(&escrow_id), Some(activity));
+  // Later touches in the same epoch only count
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert!(rollovers(&fixture).is_empty());
+  assert_eq!(fixture.contract.get_epoch_activity(&escrow_id).unwrap().releases, 1);
+}
+
+#[test]
+fn skipped_epochs_collapse_into_one_rollover_for_the_epoch_counted() {
+  let fixture = Fixture::new();
+  let escrow_id = busy_escrow(&fixture);
+  // Nothing touches the escrow in epochs 1 and 2
+  fixture.set_time(3 * EPOCH_SECS + 5);
+
+  fixture.submit(escrow_id, 1);
+
+  assert_eq!(rollovers(&fixture), [(0, 2, 1, 1, 2)]);
+  assert_eq!(fixture.contract.get_epoch_activity(&escrow_id).unwrap().epoch, 3);
+  // Epoch 3 saw a submission, which touches the escrow but counts nothing, so it has no
+  // rollover of its own
+  fixture.set_time(5 * EPOCH_SECS);
+  fixture.contract.flag_out_of_scope(&fixture.client, &escrow_id, &1, &fixture.text("Not in the brief"));
+  assert!(rollovers(&fixture).is_empty());
+  let activity = EpochActivity { epoch: 5, deposits: 0, releases: 0, messages: 1, state_changes: 0 };
+  assert_eq!(fixture.contract.get_epoch_activity(&escrow_id), Some(activity));
+}
+
+// Acceptance receipts
+#[test]
+fn countersigning_needs_an_approved_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  assert_eq!(fixture.contract.try_countersign_acceptance(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::NotApproved)));
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.contract.try_countersign_acceptance(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::NotApproved)));
+
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_countersign_acceptance(&fixture.client, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+  fixture.contract.countersign_acceptance(&fixture.freelancer, &escrow_id, &0);
+}
+
+#[test]
+fn acceptance_records_are_written_once() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(100);
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.set_time(200);
+
+  let acceptance = fixture.contract.countersign_acceptance(&fixture.freelancer, &escrow_id, &0);
+
+  let expected = AcceptanceRecord {
+    client: fixture.client.clone(),
+    freelancer: fixture.freelancer.clone(),
+    deliverable: Some(fixture.hash(1)),
+    amount: 400,
+    paid: 400,
+    submitted_at: 100,
+    countersigned_at: 200,
+  };
+  assert_eq!(acceptance, expected);
+  fixture.set_time(300);
+  assert_eq!(fixture.contract.try_countersign_acceptance(&fixture.freelancer, &escrow_id, &0), Err(Ok(Error::AlreadyAccepted)));
+  assert_eq!(fixture.contract.get_acceptance(&escrow_id, &0), Some(expected));
+}
+
+#[test]
+fn completion_certificate_includes_the_countersigned_acceptances() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.contract.countersign_acceptance(&fixture.freelancer, &escrow_id, &0);
+  assert_eq!(fixture.contract.try_get_completion_certificate(&escrow_id), Err(Ok(Error::EscrowNotCompleted)));
+
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  let certificate = fixture.contract.get_completion_certificate(&escrow_id);
+
+  assert_eq!((certificate.released, certificate.freelancer), (BUDGET, fixture.freelancer.clone()));
+  assert_eq!(certificate.acceptances.keys(), vec![&fixture.env, 0u32]);
+  assert_eq!(certificate.acceptances.get(0), fixture.contract.get_acceptance(&escrow_id, &0));
+}
+
+// Progress
+#[test]
+fn time_status_before_at_and_after_the_deadline() {
+  let fixture = Fixture::new();
+  fixture.env.ledger().with_mut(|ledger| ledger.sequence_number = 1_000);
+
+  fixture.set_time(PROJECT_DEADLINE - 50);
+  let before = fixture.contract.time_status(&PROJECT_DEADLINE);
+  assert_eq!((before.now, before.remaining_secs, before.overdue_secs, before.overdue), (PROJECT_DEADLINE - 50, 50, 0, false));
+  assert_eq!(before.deadline_ledger, 1_010);
+
+  fixture.set_time(PROJECT_DEADLINE);
+  let at = fixture.contract.time_status(&PROJECT_DEADLINE);
+  assert_eq!((at.remaining_secs, at.overdue_secs, at.overdue, at.deadline_ledger), (0, 0, false, 1_000));
+
+  fixture.set_time(PROJECT_DEADLINE + 25);
+  let after = fixture.contract.time_status(&PROJECT_DEADLINE);
+  assert_eq!((after.remaining_secs, after.overdue_secs, after.overdue, after.deadline_ledger), (0, 25, true, 995));
+
+  let none = fixture.contract.time_status(&0);
+  assert_eq!((none.overdue, none.deadline_ledger), (false, 0));
+}
+
+#[test]
+fn progress_reports_each_milestone_against_the_deadline() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.set_time(PROJECT_DEADLINE + 1);
+
+  let progress = fixture.contract.get_progress(&escrow_id);
+
+  assert_eq!((progress.state, progress.total, progress.deposited, progress.released), (EscrowState::InProgress, BUDGET, BUDGET, 400));
+  assert!(progress.deadline.overdue);
+  let build = progress.milestones.get(1).unwrap();
+  assert_eq!((build.status, build.amount, build.paid), (MilestoneStatus::Pending, 600, 0));
+  // Milestones without their own deadline run against the project's
+  assert_eq!(build.deadline, progress.deadline);
+}
+
+#[test]
+fn action_items_follow_the_review_clock() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let kinds = |user: &Address| {
+    let mut kinds = Vec::new(&fixture.env);
+    for item in fixture.contract.get_action_items(&escrow_id, user).iter() {
+      kinds.push_back((item.kind, item.milestone_index));
+    }
+    kinds
+  };
+  assert_eq!(kinds(&fixture.client), vec![&fixture.env, (ActionKind::Deposit, 0)]);
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.set_time(1_000);
+  fixture.submit(escrow_id, 0);
+  assert_eq!(kinds(&fixture.client), vec![&fixture.env, (ActionKind::Review, 0)]);
+  assert_eq!(kinds(&fixture.freelancer), vec![&fixture.env, (ActionKind::Submit, 1)]);
+  let review = fixture.contract.get_action_items(&escrow_id, &fixture.client).get(0).unwrap();
+  assert_eq!(review.due.remaining_secs, 7 * DAY);
+
+  fixture.set_time(1_000 + 7 * DAY);
+  assert_eq!(kinds(&fixture.client), Vec::new(&fixture.env));
+  assert_eq!(kinds(&fixture.freelancer), vec![&fixture.env, (ActionKind::AutoRelease, 0), (ActionKind::Submit, 1)]);
+  assert_eq!(kinds(&Address::generate(&fixture.env)), Vec::new(&fixture.env));
+}
+
+#[test]
+fn late_funding_pushes_the_milestone_deadlines_back() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &(BUDGET / 2), &None);
+  fixture.set_time(3 * DAY);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &(BUDGET / 2), &None);
+
+  // Past the deadline on paper, but the client held the work up for three days
+  fixture.set_time(PROJECT_DEADLINE + DAY);
+  let build = fixture.contract.get_progress(&escrow_id).milestones.get(1).unwrap();
+
+  assert_eq!(build.client_delay_secs, 3 * DAY);
+  assert_eq!((build.deadline.deadline, build.deadline.overdue), (PROJECT_DEADLINE + 3 * DAY, false));
+  let submit = fixture.contract.get_action_items(&escrow_id, &fixture.freelancer).get(0).unwrap();
+  assert_eq!((submit.kind, submit.due.deadline), (ActionKind::Submit, PROJECT_DEADLINE + 3 * DAY));
+  // The project deadline itself is not moved
+  assert!(fixture.contract.get_progress(&escrow_id).deadline.overdue);
+}
+
+#[test]
+fn only_reviews_past_the_review_period_count_as_client_delay() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.submit(escrow_id, 0);
+  fixture.set_time(DAY);
+  fixture.contract.reject_milestone(&fixture.client, &escrow_id, &0);
+  fixture.submit(escrow_id, 0);
+  fixture.set_time(DAY + 7 * DAY + 500);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  let delays = fixture.contract.get_client_delays(&escrow_id);
+  assert_eq!((delays.get(0), delays.get(1)), (Some(500), None));
+  assert_eq!(fixture.contract.get_progress(&escrow_id).milestones.get(1).unwrap().client_delay_secs, 0);
+}
+
+// The fixture's funded escrow, accepted by the freelancer
+fn tracked_escrow(fixture: &Fixture) -> u64 {
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  escrow_id
+}
+
+#[test]
+fn progress_reports_come_at_most_once_a_day_per_milestone() {
+  let fixture = Fixture::new();
+  let unaccepted = fixture.funded_escrow();
+  assert_eq!(fixture.contract.try_report_progress(&fixture.freelancer, &unaccepted, &0, &10), Err(Ok(Error::NotAcknowledged)));
+  let escrow_id = tracked_escrow(&fixture);
+  assert_eq!(fixture.contract.try_report_progress(&fixture.client, &escrow_id, &0, &10), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_report_progress(&fixture.freelancer, &escrow_id, &0, &101), Err(Ok(Error::InvalidAmount)));
+  assert_eq!(fixture.contract.try_report_progress(&fixture.freelancer, &escrow_id, &2, &10), Err(Ok(Error::InvalidMilestoneIndex)));
+
+  fixture.set_time(1_000);
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &0, &30);
+  fixture.set_time(1_000 + DAY - 1);
+  assert_eq!(fixture.contract.try_report_progress(&fixture.freelancer, &escrow_id, &0, &40), Err(Ok(Error::ReportTooSoon)));
+  assert_eq!(Error::ReportTooSoon as u32, 48);
+  // The bound is per milestone
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &1, &5);
+  fixture.set_time(1_000 + DAY);
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &0, &40);
+
+  let reports = fixture.contract.get_progress_reports(&escrow_id);
+  assert_eq!((reports.get(0).unwrap().percent, reports.get(0).unwrap().reported_at), (40, 1_000 + DAY));
+  assert_eq!(reports.get(1).unwrap().percent, 5);
+  // Only pending work takes reports
+  fixture.submit(escrow_id, 0);
+  fixture.set_time(1_000 + 2 * DAY);
+  assert_eq!(fixture.contract.try_report_progress(&fixture.freelancer, &escrow_id, &0, &100), Err(Ok(Error::WorkUnderReview)));
+}
+
+#[test]
+fn a_silent_week_on_a_pending_milestone_asks_the_client_to_check_in() {
+  let fixture = Fixture::new();
+  let escrow_id = tracked_escrow(&fixture);
+  let check_ins = || {
+    let mut check_ins = Vec::new(&fixture.env);
+    for item in fixture.contract.get_action_items(&escrow_id, &fixture.client).iter().filter(|item| item.kind == ActionKind::CheckIn) {
+      check_ins.push_back((item.milestone_index, item.due.deadline));
+    }
+    check_ins
+  };
+  // A freelancer who neither estimates nor reports is not tracked
+  fixture.set_time(1_000);
+  assert_eq!(check_ins(), Vec::new(&fixture.env));
+
+  fixture.contract.set_effort_estimates(&fixture.freelancer, &escrow_id, &vec![&fixture.env, 10, 30]);
+  fixture.set_time(1_000 + 7 * DAY - 1);
+  assert_eq!(check_ins(), Vec::new(&fixture.env));
+  assert!(!fixture.contract.get_progress(&escrow_id).milestones.get(0).unwrap().stale);
+
+  fixture.set_time(1_000 + 7 * DAY);
+  assert_eq!(check_ins(), vec![&fixture.env, (0, 1_000 + 7 * DAY), (1, 1_000 + 7 * DAY)]);
+  assert!(fixture.contract.get_progress(&escrow_id).milestones.get(1).unwrap().stale);
+  assert!(fixture.contract.get_action_items(&escrow_id, &fixture.freelancer).iter().all(|item| item.kind != ActionKind::CheckIn));
+
+  // A report restarts the week for its milestone; a submission ends it
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &1, &60);
+  fixture.submit(escrow_id, 0);
+  assert_eq!(check_ins(), Vec::new(&fixture.env));
+  fixture.set_time(1_000 + 14 * DAY);
+  assert_eq!(check_ins(), vec![&fixture.env, (1, 1_000 + 14 * DAY)]);
+}
+
+#[test]
+fn progress_burns_down_the_estimates_without_touching_payouts() {
+  let fixture = Fixture::new();
+  let escrow_id = tracked_escrow(&fixture);
+  assert_eq!(fixture.contract.try_set_effort_estimates(&fixture.freelancer, &escrow_id, &vec![&fixture.env, 10]), Err(Ok(Error::InvalidMilestoneIndex)));
+  assert_eq!(fixture.contract.try_set_effort_estimates(&fixture.freelancer, &escrow_id, &vec![&fixture.env, 10, 10_001]), Err(Ok(Error::InvalidAmount)));
+  fixture.contract.set_effort_estimates(&fixture.freelancer, &escrow_id, &vec![&fixture.env, 10, 30]);
+  assert_eq!(fixture.contract.try_set_effort_estimates(&fixture.freelancer, &escrow_id, &vec![&fixture.env, 20, 30]), Err(Ok(Error::AlreadyAccepted)));
+
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &0, &50);
+  fixture.contract.report_progress(&fixture.freelancer, &escrow_id, &1, &20);
+  let progress = fixture.contract.get_progress(&escrow_id);
+  // 10h at 50% and 30h at 20%; Design is 400 of the 1000, Build 600
+  assert_eq!((progress.estimated_hours, progress.remaining_hours, progress.completion_bps), (40, 5 + 24, 3_200));
+  let design = progress.milestones.get(0).unwrap();
+  assert_eq!((design.estimated_hours, design.percent_done, design.reported_at), (10, 50, 0));
+
+  // Submitted work counts as done, whatever was last reported, and pays in full
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  let progress = fixture.contract.get_progress(&escrow_id);
+  assert_eq!((progress.remaining_hours, progress.completion_bps, progress.released), (24, 5_200, 400));
+}
+
+// Review metrics
+#[test]
+fn review_times_average_over_completed_escrows() {
+  let fixture = Fixture::new();
+  let first = fixture.funded_escrow();
+  let second = fixture.funded_escrow();
+
+  // First escrow: reviewed after one and three days
+  fixture.set_time(DAY);
+  fixture.submit(first, 0);
+  fixture.submit(first, 1);
+  fixture.set_time(2 * DAY);
+  fixture.contract.release_funds(&fixture.client, &first, &0);
+  fixture.set_time(4 * DAY);
+  fixture.contract.release_funds(&fixture.client, &first, &1);
+
+  // Second escrow: a two-day rejection, then an auto-release after the review period
+  fixture.submit(second, 0);
+  fixture.set_time(6 * DAY);
+  fixture.contract.reject_milestone(&fixture.client, &second, &0);
+  fixture.submit(second, 0);
+  fixture.contract.release_funds(&fixture.client, &second, &0);
+  fixture.submit(second, 1);
+
+  // Only completed escrows count
+  let risk = fixture.contract.get_counterparty_risk(&fixture.client);
+  assert_eq!((risk.completed_escrows, risk.reviews, risk.average_review_secs, risk.worst_review_secs), (1, 2, 2 * DAY, 3 * DAY));
+
+  fixture.set_time(13 * DAY);
+  fixture.contract.auto_release(&fixture.freelancer, &second, &1);
+
+  // Reviews of 1, 3, 2, 0 and 7 days; the auto-release is the worst mark
+  let risk = fixture.contract.get_counterparty_risk(&fixture.client);
+  assert_eq!(risk.completed_escrows, 2);
+  assert_eq!(risk.reviews, 5);
+  assert_eq!(risk.average_review_secs, 13 * DAY / 5);
+  assert_eq!(risk.worst_review_secs, 7 * DAY);
+  assert_eq!(risk.auto_releases, 1);
+  assert_eq!(fixture.contract.get_user_stats(&fixture.client).completed_as_client, 2);
+  assert_eq!(fixture.contract.get_counterparty_risk(&fixture.freelancer).reviews, 0);
+}
+
+// Lump sum
+#[test]
+fn lump_sum_escrow_runs_its_full_lifecycle() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.lump_sum_escrow();
+  assert!(fixture.escrow(escrow_id).lump_sum);
+
+  // The full amount is funded before any work is submitted
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &(BUDGET - 1), &None);
+  assert_eq!(fixture.contract.try_submit_deliverable(&fixture.freelancer, &escrow_id, &fixture.hash(1)), Err(Ok(Error::NotFullyFunded)));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &1, &None);
+
+  fixture.contract.submit_deliverable(&fixture.freelancer, &escrow_id, &fixture.hash(1));
+  assert_eq!(fixture.contract.get_deliverable(&escrow_id), Some(fixture.hash(1)));
+  fixture.contract.approve_deliverable(&fixture.client, &escrow_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.released_amount, BUDGET);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!(fixture.contract.try_approve_deliverable(&fixture.client, &escrow_id), Err(Ok(Error::EscrowNotActive)));
+}
+
+#[test]
+fn lump_sum_deliverable_auto_releases_after_the_review_period() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.lump_sum_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.set_time(1_000);
+  fixture.contract.submit_deliverable(&fixture.freelancer, &escrow_id, &fixture.hash(1));
+
+  assert_eq!(fixture.contract.try_auto_release_deliverable(&fixture.freelancer, &escrow_id), Err(Ok(Error::TooEarly)));
+  fixture.set_time(1_000 + 7 * DAY);
+  fixture.contract.auto_release_deliverable(&fixture.freelancer, &escrow_id);
+
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+}
+
+#[test]
+fn milestone_calls_on_a_lump_sum_escrow_are_mode_mismatches() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.lump_sum_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  let mismatch = Err(Ok(Error::ModeMismatch));
+
+  assert_eq!(fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &0, &fixture.hash(1)), mismatch);
+  assert_eq!(fixture.contract.try_release_funds(&fixture.client, &escrow_id, &0), mismatch);
+  assert_eq!(fixture.contract.try_auto_release(&fixture.freelancer, &escrow_id, &0), mismatch);
+  assert_eq!(fixture.contract.try_release_bundle(&fixture.freelancer, &escrow_id), mismatch);
+  assert!(fixture.contract.try_submit_bundle(&fixture.freelancer, &escrow_id, &vec![&fixture.env, fixture.hash(1)]).err() == Some(Ok(Error::ModeMismatch)));
+  // The implicit milestone never shows up
+  assert!(fixture.contract.get_milestone_records(&escrow_id).is_empty());
+}
+
+#[test]
+fn lump_sum_calls_on_a_milestone_escrow_are_mode_mismatches() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let mismatch = Err(Ok(Error::ModeMismatch));
+
+  assert_eq!(fixture.contract.try_submit_deliverable(&fixture.freelancer, &escrow_id, &fixture.hash(1)), mismatch);
+  assert_eq!(fixture.contract.try_approve_deliverable(&fixture.client, &escrow_id), mismatch);
+  assert_eq!(fixture.contract.try_auto_release_deliverable(&fixture.freelancer, &escrow_id), mismatch);
+  assert!(fixture.contract.try_get_deliverable(&escrow_id).err() == Some(Ok(Error::ModeMismatch)));
+}
+
+// Services
+// Publishes "Logo, 3 days" from the fixture's freelancer at `price`
+fn logo_service(fixture: &Fixture, price: i128) -> u64 {
+  fixture.contract.create_service(&fixture.freelancer, &fixture.text("Logo"), &fixture.text("A logo"), &fixture.text("design"), &price, &fixture.asset, &3)
+}
+
+#[test]
+fn buying_a_service_opens_a_funded_lump_sum_escrow() {
+  let fixture = Fixture::new();
+  let listing_id = logo_service(&fixture, 100);
+  fixture.set_time(1_000);
+  let escrow_id = fixture.contract.purchase_service(&fixture.client, &listing_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert!(escrow.lump_sum);
+  assert_eq!((escrow.client, escrow.freelancer.clone()), (fixture.client.clone(), fixture.freelancer.clone()));
+  assert_eq!((escrow.total_amount, escrow.deposited_amount, escrow.asset), (100, 100, fixture.asset.clone()));
+  assert_eq!(escrow.state, EscrowState::InProgress);
+  assert_eq!(fixture.contract.get_terms_source(&escrow_id), TermsSource::Service(listing_id));
+  let project = fixture.contract.get_project(&escrow.project_id);
+  assert_eq!((project.title, project.deadline), (fixture.text("Logo"), 1_000 + 3 * DAY));
+  assert_eq!(project.status, ProjectStatus::InProgress);
+
+  // From here it is an ordinary lump-sum escrow
+  fixture.contract.submit_deliverable(&fixture.freelancer, &escrow_id, &fixture.hash(1));
+  fixture.contract.approve_deliverable(&fixture.client, &escrow_id);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+  fixture.contract.rate_freelancer(&fixture.client, &escrow_id, &5, &fixture.text("Great"), &Vec::new(&fixture.env));
+  assert_eq!(fixture.contract.get_rating(&escrow_id).rating, 5);
+
+  // A listing can be bought again, and not by the freelancer who published it
+  assert_ne!(fixture.contract.purchase_service(&fixture.client, &listing_id), escrow_id);
+  assert_eq!(fixture.contract.try_purchase_service(&fixture.freelancer, &listing_id), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn a_deactivated_service_can_not_be_bought() {
+  let fixture = Fixture::new();
+  let listing_id = logo_service(&fixture, 100);
+  let other_id = logo_service(&fixture, 200);
+  assert_eq!(fixture.contract.list_services(&fixture.text("design"), &0, &10).items, vec![&fixture.env, listing_id, other_id]);
+
+  // Only the freelancer who published it takes it down
+  assert_eq!(fixture.contract.try_deactivate_service(&fixture.client, &listing_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.deactivate_service(&fixture.freelancer, &listing_id);
+
+  assert!(!fixture.contract.get_service(&listing_id).active);
+  assert_eq!(fixture.contract.try_purchase_service(&fixture.client, &listing_id), Err(Ok(Error::ProjectNotOpen)));
+  assert_eq!(fixture.contract.list_services(&fixture.text("design"), &0, &10).items, vec![&fixture.env, other_id]);
+  assert!(fixture.contract.check_index_integrity(&IndexKind::CategoryServices(fixture.text("design")), &0).dangling.is_empty());
+}
+
+#[test]
+fn repricing_a_service_leaves_earlier_purchases_alone() {
+  let fixture = Fixture::new();
+  let listing_id = logo_service(&fixture, 100);
+  let escrow_id = fixture.contract.purchase_service(&fixture.client, &listing_id);
+
+  assert_eq!(fixture.contract.try_update_service(&fixture.client, &listing_id, &fixture.text("Logo"), &fixture.text("A logo"), &150, &5), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_update_service(&fixture.freelancer, &listing_id, &fixture.text("Logo"), &fixture.text("A logo"), &0, &5), Err(Ok(Error::InvalidAmount)));
+  fixture.contract.update_service(&fixture.freelancer, &listing_id, &fixture.text("Logo"), &fixture.text("A logo"), &150, &5);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.total_amount, escrow.deposited_amount), (100, 100));
+  assert_eq!(fixture.contract.get_project(&escrow.project_id).deadline, 3 * DAY);
+
+  let repriced = fixture.escrow(fixture.contract.purchase_service(&fixture.client, &listing_id));
+  assert_eq!((repriced.total_amount, repriced.deposited_amount), (150, 150));
+  assert_eq!(fixture.contract.get_project(&repriced.project_id).deadline, 5 * DAY);
+}
+
+// Subcontracting
+// Has the client consent to subcontracting the Build milestone (600) and opens a lump-sum
+// child escrow for 300 with `subcontractor`
+fn subcontracted_build(fixture: &Fixture, escrow_id: u64, subcontractor: &Address) -> u64 {
+  fixture.contract.consent_subcontract(&fixture.client, &escrow_id, &1);
+  fixture.contract.open_subcontract(&fixture.freelancer, &escrow_id, &1, subcontractor, &300, &Vec::new(&fixture.env))
+}
+
+#[test]
+fn subcontracting_needs_the_clients_consent_per_milestone() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let subcontractor = Address::generate(&fixture.env);
+  let none = Vec::new(&fixture.env);
+
+  assert_eq!(fixture.contract.try_open_subcontract(&fixture.freelancer, &escrow_id, &1, &subcontractor, &300, &none), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_consent_subcontract(&fixture.freelancer, &escrow_id, &1), Err(Ok(Error::Unauthorized)));
+  fixture.contract.consent_subcontract(&fixture.client, &escrow_id, &1);
+  assert!(fixture.contract.has_subcontract_consent(&escrow_id, &1));
+  assert!(!fixture.contract.has_subcontract_consent(&escrow_id, &0));
+
+  // Consent on Build says nothing about Design, and only the freelancer subcontracts
+  assert_eq!(fixture.contract.try_open_subcontract(&fixture.freelancer, &escrow_id, &0, &subcontractor, &100, &none), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_open_subcontract(&fixture.client, &escrow_id, &1, &subcontractor, &300, &none), Err(Ok(Error::Unauthorized)));
+
+  let child_id = fixture.contract.open_subcontract(&fixture.freelancer, &escrow_id, &1, &subcontractor, &300, &none);
+  let child = fixture.escrow(child_id);
+  assert_eq!((child.client, child.freelancer), (fixture.freelancer.clone(), subcontractor.clone()));
+  assert_eq!((child.total_amount, child.deposited_amount, child.state), (300, 0, EscrowState::Created));
+  assert_eq!(fixture.contract.get_subcontract(&child_id).map(|link| (link.parent_id, link.milestone_index, link.credited)), Some((escrow_id, 1, 0)));
+  assert_eq!(fixture.contract.list_subcontracts(&escrow_id, &1, &0, &MAX_LIST_LIMIT).items, vec![&fixture.env, child_id]);
+
+  // Children share the milestone: 300 of its 600 are left
+  assert_eq!(fixture.contract.try_open_subcontract(&fixture.freelancer, &escrow_id, &1, &subcontractor, &301, &none), Err(Ok(Error::InvalidAmount)));
+  fixture.contract.open_subcontract(&fixture.freelancer, &escrow_id, &1, &Address::generate(&fixture.env), &300, &none);
+}
+
+#[test]
+fn parent_release_funds_the_subcontract() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let subcontractor = Address::generate(&fixture.env);
+  let child_id = subcontracted_build(&fixture, escrow_id, &subcontractor);
+  fixture.contract.accept_engagement(&subcontractor, &child_id, &fixture.digest(child_id));
+
+  // Nothing to work against until the parent milestone pays out
+  assert_eq!(fixture.contract.try_submit_deliverable(&subcontractor, &child_id, &fixture.hash(9)), Err(Ok(Error::NotFullyFunded)));
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+
+  let child = fixture.escrow(child_id);
+  assert_eq!((child.deposited_amount, child.state), (300, EscrowState::InProgress));
+  assert_eq!(fixture.contract.get_deposits(&child_id, &0, &MAX_LIST_LIMIT).items.get(0).unwrap().depositor, fixture.freelancer);
+  assert_eq!(fixture.contract.get_subcontract(&child_id).unwrap().credited, 300);
+
+  // The payout moved on to the subcontractor, so the approval stays
+  assert_eq!(fixture.contract.try_revoke_approval(&fixture.client, &escrow_id, &1), Err(Ok(Error::RevokeWindowClosed)));
+
+  // The freelancer reviews the subcontractor like any client
+  fixture.contract.submit_deliverable(&subcontractor, &child_id, &fixture.hash(9));
+  fixture.contract.approve_deliverable(&fixture.freelancer, &child_id);
+  assert_eq!(fixture.escrow(child_id).state, EscrowState::Completed);
+}
+
+#[test]
+fn parent_dispute_freezes_subcontract_payouts() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let subcontractor = Address::generate(&fixture.env);
+  let child_id = subcontracted_build(&fixture, escrow_id, &subcontractor);
+  // The freelancer funds the child themselves, ahead of the parent payout
+  fixture.contract.deposit_funds(&fixture.freelancer, &child_id, &300, &None);
+  fixture.contract.submit_deliverable(&subcontractor, &child_id, &fixture.hash(9));
+
+  fixture.contract.raise_dispute(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.contract.try_approve_deliverable(&fixture.freelancer, &child_id), Err(Ok(Error::MilestoneDisputed)));
+  fixture.set_time(fixture.contract.get_config().review_period_secs);
+  assert_eq!(fixture.contract.try_auto_release_deliverable(&subcontractor, &child_id), Err(Ok(Error::MilestoneDisputed)));
+
+  // Settling the parent dispute lifts the freeze
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &1, &600);
+  fixture.contract.approve_deliverable(&fixture.freelancer, &child_id);
+  assert_eq!(fixture.escrow(child_id).released_amount, 300);
+}
+
+#[test]
+fn partly_settled_parent_dispute_funds_the_subcontract_in_proportion() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let subcontractor = Address::generate(&fixture.env);
+  let child_id = subcontracted_build(&fixture, escrow_id, &subcontractor);
+
+  fixture.contract.raise_dispute(&fixture.client, &escrow_id, &1);
+  fixture.contract.resolve_dispute(&fixture.admin, &escrow_id, &1, &200);
+
+  // A third of the milestone went to the freelancer, so a third of the child is funded
+  let child = fixture.escrow(child_id);
+  assert_eq!((child.deposited_amount, child.state), (100, EscrowState::Created));
+  assert_eq!(fixture.contract.get_subcontract(&child_id).unwrap().credited, 100);
+  fixture.contract.deposit_funds(&fixture.freelancer, &child_id, &200, &None);
+  assert_eq!(fixture.escrow(child_id).state, EscrowState::InProgress);
+}
+
+// Bundle release
+#[test]
+fn bundle_is_released_when_its_clock_runs_out() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let deliverables = vec![&fixture.env, fixture.hash(1), fixture.hash(2)];
+
+  fixture.set_time(PROJECT_DEADLINE + 30 * DAY - 1);
+  assert_eq!(fixture.contract.try_submit_bundle(&fixture.freelancer, &escrow_id, &deliverables), Err(Ok(Error::TooEarly)));
+
+  fixture.set_time(PROJECT_DEADLINE + 30 * DAY);
+  let short = vec![&fixture.env, fixture.hash(1)];
+  assert_eq!(fixture.contract.try_submit_bundle(&fixture.freelancer, &escrow_id, &short), Err(Ok(Error::DeliverableMismatch)));
+  let bundle = fixture.contract.submit_bundle(&fixture.freelancer, &escrow_id, &deliverables);
+  assert_eq!(bundle.release_at, PROJECT_DEADLINE + 37 * DAY);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Submitted);
+  assert_eq!(fixture.contract.try_release_bundle(&fixture.freelancer, &escrow_id), Err(Ok(Error::TooEarly)));
+
+  fixture.set_time(bundle.release_at);
+  fixture.contract.release_bundle(&fixture.freelancer, &escrow_id);
+
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!(escrow.released_amount, BUDGET);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert!(fixture.contract.get_bundle(&escrow_id).is_none());
+}
+
+#[test]
+fn client_returning_mid_clock_converts_the_bundle_to_per_milestone_review() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(PROJECT_DEADLINE + 30 * DAY);
+  let deliverables = vec![&fixture.env, fixture.hash(1), fixture.hash(2)];
+  fixture.contract.submit_bundle(&fixture.freelancer, &escrow_id, &deliverables);
+
+  fixture.set_time(PROJECT_DEADLINE + 31 * DAY);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+
+  // The bundle is gone, but the second milestone stays under review on its own clock
+  assert!(fixture.contract.get_bundle(&escrow_id).is_none());
+  fixture.set_time(PROJECT_DEADLINE + 37 * DAY);
+  assert_eq!(fixture.contract.try_release_bundle(&fixture.freelancer, &escrow_id), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Submitted);
+  fixture.contract.auto_release(&fixture.freelancer, &escrow_id, &1);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+}
+
+#[test]
+fn bundle_needs_a_silent_client() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.set_time(PROJECT_DEADLINE + 1);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+
+  fixture.set_time(PROJECT_DEADLINE + 30 * DAY);
+  let deliverables = vec![&fixture.env, fixture.hash(1), fixture.hash(2)];
+  let result = fixture.contract.try_submit_bundle(&fixture.freelancer, &escrow_id, &deliverables);
+
+  assert_eq!(result, Err(Ok(Error::ClientActive)));
+}
+
+// Registry labels
+#[test]
+fn registry_entry_keeps_a_label_per_language() {
+  let fixture = Fixture::new();
+  let id = fixture.add_category(symbol_short!("en"), "Design");
+
+  fixture.enact(AdminAction::SetLabel(RegistryKind::Category, id, symbol_short!("es"), fixture.text("Diseno")));
+  fixture.enact(AdminAction::SetLabel(RegistryKind::Category, id, symbol_short!("fr"), fixture.text("Conception")));
+
+  let labels = fixture.contract.get_labels(&RegistryKind::Category, &id);
+  assert_eq!(labels.len(), 3);
+  assert_eq!(labels.get(symbol_short!("es")), Some(fixture.text("Diseno")));
+  assert_eq!(labels.get(symbol_short!("en")), Some(fixture.text("Design")));
+  // Skills are a separate registry with their own IDs
+  assert_eq!(fixture.contract.try_get_labels(&RegistryKind::Skill, &id), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn label_languages_are_bounded() {
+  let fixture = Fixture::new();
+  let id = fixture.add_category(symbol_short!("l0"), "Design");
+  let langs = ["l1", "l2", "l3", "l4", "l5", "l6", "l7", "l8", "l9"];
+  for lang in langs {
+    fixture.enact(AdminAction::SetLabel(RegistryKind::Category, id, Symbol::new(&fixture.env, lang), fixture.text("Design")));
+  }
+
+  let result = fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::SetLabel(RegistryKind::Category, id, symbol_short!("extra"), fixture.text("Design")));
+  assert_eq!(result, Err(Ok(Error::LimitReached)));
+
+  // Replacing an existing language does not count against the bound
+  fixture.enact(AdminAction::SetLabel(RegistryKind::Category, id, symbol_short!("l0"), fixture.text("Graphic design")));
+  assert_eq!(fixture.contract.get_labels(&RegistryKind::Category, &id).len(), 10);
+}
+
+#[test]
+fn listing_falls_back_to_the_registration_language() {
+  let fixture = Fixture::new();
+  let design = fixture.add_category(symbol_short!("en"), "Design");
+  let writing = fixture.add_category(symbol_short!("es"), "Redaccion");
+  fixture.enact(AdminAction::SetLabel(RegistryKind::Category, design, symbol_short!("es"), fixture.text("Diseno")));
+
+  let listing = fixture.contract.list_registry(&RegistryKind::Category, &0, &10).items;
+  assert_eq!(listing.len(), 2);
+  assert_eq!(listing.get(0).unwrap().label, fixture.text("Design"));
+  assert_eq!(listing.get(1).unwrap().lang, symbol_short!("es"));
+  assert_eq!(listing.get(1).unwrap().label, fixture.text("Redaccion"));
+
+  // Once the default language is labelled it takes over
+  fixture.enact(AdminAction::SetLabel(RegistryKind::Category, writing, symbol_short!("en"), fixture.text("Writing")));
+  let listing = fixture.contract.list_registry(&RegistryKind::Category, &((writing - 1) as u64), &10).items;
+  assert_eq!(listing.len(), 1);
+  assert_eq!(listing.get(0).unwrap().label, fixture.text("Writing"));
+}
+
+#[test]
+fn only_the_admin_can_label_entries() {
+  let fixture = Fixture::new();
+  let id = fixture.add_category(symbol_short!("en"), "Design");
+
+  let result = fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::SetLabel(RegistryKind::Category, id, symbol_short!("es"), fixture.text("Diseno")));
+
+  assert_eq!(result, Err(Ok(Error::Unauthorized)));
+}
+
+// Category templates
+// Template shares: a description and basis points each, with no deadline
+fn template_shares(fixture: &Fixture, bps: &[(&str, u32)]) -> Vec<MilestoneShare> {
+  let mut shares = Vec::new(&fixture.env);
+  for (description, share) in bps {
+    shares.push_back(MilestoneShare { description: fixture.text(description), bps: *share, deadline: 0 });
+  }
+  shares
+}
+
+fn post_from_template(fixture: &Fixture, category_id: u32, template_id: u32, budget: i128) -> Result<u64, Error> {
+  match fixture.contract.try_post_project_from_template(&fixture.client, &fixture.text("Audit"), &fixture.text("A contract audit"), &category_id, &template_id, &budget, &fixture.asset, &PROJECT_DEADLINE, &Locale::default()) {
+    Ok(project_id) => Ok(project_id.unwrap()),
+    Err(error) => Err(error.unwrap()),
+  }
+}
+
+#[test]
+fn a_template_expands_against_the_budget_under_its_category_label() {
+  let fixture = Fixture::new();
+  let audit = fixture.add_category(symbol_short!("en"), "Audit");
+  let shares = template_shares(&fixture, &[("Scoping", 2_000), ("Review", 3_000), ("Report", 5_000)]);
+  let template_id = fixture.add_template(audit, "Standard audit", &shares);
+
+  let templates = fixture.contract.get_category_templates(&audit, &0, &MAX_LIST_LIMIT).items;
+  assert_eq!(templates, vec![&fixture.env, CategoryTemplate { id: template_id, category_id: audit, name: fixture.text("Standard audit"), shares, version: 1 }]);
+
+  // 20% and 30% of 1001 round down; the report takes the remaining 501
+  let project_id = post_from_template(&fixture, audit, template_id, 1_001).unwrap();
+  let project = fixture.contract.get_project(&project_id);
+  assert_eq!(fixture.milestone_amounts(project_id), vec![&fixture.env, 200, 300, 501]);
+  assert_eq!(project.milestones.get(2).unwrap().description, fixture.text("Report"));
+  assert!(project.milestones.iter().all(|milestone| milestone.deadline == 0));
+  assert_eq!(project.category, fixture.text("Audit"));
+  assert_eq!(fixture.contract.get_project_template(&project_id), Some(TemplateRef { template_id, version: 1 }));
+  // Projects posted with explicit milestones come from no template
+  assert_eq!(fixture.contract.get_project_template(&fixture.post_project()), None);
+}
+
+#[test]
+fn a_template_only_posts_projects_in_its_own_category() {
+  let fixture = Fixture::new();
+  let audit = fixture.add_category(symbol_short!("en"), "Audit");
+  let logo = fixture.add_category(symbol_short!("en"), "Logo");
+  let shares = template_shares(&fixture, &[("Sketches", 5_000), ("Final", 5_000)]);
+  let template_id = fixture.add_template(logo, "Logo", &shares);
+
+  assert_eq!(post_from_template(&fixture, audit, template_id, BUDGET), Err(Error::NotFound));
+  assert_eq!(post_from_template(&fixture, logo, template_id + 1, BUDGET), Err(Error::NotFound));
+  assert!(fixture.contract.get_category_templates(&audit, &0, &MAX_LIST_LIMIT).items.is_empty());
+  assert!(post_from_template(&fixture, logo, template_id, BUDGET).is_ok());
+
+  // Templates hang off existing categories, only the admin adds them, and shares cover the budget
+  let template = |category_id: u32, shares: &Vec<MilestoneShare>| AdminAction::AddCategoryTemplate(category_id, fixture.text("Logo"), shares.clone());
+  let result = fixture.contract.try_queue_admin_action(&fixture.admin, &template(logo + 1, &shares));
+  assert_eq!(result, Err(Ok(Error::NotFound)));
+  let result = fixture.contract.try_queue_admin_action(&fixture.client, &template(logo, &shares));
+  assert_eq!(result, Err(Ok(Error::Unauthorized)));
+  let short = template_shares(&fixture, &[("Sketches", 5_000)]);
+  let result = fixture.contract.try_queue_admin_action(&fixture.admin, &template(logo, &short));
+  assert_eq!(result, Err(Ok(Error::InvalidShares)));
+}
+
+#[test]
+fn editing_a_template_bumps_its_version_and_leaves_posted_projects_alone() {
+  let fixture = Fixture::new();
+  let audit = fixture.add_category(symbol_short!("en"), "Audit");
+  let shares = template_shares(&fixture, &[("Review", 4_000), ("Report", 6_000)]);
+  let template_id = fixture.add_template(audit, "Audit", &shares);
+  let before = post_from_template(&fixture, audit, template_id, BUDGET).unwrap();
+
+  let reshaped = template_shares(&fixture, &[("Scoping", 1_000), ("Review", 4_000), ("Report", 5_000)]);
+  fixture.enact(AdminAction::UpdateCategoryTemplate(template_id, reshaped));
+  let after = post_from_template(&fixture, audit, template_id, BUDGET).unwrap();
+
+  assert_eq!(fixture.milestone_amounts(before), vec![&fixture.env, 400, 600]);
+  assert_eq!(fixture.contract.get_project_template(&before), Some(TemplateRef { template_id, version: 1 }));
+  assert_eq!(fixture.milestone_amounts(after), vec![&fixture.env, 100, 400, 500]);
+  assert_eq!(fixture.contract.get_project_template(&after), Some(TemplateRef { template_id, version: 2 }));
+  assert_eq!(fixture.contract.get_category_templates(&audit, &0, &MAX_LIST_LIMIT).items.get(0).unwrap().version, 2);
+
+  let result = fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::UpdateCategoryTemplate(template_id, shares));
+  assert_eq!(result, Err(Ok(Error::Unauthorized)));
+}
+
+// State indexes
+#[test]
+fn transitions_move_ids_between_state_indexes() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let listed = |state| fixture.contract.list_escrows_by_state(&state, &0, &10).items;
+  assert_eq!(listed(EscrowState::Created), vec![&fixture.env, escrow_id]);
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  assert!(listed(EscrowState::Created).is_empty());
+  assert_eq!(listed(EscrowState::InProgress), vec![&fixture.env, escrow_id]);
+
+  fixture.release_all(escrow_id);
+  assert!(listed(EscrowState::InProgress).is_empty());
+  assert_eq!(listed(EscrowState::Completed), vec![&fixture.env, escrow_id]);
+}
+
+#[test]
+fn state_counts_match_the_indexes() {
+  let fixture = Fixture::new();
+  let created = fixture.open_escrow();
+  let funded = fixture.funded_escrow();
+  let completed = fixture.funded_escrow();
+  fixture.release_all(completed);
+  let refunded = fixture.open_escrow();
+  fixture.contract.refund_funds(&fixture.client, &refunded);
+
+  let counts = fixture.contract.count_escrows_by_state();
+  assert_eq!((counts.created, counts.in_progress, counts.completed, counts.refunded), (1, 1, 1, 1));
+  assert_eq!(fixture.contract.list_escrows_by_state(&EscrowState::Created, &0, &10).items, vec![&fixture.env, created]);
+  assert_eq!(fixture.contract.list_escrows_by_state(&EscrowState::InProgress, &0, &10).items, vec![&fixture.env, funded]);
+  assert_eq!(fixture.contract.list_escrows_by_state(&EscrowState::Completed, &0, &10).items, vec![&fixture.env, completed]);
+  assert_eq!(fixture.contract.list_escrows_by_state(&EscrowState::Refunded, &0, &10).items, vec![&fixture.env, refunded]);
+}
+
+// Stale engagements
+#[test]
+fn submission_takes_an_escrow_out_of_the_stale_list() {
+  let fixture = Fixture::new();
+  fixture.set_time(1_000);
+  let escrow_id = fixture.funded_escrow();
+  fixture.set_time(1_000 + 31 * DAY);
+
+  let stale = fixture.contract.list_stale_escrows(&(30 * DAY), &0, &10);
+  assert_eq!((stale.items, stale.next_cursor), (vec![&fixture.env, escrow_id], None));
+
+  fixture.submit(escrow_id, 0);
+  assert_eq!(fixture.contract.list_stale_escrows(&(30 * DAY), &0, &10).items, Vec::new(&fixture.env));
+  assert_eq!(fixture.contract.get_progress(&escrow_id).last_activity_at, 1_000 + 31 * DAY);
+}
+
+#[test]
+fn stale_listing_resumes_across_bucket_boundaries() {
+  let fixture = Fixture::new();
+  let mut opened = std::vec::Vec::new();
+  for time in [0, DAY, 8 * DAY, 15 * DAY, 40 * DAY] {
+    fixture.set_time(time);
+    opened.push(fixture.open_escrow());
+  }
+  // The escrow refunded in the first bucket leaves it
+  fixture.contract.refund_funds(&fixture.client, &opened[1]);
+  fixture.set_time(46 * DAY);
+
+  let first = fixture.contract.list_stale_escrows(&(30 * DAY), &0, &2);
+  assert_eq!(first.items, vec![&fixture.env, opened[0], opened[2]]);
+  let second = fixture.contract.list_stale_escrows(&(30 * DAY), &first.next_cursor.unwrap(), &2);
+  assert_eq!((second.items, second.next_cursor), (vec![&fixture.env, opened[3]], None));
+  for index in [IndexKind::ActivityBuckets, IndexKind::ActivityBucket(0)] {
+    let report = fixture.contract.check_index_integrity(&index, &0);
+    assert_eq!((report.duplicates.len(), report.dangling.len()), (0, 0));
+  }
+}
+
+#[test]
+fn stale_listing_with_a_cutoff_before_genesis_is_empty() {
+  let fixture = Fixture::new();
+  fixture.open_escrow();
+
+  let stale = fixture.contract.list_stale_escrows(&DAY, &0, &10);
+
+  assert_eq!((stale.items, stale.next_cursor), (Vec::new(&fixture.env), None));
+}
+
+// Ratings and profiles
+#[test]
+fn ratings_need_a_completed_escrow_and_come_once() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let rate = |from: &Address, stars| fixture.contract.try_rate_freelancer(from, &escrow_id, &stars, &fixture.text(""), &Vec::new(&fixture.env));
+
+  assert_eq!(rate(&fixture.client, 5), Err(Ok(Error::EscrowNotCompleted)));
+  fixture.release_all(escrow_id);
+  assert_eq!(rate(&fixture.freelancer, 5), Err(Ok(Error::Unauthorized)));
+  assert_eq!(rate(&fixture.client, 0), Err(Ok(Error::InvalidRating)));
+  assert_eq!(rate(&fixture.client, 6), Err(Ok(Error::InvalidRating)));
+  assert_eq!(rate(&fixture.client, 4), Ok(Ok(())));
+  assert_eq!(rate(&fixture.client, 5), Err(Ok(Error::AlreadyRated)));
+
+  let rating = fixture.contract.get_rating(&escrow_id);
+  assert_eq!((rating.from, rating.to, rating.rating), (fixture.client.clone(), fixture.freelancer.clone(), 4));
+}
+
+#[test]
+fn a_new_user_gets_an_empty_profile() {
+  let fixture = Fixture::new();
+  let newcomer = Address::generate(&fixture.env);
+
+  let profile = fixture.contract.get_profile(&newcomer);
+
+  assert_eq!((profile.deactivated, profile.available, profile.completed_as_client), (false, true, 0));
+  assert_eq!((profile.ratings.count, profile.average_rating, profile.latest_ratings.len()), (0, 0, 0));
+  // Unrated accounts sit at the neutral prior
+  assert_eq!(profile.reputation, 300);
+}
+
+#[test]
+fn a_veteran_profile_bundles_stats_and_the_latest_ratings() {
+  let fixture = Fixture::new();
+  let rated: std::vec::Vec<u64> = [5, 4, 5, 3].iter().map(|stars| fixture.rated_escrow(*stars)).collect();
+  fixture.open_escrow();
+  fixture.contract.set_availability(&fixture.freelancer, &false);
+
+  let profile = fixture.contract.get_profile(&fixture.freelancer);
+
+  assert_eq!((profile.deactivated, profile.available, profile.stats.active_escrows), (false, false, 1));
+  assert_eq!((profile.ratings.count, profile.ratings.stars, profile.average_rating), (4, 17, 425));
+  assert_eq!(profile.reputation, (17 + 6) * 100 / 6);
+  let latest: std::vec::Vec<u64> = profile.latest_ratings.iter().map(|rating| rating.escrow_id).collect();
+  assert_eq!(latest, [rated[3], rated[2], rated[1]]);
+  assert_eq!(fixture.contract.get_profile(&fixture.client).completed_as_client, 4);
+  let page = fixture.contract.get_ratings(&fixture.freelancer, &0, &10);
+  assert_eq!((page.items.len(), page.total), (4, Some(4)));
+}
+
+#[test]
+fn a_deactivated_account_shows_a_redacted_profile() {
+  let fixture = Fixture::new();
+  fixture.rated_escrow(5);
+
+  fixture.enact(AdminAction::SetDeactivated(fixture.freelancer.clone(), true));
+  let profile = fixture.contract.get_profile(&fixture.freelancer);
+
+  assert!(profile.deactivated);
+  assert_eq!((profile.ratings.count, profile.latest_ratings.len(), profile.reputation, profile.available), (0, 0, 0, false));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::SetDeactivated(fixture.freelancer.clone(), false)), Err(Ok(Error::Unauthorized)));
+  fixture.enact(AdminAction::SetDeactivated(fixture.freelancer.clone(), false));
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).ratings.count, 1);
+}
+
+#[test]
+fn outcome_tags_add_up_on_the_profile() {
+  let fixture = Fixture::new();
+  let tag = |label: &str| fixture.add_registry_entry(RegistryKind::OutcomeTag, symbol_short!("en"), label);
+  let (on_time, great_communication, scope_creep) = (tag("OnTime"), tag("GreatCommunication"), tag("ScopeCreep"));
+  let rate = |stars: u32, tags: Vec<u32>| {
+    let escrow_id = fixture.funded_escrow();
+    fixture.release_all(escrow_id);
+    fixture.contract.rate_freelancer(&fixture.client, &escrow_id, &stars, &fixture.text(""), &tags);
+    escrow_id
+  };
+
+  let first = rate(5, vec![&fixture.env, on_time, great_communication]);
+  rate(2, vec![&fixture.env, scope_creep, on_time]);
+  rate(4, Vec::new(&fixture.env));
+
+  let counts = fixture.contract.get_profile(&fixture.freelancer).tag_counts;
+  assert_eq!((counts.get(on_time), counts.get(great_communication), counts.get(scope_creep)), (Some(2), Some(1), Some(1)));
+  assert_eq!(fixture.contract.get_rating_tags(&first), vec![&fixture.env, on_time, great_communication]);
+}
+
+#[test]
+fn rating_tags_must_be_registered_unique_and_few() {
+  let fixture = Fixture::new();
+  let mut tags = Vec::new(&fixture.env);
+  for label in ["OnTime", "OverBudget", "GreatCommunication", "ScopeCreep", "Responsive", "Thorough"] {
+    tags.push_back(fixture.add_registry_entry(RegistryKind::OutcomeTag, symbol_short!("en"), label));
+  }
+  // A category ID is not an outcome tag
+  fixture.add_category(symbol_short!("en"), "web");
+  let escrow_id = fixture.funded_escrow();
+  fixture.release_all(escrow_id);
+  let rate = |tags: Vec<u32>| fixture.contract.try_rate_freelancer(&fixture.client, &escrow_id, &4, &fixture.text(""), &tags);
+
+  assert_eq!(rate(vec![&fixture.env, 7]), Err(Ok(Error::UnknownTag)));
+  assert_eq!(rate(vec![&fixture.env, 0]), Err(Ok(Error::UnknownTag)));
+  assert_eq!(rate(vec![&fixture.env, 1, 1]), Err(Ok(Error::InvalidRating)));
+  assert_eq!(rate(tags.clone()), Err(Ok(Error::LimitReached)));
+  assert_eq!(rate(tags.slice(0..5)), Ok(Ok(())));
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).tag_counts.len(), 5);
+}
+
+// Locales
+#[test]
+fn a_project_keeps_its_locale_hint() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_with_locale(Some(symbol_short!("es")), Some(-180)).unwrap();
+
+  let locale = fixture.contract.get_project_locale(&project_id);
+  assert_eq!(locale, Locale { language: Some(symbol_short!("es")), tz_offset_mins: Some(-180) });
+  assert_eq!(fixture.contract.get_project_locale(&fixture.post_project()), Locale::default());
+}
+
+#[test]
+fn out_of_range_timezone_offsets_are_rejected() {
+  let fixture = Fixture::new();
+
+  assert_eq!(fixture.post_with_locale(None, Some(-721)), Err(Error::InvalidConfig));
+  assert_eq!(fixture.post_with_locale(None, Some(841)), Err(Error::InvalidConfig));
+  assert!(fixture.post_with_locale(None, Some(-720)).is_ok());
+  assert!(fixture.post_with_locale(None, Some(840)).is_ok());
+}
+
+#[test]
+fn language_match_follows_the_declared_languages() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_with_locale(Some(symbol_short!("es")), None).unwrap();
+  fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+  let listed_match = || fixture.contract.list_proposals(&project_id, &0, &10, &true).items.get(0).unwrap().language_match;
+
+  assert!(!fixture.contract.language_match(&project_id, &fixture.freelancer));
+  assert!(!listed_match());
+
+  fixture.contract.set_languages(&fixture.freelancer, &vec![&fixture.env, symbol_short!("en"), symbol_short!("es")]);
+  assert!(fixture.contract.language_match(&project_id, &fixture.freelancer));
+  assert!(listed_match());
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).languages.len(), 2);
+
+  // A project without a language matches everyone
+  let open_project = fixture.post_project();
+  assert!(fixture.contract.language_match(&open_project, &Address::generate(&fixture.env)));
+}
+
+#[test]
+fn users_declare_at_most_five_languages() {
+  let fixture = Fixture::new();
+  let mut languages = vec![&fixture.env, symbol_short!("en"), symbol_short!("es"), symbol_short!("fr"), symbol_short!("de"), symbol_short!("pt")];
+
+  assert_eq!(fixture.contract.try_set_languages(&fixture.freelancer, &languages), Ok(Ok(())));
+  languages.push_back(symbol_short!("it"));
+  assert_eq!(fixture.contract.try_set_languages(&fixture.freelancer, &languages), Err(Ok(Error::LimitReached)));
+  assert_eq!(fixture.contract.get_profile(&fixture.freelancer).languages.len(), 5);
+}
+
+// Escrow views
+#[test]
+fn parties_and_the_admin_see_the_full_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  for viewer in [&fixture.client, &fixture.freelancer, &fixture.admin] {
+    let EscrowView::Full(escrow) = fixture.contract.get_escrow(viewer, &escrow_id) else {
+      panic!("a party or the admin sees the full escrow");
+    };
+    assert_eq!((escrow.client, escrow.freelancer), (fixture.client.clone(), fixture.freelancer.clone()));
+    assert_eq!((escrow.deposited_amount, escrow.milestones.len()), (BUDGET, 2));
+    assert_eq!(escrow.state, EscrowState::InProgress);
+  }
+}
+
+#[test]
+fn a_stranger_sees_only_the_escrow_header() {
+  let fixture = Fixture::new();
+  fixture.set_time(500);
+  let escrow_id = fixture.funded_escrow();
+  let escrow = fixture.escrow(escrow_id);
+  let stranger = Address::generate(&fixture.env);
+
+  let header = EscrowHeader {
+    escrow_id,
+    project_id: escrow.project_id,
+    state: EscrowState::InProgress,
+    asset: fixture.asset.clone(),
+    total_amount: BUDGET,
+    created_at: 500,
+  };
+  let EscrowView::Header(seen) = fixture.contract.get_escrow(&stranger, &escrow_id) else {
+    panic!("a stranger sees only the header");
+  };
+  assert_eq!(seen, header);
+  assert!(fixture.contract.try_get_escrow(&stranger, &99).err() == Some(Ok(Error::NotFound)));
+}
+
+#[test]
+fn the_assigned_arbitrator_sees_the_full_escrow_while_the_dispute_is_open() {
+  let fixture = Fixture::new();
+  random_arbitrators(&fixture, 2);
+  let escrow_id = fixture.disputed_escrow();
+  let arbitrator = fixture.contract.get_dispute_arbitrator(&escrow_id, &1).unwrap();
+  let other = random_arbitrators(&fixture, 1).pop().unwrap();
+
+  assert!(matches!(fixture.contract.get_escrow(&arbitrator, &escrow_id), EscrowView::Full(_)));
+  assert!(matches!(fixture.contract.get_escrow(&other, &escrow_id), EscrowView::Header(_)));
+
+  fixture.contract.resolve_dispute(&arbitrator, &escrow_id, &1, &300);
+  assert!(matches!(fixture.contract.get_escrow(&arbitrator, &escrow_id), EscrowView::Header(_)));
+}
+
+#[test]
+fn each_party_reads_back_only_their_own_private_note() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.set_private_note(&fixture.client, &escrow_id, &fixture.hash(1));
+
+  assert_eq!(fixture.contract.get_private_note(&fixture.client, &escrow_id), Some(fixture.hash(1)));
+  // The viewer has to sign for the read
+  assert_eq!(fixture.env.auths()[0].0, fixture.client);
+  // The counterparty gets their own, empty slot, never the client's note
+  assert_eq!(fixture.contract.get_private_note(&fixture.freelancer, &escrow_id), None);
+  fixture.contract.set_private_note(&fixture.freelancer, &escrow_id, &fixture.hash(2));
+  assert_eq!(fixture.contract.get_private_note(&fixture.freelancer, &escrow_id), Some(fixture.hash(2)));
+  assert_eq!(fixture.contract.get_private_note(&fixture.client, &escrow_id), Some(fixture.hash(1)));
+}
+
+#[test]
+fn strangers_neither_read_nor_keep_private_notes() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.contract.set_private_note(&fixture.client, &escrow_id, &fixture.hash(1));
+  let stranger = Address::generate(&fixture.env);
+
+  assert_eq!(fixture.contract.try_get_private_note(&stranger, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_get_private_note(&fixture.admin, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_set_private_note(&stranger, &escrow_id, &fixture.hash(3)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_get_private_note(&fixture.client, &99), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn a_second_private_note_replaces_the_first() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let before = (fixture.contract.terms_digest(&escrow_id), fixture.contract.export_ledger(&escrow_id, &0, &10));
+  fixture.contract.set_private_note(&fixture.client, &escrow_id, &fixture.hash(1));
+  fixture.contract.set_private_note(&fixture.client, &escrow_id, &fixture.hash(2));
+
+  assert_eq!(fixture.contract.get_private_note(&fixture.client, &escrow_id), Some(fixture.hash(2)));
+  // Neither the terms nor the exported ledger carry notes
+  let after = (fixture.contract.terms_digest(&escrow_id), fixture.contract.export_ledger(&escrow_id, &0, &10));
+  assert_eq!(after, before);
+}
+
+// Observers
+// Adds an observer to the escrow, acknowledged by the freelancer
+fn observed_escrow(fixture: &Fixture, escrow_id: u64) -> Address {
+  let observer = Address::generate(&fixture.env);
+  fixture.contract.add_observer(&fixture.client, &escrow_id, &observer);
+  fixture.contract.acknowledge_observer(&fixture.freelancer, &escrow_id, &observer);
+  observer
+}
+
+#[test]
+fn an_observer_reads_the_full_escrow_once_acknowledged() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let observer = Address::generate(&fixture.env);
+  assert_eq!(fixture.contract.try_add_observer(&fixture.freelancer, &escrow_id, &observer), Err(Ok(Error::Unauthorized)));
+  fixture.contract.add_observer(&fixture.client, &escrow_id, &observer);
+
+  assert!(matches!(fixture.contract.get_escrow(&observer, &escrow_id), EscrowView::Header(_)));
+  assert_eq!(fixture.contract.try_acknowledge_observer(&fixture.client, &escrow_id, &observer), Err(Ok(Error::Unauthorized)));
+  fixture.contract.acknowledge_observer(&fixture.freelancer, &escrow_id, &observer);
+
+  let (topics, data) = fixture.last_event(symbol_short!("observer"));
+  assert_eq!(topics, fixture.escrow_topics("observer", escrow_id));
+  assert_eq!(<(Address, bool)>::from_val(&fixture.env, &data), (observer.clone(), true));
+  assert!(matches!(fixture.contract.get_escrow(&observer, &escrow_id), EscrowView::Full(_)));
+  assert_eq!(fixture.contract.get_observers(&escrow_id, &0, &MAX_LIST_LIMIT).items.get(0).unwrap(), Observer { address: observer, acknowledged: true });
+}
+
+#[test]
+fn observers_get_an_event_naming_each_escrow_event() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let observer = observed_escrow(&fixture, escrow_id);
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &1, &500);
+
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &1, &500);
+
+  // The reprice and the amendment it is recorded as
+  let name: Val = symbol_short!("observed").into_val(&fixture.env);
+  let observed: std::vec::Vec<(Vec<Val>, Symbol)> = fixture.env.events().all().iter()
+    .filter(|(_, topics, _)| topics.get(0).is_some_and(|topic| topic.shallow_eq(&name)))
+    .map(|(_, topics, data)| (topics, Symbol::from_val(&fixture.env, &data)))
+    .collect();
+  let topics: Vec<Val> = (symbol_short!("observed"), observer, escrow_id).into_val(&fixture.env);
+  assert_eq!(observed, [(topics.clone(), symbol_short!("reprice")), (topics, symbol_short!("amended"))]);
+  // The escrow event itself keeps its topics
+  assert_eq!(fixture.last_event(symbol_short!("reprice")).0, fixture.escrow_topics("reprice", escrow_id));
+}
+
+#[test]
+fn observers_are_refused_every_change_to_the_escrow() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let observer = observed_escrow(&fixture, escrow_id);
+  fixture.submit(escrow_id, 0);
+  let contract = &fixture.contract;
+
+  assert_eq!(contract.try_deposit_funds(&observer, &escrow_id, &100, &None), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_submit_milestone(&observer, &escrow_id, &1, &fixture.hash(2)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_release_funds(&observer, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_raise_dispute(&observer, &escrow_id, &0), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_refund_funds(&observer, &escrow_id), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_propose_reprice(&observer, &escrow_id, &1, &800), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_add_observer(&observer, &escrow_id, &Address::generate(&fixture.env)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_set_private_note(&observer, &escrow_id, &fixture.hash(3)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(contract.try_get_private_note(&observer, &escrow_id), Err(Ok(Error::Unauthorized)));
+  let escrow = fixture.escrow(escrow_id);
+  assert_eq!((escrow.deposited_amount, escrow.released_amount), (BUDGET, 0));
+}
+
+#[test]
+fn the_client_can_remove_an_observer_at_any_time() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  let observer = observed_escrow(&fixture, escrow_id);
+  let pending = Address::generate(&fixture.env);
+  fixture.contract.add_observer(&fixture.client, &escrow_id, &pending);
+
+  assert_eq!(fixture.contract.try_remove_observer(&fixture.freelancer, &escrow_id, &observer), Err(Ok(Error::Unauthorized)));
+  fixture.contract.remove_observer(&fixture.client, &escrow_id, &observer);
+
+  let (_, data) = fixture.last_event(symbol_short!("observer"));
+  assert_eq!(<(Address, bool)>::from_val(&fixture.env, &data), (observer.clone(), false));
+  assert!(matches!(fixture.contract.get_escrow(&observer, &escrow_id), EscrowView::Header(_)));
+  // Later events reach nobody who left
+  fixture.contract.propose_reprice(&fixture.freelancer, &escrow_id, &1, &800);
+  fixture.contract.accept_reprice(&fixture.client, &escrow_id, &1, &800);
+  assert!(fixture.env.events().all().iter().all(|(_, topics, _)| topics.get(0).is_none_or(|topic| !topic.shallow_eq(&symbol_short!("observed").into_val(&fixture.env)))));
+  // A grant never acknowledged goes the same way
+  fixture.contract.remove_observer(&fixture.client, &escrow_id, &pending);
+  assert_eq!(fixture.contract.get_observers(&escrow_id, &0, &MAX_LIST_LIMIT).items.len(), 0);
+  assert_eq!(fixture.contract.try_remove_observer(&fixture.client, &escrow_id, &observer), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn observers_are_outsiders_and_few() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+
+  assert_eq!(fixture.contract.try_add_observer(&fixture.client, &escrow_id, &fixture.freelancer), Err(Ok(Error::InvalidOwner)));
+  assert_eq!(fixture.contract.try_add_observer(&fixture.client, &escrow_id, &fixture.client), Err(Ok(Error::InvalidOwner)));
+  for _ in 0..MAX_OBSERVERS {
+    fixture.contract.add_observer(&fixture.client, &escrow_id, &Address::generate(&fixture.env));
+  }
+  assert_eq!(fixture.contract.try_add_observer(&fixture.client, &escrow_id, &Address::generate(&fixture.env)), Err(Ok(Error::LimitReached)));
+}
+
+// Admin queue
+#[test]
+fn a_settings_change_waits_a_day_after_it_is_queued() {
+  let fixture = Fixture::new();
+  fixture.set_time(1000);
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::SetPlatformFee(250));
+  let (topics, data) = fixture.last_event(symbol_short!("queued"));
+  assert_eq!(topics, (symbol_short!("queued"), action_id).into_val(&fixture.env));
+  assert_eq!(<(AdminAction, u64)>::from_val(&fixture.env, &data).1, 1000 + DAY);
+
+  fixture.set_time(1000 + DAY - 1);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::TooEarly)));
+  assert_eq!(fixture.contract.get_platform_fee(), 0);
+
+  fixture.set_time(1000 + DAY);
+  fixture.contract.execute_admin_action(&action_id);
+  assert_eq!(fixture.contract.get_platform_fee(), 250);
+  assert!(fixture.contract.get_admin_action(&action_id).is_none());
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::NotFound)));
+}
+
+#[test]
+fn the_admin_can_cancel_a_queued_action() {
+  let fixture = Fixture::new();
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::SetRandomAssignment(true));
+
+  assert_eq!(fixture.contract.try_cancel_admin_action(&fixture.client, &action_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.cancel_admin_action(&fixture.admin, &action_id);
+  assert_eq!(fixture.env.events().all().len(), 1);
+  fixture.set_time(DAY);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::NotFound)));
+  assert_eq!(fixture.contract.try_cancel_admin_action(&fixture.admin, &action_id), Err(Ok(Error::NotFound)));
+  assert!(!fixture.contract.get_random_assignment());
+}
+
+#[test]
+fn a_ban_through_the_queue_applies_at_once() {
+  let fixture = Fixture::new();
+  fixture.set_time(1000);
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::SetDeactivated(fixture.freelancer.clone(), true));
+
+  assert_eq!(fixture.contract.get_admin_action(&action_id).unwrap().execute_after, 1000);
+  fixture.contract.execute_admin_action(&action_id);
+  assert!(fixture.contract.get_profile(&fixture.freelancer).deactivated);
+}
+
+#[test]
+fn overriding_an_assigned_arbitrator_waits_three_days() {
+  let fixture = Fixture::new();
+  random_arbitrators(&fixture, 1);
+  fixture.set_time(1000);
+  let escrow_id = fixture.disputed_escrow();
+  assert_eq!(fixture.contract.try_resolve_dispute(&fixture.admin, &escrow_id, &1, &300), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::ForceResolve(escrow_id, 1, 601)), Err(Ok(Error::InvalidAmount)));
+
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::ForceResolve(escrow_id, 1, 300));
+  fixture.set_time(1000 + 3 * DAY - 1);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::TooEarly)));
+  fixture.set_time(1000 + 3 * DAY);
+  fixture.contract.execute_admin_action(&action_id);
+  assert_eq!(fixture.status(escrow_id, 1), MilestoneStatus::Resolved);
+}
+
+// Launch mode
+#[test]
+fn allowlist_mode_gates_what_starts_something_new() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::RestrictToAllowlist);
+  let create_service = |from: &Address| {
+    fixture.contract.try_create_service(from, &fixture.text("Logo"), &fixture.text("A logo"), &fixture.text("design"), &100, &fixture.asset, &3)
+  };
+
+  assert_eq!(fixture.contract.get_config().launch_mode, LaunchMode::Allowlist);
+  let schedule = fixture.layout_milestones();
+  let post = fixture.contract.try_post_project(&fixture.client, &fixture.text("Site"), &fixture.text("A website"), &fixture.text("web"), &BUDGET, &fixture.asset, &PROJECT_DEADLINE, &schedule, &Locale::default());
+  assert_eq!(post, Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.post_with_shares(BUDGET, &[4_000, 6_000]), Err(Error::Unauthorized));
+  assert_eq!(create_service(&fixture.freelancer), Err(Ok(Error::Unauthorized)));
+  fixture.enact(AdminAction::SetAllowlisted(fixture.client.clone(), true));
+  let project_id = fixture.post_project();
+  assert_eq!(fixture.contract.try_submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1)), Err(Ok(Error::Unauthorized)));
+
+  fixture.enact(AdminAction::SetAllowlisted(fixture.freelancer.clone(), true));
+  fixture.contract.submit_proposal(&fixture.freelancer, &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+  assert!(create_service(&fixture.freelancer).is_ok());
+  fixture.enact(AdminAction::SetAllowlisted(fixture.client.clone(), false));
+  assert!(!fixture.contract.is_allowlisted(&fixture.client));
+  assert_eq!(fixture.post_with_shares(BUDGET, &[4_000, 6_000]), Err(Error::Unauthorized));
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::SetAllowlisted(fixture.client.clone(), true)), Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn escrows_opened_before_allowlist_mode_keep_working() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let project_id = fixture.post_project();
+
+  fixture.enact(AdminAction::RestrictToAllowlist);
+
+  // Neither party is allowlisted, and nothing they already have stops
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  fixture.release_all(escrow_id);
+  assert_eq!(fixture.escrow(escrow_id).state, EscrowState::Completed);
+  fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+}
+
+#[test]
+fn leaving_allowlist_mode_takes_a_timelocked_action_to_undo() {
+  let fixture = Fixture::new();
+  let config = |launch_mode| Config { launch_mode, ..fixture.contract.get_config() };
+  // New contracts start open, and a config change can't close them
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::SetConfig(config(LaunchMode::Allowlist))), Err(Ok(Error::InvalidConfig)));
+
+  fixture.set_time(1_000);
+  let action_id = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::RestrictToAllowlist);
+  assert_eq!(fixture.contract.get_admin_action(&action_id).unwrap().execute_after, 1_000 + 7 * DAY);
+  fixture.set_time(1_000 + 7 * DAY - 1);
+  assert_eq!(fixture.contract.try_execute_admin_action(&action_id), Err(Ok(Error::TooEarly)));
+  fixture.set_time(1_000 + 7 * DAY);
+  fixture.contract.execute_admin_action(&action_id);
+  assert_eq!(fixture.contract.get_config().launch_mode, LaunchMode::Allowlist);
+
+  // While allowlisted, a config that keeps the mode can be queued; once the marketplace has
+  // opened, it can no longer apply
+  let stale = fixture.contract.queue_admin_action(&fixture.admin, &AdminAction::SetConfig(config(LaunchMode::Allowlist)));
+  fixture.enact(AdminAction::SetConfig(config(LaunchMode::Open)));
+  assert_eq!(fixture.contract.get_config().launch_mode, LaunchMode::Open);
+  fixture.set_time(1_000 + 8 * DAY);
+  assert_eq!(fixture.contract.try_execute_admin_action(&stale), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(fixture.contract.get_config().launch_mode, LaunchMode::Open);
+}
+
+// Self-check
+#[test]
+fn self_check_passes_healthy_escrows_without_events() {
+  let fixture = Fixture::new();
+  let funded = fixture.funded_escrow();
+  let disputed = fixture.disputed_escrow();
+  let open = fixture.open_escrow();
+
+  let results = fixture.contract.self_check(&vec![&fixture.env, funded, disputed, open, disputed, 99]);
+  assert_eq!(fixture.env.events().all().len(), 0);
+  // Four checks per escrow, the repeat and the unknown ID skipped, then the asset
+  assert_eq!(results.len(), 13);
+  assert!(results.iter().all(|result| result.passed));
+  let last = results.last().unwrap();
+  assert_eq!((last.invariant, last.escrow_id, last.asset), (Invariant::Obligations, None, fixture.asset.clone()));
+}
+
+#[test]
+fn self_check_reports_a_corrupted_escrow() {
+  let fixture = Fixture::new();
+  let healthy = fixture.funded_escrow();
+  let corrupted = fixture.funded_escrow();
+  let mut escrow = fixture.escrow(corrupted);
+  escrow.released_amount = BUDGET + 1;
+  fixture.env.as_contract(&fixture.contract.address, || storage::escrows(corrupted).set(&fixture.env, &escrow));
+
+  let results = fixture.contract.self_check(&vec![&fixture.env, healthy, corrupted]);
+  let events = fixture.env.events().all();
+  let failed: std::vec::Vec<(Invariant, Option<u64>)> = results.iter().filter(|result| !result.passed).map(|result| (result.invariant, result.escrow_id)).collect();
+  assert_eq!(failed, [(Invariant::Funds, Some(corrupted)), (Invariant::Frozen, Some(corrupted))]);
+  assert_eq!(events.len(), 2);
+  let (_, topics, data) = events.get(0).unwrap();
+  assert_eq!(topics, fixture.escrow_topics("chk_fail", corrupted));
+  assert_eq!(Invariant::from_val(&fixture.env, &data), Invariant::Funds);
+
+  // Dropping the escrow from its state index breaks that check too
+  fixture.env.as_contract(&fixture.contract.address, || index::remove_value(&fixture.env, &IndexKind::EscrowsByState(EscrowState::InProgress), healthy));
+  let results = fixture.contract.self_check(&vec![&fixture.env, healthy]);
+  assert_eq!(results.iter().filter(|result| !result.passed).map(|result| result.invariant).collect::<std::vec::Vec<_>>(), [Invariant::StateIndex]);
+}
+
+#[test]
+fn self_check_flags_obligations_short_of_the_sample() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.funded_escrow();
+  fixture.env.as_contract(&fixture.contract.address, || storage::obligations(fixture.asset.clone()).set(&fixture.env, &(BUDGET - 1)));
+
+  let results = fixture.contract.self_check(&vec![&fixture.env, escrow_id]);
+  let (_, topics, _) = fixture.env.events().all().get(0).unwrap();
+  assert_eq!(topics, (symbol_short!("chk_fail"), fixture.asset.clone()).into_val(&fixture.env));
+  assert!(!results.last().unwrap().passed);
+}
+
+#[test]
+fn self_check_samples_a_bounded_number_of_escrows() {
+  let fixture = Fixture::new();
+  let sample = Vec::from_array(&fixture.env, [1u64; 21]);
+  assert_eq!(fixture.contract.try_self_check(&sample), Err(Ok(Error::LimitReached)));
+}
+
+// Tombstones
+#[test]
+fn a_cancelled_project_leaves_a_tombstone() {
+  let fixture = Fixture::new();
+  fixture.set_time(500);
+  let project_id = fixture.post_project();
+
+  assert_eq!(fixture.contract.try_cancel_project(&fixture.freelancer, &project_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.cancel_project(&fixture.client, &project_id);
+
+  assert!(fixture.contract.try_get_project(&project_id).err() == Some(Ok(Error::Collected)));
+  assert_eq!(fixture.contract.get_project_tombstone(&project_id), Some(Tombstone { id: project_id, ending: Ending::ProjectCancelled, closed_at: 500 }));
+  assert!(fixture.contract.try_get_project(&(project_id + 1)).err() == Some(Ok(Error::NotFound)));
+  let escrow_id = fixture.open_escrow();
+  let taken = fixture.escrow(escrow_id).project_id;
+  assert_eq!(fixture.contract.try_cancel_project(&fixture.client, &taken), Err(Ok(Error::ProjectNotOpen)));
+}
+
+#[test]
+fn declining_an_escrow_reopens_its_project() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let project_id = fixture.escrow(escrow_id).project_id;
+
+  assert_eq!(fixture.contract.try_decline_escrow(&fixture.client, &escrow_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.decline_escrow(&fixture.freelancer, &escrow_id);
+
+  assert!(fixture.contract.try_get_escrow(&fixture.admin, &escrow_id).err() == Some(Ok(Error::Collected)));
+  assert_eq!(fixture.contract.get_escrow_tombstone(&escrow_id).unwrap().ending, Ending::EscrowDeclined);
+  assert_eq!(fixture.contract.get_project(&project_id).status, ProjectStatus::Open);
+  assert_eq!(fixture.contract.count_escrows_by_state().created, 0);
+  assert_eq!(fixture.contract.get_freelancer_stats(&fixture.freelancer).active_escrows, 0);
+  assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.asset), Commitments::default());
+  // The project can be taken again
+  fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+}
+
+#[test]
+fn funded_escrows_cannot_be_declined() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &100, &None);
+
+  assert_eq!(fixture.contract.try_decline_escrow(&fixture.freelancer, &escrow_id), Err(Ok(Error::HasDeposits)));
+}
+
+#[test]
+fn a_rating_still_resolves_its_collected_escrow() {
+  let fixture = Fixture::new();
+  let active = fixture.funded_escrow();
+  fixture.set_time(2_000);
+  let escrow_id = fixture.rated_escrow(5);
+  fixture.set_time(9_000);
+
+  assert_eq!(fixture.contract.try_collect_escrow(&fixture.admin, &active), Err(Ok(Error::EscrowActive)));
+  assert_eq!(fixture.contract.try_collect_escrow(&fixture.client, &escrow_id), Err(Ok(Error::Unauthorized)));
+  fixture.contract.collect_escrow(&fixture.admin, &escrow_id);
+
+  let rating = fixture.contract.get_rating(&escrow_id);
+  assert!(fixture.contract.try_get_escrow(&fixture.admin, &rating.escrow_id).err() == Some(Ok(Error::Collected)));
+  assert_eq!(fixture.contract.get_escrow_tombstone(&rating.escrow_id), Some(Tombstone { id: escrow_id, ending: Ending::EscrowCompleted, closed_at: 2_000 }));
+  assert_eq!(fixture.contract.get_deposits(&escrow_id, &0, &MAX_LIST_LIMIT).items.len(), 0);
+  let report = fixture.contract.check_index_integrity(&IndexKind::EscrowsByState(EscrowState::Completed), &0);
+  assert_eq!((report.len, report.dangling.len()), (0, 0));
+}
+
+// Event topics
+#[test]
+fn escrow_events_name_both_parties_at_fixed_positions() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  assert_eq!(fixture.last_event(symbol_short!("opened")).0, fixture.escrow_topics("opened", escrow_id));
+
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  assert_eq!(fixture.last_event(symbol_short!("deposit")).0, fixture.escrow_topics("deposit", escrow_id));
+
+  fixture.submit(escrow_id, 0);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &0);
+  fixture.submit(escrow_id, 1);
+  fixture.contract.release_funds(&fixture.client, &escrow_id, &1);
+  assert_eq!(fixture.last_event(symbol_short!("closed")).0, fixture.escrow_topics("closed", escrow_id));
+}
+
+#[test]
+fn bounty_refunds_name_both_parties() {
+  let fixture = Fixture::new();
+  let (escrow_id, sponsors) = fixture.bounty(DAY, &[100]);
+
+  fixture.contract.refund_funds(&fixture.client, &escrow_id);
+
+  let (topics, data) = fixture.last_event(symbol_short!("refund"));
+  assert_eq!(topics, fixture.escrow_topics("refund", escrow_id));
+  let data: (Address, i128) = data.into_val(&fixture.env);
+  assert_eq!(data, (sponsors[0].clone(), 100));
+}
+
+#[test]
+fn dispute_events_name_the_parties_and_the_arbitrator() {
+  let fixture = Fixture::new();
+  let arbitrator = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::AddArbitrator(arbitrator.clone()));
+  fixture.enact(AdminAction::PromoteArbitrator(arbitrator.clone()));
+  let escrow_id = fixture.disputed_escrow();
+
+  fixture.contract.next_dispute(&arbitrator);
+  let (topics, data) = fixture.last_event(symbol_short!("assigned"));
+  assert_eq!(topics, (symbol_short!("assigned"), arbitrator.clone(), escrow_id).into_val(&fixture.env));
+  assert_eq!(u32::from_val(&fixture.env, &data), 1);
+
+  fixture.contract.resolve_dispute(&arbitrator, &escrow_id, &1, &200);
+  let (topics, data) = fixture.last_event(symbol_short!("ruled"));
+  assert_eq!(topics, (symbol_short!("ruled"), arbitrator.clone(), escrow_id).into_val(&fixture.env));
+  let data: (u32, i128, bool) = data.into_val(&fixture.env);
+  assert_eq!(data, (1, 200, false));
+  let (topics, data) = fixture.last_event(symbol_short!("resolved"));
+  assert_eq!(topics, fixture.escrow_topics("resolved", escrow_id));
+  let data: (u32, i128) = data.into_val(&fixture.env);
+  assert_eq!(data, (1, 200));
+}
+
+#[test]
+fn fee_withdrawals_are_published_under_the_asset() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let escrow_id = fixture.funded_escrow();
+  fixture.release_all(escrow_id);
+  fixture.fund_contract(25);
+  let treasury = Address::generate(&fixture.env);
+
+  fixture.contract.withdraw_fees(&fixture.admin, &fixture.asset, &treasury, &25);
+
+  let (topics, data) = fixture.last_event(symbol_short!("fees_out"));
+  assert_eq!(topics, (symbol_short!("fees_out"), fixture.asset.clone()).into_val(&fixture.env));
+  let data: (Address, i128) = data.into_val(&fixture.env);
+  assert_eq!(data, (treasury, 25));
+}
+
+// Paging
+#[test]
+fn window_clamps_the_limit_and_stops_at_the_end() {
+  assert_eq!(page::window(0, 0, 10), (0..0, None));
+  assert_eq!(page::window(5, 0, 0), (0..1, Some(1)));
+  assert_eq!(page::window(60, 0, 100), (0..MAX_LIST_LIMIT, Some(MAX_LIST_LIMIT as u64)));
+  assert_eq!(page::window(5, 3, 10), (3..5, None));
+  assert_eq!(page::window(5, u64::MAX, 10), (5..5, None));
+}
+
+// Reads pages of a list of `len` items through one getter, which returns the page's item
+// count, next cursor and total, and checks it cuts them the way page::window does
+fn assert_pages_like_window(len: u32, read: impl Fn(u64, u32) -> (u32, Option<u64>, Option<u32>)) {
+  let full = MAX_LIST_LIMIT as u64;
+  assert_eq!(read(0, 0), (1, Some(1), Some(len)));
+  assert_eq!(read(0, 100), (MAX_LIST_LIMIT, Some(full), Some(len)));
+  assert_eq!(read(full, 100), (len - MAX_LIST_LIMIT, None, Some(len)));
+  assert_eq!(read(len as u64 - 1, 1), (1, None, Some(len)));
+  assert_eq!(read(len as u64 + 10, 10), (0, None, Some(len)));
+}
+
+#[test]
+fn list_getters_share_paging_boundaries() {
+  let fixture = Fixture::new();
+  let len = MAX_LIST_LIMIT + 5;
+  let project_id = fixture.post_project();
+  for id in 0..len {
+    fixture.add_category(symbol_short!("en"), "Design");
+    fixture.contract.submit_proposal(&Address::generate(&fixture.env), &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+    fixture.env.as_contract(&fixture.contract.address, || {
+      index::append_unique(&fixture.env, &IndexKind::EscrowsByState(EscrowState::Refunded), id as u64 + 100, len);
+    });
+  }
+
+  assert_pages_like_window(len, |cursor, limit| {
+    let page = fixture.contract.list_registry(&RegistryKind::Category, &cursor, &limit);
+    (page.items.len(), page.next_cursor, page.total)
+  });
+  assert_pages_like_window(len, |cursor, limit| {
+    let page = fixture.contract.list_proposals(&project_id, &cursor, &limit, &false);
+    (page.items.len(), page.next_cursor, page.total)
+  });
+  assert_pages_like_window(len, |cursor, limit| {
+    let page = fixture.contract.list_proposals_sorted(&project_id, &cursor, &limit, &false);
+    (page.items.len(), page.next_cursor, page.total)
+  });
+  assert_pages_like_window(len, |cursor, limit| {
+    let page = fixture.contract.list_escrows_by_state(&EscrowState::Refunded, &cursor, &limit);
+    (page.items.len(), page.next_cursor, page.total)
+  });
+  // The project posted above plus one per round
+  for _ in 1..len {
+    fixture.post_project();
+  }
+  assert_pages_like_window(len, |cursor, limit| {
+    let page = fixture.contract.list_projects(&cursor, &limit);
+    (page.items.len(), page.next_cursor, page.total)
+  });
+}
+
+#[test]
+fn pages_of_large_items_end_at_the_byte_budget() {
+  let fixture = Fixture::new();
+  // Ten kilobytes per title, so three summaries fit in a page and the fourth does not
+  let title = fixture.text(&"t".repeat(10 * 1024));
+  let milestones = vec![&fixture.env, fixture.milestone("Design", 400), fixture.milestone("Build", 600)];
+  for _ in 0..5 {
+    fixture.contract.post_project(&fixture.client, &title, &fixture.text("A website"), &fixture.text("web"), &BUDGET, &fixture.asset, &PROJECT_DEADLINE, &milestones, &Locale::default());
+  }
+
+  let first = fixture.contract.list_projects(&0, &10);
+  let second = fixture.contract.list_projects(&first.next_cursor.unwrap(), &10);
+
+  assert_eq!((first.items.len(), first.next_cursor, first.total), (3, Some(3), Some(5)));
+  assert_eq!((second.items.len(), second.next_cursor), (2, None));
+  assert_eq!(second.items.get(0).unwrap().id, 4);
+}
+
+#[test]
+fn an_item_over_the_byte_budget_still_gets_a_page_of_its_own() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  let description = "d".repeat(page::MAX_PAGE_BYTES as usize);
+  let milestones = vec![&fixture.env, fixture.milestone(&description, 900)];
+  for _ in 0..2 {
+    fixture.contract.submit_proposal(&Address::generate(&fixture.env), &project_id, &900, &milestones, &fixture.hash(1));
+  }
+
+  let first = fixture.contract.list_proposals(&project_id, &0, &10, &false);
+  let second = fixture.contract.list_proposals_sorted(&project_id, &1, &10, &false);
+
+  assert_eq!((first.items.len(), first.next_cursor), (1, Some(1)));
+  assert_eq!((second.items.len(), second.next_cursor), (1, None));
+}
+
+#[test]
+fn pages_chain_through_every_item() {
+  let fixture = Fixture::new();
+  let project_id = fixture.post_project();
+  for _ in 0..7 {
+    fixture.contract.submit_proposal(&Address::generate(&fixture.env), &project_id, &900, &Vec::new(&fixture.env), &fixture.hash(1));
+  }
+
+  let mut ids = std::vec::Vec::new();
+  let mut cursor = Some(0);
+  while let Some(next) = cursor {
+    let page = fixture.contract.list_proposals(&project_id, &next, &3, &false);
+    ids.extend(page.items.iter().map(|listing| listing.proposal.id));
+    cursor = page.next_cursor;
+  }
+
+  assert_eq!(ids, [1, 2, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn describe_reports_the_interface_and_data_versions() {
+  let fixture = Fixture::new();
+
+  let description = fixture.contract.describe();
+
+  assert_eq!((description.interface_version, description.data_version), (35, migration::DATA_VERSION));
+  assert_eq!(description.deprecated, vec![&fixture.env, Symbol::new(&fixture.env, "post_project_v1"), Symbol::new(&fixture.env, "deposit_funds_v1")]);
+}
+
+// Legacy entry points
+fn legacy_milestone(fixture: &Fixture, description: &str, amount: u64) -> LegacyMilestone {
+  LegacyMilestone { description: fixture.text(description), amount, completed: false, deadline: 0 }
+}
+
+#[test]
+fn post_project_v1_stores_what_post_project_stores() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetLegacyAsset(fixture.asset.clone()));
+  let milestones = vec![&fixture.env, legacy_milestone(&fixture, "Design", 400), legacy_milestone(&fixture, "Build", 600)];
+
+  let legacy_id = fixture.contract.post_project_v1(&fixture.client, &fixture.text("Site"), &fixture.text("A website"), &fixture.text("web"), &1000, &PROJECT_DEADLINE, &milestones);
+  let current_id = fixture.post_project();
+
+  let stored = |project_id| Project { id: 0, ..fixture.contract.get_project(&project_id) }.to_xdr(&fixture.env);
+  assert_eq!(stored(legacy_id), stored(current_id));
+  assert_eq!(fixture.contract.get_project_locale(&legacy_id), fixture.contract.get_project_locale(&current_id));
+}
+
+#[test]
+fn deposit_funds_v1_records_what_deposit_funds_records() {
+  let fixture = Fixture::new();
+  let (legacy_id, current_id) = (fixture.open_escrow(), fixture.open_escrow());
+
+  fixture.contract.deposit_funds_v1(&fixture.client, &legacy_id, &(BUDGET as u64));
+  fixture.contract.deposit_funds(&fixture.client, &current_id, &BUDGET, &None);
+
+  let stored = |escrow_id| Escrow { project_id: 0, ..fixture.escrow(escrow_id) }.to_xdr(&fixture.env);
+  assert_eq!(stored(legacy_id), stored(current_id));
+  assert_eq!(fixture.contract.get_deposits(&legacy_id, &0, &MAX_LIST_LIMIT).items, fixture.contract.get_deposits(&current_id, &0, &MAX_LIST_LIMIT).items);
+}
+
+#[test]
+fn post_project_v1_needs_the_legacy_asset() {
+  let fixture = Fixture::new();
+  let milestones = vec![&fixture.env, legacy_milestone(&fixture, "Logo", 500)];
+
+  let result = fixture.contract.try_post_project_v1(&fixture.client, &fixture.text("Logo"), &fixture.text("A logo"), &fixture.text("design"), &500, &PROJECT_DEADLINE, &milestones);
+
+  assert_eq!(result, Err(Ok(Error::InvalidConfig)));
+}
+
+#[test]
+fn turning_the_legacy_api_off_deprecates_the_v1_calls() {
+  let fixture = Fixture::new();
+  fixture.enact(AdminAction::SetLegacyAsset(fixture.asset.clone()));
+  let escrow_id = fixture.open_escrow();
+
+  fixture.enact(AdminAction::SetConfig(Config { legacy_api: false, ..fixture.contract.get_config() }));
+
+  let milestones = vec![&fixture.env, legacy_milestone(&fixture, "Logo", 500)];
+  let posted = fixture.contract.try_post_project_v1(&fixture.client, &fixture.text("Logo"), &fixture.text("A logo"), &fixture.text("design"), &500, &PROJECT_DEADLINE, &milestones);
+  assert_eq!(posted, Err(Ok(Error::Deprecated)));
+  assert_eq!(fixture.contract.try_deposit_funds_v1(&fixture.client, &escrow_id, &500), Err(Ok(Error::Deprecated)));
+  assert!(fixture.contract.describe().deprecated.is_empty());
+  // The current calls are untouched
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &500, &None);
+  assert_eq!(fixture.escrow(escrow_id).deposited_amount, 500);
+}
+
+// Storage TTL
+#[test]
+fn each_data_class_extends_its_own_entries() {
+  let fixture = Fixture::new();
+  let open = fixture.post_project();
+  let cancelled = fixture.post_project();
+  fixture.contract.cancel_project(&fixture.client, &cancelled);
+  fixture.rated_escrow(5);
+
+  assert_eq!(fixture.entry_ttl(&StorageKey::Projects(open)), 120 * DAY_IN_LEDGERS);
+  assert_eq!(fixture.entry_ttl(&StorageKey::ProjectTombstones(cancelled)), 30 * DAY_IN_LEDGERS);
+  assert_eq!(fixture.entry_ttl(&StorageKey::FreelancerRatings(fixture.freelancer.clone())), 60 * DAY_IN_LEDGERS);
+  // The instance holds the settings and counters every entry needs, so any touch keeps it
+  assert_eq!(fixture.instance_ttl(), 120 * DAY_IN_LEDGERS);
+}
+
+#[test]
+fn a_touch_extends_only_the_escrow_it_writes() {
+  let fixture = Fixture::new();
+  let live = fixture.funded_escrow();
+  let finished = fixture.funded_escrow();
+  fixture.release_all(finished);
+
+  // Both are under the Hot threshold; only the one written is extended
+  fixture.advance_ledgers(100 * DAY_IN_LEDGERS);
+  fixture.submit(live, 0);
+  fixture.contract.release_funds(&fixture.client, &live, &0);
+
+  assert_eq!(fixture.entry_ttl(&StorageKey::Escrows(live)), 120 * DAY_IN_LEDGERS);
+  assert_eq!(fixture.entry_ttl(&StorageKey::Escrows(finished)), 20 * DAY_IN_LEDGERS);
+}
+
+#[test]
+fn ttl_policy_changes_apply_from_the_next_touch() {
+  let fixture = Fixture::new();
+  let policy = TtlPolicy { threshold: 150 * DAY_IN_LEDGERS, extend_to: 200 * DAY_IN_LEDGERS };
+
+  fixture.enact(AdminAction::SetTtlPolicy(TtlClass::Warm, policy));
+  assert_eq!(fixture.contract.get_ttl_policy(&TtlClass::Warm), policy);
+  fixture.contract.set_availability(&fixture.freelancer, &false);
+
+  assert_eq!(fixture.instance_ttl(), 200 * DAY_IN_LEDGERS);
+  assert_eq!(fixture.contract.get_ttl_policy(&TtlClass::Hot).extend_to, 120 * DAY_IN_LEDGERS);
+}
+
+#[test]
+fn ttl_policies_outside_the_bounds_are_rejected() {
+  let fixture = Fixture::new();
+  let max_ttl = fixture.env.as_contract(&fixture.contract.address, || fixture.env.storage().max_ttl());
+  let set = |threshold, extend_to| fixture.contract.try_queue_admin_action(&fixture.admin, &AdminAction::SetTtlPolicy(TtlClass::Cold, TtlPolicy { threshold, extend_to }));
+
+  assert_eq!(set(DAY_IN_LEDGERS - 1, 30 * DAY_IN_LEDGERS), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(set(30 * DAY_IN_LEDGERS, 30 * DAY_IN_LEDGERS), Err(Ok(Error::InvalidConfig)));
+  assert_eq!(set(30 * DAY_IN_LEDGERS, max_ttl + 1), Err(Ok(Error::InvalidConfig)));
+  let policy = TtlPolicy { threshold: DAY_IN_LEDGERS, extend_to: 2 * DAY_IN_LEDGERS };
+  assert_eq!(fixture.contract.try_queue_admin_action(&fixture.client, &AdminAction::SetTtlPolicy(TtlClass::Cold, policy)), Err(Ok(Error::Unauthorized)));
+  assert_eq!(fixture.contract.get_ttl_policy(&TtlClass::Cold).extend_to, 30 * DAY_IN_LEDGERS);
+}
+
+// Index helpers
+#[test]
+fn append_unique_skips_listed_ids_and_respects_the_cap() {
+  let fixture = Fixture::new();
+  let index = IndexKind::EscrowsByState(EscrowState::Created);
+
+  fixture.env.as_contract(&fixture.contract.address, || {
+    assert!(index::append_unique(&fixture.env, &index, 7, 2));
+    assert!(index::append_unique(&fixture.env, &index, 7, 2));
+    assert!(index::append_unique(&fixture.env, &index, 8, 2));
+    assert!(!index::append_unique(&fixture.env, &index, 9, 2));
+    assert_eq!(index::paged_read(&fixture.env, &index, 0, 10).items, vec![&fixture.env, 7, 8]);
+  });
+}
+
+#[test]
+fn remove_value_drops_every_copy_and_the_emptied_entry() {
+  let fixture = Fixture::new();
+  let index = IndexKind::EscrowsByState(EscrowState::Created);
+
+  fixture.env.as_contract(&fixture.contract.address, || {
+    fixture.env.storage().persistent().set(&StorageKey::EscrowsByState(EscrowState::Created), &vec![&fixture.env, 7u64, 8, 7]);
+    assert!(index::remove_value(&fixture.env, &index, 7));
+    assert!(!index::remove_value(&fixture.env, &index, 7));
+    assert_eq!(index::paged_read(&fixture.env, &index, 0, 10).items, vec![&fixture.env, 8]);
+    assert!(index::remove_value(&fixture.env, &index, 8));
+    assert!(!fixture.env.storage().persistent().has(&StorageKey::EscrowsByState(EscrowState::Created)));
+  });
+}
+
+#[test]
+fn index_integrity_reports_duplicates_and_dangling_ids() {
+  let fixture = Fixture::new();
+  let escrow_id = fixture.open_escrow();
+  let created = IndexKind::EscrowsByState(EscrowState::Created);
+  let pair = IndexKind::PairEscrows(fixture.client.clone(), fixture.freelancer.clone());
+
+  let report = fixture.contract.check_index_integrity(&created, &0);
+  assert_eq!((report.len, report.scanned), (1, 1));
+  assert!(report.duplicates.is_empty() && report.dangling.is_empty());
+  assert!(fixture.contract.check_index_integrity(&pair, &0).dangling.is_empty());
+
+  // Corrupt the index with a repeated ID and one with no escrow behind it
+  fixture.env.as_contract(&fixture.contract.address, || {
+    fixture.env.storage().persistent().set(&StorageKey::EscrowsByState(EscrowState::Created), &vec![&fixture.env, escrow_id, 99, escrow_id]);
+  });
+  let report = fixture.contract.check_index_integrity(&created, &0);
+  assert_eq!(report.duplicates, vec![&fixture.env, escrow_id]);
+  assert_eq!(report.dangling, vec![&fixture.env, 99]);
+  assert_eq!(fixture.contract.check_index_integrity(&created, &2).scanned, 1);
+}
+
+// Read-your-writes
+// A deployer-style contract that sets up demo data: it posts a project as itself, has
+// `freelancer` bid on it and opens an escrow, listing each right after creating it. Returns
+// whether the project, the proposal and the escrow were each listed.
+#[contract]
+struct SeedingContract;
+
+#[contractimpl]
+impl SeedingContract {
+  pub fn seed(env: Env, marketplace: Address, asset: Address, freelancer: Address) -> (bool, bool, bool) {
+    let contract = EscrowServiceContractClient::new(&env, &marketplace);
+    let owner = env.current_contract_address();
+    let text = |value| String::from_str(&env, value);
+    let milestones = vec![&env, testutils::milestone(&env, "Design", 400), testutils::milestone(&env, "Build", 600)];
+
+    let project_id = contract.post_project(&owner, &text("Demo"), &text("Seeded project"), &text("web"), &BUDGET, &asset, &PROJECT_DEADLINE, &milestones, &Locale::default());
+    let projects = contract.list_projects(&0, &MAX_LIST_LIMIT).items;
+    let project_listed = projects.iter().any(|summary| summary.id == project_id);
+
+    let proposal_id = contract.submit_proposal(&freelancer, &project_id, &BUDGET, &Vec::new(&env), &BytesN::from_array(&env, &[1; 32]));
+    let proposals = contract.list_proposals(&project_id, &0, &MAX_LIST_LIMIT, &false).items;
+    let proposal_listed = proposals.iter().any(|listing| listing.proposal.id == proposal_id);
+
+    let escrow_id = contract.initiate_escrow(&owner, &project_id, &freelancer);
+    let escrow_listed = contract.list_escrows_by_state(&EscrowState::Created, &0, &MAX_LIST_LIMIT).items.contains(escrow_id);
+
+    (project_listed, proposal_listed, escrow_listed)
+  }
+}
+
+#[test]
+fn objects_are_listed_within_the_invocation_that_creates_them() {
+  let market = Marketplace::new();
+  // The freelancer's bid is authorized inside the seeding call, not at its root
+  market.env.mock_all_auths_allowing_non_root_auth();
+  let seeder = market.env.register(SeedingContract, ());
+  let freelancer = Address::generate(&market.env);
+
+  let listed = SeedingContractClient::new(&market.env, &seeder).seed(&market.contract.address, &market.asset, &freelancer);
+
+  assert_eq!(listed, (true, true, true));
+}
+
+// Drives a pseudo-random mix of transitions over several escrows and checks after every
+// step that each escrow is listed exactly under its current state and the counts agree
+#[test]
+fn state_indexes_track_random_transition_sequences() {
+  let fixture = Fixture::new();
+  let states = [EscrowState::Created, EscrowState::InProgress, EscrowState::Completed, EscrowState::Refunded];
+  let mut escrows: Vec<u64> = Vec::new(&fixture.env);
+  let mut seed: u64 = 0x2545_f491;
+  for _ in 0..40 {
+    seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+    let pick = (seed >> 33) as u32;
+    match pick % 8 {
+      0 | 1 => escrows.push_back(fixture.open_escrow()),
+      action if !escrows.is_empty() => {
+        let escrow_id = escrows.get((pick >> 3) % escrows.len()).unwrap();
+        match action {
+          2 | 3 => {
+            let _ = fixture.contract.try_deposit_funds(&fixture.client, &escrow_id, &500, &None);
+          }
+          4 => {
+            let _ = fixture.contract.try_refund_funds(&fixture.client, &escrow_id);
+          }
+          _ => {
+            let milestone_index = (pick >> 12) % 2;
+            let _ = fixture.contract.try_submit_milestone(&fixture.freelancer, &escrow_id, &milestone_index, &fixture.hash(1));
+            let _ = fixture.contract.try_release_funds(&fixture.client, &escrow_id, &milestone_index);
+          }
+        }
+      }
+      _ => {}
+    }
+
+    let counts = fixture.contract.count_escrows_by_state();
+    let counted = [counts.created, counts.in_progress, counts.completed, counts.refunded];
+    for (state, count) in states.iter().zip(counted) {
+      let listed = fixture.contract.list_escrows_by_state(state, &0, &50).items;
+      assert_eq!(listed.len(), count);
+      for escrow_id in listed.iter() {
+        assert_eq!(fixture.escrow(escrow_id).state, *state);
+      }
+    }
+    assert_eq!(counted.iter().sum::<u32>(), escrows.len());
+  }
+  // The sequence reaches every state
+  let counts = fixture.contract.count_escrows_by_state();
+  assert!(counts.created > 0 && counts.in_progress > 0 && counts.completed > 0 && counts.refunded > 0);
+}
+
+// Marketplace scenario
+// Checks that every derived view agrees with the escrows themselves: the state indexes and
+// counts, each party's stats, commitments and pair indexes, and the platform's volume, fees,
+// fee pool and obligations. `withdrawn` is what the admin has taken out of the fee pool.
+fn check_books(fixture: &Fixture, escrow_ids: &[u64], parties: &[Address], withdrawn: i128) {
+  use std::vec::Vec;
+
+  let escrows: Vec<(u64, Escrow)> = escrow_ids.iter().map(|id| (*id, fixture.escrow(*id))).collect();
+  let active = |escrow: &Escrow| matches!(escrow.state, EscrowState::Created | EscrowState::InProgress);
+
+  let counts = fixture.contract.count_escrows_by_state();
+  let counted = [counts.created, counts.in_progress, counts.completed, counts.refunded];
+  for (state, count) in [EscrowState::Created, EscrowState::InProgress, EscrowState::Completed, EscrowState::Refunded].into_iter().zip(counted) {
+    let mut listed: Vec<u64> = fixture.contract.list_escrows_by_state(&state, &0, &50).items.iter().collect();
+    listed.sort();
+    let expected: Vec<u64> = escrows.iter().filter(|(_, escrow)| escrow.state == state).map(|(id, _)| *id).collect();
+    assert_eq!((listed.len() as u32, &listed), (count, &expected));
+    let report = fixture.contract.check_index_integrity(&IndexKind::EscrowsByState(state), &0);
+    assert!(report.duplicates.is_empty() && report.dangling.is_empty());
+  }
+
+  for party in parties {
+    let as_freelancer = escrows.iter().filter(|(_, escrow)| escrow.freelancer == *party && active(escrow)).count() as u32;
+    assert_eq!(fixture.contract.get_freelancer_stats(party).active_escrows, as_freelancer);
+    let completed = escrows.iter().filter(|(_, escrow)| escrow.client == *party && escrow.state == EscrowState::Completed).count() as u32;
+    assert_eq!(fixture.contract.get_user_stats(party).completed_as_client, completed);
+    assert_eq!(fixture.contract.get_counterparty_risk(party).completed_escrows, completed);
+
+    let live = || escrows.iter().filter(|(_, escrow)| escrow.client == *party && active(escrow));
+    let unfunded: i128 = live().map(|(_, escrow)| (escrow.total_amount - escrow.deposited_amount).max(0)).sum();
+    let held: i128 = live().map(|(_, escrow)| escrow.deposited_amount - escrow.released_amount - escrow.refunded_amount).sum();
+    let commitments = fixture.contract.get_commitments(party, &fixture.asset);
+    assert_eq!((commitments.unfunded, commitments.held), (unfunded, held));
+
+    for freelancer in parties {
+      let report = fixture.contract.check_index_integrity(&IndexKind::PairEscrows(party.clone(), freelancer.clone()), &0);
+      assert_eq!(report.len, live().filter(|(_, escrow)| escrow.freelancer == *freelancer).count() as u32);
+      assert!(report.duplicates.is_empty() && report.dangling.is_empty());
+    }
+  }
+
+  let closed = || escrows.iter().filter(|(_, escrow)| !active(escrow));
+  let stats = fixture.contract.get_platform_stats();
+  let fees: i128 = closed().map(|(id, escrow)| bps_of(escrow.released_amount, fixture.contract.get_fee_terms(id).bps).unwrap()).sum();
+  assert_eq!((stats.volume, stats.fees), (closed().map(|(_, escrow)| escrow.released_amount).sum(), fees));
+  assert_eq!(fixture.contract.get_fee_pool(&fixture.asset), fees - withdrawn);
+  let obligations: i128 = escrows.iter().filter(|(_, escrow)| active(escrow)).map(|(_, escrow)| escrow.deposited_amount - escrow.released_amount - escrow.refunded_amount).sum();
+  assert_eq!(fixture.contract.get_obligations(&fixture.asset), obligations);
+  let sample = soroban_sdk::Vec::from_slice(&fixture.env, escrow_ids);
+  assert!(fixture.contract.self_check(&sample).iter().all(|result| result.passed));
+}
+
+// Two clients and three freelancers through overlapping lifecycles, checking the books
+// after every phase
+#[test]
+fn overlapping_lifecycles_keep_the_books_consistent() {
+  let fixture = Fixture::new();
+  let (alice, bianca) = (fixture.client.clone(), Address::generate(&fixture.env));
+  let (frank, grace, henry) = (fixture.freelancer.clone(), Address::generate(&fixture.env), Address::generate(&fixture.env));
+  let parties = [alice.clone(), bianca.clone(), frank.clone(), grace.clone(), henry.clone()];
+  let treasury = Address::generate(&fixture.env);
+  fixture.enact(AdminAction::SetPlatformFee(250));
+  let cover = fixture.hash(0);
+  let no_milestones = Vec::new(&fixture.env);
+
+  // Five projects draw proposals; each client picks one per project
+  let projects = [ProjectBuilder::new(&fixture, &alice).post(), ProjectBuilder::new(&fixture, &alice).post(), ProjectBuilder::new(&fixture, &bianca).post(), ProjectBuilder::new(&fixture, &bianca).post(), ProjectBuilder::new(&fixture, &bianca).post()];
+  let propose = |freelancer: &Address, project: usize, amount: i128| fixture.contract.submit_proposal(freelancer, &projects[project], &amount, &no_milestones, &cover);
+  let site = propose(&frank, 0, 1000);
+  propose(&grace, 0, 900);
+  let shop = propose(&henry, 1, 800);
+  let app = propose(&grace, 2, 600);
+  propose(&frank, 2, 650);
+  let logo = propose(&henry, 3, 500);
+  let copy = propose(&frank, 4, 700);
+  let site = fixture.contract.accept_proposal(&alice, &site);
+  let shop = fixture.contract.accept_proposal(&alice, &shop);
+  let app = fixture.contract.accept_proposal(&bianca, &app);
+  let logo = fixture.contract.accept_proposal(&bianca, &logo);
+  let copy = fixture.contract.accept_proposal(&bianca, &copy);
+  let escrows = [site, shop, app, logo, copy];
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // Funding, with one escrow only part funded; the contract holds the deposits
+  fixture.contract.deposit_funds(&alice, &site, &1000, &None);
+  fixture.contract.deposit_funds(&alice, &shop, &800, &None);
+  fixture.contract.deposit_funds(&bianca, &app, &600, &None);
+  fixture.contract.deposit_funds(&bianca, &logo, &300, &None);
+  fixture.fund_contract(2700);
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // Two escrows complete cleanly, interleaved with work on a third
+  fixture.contract.submit_milestone(&henry, &shop, &0, &fixture.hash(1));
+  testutils::run_to_completed(&fixture, site);
+  fixture.contract.release_funds(&alice, &shop, &0);
+  testutils::run_to_completed(&fixture, app);
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // A dispute on the last milestone of the third is split and completes it
+  fixture.contract.submit_milestone(&henry, &shop, &1, &fixture.hash(2));
+  fixture.contract.raise_dispute(&alice, &shop, &1);
+  check_books(&fixture, &escrows, &parties, 0);
+  fixture.contract.resolve_dispute(&fixture.admin, &shop, &1, &200);
+  assert_eq!(fixture.escrow(shop).state, EscrowState::Completed);
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // Past the deadline the part-funded escrow is refunded, and the freelancer declines the
+  // unfunded one, after which its client cancels the project
+  fixture.set_time(PROJECT_DEADLINE + 1);
+  fixture.contract.refund_funds(&bianca, &logo);
+  fixture.contract.decline_escrow(&frank, &copy);
+  fixture.contract.cancel_project(&bianca, &projects[4]);
+  assert_eq!(fixture.contract.get_project(&projects[3]).status, ProjectStatus::Cancelled);
+  let escrows = [site, shop, app, logo];
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // Ratings: the clients rate their freelancers, and the freelancers see each client's
+  // review record in its counterparty risk
+  fixture.contract.rate_freelancer(&alice, &site, &5, &fixture.text("Great"), &Vec::new(&fixture.env));
+  fixture.contract.rate_freelancer(&alice, &shop, &3, &fixture.text("Late"), &Vec::new(&fixture.env));
+  fixture.contract.rate_freelancer(&bianca, &app, &4, &fixture.text("Good"), &Vec::new(&fixture.env));
+  let summaries = [&frank, &grace, &henry].map(|freelancer| fixture.contract.get_rating_summary(freelancer));
+  assert_eq!(summaries.map(|summary| (summary.count, summary.stars)), [(1, 5), (1, 4), (1, 3)]);
+  // The disputed submission never got a review
+  assert_eq!((fixture.contract.get_counterparty_risk(&alice).reviews, fixture.contract.get_counterparty_risk(&bianca).reviews), (3, 2));
+  check_books(&fixture, &escrows, &parties, 0);
+
+  // The admin withdraws the fees: 2.5% of 1000, 520 and 600
+  let withdrawable = fixture.contract.get_withdrawable_fees(&fixture.asset);
+  assert_eq!(withdrawable, 25 + 13 + 15);
+  fixture.contract.withdraw_fees(&fixture.admin, &fixture.asset, &treasury, &withdrawable);
+  assert_eq!(token::Client::new(&fixture.env, &fixture.asset).balance(&treasury), withdrawable);
+  check_books(&fixture, &escrows, &parties, withdrawable);
+}
+
+// Resource budgets
+// What the core calls of an escrow's opening may cost, measured on the fixture's
+// two-milestone escrow. A change that pushes a call more than BUDGET_TOLERANCE_PCT over its
+// budget fails here: trim the change, or raise the budget in the same change and say why.
+// CPU is in instructions, writes in ledger bytes of the entries the call wrote, each counted
+// whole: the instance, which holds the settings, counters and an escrow's side entries, and
+// every persistent entry (project, escrow, accepted digest, index) it touched, besides the
+// caller's auth nonce. Extending an entry's TTL is not a write.
+const BUDGET_TOLERANCE_PCT: u64 = 10;
+// Posting checks the launch mode, so it decodes the config the constructor now stores, and
+// extends the project's own entry
+const POST_PROJECT_CPU: u64 = 160_000;
+// Opening writes several indexes, each an entry of its own read back once in the debug builds
+// these run in
+const INITIATE_ESCROW_CPU: u64 = 700_000;
+// The instance, the escrow and the indexes it joins. Every escrow write reads the
+// escrow's epoch counters and most count something, and the counters add to the instance.
+const INITIATE_ESCROW_WRITE_BYTES: u64 = 4_400;
+const ACCEPT_ENGAGEMENT_CPU: u64 = 640_000;
+// The instance, the escrow and the accepted digest's own entry, written once here
+const ACCEPT_ENGAGEMENT_WRITE_BYTES: u64 = 3_200;
+const FIRST_DEPOSIT_CPU: u64 = 840_000;
+// The instance, the escrow and the token balances it moves, without the accepted digest
+const FIRST_DEPOSIT_WRITE_BYTES: u64 = 4_000;
+// The escrow's ledger entry, which every write of the escrow costs in full. The accepted
+// digest lives beside it since layout 8, which took the value from 904 bytes to 840.
+const ESCROW_ENTRY_BYTES: u64 = 940;
+
+// What the last contract call cost
+struct Cost {
+  cpu: u64,
+  write_bytes: u64,
+}
+
+fn last_call_cost(env: &Env) -> Cost {
+  let estimate = env.cost_estimate();
+  Cost { cpu: estimate.budget().cpu_instruction_cost(), write_bytes: estimate.resources().write_bytes as u64 }
+}
+
+fn assert_within_budget(call: &str, what: &str, cost: u64, budget: u64) {
+  let limit = budget + budget * BUDGET_TOLERANCE_PCT / 100;
+  assert!(cost <= limit, "{call} {what} {cost} is over its budget of {budget} by more than {BUDGET_TOLERANCE_PCT}%");
+}
+
+#[test]
+fn opening_an_escrow_stays_within_its_resource_budgets() {
+  let fixture = Fixture::new();
+
+  let project_id = fixture.post_project();
+  let post = last_call_cost(&fixture.env);
+  let escrow_id = fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+  let initiate = last_call_cost(&fixture.env);
+  fixture.contract.accept_engagement(&fixture.freelancer, &escrow_id, &fixture.digest(escrow_id));
+  let accept = last_call_cost(&fixture.env);
+  fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+  let deposit = last_call_cost(&fixture.env);
+
+  assert_within_budget("post_project", "cpu", post.cpu, POST_PROJECT_CPU);
+  assert_within_budget("initiate_escrow", "cpu", initiate.cpu, INITIATE_ESCROW_CPU);
+  assert_within_budget("initiate_escrow", "write bytes", initiate.write_bytes, INITIATE_ESCROW_WRITE_BYTES);
+  assert_within_budget("accept_engagement", "cpu", accept.cpu, ACCEPT_ENGAGEMENT_CPU);
+  assert_within_budget("accept_engagement", "write bytes", accept.write_bytes, ACCEPT_ENGAGEMENT_WRITE_BYTES);
+  assert_within_budget("deposit_funds", "cpu", deposit.cpu, FIRST_DEPOSIT_CPU);
+  assert_within_budget("deposit_funds", "write bytes", deposit.write_bytes, FIRST_DEPOSIT_WRITE_BYTES);
+  let entry = fixture.entry_bytes(&StorageKey::Escrows(escrow_id));
+  assert_within_budget("escrow entry", "bytes", entry, ESCROW_ENTRY_BYTES);
+}
+
+#[test]
+fn an_escrow_write_does_not_rewrite_the_accepted_digest() {
+  // The same first deposit, on escrows that differ only in having an accepted digest
+  let deposit_write_bytes = |accepted: bool| {
+    let fixture = Fixture::new();
+    let project_id = fixture.post_project();
+    let escrow_id = fixture.contract.initiate_escrow(&fixture.client, &project_id, &fixture.freelancer);
+    if accepted {
+      let digest = fixture.digest(escrow_id);
+      fixture.env.as_contract(&fixture.contract.address, || storage::accepted_terms(escrow_id).set(&fixture.env, &digest));
+    }
+    fixture.contract.deposit_funds(&fixture.client, &escrow_id, &BUDGET, &None);
+    last_call_cost(&fixture.env).write_bytes
+  };
+
+  // Kept inside the escrow, as before layout 8, or beside it in the instance, as before layout
+  // 12, the digest was rewritten with every change to the escrow
+  assert_eq!(deposit_write_bytes(true), deposit_write_bytes(false));
+}
+
+#[test]
+fn a_call_over_its_budget_by_more_than_the_tolerance_fails() {
+  assert_within_budget("call", "cpu", 110, 100);
+  let over = std::panic::catch_unwind(|| assert_within_budget("call", "cpu", 111, 100));
+  assert!(over.is_err());
+}
+
+// Storage migration
+const LAYOUT_V1_FIXTURE: &str = include_str!("../test_fixtures/layout_v1.snap");
+const LAYOUT_V2_FIXTURE: &str = include_str!("../test_fixtures/layout_v2.snap");
+const LAYOUT_V3_FIXTURE: &str = include_str!("../test_fixtures/layout_v3.snap");
+const LAYOUT_V4_FIXTURE: &str = include_str!("../test_fixtures/layout_v4.snap");
+const LAYOUT_V5_FIXTURE: &str = include_str!("../test_fixtures/layout_v5.snap");
+const LAYOUT_V6_FIXTURE: &str = include_str!("../test_fixtures/layout_v6.snap");
+const LAYOUT_V7_FIXTURE: &str = include_str!("../test_fixtures/layout_v7.snap");
+const LAYOUT_V8_FIXTURE: &str = include_str!("../test_fixtures/layout_v8.snap");
+const LAYOUT_V9_FIXTURE: &str = include_str!("../test_fixtures/layout_v9.snap");
+const LAYOUT_V10_FIXTURE: &str = include_str!("../test_fixtures/layout_v10.snap");
+
+// Writes a contract state in an older storage layout
+type LayoutWriter = fn(&Fixture);
+
+// Writes, in layout 1, an open project and a funded escrow with its first milestone paid
+// and the second one submitted
+fn write_layout_v1_state(fixture: &Fixture) {
+  let env = &fixture.env;
+  let milestones = vec![
+    env,
+    MilestoneV1 { description: fixture.text("Design"), amount: 400, completed: true, deadline: 0 },
+    MilestoneV1 { description: fixture.text("Build"), amount: 600, completed: false, deadline: 0 },
+  ];
+  let project = |id: u64, status: ProjectStatus| ProjectV1 {
+    id,
+    client: fixture.client.clone(),
+    title: fixture.text("Site"),
+    description: fixture.text("A website"),
+    category: fixture.text("web"),
+    budget: 1000,
+    deadline: PROJECT_DEADLINE,
+    milestones: milestones.clone(),
+    status,
+  };
+  let escrow = EscrowV1 {
+    project_id: 1,
+    client: fixture.client.clone(),
+    freelancer: fixture.freelancer.clone(),
+    total_amount: 1000,
+    milestones: milestones.clone(),
+    deposited_amount: 1000,
+    released_amount: 400,
+    state: EscrowState::InProgress,
+    client_active_at: 120,
+    lump_sum: false,
+    accepted_terms: None,
+  };
+  let records = vec![
+    env,
+    MilestoneRecordV1 { status: MilestoneStatus::Released, deliverable: Some(fixture.hash(1)), submitted_at: 100, paid: 400 },
+    MilestoneRecordV1 { status: MilestoneStatus::Submitted, deliverable: Some(fixture.hash(2)), submitted_at: 200, paid: 0 },
+  ];
+  let deposits = vec![env, DepositV1 { depositor: fixture.client.clone(), amount: 1000, memo: None, timestamp: 50 }];
+
+  env.as_contract(&fixture.contract.address, || {
+    let storage = env.storage().instance();
+    storage.remove(&StorageKey::DataVersion);
+    storage.remove(&StorageKey::Config);
+    storage.set(&StorageKey::ProjectCount, &2u64);
+    storage.set(&StorageKey::Projects(1), &project(1, ProjectStatus::InProgress));
+    storage.set(&StorageKey::Projects(2), &project(2, ProjectStatus::Open));
+    storage.set(&StorageKey::EscrowCount, &1u64);
+    storage.set(&StorageKey::Escrows(1), &escrow);
+    storage.set(&StorageKey::MilestoneRecords(1), &records);
+    storage.set(&StorageKey::Deposits(1), &deposits);
+    storage.set(&StorageKey::EscrowStateCount(EscrowState::InProgress), &1u32);
+    storage.set(&StorageKey::EscrowsByState(EscrowState::InProgress), &vec![env, 1u64]);
+    storage.set(&StorageKey::PairEscrows(fixture.client.clone(), fixture.freelancer.clone()), &vec![env, 1u64]);
+  });
+}
+
+// Writes a funded escrow with nothing paid yet, in a layout whose projects had no asset
+fn write_funded_escrow_state(fixture: &Fixture, version: u32, escrow: Val) {
+  let env = &fixture.env;
+  let project = ProjectV3 {
+    id: 1,
+    client: fixture.client.clone(),
+    title: fixture.text("Site"),
+    description: fixture.text("A website"),
+    category: fixture.text("web"),
+    budget: BUDGET,
+    deadline: PROJECT_DEADLINE,
+    milestones: fixture.layout_milestones(),
+    status: ProjectStatus::InProgress,
+  };
+  let pending = MilestoneRecord { status: MilestoneStatus::Pending, deliverable: None, submitted_at: 0, paid: 0 };
+  let deposits = vec![env, Deposit { depositor: fixture.client.clone(), amount: BUDGET, memo: None, timestamp: 50 }];
+
+  env.as_contract(&fixture.contract.address, || {
+    let storage = env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &version);
+    storage.remove(&StorageKey::Config);
+    storage.set(&StorageKey::ProjectCount, &1u64);
+    storage.set(&StorageKey::Projects(1), &project);
+    storage.set(&StorageKey::EscrowCount, &1u64);
+    storage.set(&StorageKey::Escrows(1), &escrow);
+    storage.set(&StorageKey::MilestoneRecords(1), &vec![env, pending.clone(), pending]);
+    storage.set(&StorageKey::Deposits(1), &deposits);
+    storage.set(&StorageKey::EscrowStateCount(EscrowState::InProgress), &1u32);
+    storage.set(&StorageKey::EscrowsByState(EscrowState::InProgress), &vec![env, 1u64]);
+    storage.set(&StorageKey::PairEscrows(fixture.client.clone(), fixture.freelancer.clone()), &vec![env, 1u64]);
+  });
+}
+
+fn write_layout_v2_state(fixture: &Fixture) {
+  let escrow = EscrowV2 {
+    project_id: 1,
+    client: fixture.client.clone(),
+    freelancer: fixture.freelancer.clone(),
+    total_amount: BUDGET,
+    milestones: fixture.layout_milestones(),
+    deposited_amount: BUDGET,
+    released_amount: 0,
+    state: EscrowState::InProgress,
+    created_at: 40,
+    client_active_at: 50,
+    lump_sum: false,
+    accepted_terms: None,
+  };
+  write_funded_escrow_state(fixture, 2, escrow.into_val(&fixture.env));
+}
+
+fn write_layout_v3_state(fixture: &Fixture) {
+  let escrow = EscrowV3 {
+    project_id: 1,
+    client: fixture.client.clone(),
+    freelancer: fixture.freelancer.clone(),
+    total_amount: BUDGET,
+    milestones: fixture.layout_milestones(),
+    deposited_amount: BUDGET,
+    released_amount: 0,
+    refunded_amount: 0,
+    state: EscrowState::InProgress,
+    created_at: 40,
+    client_active_at: 50,
+    lump_sum: false,
+    accepted_terms: None,
+  };
+  write_funded_escrow_state(fixture, 3, escrow.into_val(&fixture.env));
+}
+
+// Layout 4 only differs in the config, so its fixture holds just a config set by the admin
+fn write_layout_v4_state(fixture: &Fixture) {
+  let config = ConfigV4 { review_period_secs: 3 * DAY, bundle_grace_secs: 10 * DAY };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &4u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// Layout 5 only differs in the config too
+fn write_layout_v5_state(fixture: &Fixture) {
+  let config = ConfigV5 { review_period_secs: 3 * DAY, bundle_grace_secs: 10 * DAY, contest_window_secs: 2 * DAY };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &5u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// And so does layout 6
+fn write_layout_v6_state(fixture: &Fixture) {
+  let config = ConfigV6 { review_period_secs: 3 * DAY, bundle_grace_secs: 10 * DAY, contest_window_secs: 2 * DAY, revoke_window_secs: 60 };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &6u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// Layout 8 differs from 7 only in the config
+fn write_layout_v8_state(fixture: &Fixture) {
+  let config = ConfigV8 { review_period_secs: 3 * DAY, bundle_grace_secs: 10 * DAY, contest_window_secs: 2 * DAY, revoke_window_secs: 60, rate_max_age_secs: 2 * DAY };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &8u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// And so does layout 9, an allowlisted marketplace here
+fn write_layout_v9_state(fixture: &Fixture) {
+  let config = ConfigV9 {
+    review_period_secs: 3 * DAY,
+    bundle_grace_secs: 10 * DAY,
+    contest_window_secs: 2 * DAY,
+    revoke_window_secs: 60,
+    rate_max_age_secs: 2 * DAY,
+    launch_mode: LaunchMode::Allowlist,
+  };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &9u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// Layout 10 too, with a cap on new freelancers
+fn write_layout_v10_state(fixture: &Fixture) {
+  let config = ConfigV10 {
+    review_period_secs: 3 * DAY,
+    bundle_grace_secs: 10 * DAY,
+    contest_window_secs: 2 * DAY,
+    revoke_window_secs: 60,
+    rate_max_age_secs: 2 * DAY,
+    launch_mode: LaunchMode::Open,
+    risk_caps: RiskCaps { new_cap: 5_000, established_cap: 0, established_after: 0 },
+  };
+  fixture.env.as_contract(&fixture.contract.address, || {
+    let storage = fixture.env.storage().instance();
+    storage.set(&StorageKey::DataVersion, &10u32);
+    storage.set(&StorageKey::Config, &config);
+  });
+}
+
+// An accepted, funded escrow whose accepted digest is still inside it, in the asset-bearing
+// layout every version from 4 to 7 stored escrows in
+fn write_layout_v7_state(fixture: &Fixture) {
+  let escrow = EscrowV7 {
+    project_id: 1,
+    client: fixture.client.clone(),
+    freelancer: fixture.freelancer.clone(),
+    total_amount: BUDGET,
+    asset: fixture.asset.clone(),
+    milestones: fixture.layout_milestones(),
+    deposited_amount: BUDGET,
+    released_amount: 0,
+    refunded_amount: 0,
+    state: EscrowState::InProgress,
+    created_at: 40,
+    client_active_at: 50,
+    lump_sum: false,
+    accepted_terms: Some(fixture.hash(7)),
+  };
+  write_funded_escrow_state(fixture, 7, escrow.into_val(&fixture.env));
+  let project = Project {
+    id: 1,
+    client: fixture.client.clone(),
+    title: fixture.text("Site"),
+    description: fixture.text("A website"),
+    category: fixture.text("web"),
+    budget: BUDGET,
+    asset: fixture.asset.clone(),
+    deadline: PROJECT_DEADLINE,
+    milestones: fixture.layout_milestones(),
+    status: ProjectStatus::InProgress,
+  };
+  fixture.env.as_contract(&fixture.contract.address, || fixture.env.storage().instance().set(&StorageKey::Projects(1), &project));
+}
+
+// The committed fixtures are what the layout writers capture to. Set UPDATE_FIXTURES=1 to
+// rewrite them after an intentional change.
+#[test]
+fn layout_fixtures_are_deterministic() {
+  let writers: [(LayoutWriter, &str, &str); 10] = [
+    (write_layout_v1_state, "layout_v1.snap", LAYOUT_V1_FIXTURE),
+    (write_layout_v2_state, "layout_v2.snap", LAYOUT_V2_FIXTURE),
+    (write_layout_v3_state, "layout_v3.snap", LAYOUT_V3_FIXTURE),
+    (write_layout_v4_state, "layout_v4.snap", LAYOUT_V4_FIXTURE),
+    (write_layout_v5_state, "layout_v5.snap", LAYOUT_V5_FIXTURE),
+    (write_layout_v6_state, "layout_v6.snap", LAYOUT_V6_FIXTURE),
+    (write_layout_v7_state, "layout_v7.snap", LAYOUT_V7_FIXTURE),
+    (write_layout_v8_state, "layout_v8.snap", LAYOUT_V8_FIXTURE),
+    (write_layout_v9_state, "layout_v9.snap", LAYOUT_V9_FIXTURE),
+    (write_layout_v10_state, "layout_v10.snap", LAYOUT_V10_FIXTURE),
+  ];
+  for (write, file, committed) in writers {
+    let fixture = Fixture::new();
+    write(&fixture);
+
+    let captured = snapshot::capture(&fixture.env, &fixture.contract.address);
+
+    if std::env::var("UPDATE_FIXTURES").is_ok() {
+      std::fs::write(std::format!("{}/test_fixtures/{}", env!("CARGO_MANIFEST_DIR"), file), &captured).unwrap();
+      continue;
+    }
+    assert_eq!(captured, committed);
+  }
+}
+
+#[test]
+fn layout_v1_fixture_reads_correctly_after_migration() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V1_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 1);
+  assert_eq!(fixture.contract.try_migrate(&fixture.admin, &1), Err(Ok(Error::InvalidConfig)));
+  fixture.enact(AdminAction::SetLegacyAsset(fixture.asset.clone()));
+
+  // Bounded batches: the first call only reaches ID 1
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), 1);
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  // Amounts read back as i128 and the escrow gets its opening time from the first deposit
+  let escrow = fixture.escrow(1);
+  assert_eq!((escrow.total_amount, escrow.deposited_amount, escrow.released_amount), (1000, 1000, 400));
+  assert_eq!((escrow.created_at, escrow.client_active_at), (50, 120));
+  assert_eq!(escrow.refunded_amount, 0);
+  assert_eq!(escrow.milestones.get(1).unwrap().amount, 600i128);
+  assert_eq!(fixture.contract.get_deposits(&1, &0, &MAX_LIST_LIMIT).items.get(0).unwrap().amount, 1000i128);
+  assert_eq!(fixture.contract.get_project(&2).budget, 1000i128);
+  assert_eq!(fixture.contract.get_project(&2).asset, fixture.asset);
+  let commitments = fixture.contract.get_commitments(&fixture.client, &fixture.asset);
+  assert_eq!((commitments.unfunded, commitments.held), (0, 600));
+  let record = fixture.contract.get_milestone_records(&1).get(0).unwrap();
+  assert_eq!(record, MilestoneRecord { status: MilestoneStatus::Released, deliverable: Some(fixture.hash(1)), submitted_at: 100, paid: 400 });
+
+  // The migrated escrow carries on under the current code
+  fixture.contract.release_funds(&fixture.client, &1, &1);
+  let escrow = fixture.escrow(1);
+  assert_eq!((escrow.state, escrow.released_amount), (EscrowState::Completed, 1000));
+  assert_eq!(fixture.contract.count_escrows_by_state().completed, 1);
+}
+
+// Layouts 2 and 3 hold the same funded escrow and read back the same way
+#[test]
+fn layout_v2_and_v3_fixtures_read_correctly_after_migration() {
+  for (version, state) in [(2, LAYOUT_V2_FIXTURE), (3, LAYOUT_V3_FIXTURE)] {
+    let fixture = Fixture::new();
+    snapshot::restore(&fixture.env, &fixture.contract.address, state);
+    assert_eq!(fixture.contract.get_data_version(), version);
+    fixture.enact(AdminAction::SetLegacyAsset(fixture.asset.clone()));
+
+    assert_eq!(fixture.contract.migrate(&fixture.admin, &10), migration::DATA_VERSION);
+
+    let escrow = fixture.escrow(1);
+    assert_eq!((escrow.deposited_amount, escrow.released_amount, escrow.refunded_amount), (BUDGET, 0, 0));
+    assert_eq!((escrow.state, escrow.created_at), (EscrowState::InProgress, 40));
+    assert_eq!(escrow.asset, fixture.asset);
+    assert_eq!(fixture.contract.get_project(&1).asset, fixture.asset);
+    assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.asset).held, BUDGET);
+    fixture.release_all(1);
+    assert_eq!(fixture.escrow(1).state, EscrowState::Completed);
+    assert_eq!(fixture.contract.get_commitments(&fixture.client, &fixture.asset).held, 0);
+  }
+}
+
+// The stored config keeps its values and gets the default contest and revoke windows
+#[test]
+fn layout_v4_config_reads_correctly_after_migration() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V4_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 4);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!((config.review_period_secs, config.bundle_grace_secs, config.contest_window_secs), (3 * DAY, 10 * DAY, 5 * DAY));
+  assert_eq!(config.revoke_window_secs, 600);
+}
+
+#[test]
+fn layout_v5_config_gets_the_default_revoke_window() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V5_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 5);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!((config.review_period_secs, config.bundle_grace_secs, config.contest_window_secs), (3 * DAY, 10 * DAY, 2 * DAY));
+  assert_eq!(config.revoke_window_secs, 600);
+}
+
+#[test]
+fn layout_v6_config_gets_the_default_rate_age() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V6_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 6);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!((config.contest_window_secs, config.revoke_window_secs, config.rate_max_age_secs), (2 * DAY, 60, DAY));
+}
+
+#[test]
+fn layout_v8_config_stays_open_to_everyone() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V8_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 8);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!((config.revoke_window_secs, config.rate_max_age_secs, config.launch_mode), (60, 2 * DAY, LaunchMode::Open));
+  fixture.post_project();
+}
+
+#[test]
+fn layout_v9_config_starts_uncapped() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V9_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 9);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!((config.rate_max_age_secs, config.launch_mode), (2 * DAY, LaunchMode::Allowlist));
+  assert_eq!(config.risk_caps, RiskCaps { new_cap: 0, established_cap: 0, established_after: 0 });
+}
+
+#[test]
+fn layout_v10_config_keeps_the_legacy_api_on() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V10_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 10);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &1), migration::DATA_VERSION);
+
+  let config = fixture.contract.get_config();
+  assert_eq!(config.risk_caps, RiskCaps { new_cap: 5_000, established_cap: 0, established_after: 0 });
+  assert!(config.legacy_api);
+}
+
+// The accepted digest moves out of the escrow, which keeps going under the current code
+#[test]
+fn layout_v7_escrow_keeps_its_accepted_terms_beside_it() {
+  let fixture = Fixture::new();
+  snapshot::restore(&fixture.env, &fixture.contract.address, LAYOUT_V7_FIXTURE);
+  assert_eq!(fixture.contract.get_data_version(), 7);
+
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &10), migration::DATA_VERSION);
+
+  assert_eq!(fixture.contract.get_accepted_terms(&1), Some(fixture.hash(7)));
+  let escrow = fixture.escrow(1);
+  assert_eq!((escrow.asset, escrow.deposited_amount, escrow.created_at), (fixture.asset.clone(), BUDGET, 40));
+  assert_eq!(fixture.contract.try_accept_engagement(&fixture.freelancer, &1, &fixture.hash(7)), Err(Ok(Error::AlreadyAccepted)));
+  fixture.release_all(1);
+  assert_eq!(fixture.escrow(1).state, EscrowState::Completed);
+}
+
+#[test]
+fn fresh_contracts_start_on_the_current_layout() {
+  let fixture = Fixture::new();
+
+  assert_eq!(fixture.contract.get_data_version(), migration::DATA_VERSION);
+  assert_eq!(fixture.contract.migrate(&fixture.admin, &10), migration::DATA_VERSION);
+  assert_eq!(fixture.contract.try_migrate(&fixture.client, &10), Err(Ok(Error::Unauthorized)));
+}
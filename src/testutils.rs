@@ -0,0 +1,284 @@
+// Builders and golden fixtures for tests against the contract, this crate's own and those of
+// frontends and bots built on it (the `testutils` feature). Everything goes through the
+// contract client with auths mocked, the way a deployed contract is driven, so a state built
+// here is one the contract could reach on-chain.
+
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{ vec, Address, BytesN, Env, String, Vec };
+
+use crate::{ EscrowServiceContract, EscrowServiceContractClient, EscrowState, EscrowView, Locale, Milestone, MilestoneStatus };
+
+// The default project: "Design" for DESIGN_AMOUNT, then "Build" for the rest of BUDGET, due
+// by PROJECT_DEADLINE
+pub const BUDGET: i128 = 1_000;
+pub const DESIGN_AMOUNT: i128 = 400;
+pub const PROJECT_DEADLINE: u64 = 10_000;
+
+// A freshly registered marketplace, its admin and an asset to post projects in. Escrow
+// amounts are only counted, never transferred, so nobody needs a balance of the asset.
+pub struct Marketplace<'a> {
+  pub env: Env,
+  pub admin: Address,
+  pub contract: EscrowServiceContractClient<'a>,
+  pub asset: Address,
+}
+
+impl Marketplace<'_> {
+  pub fn new() -> Self {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let contract_id = env.register(EscrowServiceContract, (&admin,));
+    let contract = EscrowServiceContractClient::new(&env, &contract_id);
+    let asset = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    Marketplace { env, admin, contract, asset }
+  }
+
+  // Where an escrow stands, read as the admin
+  pub fn escrow(&self, escrow_id: u64) -> EscrowSummary {
+    match self.contract.get_escrow(&self.admin, &escrow_id) {
+      EscrowView::Full(escrow) => EscrowSummary {
+        client: escrow.client,
+        freelancer: escrow.freelancer,
+        state: escrow.state,
+        total: escrow.total_amount,
+        deposited: escrow.deposited_amount,
+        released: escrow.released_amount,
+        refunded: escrow.refunded_amount,
+        lump_sum: escrow.lump_sum,
+        accepted: self.contract.get_accepted_terms(&escrow_id).is_some(),
+      },
+      EscrowView::Header(_) => panic!("the admin sees the full escrow"),
+    }
+  }
+}
+
+impl Default for Marketplace<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// What an escrow came to, in fields a test outside the crate can read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowSummary {
+  pub client: Address,
+  pub freelancer: Address,
+  pub state: EscrowState,
+  pub total: i128,
+  pub deposited: i128,
+  pub released: i128,
+  pub refunded: i128,
+  pub lump_sum: bool,
+  pub accepted: bool,
+}
+
+pub fn milestone(env: &Env, description: &str, amount: i128) -> Milestone {
+  Milestone { description: String::from_str(env, description), amount, completed: false, deadline: 0 }
+}
+
+// Projects
+pub struct ProjectBuilder<'m, 'a> {
+  market: &'m Marketplace<'a>,
+  client: Address,
+  title: String,
+  description: String,
+  category: String,
+  budget: i128,
+  deadline: u64,
+  milestones: Vec<Milestone>,
+  locale: Locale,
+}
+
+impl<'m, 'a> ProjectBuilder<'m, 'a> {
+  // Starts from the default project
+  pub fn new(market: &'m Marketplace<'a>, client: &Address) -> Self {
+    let env = &market.env;
+    ProjectBuilder {
+      market,
+      client: client.clone(),
+      title: String::from_str(env, "Site"),
+      description: String::from_str(env, "A website"),
+      category: String::from_str(env, "web"),
+      budget: BUDGET,
+      deadline: PROJECT_DEADLINE,
+      milestones: vec![env, milestone(env, "Design", DESIGN_AMOUNT), milestone(env, "Build", BUDGET - DESIGN_AMOUNT)],
+      locale: Locale::default(),
+    }
+  }
+
+  pub fn title(mut self, title: &str) -> Self {
+    self.title = String::from_str(&self.market.env, title);
+    self
+  }
+
+  pub fn description(mut self, description: &str) -> Self {
+    self.description = String::from_str(&self.market.env, description);
+    self
+  }
+
+  pub fn category(mut self, category: &str) -> Self {
+    self.category = String::from_str(&self.market.env, category);
+    self
+  }
+
+  // Replaces the milestones; the budget becomes their sum
+  pub fn milestones(mut self, milestones: Vec<Milestone>) -> Self {
+    self.budget = milestones.iter().map(|milestone| milestone.amount).sum();
+    self.milestones = milestones;
+    self
+  }
+
+  // A project without milestones, which opens a lump-sum escrow
+  pub fn lump_sum(mut self, budget: i128) -> Self {
+    self.budget = budget;
+    self.milestones = Vec::new(&self.market.env);
+    self
+  }
+
+  pub fn deadline(mut self, deadline: u64) -> Self {
+    self.deadline = deadline;
+    self
+  }
+
+  pub fn locale(mut self, locale: Locale) -> Self {
+    self.locale = locale;
+    self
+  }
+
+  pub fn post(self) -> u64 {
+    self.market.contract.post_project(&self.client, &self.title, &self.description, &self.category, &self.budget, &self.market.asset, &self.deadline, &self.milestones, &self.locale)
+  }
+}
+
+// Escrows
+pub struct EscrowBuilder<'m, 'a> {
+  project: ProjectBuilder<'m, 'a>,
+  freelancer: Address,
+  deposit: i128,
+  accept: bool,
+}
+
+impl<'m, 'a> EscrowBuilder<'m, 'a> {
+  // An escrow on the default project, opened and nothing more
+  pub fn new(market: &'m Marketplace<'a>, client: &Address, freelancer: &Address) -> Self {
+    EscrowBuilder { project: ProjectBuilder::new(market, client), freelancer: freelancer.clone(), deposit: 0, accept: false }
+  }
+
+  pub fn project(mut self, build: impl FnOnce(ProjectBuilder<'m, 'a>) -> ProjectBuilder<'m, 'a>) -> Self {
+    self.project = build(self.project);
+    self
+  }
+
+  // The client deposits `amount` once the escrow is open
+  pub fn deposit(mut self, amount: i128) -> Self {
+    self.deposit = amount;
+    self
+  }
+
+  // The client deposits the whole budget
+  pub fn funded(mut self) -> Self {
+    self.deposit = self.project.budget;
+    self
+  }
+
+  // The freelancer accepts the terms once the escrow is open (and funded, if it is)
+  pub fn accepted(mut self) -> Self {
+    self.accept = true;
+    self
+  }
+
+  // Posts the project and opens the escrow, returning the escrow ID
+  pub fn open(self) -> u64 {
+    let (market, client) = (self.project.market, self.project.client.clone());
+    let project_id = self.project.post();
+    let escrow_id = market.contract.initiate_escrow(&client, &project_id, &self.freelancer);
+    if self.deposit > 0 {
+      market.contract.deposit_funds(&client, &escrow_id, &self.deposit, &None);
+    }
+    if self.accept {
+      market.contract.accept_engagement(&self.freelancer, &escrow_id, &market.contract.terms_digest(&escrow_id));
+    }
+    escrow_id
+  }
+}
+
+// The client deposits whatever the escrow still misses, and the freelancer accepts the terms
+// as they stand, unless they already have
+pub fn fund_and_accept(market: &Marketplace, escrow_id: u64) {
+  let escrow = market.escrow(escrow_id);
+  if escrow.deposited < escrow.total {
+    market.contract.deposit_funds(&escrow.client, &escrow_id, &(escrow.total - escrow.deposited), &None);
+  }
+  if !escrow.accepted {
+    market.contract.accept_engagement(&escrow.freelancer, &escrow_id, &market.contract.terms_digest(&escrow_id));
+  }
+}
+
+// Funds and accepts the escrow as needed, then has the freelancer deliver every pending
+// milestone (or the deliverable) and the client approve each submission. Milestone i is
+// delivered with a hash of bytes i + 1.
+pub fn run_to_completed(market: &Marketplace, escrow_id: u64) {
+  fund_and_accept(market, escrow_id);
+  let escrow = market.escrow(escrow_id);
+  let contract = &market.contract;
+  if escrow.lump_sum {
+    contract.submit_deliverable(&escrow.freelancer, &escrow_id, &deliverable(&market.env, 0));
+    contract.approve_deliverable(&escrow.client, &escrow_id);
+    return;
+  }
+  for (milestone_index, record) in contract.get_milestone_records(&escrow_id).iter().enumerate() {
+    let milestone_index = milestone_index as u32;
+    if record.status == MilestoneStatus::Pending {
+      contract.submit_milestone(&escrow.freelancer, &escrow_id, &milestone_index, &deliverable(&market.env, milestone_index));
+    }
+    if matches!(record.status, MilestoneStatus::Pending | MilestoneStatus::Submitted) {
+      contract.release_funds(&escrow.client, &escrow_id, &milestone_index);
+    }
+  }
+}
+
+fn deliverable(env: &Env, milestone_index: u32) -> BytesN<32> {
+  BytesN::from_array(env, &[milestone_index as u8 + 1; 32])
+}
+
+// Golden fixtures
+// Canonical escrow states on the default project, each between a fresh client and freelancer
+pub struct GoldenEscrow {
+  pub escrow_id: u64,
+  pub client: Address,
+  pub freelancer: Address,
+}
+
+fn golden<'m, 'a>(market: &'m Marketplace<'a>, build: impl FnOnce(EscrowBuilder<'m, 'a>) -> EscrowBuilder<'m, 'a>) -> GoldenEscrow {
+  let (client, freelancer) = (Address::generate(&market.env), Address::generate(&market.env));
+  let escrow_id = build(EscrowBuilder::new(market, &client, &freelancer)).open();
+  GoldenEscrow { escrow_id, client, freelancer }
+}
+
+// Funded, accepted, and both milestones delivered and paid: Completed with BUDGET released
+pub fn completed_escrow(market: &Marketplace) -> GoldenEscrow {
+  let golden = golden(market, |builder| builder.funded().accepted());
+  run_to_completed(market, golden.escrow_id);
+  golden
+}
+
+// Funded and accepted, Design paid, Build submitted and disputed by the client: InProgress
+// with DESIGN_AMOUNT released and an open dispute on milestone 1
+pub fn disputed_escrow(market: &Marketplace) -> GoldenEscrow {
+  let golden = golden(market, |builder| builder.funded().accepted());
+  let contract = &market.contract;
+  contract.submit_milestone(&golden.freelancer, &golden.escrow_id, &0, &deliverable(&market.env, 0));
+  contract.release_funds(&golden.client, &golden.escrow_id, &0);
+  contract.submit_milestone(&golden.freelancer, &golden.escrow_id, &1, &deliverable(&market.env, 1));
+  contract.raise_dispute(&golden.client, &golden.escrow_id, &1);
+  golden
+}
+
+// DESIGN_AMOUNT deposited, then taken back by the client before the escrow was fully funded:
+// Refunded with DESIGN_AMOUNT refunded
+pub fn refunded_escrow(market: &Marketplace) -> GoldenEscrow {
+  let golden = golden(market, |builder| builder.deposit(DESIGN_AMOUNT));
+  market.contract.refund_funds(&golden.client, &golden.escrow_id);
+  golden
+}
@@ -0,0 +1,177 @@
+// The admin queue: admin changes are announced on-chain before they apply. The admin queues
+// an action; once the delay of its class has passed anyone can execute it, and until then the
+// admin can cancel it. Bans and reference rates are the immediate class: a ban can't wait,
+// and a price observed a day ago is already past the default maximum rate age.
+
+use soroban_sdk::{ Address, Env };
+
+use crate::admin::{ add_registry_entry, check_asset_migration, check_config_change, check_label, check_min_deposit_increment, check_platform_fee, check_reference_rate, require_admin, restrict_to_allowlist, set_allowlisted, set_asset_migration, set_config, set_label, set_legacy_asset, set_min_deposit_increment, set_nonprofit, set_platform_fee, set_reference_rate };
+use crate::dispute::{ add_arbitrator, check_arbitration_policy, check_arbitrator_room, check_split, force_resolve, overturn_ruling, promote_arbitrator, set_arbitration_policy, set_random_assignment };
+use crate::recovery::{ check_recovery, recover };
+use crate::risk::set_verified;
+use crate::template::{ add_category_template, check_new_template, check_template_update, update_category_template };
+use crate::user::deactivate;
+use crate::{ events, storage, ttl, AdminAction, Error, QueuedAction, DAY_SECS, RECOVERY_DELAY_SECS };
+
+// Delay before a settings change applies
+pub const SETTINGS_DELAY_SECS: u64 = DAY_SECS;
+// Delay before the admin can settle a dispute over its assigned arbitrator's head
+pub const FORCE_RESOLVE_DELAY_SECS: u64 = 3 * DAY_SECS;
+// Delay before an open marketplace goes back to allowlist mode
+pub const LAUNCH_MODE_DELAY_SECS: u64 = 7 * DAY_SECS;
+
+pub fn queue_admin_action(env: &Env, admin: Address, action: AdminAction) -> Result<u64, Error> {
+  require_admin(env, &admin)?;
+  // Refused now rather than at execution, where the announced change would only fail
+  check_action(env, &action)?;
+
+  let action_id = storage::admin_action_count().get(env).unwrap_or(0) + 1;
+  let queued_at = env.ledger().timestamp();
+  let execute_after = queued_at + delay(&action);
+  events::action_queued(env, action_id, &action, execute_after);
+  storage::admin_actions(action_id).set(env, &QueuedAction { action, queued_at, execute_after });
+  storage::admin_action_count().set(env, &action_id);
+  Ok(action_id)
+}
+
+pub fn execute_admin_action(env: &Env, action_id: u64) -> Result<(), Error> {
+  let key = storage::admin_actions(action_id);
+  let queued = key.get(env).ok_or(Error::NotFound)?;
+  if env.ledger().timestamp() < queued.execute_after {
+    return Err(Error::TooEarly);
+  }
+  key.remove(env);
+  apply(env, queued.action)?;
+  events::action_executed(env, action_id);
+  Ok(())
+}
+
+pub fn cancel_admin_action(env: &Env, admin: Address, action_id: u64) -> Result<(), Error> {
+  require_admin(env, &admin)?;
+  let key = storage::admin_actions(action_id);
+  if !key.has(env) {
+    return Err(Error::NotFound);
+  }
+  key.remove(env);
+  events::action_cancelled(env, action_id);
+  Ok(())
+}
+
+pub fn get_admin_action(env: &Env, action_id: u64) -> Option<QueuedAction> {
+  storage::admin_actions(action_id).get(env)
+}
+
+// Every action names its class here, so a new kind of action can't slip in without a delay
+pub fn delay(action: &AdminAction) -> u64 {
+  match action {
+    AdminAction::SetConfig(_)
+    | AdminAction::SetTtlPolicy(..)
+    | AdminAction::SetPlatformFee(_)
+    | AdminAction::SetNonprofit(..)
+    | AdminAction::SetVerified(..)
+    | AdminAction::SetRandomAssignment(_)
+    | AdminAction::SetArbitrationPolicy(_)
+    | AdminAction::SetAssetMigration(..)
+    | AdminAction::SetLegacyAsset(_)
+    | AdminAction::SetMinDepositIncrement(..)
+    | AdminAction::AddRegistryEntry(..)
+    | AdminAction::SetLabel(..)
+    | AdminAction::AddCategoryTemplate(..)
+    | AdminAction::UpdateCategoryTemplate(..)
+    | AdminAction::AddArbitrator(_)
+    | AdminAction::PromoteArbitrator(_)
+    | AdminAction::SetAllowlisted(..) => SETTINGS_DELAY_SECS,
+    AdminAction::ForceResolve(..) | AdminAction::OverturnRuling(..) => FORCE_RESOLVE_DELAY_SECS,
+    AdminAction::RestrictToAllowlist => LAUNCH_MODE_DELAY_SECS,
+    AdminAction::RecoverFreelancer(..) => RECOVERY_DELAY_SECS,
+    AdminAction::SetDeactivated(..) | AdminAction::SetReferenceRate(..) => 0,
+  }
+}
+
+fn check_action(env: &Env, action: &AdminAction) -> Result<(), Error> {
+  match action {
+    AdminAction::SetConfig(config) => check_config_change(env, config),
+    AdminAction::SetTtlPolicy(_, policy) => ttl::check_policy(env, policy),
+    AdminAction::SetPlatformFee(bps) => check_platform_fee(*bps),
+    AdminAction::SetArbitrationPolicy(policy) => check_arbitration_policy(policy),
+    AdminAction::ForceResolve(escrow_id, milestone_index, freelancer_amount) => check_split(env, *escrow_id, *milestone_index, *freelancer_amount).map(|_| ()),
+    AdminAction::OverturnRuling(escrow_id, milestone_index, freelancer_amount) => {
+      if !storage::pending_rulings(*escrow_id, *milestone_index).has(env) {
+        return Err(Error::NotFound);
+      }
+      check_split(env, *escrow_id, *milestone_index, *freelancer_amount).map(|_| ())
+    }
+    AdminAction::SetAssetMigration(old, new) => check_asset_migration(old, new),
+    AdminAction::RecoverFreelancer(escrow_id, new_freelancer) => check_recovery(env, *escrow_id, new_freelancer).map(|_| ()),
+    AdminAction::SetReferenceRate(asset, rate, timestamp) => check_reference_rate(env, asset, *rate, *timestamp),
+    AdminAction::SetMinDepositIncrement(asset, increment) => check_min_deposit_increment(env, asset, *increment),
+    AdminAction::SetLabel(registry, id, lang, _) => check_label(env, *registry, *id, lang),
+    AdminAction::AddCategoryTemplate(category_id, _, shares) => check_new_template(env, *category_id, shares),
+    AdminAction::UpdateCategoryTemplate(template_id, shares) => check_template_update(env, *template_id, shares),
+    AdminAction::AddArbitrator(arbitrator) => check_arbitrator_room(env, arbitrator),
+    AdminAction::PromoteArbitrator(arbitrator) => storage::arbitrators(arbitrator.clone()).get(env).map(|_| ()).ok_or(Error::NotFound),
+    AdminAction::SetNonprofit(..)
+    | AdminAction::SetVerified(..)
+    | AdminAction::SetRandomAssignment(_)
+    | AdminAction::SetDeactivated(..)
+    | AdminAction::RestrictToAllowlist
+    | AdminAction::SetLegacyAsset(_)
+    | AdminAction::AddRegistryEntry(..)
+    | AdminAction::SetAllowlisted(..) => Ok(()),
+  }
+}
+
+fn apply(env: &Env, action: AdminAction) -> Result<(), Error> {
+  match action {
+    AdminAction::SetConfig(config) => set_config(env, config),
+    AdminAction::SetTtlPolicy(class, policy) => ttl::set_policy(env, class, policy),
+    AdminAction::SetPlatformFee(bps) => set_platform_fee(env, bps),
+    AdminAction::SetNonprofit(user, nonprofit) => {
+      set_nonprofit(env, user, nonprofit);
+      Ok(())
+    }
+    AdminAction::SetRandomAssignment(enabled) => {
+      set_random_assignment(env, enabled);
+      Ok(())
+    }
+    AdminAction::SetArbitrationPolicy(policy) => set_arbitration_policy(env, policy),
+    AdminAction::ForceResolve(escrow_id, milestone_index, freelancer_amount) => force_resolve(env, escrow_id, milestone_index, freelancer_amount),
+    AdminAction::OverturnRuling(escrow_id, milestone_index, freelancer_amount) => overturn_ruling(env, escrow_id, milestone_index, freelancer_amount),
+    AdminAction::SetDeactivated(user, deactivated) => {
+      deactivate(env, user, deactivated);
+      Ok(())
+    }
+    AdminAction::SetAssetMigration(old, new) => {
+      set_asset_migration(env, old, new);
+      Ok(())
+    }
+    AdminAction::RestrictToAllowlist => {
+      restrict_to_allowlist(env);
+      Ok(())
+    }
+    AdminAction::RecoverFreelancer(escrow_id, new_freelancer) => recover(env, escrow_id, new_freelancer),
+    AdminAction::SetVerified(freelancer, verified) => {
+      set_verified(env, freelancer, verified);
+      Ok(())
+    }
+    AdminAction::SetLegacyAsset(asset) => {
+      set_legacy_asset(env, asset);
+      Ok(())
+    }
+    AdminAction::SetReferenceRate(asset, rate, timestamp) => set_reference_rate(env, asset, rate, timestamp),
+    AdminAction::SetMinDepositIncrement(asset, increment) => set_min_deposit_increment(env, asset, increment),
+    AdminAction::AddRegistryEntry(registry, lang, label) => {
+      add_registry_entry(env, registry, lang, label);
+      Ok(())
+    }
+    AdminAction::SetLabel(registry, id, lang, label) => set_label(env, registry, id, lang, label),
+    AdminAction::AddCategoryTemplate(category_id, name, shares) => add_category_template(env, category_id, name, shares),
+    AdminAction::UpdateCategoryTemplate(template_id, shares) => update_category_template(env, template_id, shares),
+    AdminAction::AddArbitrator(arbitrator) => add_arbitrator(env, arbitrator),
+    AdminAction::PromoteArbitrator(arbitrator) => promote_arbitrator(env, arbitrator),
+    AdminAction::SetAllowlisted(user, allowed) => {
+      set_allowlisted(env, user, allowed);
+      Ok(())
+    }
+  }
+}
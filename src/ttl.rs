@@ -0,0 +1,66 @@
+// Storage lifetime by data class. Every write that keeps data alive goes through `bump`,
+// which extends the entry written by its class's policy. Projects, escrows, accepted digests,
+// indexes and tombstones are persistent entries with a TTL each, so a finished escrow's record lapses on
+// the Cold policy while a live one next to it is kept on the Hot one. Everything else shares
+// the instance's TTL, which every bump extends as well.
+
+use soroban_sdk::{ contracttype, Env };
+
+use crate::storage::Entry;
+use crate::{ storage, Error };
+
+// Ledgers per day at roughly five seconds per ledger
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+// Smallest threshold the admin may set, so a touch never extends on every call
+pub const MIN_TTL_THRESHOLD: u32 = DAY_IN_LEDGERS;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub enum TtlClass {
+  Hot, // Active escrows and open projects
+  Warm, // Profiles and ratings
+  Cold, // Finished escrows and tombstones
+}
+
+// An entry touched with fewer than `threshold` ledgers left is extended to `extend_to`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[contracttype]
+pub struct TtlPolicy {
+  pub threshold: u32,
+  pub extend_to: u32,
+}
+
+// Policies in force until the admin changes them
+pub fn default_policy(class: TtlClass) -> TtlPolicy {
+  match class {
+    TtlClass::Hot => TtlPolicy { threshold: 30 * DAY_IN_LEDGERS, extend_to: 120 * DAY_IN_LEDGERS },
+    TtlClass::Warm => TtlPolicy { threshold: 14 * DAY_IN_LEDGERS, extend_to: 60 * DAY_IN_LEDGERS },
+    TtlClass::Cold => TtlPolicy { threshold: 7 * DAY_IN_LEDGERS, extend_to: 30 * DAY_IN_LEDGERS },
+  }
+}
+
+pub fn policy(env: &Env, class: TtlClass) -> TtlPolicy {
+  storage::ttl_policies(class).get(env).unwrap_or(default_policy(class))
+}
+
+// The new policy applies from the next touch of its class
+// Applied through the admin queue
+pub fn set_policy(env: &Env, class: TtlClass, policy: TtlPolicy) -> Result<(), Error> {
+  check_policy(env, &policy)?;
+  storage::ttl_policies(class).set(env, &policy);
+  Ok(())
+}
+
+pub fn check_policy(env: &Env, policy: &TtlPolicy) -> Result<(), Error> {
+  if policy.threshold < MIN_TTL_THRESHOLD || policy.threshold >= policy.extend_to || policy.extend_to > env.storage().max_ttl() {
+    return Err(Error::InvalidConfig);
+  }
+  Ok(())
+}
+
+pub fn bump<V>(env: &Env, entry: &Entry<V>, class: TtlClass) {
+  let policy = policy(env, class);
+  let extend_to = policy.extend_to.min(env.storage().max_ttl());
+  entry.extend_ttl(env, policy.threshold, extend_to);
+  env.storage().instance().extend_ttl(policy.threshold, extend_to);
+}
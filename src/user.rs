@@ -0,0 +1,232 @@
+// Per-address settings, and the profile and stats views built from them
+
+use soroban_sdk::{ Address, BytesN, Env, Map, Symbol, Vec };
+
+use crate::index::IndexKind;
+use crate::rating::reputation;
+use crate::ttl::TtlClass;
+use crate::{ events, index, storage, ttl, CounterpartyRisk, Error, FreelancerStats, Profile, RatingSummary, RecoverySetting, UserStats, WipLimit, MAX_USER_LANGUAGES, PROFILE_RATINGS, RECOVERY_DELAY_SECS };
+
+pub fn set_wip_limit(env: &Env, from: Address, max_active: u32, hard: bool) {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  storage::wip_limits(from).set(env, &WipLimit { max_active, hard });
+}
+
+pub fn get_wip_limit(env: &Env, client: Address) -> WipLimit {
+  storage::wip_limits(client).get(env).unwrap_or(WipLimit { max_active: 0, hard: false })
+}
+
+pub fn set_notification_pref(env: &Env, from: Address, pref: BytesN<32>) {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  storage::notification_prefs(from).set(env, &pref);
+}
+
+pub fn get_notification_pref(env: &Env, user: Address) -> Option<BytesN<32>> {
+  storage::notification_prefs(user).get(env)
+}
+
+pub fn get_profile(env: &Env, user: Address) -> Profile {
+  let deactivated = storage::deactivated(user.clone()).has(env);
+  let mut profile = Profile {
+    user: user.clone(),
+    deactivated,
+    available: false,
+    languages: Vec::new(env),
+    stats: FreelancerStats::default(),
+    completed_as_client: 0,
+    ratings: RatingSummary::default(),
+    average_rating: 0,
+    latest_ratings: Vec::new(env),
+    reputation: 0,
+    tag_counts: Map::new(env),
+    recovery: None,
+  };
+  if deactivated {
+    return profile;
+  }
+
+  profile.available = !storage::unavailable(user.clone()).has(env);
+  profile.languages = user_languages(env, &user);
+  profile.stats = storage::freelancer_stats(user.clone()).get(env).unwrap_or_default();
+  profile.completed_as_client = storage::user_stats(user.clone()).get(env).unwrap_or_default().completed_as_client;
+  profile.ratings = storage::rating_summaries(user.clone()).get(env).unwrap_or_default();
+  profile.average_rating = (profile.ratings.stars * 100).checked_div(profile.ratings.count).unwrap_or(0);
+  profile.reputation = reputation(&profile.ratings);
+  profile.tag_counts = storage::tag_counts(user.clone()).get(env).unwrap_or(Map::new(env));
+  profile.recovery = recovery_address(env, &user);
+  let rated = index::read(env, &IndexKind::FreelancerRatings(user));
+  for position in (rated.len().saturating_sub(PROFILE_RATINGS)..rated.len()).rev() {
+    if let Some(rating) = storage::ratings(rated.get(position).unwrap()).get(env) {
+      profile.latest_ratings.push_back(rating);
+    }
+  }
+  profile
+}
+
+pub fn set_languages(env: &Env, from: Address, languages: Vec<Symbol>) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  if languages.len() > MAX_USER_LANGUAGES {
+    return Err(Error::LimitReached);
+  }
+  let key = storage::user_languages(from);
+  if languages.is_empty() {
+    key.remove(env);
+  } else {
+    key.set(env, &languages);
+  }
+  ttl::bump(env, &key, TtlClass::Warm);
+  Ok(())
+}
+
+// Applied through the admin queue, at once
+pub fn deactivate(env: &Env, user: Address, deactivated: bool) {
+  let key = storage::deactivated(user);
+  if deactivated {
+    key.set(env, &true);
+  } else {
+    key.remove(env);
+  }
+  ttl::bump(env, &key, TtlClass::Warm);
+}
+
+// The first recovery address applies at once; a replacement waits RECOVERY_DELAY_SECS, so a
+// stolen key can't quietly redirect the recovery before the owner notices
+pub fn set_recovery_address(env: &Env, from: Address, recovery: Address) -> Result<(), Error> {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  if recovery == from {
+    return Err(Error::InvalidConfig);
+  }
+
+  let now = env.ledger().timestamp();
+  let key = storage::recovery_addresses(from.clone());
+  let setting = match key.get(env) {
+    None => RecoverySetting { address: recovery, next: None, next_at: 0 },
+    Some(setting) => RecoverySetting { address: effective_recovery(&setting, now), next: Some(recovery), next_at: now + RECOVERY_DELAY_SECS },
+  };
+  key.set(env, &setting);
+  ttl::bump(env, &key, TtlClass::Warm);
+  Ok(())
+}
+
+pub fn get_recovery_setting(env: &Env, user: Address) -> Option<RecoverySetting> {
+  storage::recovery_addresses(user).get(env)
+}
+
+// The user's recovery address in effect now
+pub fn recovery_address(env: &Env, user: &Address) -> Option<Address> {
+  let setting = storage::recovery_addresses(user.clone()).get(env)?;
+  Some(effective_recovery(&setting, env.ledger().timestamp()))
+}
+
+fn effective_recovery(setting: &RecoverySetting, now: u64) -> Address {
+  match &setting.next {
+    Some(next) if now >= setting.next_at => next.clone(),
+    _ => setting.address.clone(),
+  }
+}
+
+pub fn get_user_stats(env: &Env, user: Address) -> UserStats {
+  storage::user_stats(user).get(env).unwrap_or_default()
+}
+
+pub fn get_freelancer_stats(env: &Env, freelancer: Address) -> FreelancerStats {
+  storage::freelancer_stats(freelancer).get(env).unwrap_or_default()
+}
+
+pub fn set_availability(env: &Env, from: Address, available: bool) {
+  // Ensure the sender authorized this call
+  from.require_auth();
+  let key = storage::unavailable(from);
+  if available {
+    key.remove(env);
+  } else {
+    key.set(env, &true);
+  }
+  ttl::bump(env, &key, TtlClass::Warm);
+}
+
+pub fn get_counterparty_risk(env: &Env, client: Address) -> CounterpartyRisk {
+  let stats = storage::user_stats(client).get(env).unwrap_or_default();
+  let tally = stats.reviews;
+  CounterpartyRisk {
+    completed_escrows: stats.completed_as_client,
+    reviews: tally.reviews,
+    average_review_secs: if tally.reviews == 0 { 0 } else { tally.total_secs / tally.reviews as u64 },
+    worst_review_secs: tally.worst_secs,
+    auto_releases: tally.auto_releases,
+  }
+}
+
+// Applies the client's work-in-progress limit to one more escrow with the freelancer
+pub fn check_wip_limit(env: &Env, project_id: u64, client: &Address, freelancer: &Address) -> Result<(), Error> {
+  let Some(limit) = storage::wip_limits(client.clone()).get(env) else {
+    return Ok(());
+  };
+  let active = index::len(env, &IndexKind::PairEscrows(client.clone(), freelancer.clone()));
+  if limit.max_active == 0 || active < limit.max_active {
+    return Ok(());
+  }
+  if limit.hard {
+    return Err(Error::WipLimitReached);
+  }
+  events::wip_warning(env, client, freelancer, project_id, active, limit.max_active);
+  Ok(())
+}
+
+pub fn user_languages(env: &Env, user: &Address) -> Vec<Symbol> {
+  storage::user_languages(user.clone()).get(env).unwrap_or(Vec::new(env))
+}
+
+pub fn update_freelancer_stats(env: &Env, freelancer: &Address, update: impl FnOnce(&mut FreelancerStats)) {
+  let key = storage::freelancer_stats(freelancer.clone());
+  let mut stats = key.get(env).unwrap_or_default();
+  update(&mut stats);
+  key.set(env, &stats);
+}
+
+#[cfg(test)]
+mod test {
+  use soroban_sdk::testutils::{ Address as _, Events as _ };
+  use soroban_sdk::{ vec, Address };
+
+  use crate::index::{ self, IndexKind, MAX_PAIR_INDEX_LEN };
+  use crate::test::harness;
+  use crate::user::{ check_wip_limit, update_freelancer_stats, user_languages };
+  use crate::{ storage, Error, FreelancerStats, WipLimit };
+
+  #[test]
+  fn freelancer_stats_start_empty_and_accumulate() {
+    let (env, contract) = harness();
+    let freelancer = Address::generate(&env);
+    env.as_contract(&contract, || {
+      update_freelancer_stats(&env, &freelancer, |stats| stats.responses += 1);
+      update_freelancer_stats(&env, &freelancer, |stats| {
+        stats.responses += 1;
+        stats.response_secs += 40;
+      });
+      assert_eq!(storage::freelancer_stats(freelancer.clone()).get(&env), Some(FreelancerStats { active_escrows: 0, responses: 2, response_secs: 40 }));
+      assert_eq!(user_languages(&env, &freelancer), vec![&env]);
+    });
+  }
+
+  #[test]
+  fn a_soft_wip_limit_warns_where_a_hard_one_refuses() {
+    let (env, contract) = harness();
+    let (client, freelancer) = (Address::generate(&env), Address::generate(&env));
+    env.as_contract(&contract, || {
+      assert_eq!(check_wip_limit(&env, 1, &client, &freelancer), Ok(()));
+      index::append_unique(&env, &IndexKind::PairEscrows(client.clone(), freelancer.clone()), 1, MAX_PAIR_INDEX_LEN);
+      storage::wip_limits(client.clone()).set(&env, &WipLimit { max_active: 2, hard: true });
+      assert_eq!(check_wip_limit(&env, 1, &client, &freelancer), Ok(()));
+      storage::wip_limits(client.clone()).set(&env, &WipLimit { max_active: 1, hard: true });
+      assert_eq!(check_wip_limit(&env, 1, &client, &freelancer), Err(Error::WipLimitReached));
+      storage::wip_limits(client.clone()).set(&env, &WipLimit { max_active: 1, hard: false });
+      assert_eq!(check_wip_limit(&env, 1, &client, &freelancer), Ok(()));
+    });
+    assert_eq!(env.events().all().len(), 1);
+  }
+}
@@ -0,0 +1,76 @@
+// The project and escrow builders, driven from outside the crate
+
+use freelance_marketplace::testutils::{ fund_and_accept, milestone, run_to_completed, EscrowBuilder, Marketplace, ProjectBuilder, BUDGET, PROJECT_DEADLINE };
+use freelance_marketplace::EscrowState;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{ vec, Address };
+
+fn parties(market: &Marketplace) -> (Address, Address) {
+  (Address::generate(&market.env), Address::generate(&market.env))
+}
+
+#[test]
+fn each_post_is_a_new_project() {
+  let market = Marketplace::new();
+  let (client, freelancer) = parties(&market);
+  let first = ProjectBuilder::new(&market, &client).post();
+  let second = ProjectBuilder::new(&market, &client).title("Shop").deadline(PROJECT_DEADLINE * 2).post();
+  assert_eq!(second, first + 1);
+  let escrow_id = market.contract.initiate_escrow(&client, &second, &freelancer);
+  assert_eq!(market.escrow(escrow_id).total, BUDGET);
+}
+
+#[test]
+fn an_opened_escrow_waits_for_funds_and_acceptance() {
+  let market = Marketplace::new();
+  let (client, freelancer) = parties(&market);
+  let escrow_id = EscrowBuilder::new(&market, &client, &freelancer).open();
+  let escrow = market.escrow(escrow_id);
+  assert_eq!(escrow.state, EscrowState::Created);
+  assert_eq!((escrow.total, escrow.deposited, escrow.accepted), (BUDGET, 0, false));
+
+  fund_and_accept(&market, escrow_id);
+  let escrow = market.escrow(escrow_id);
+  assert_eq!((escrow.deposited, escrow.accepted), (BUDGET, true));
+  // Nothing is missing the second time round
+  fund_and_accept(&market, escrow_id);
+  assert_eq!(market.escrow(escrow_id).deposited, BUDGET);
+}
+
+#[test]
+fn fund_and_accept_tops_up_a_partial_deposit() {
+  let market = Marketplace::new();
+  let (client, freelancer) = parties(&market);
+  let escrow_id = EscrowBuilder::new(&market, &client, &freelancer).deposit(250).open();
+  assert_eq!(market.escrow(escrow_id).deposited, 250);
+  fund_and_accept(&market, escrow_id);
+  assert_eq!(market.escrow(escrow_id).deposited, BUDGET);
+}
+
+#[test]
+fn custom_milestones_set_the_budget_and_run_to_completion() {
+  let market = Marketplace::new();
+  let (client, freelancer) = parties(&market);
+  let env = &market.env;
+  let milestones = vec![env, milestone(env, "Research", 100), milestone(env, "Draft", 200), milestone(env, "Final", 300)];
+  let escrow_id = EscrowBuilder::new(&market, &client, &freelancer).project(|project| project.title("Report").milestones(milestones)).funded().accepted().open();
+  assert_eq!(market.escrow(escrow_id).total, 600);
+
+  run_to_completed(&market, escrow_id);
+  let escrow = market.escrow(escrow_id);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!(escrow.released, 600);
+}
+
+#[test]
+fn a_lump_sum_escrow_runs_to_completion_through_its_deliverable() {
+  let market = Marketplace::new();
+  let (client, freelancer) = parties(&market);
+  let escrow_id = EscrowBuilder::new(&market, &client, &freelancer).project(|project| project.lump_sum(500)).open();
+  assert!(market.escrow(escrow_id).lump_sum);
+
+  run_to_completed(&market, escrow_id);
+  let escrow = market.escrow(escrow_id);
+  assert_eq!((escrow.state, escrow.released), (EscrowState::Completed, 500));
+  assert!(market.contract.get_deliverable(&escrow_id).is_some());
+}
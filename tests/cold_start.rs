@@ -0,0 +1,54 @@
+// A marketplace deployed with nothing but its admin, never configured. Whatever a new config
+// field defaults to, these flows have to keep working on it.
+
+use freelance_marketplace::testutils::{ completed_escrow, disputed_escrow, EscrowBuilder, Marketplace, BUDGET, DESIGN_AMOUNT };
+use freelance_marketplace::{ Config, EscrowState };
+use soroban_sdk::testutils::{ Address as _, Ledger };
+use soroban_sdk::{ Address, BytesN };
+
+const DAY: u64 = 86_400;
+
+#[test]
+fn the_constructor_stores_the_default_config() {
+  let market = Marketplace::new();
+  assert_eq!(market.contract.get_config(), Config::default_values());
+  assert_eq!(market.contract.get_platform_fee(), 0);
+}
+
+#[test]
+fn an_escrow_runs_to_completion_without_configuration() {
+  let market = Marketplace::new();
+  let golden = completed_escrow(&market);
+  let escrow = market.escrow(golden.escrow_id);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!((escrow.released, escrow.refunded), (BUDGET, 0));
+  // No fee was set, so the platform took nothing
+  assert_eq!(market.contract.get_withdrawable_fees(&market.asset), 0);
+}
+
+#[test]
+fn the_default_review_period_lets_the_freelancer_release_after_a_week() {
+  let market = Marketplace::new();
+  let (client, freelancer) = (Address::generate(&market.env), Address::generate(&market.env));
+  let escrow_id = EscrowBuilder::new(&market, &client, &freelancer).funded().accepted().open();
+  market.contract.submit_milestone(&freelancer, &escrow_id, &0, &BytesN::from_array(&market.env, &[1; 32]));
+
+  market.env.ledger().with_mut(|ledger| ledger.timestamp = 7 * DAY - 1);
+  assert!(market.contract.try_auto_release(&freelancer, &escrow_id, &0).is_err());
+  market.env.ledger().with_mut(|ledger| ledger.timestamp = 7 * DAY);
+  market.contract.auto_release(&freelancer, &escrow_id, &0);
+
+  assert_eq!(market.escrow(escrow_id).released, DESIGN_AMOUNT);
+}
+
+#[test]
+fn the_admin_settles_a_dispute_without_configuration() {
+  let market = Marketplace::new();
+  let golden = disputed_escrow(&market);
+
+  market.contract.resolve_dispute(&market.admin, &golden.escrow_id, &1, &100);
+
+  let escrow = market.escrow(golden.escrow_id);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!((escrow.released, escrow.refunded), (DESIGN_AMOUNT + 100, BUDGET - DESIGN_AMOUNT - 100));
+}
@@ -0,0 +1,278 @@
+// Smart-wallet parties: the client and the freelancer are custom account contracts, and every
+// call they make is signed for real rather than mocked. The host hands each authorization to
+// the wallet's __check_auth, so these flows pass only if the contract asks for the parties'
+// auth the way the host expects, with any token transfer declared under the call it runs in.
+
+use std::cell::Cell;
+
+use ed25519_dalek::{ Signer as _, SigningKey };
+use freelance_marketplace::testutils::{ milestone, EscrowBuilder, EscrowSummary, Marketplace, BUDGET, DESIGN_AMOUNT, PROJECT_DEADLINE };
+use freelance_marketplace::{ EscrowState, Locale };
+use soroban_sdk::auth::{ Context, CustomAccountInterface };
+use soroban_sdk::crypto::Hash;
+use soroban_sdk::token::{ StellarAssetClient, TokenClient };
+use soroban_sdk::xdr::{ self, HashIdPreimage, HashIdPreimageSorobanAuthorization, InvokeContractArgs, Limits, ScAddress, ScSymbol, ScVal, SorobanAddressCredentials, SorobanAuthorizationEntry, SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials, VecM, WriteXdr };
+use soroban_sdk::{ contract, contracterror, contractimpl, contracttype, symbol_short, vec, Address, Bytes, BytesN, Env, IntoVal, String, TryFromVal, Val, Vec };
+
+const BOND: i128 = 100;
+
+// The SDK's example account, trimmed to its signer check: a wallet of ed25519 signers that
+// authorizes a call once `threshold` of them have signed its payload
+#[contract]
+struct MultisigAccount;
+
+#[contracttype]
+#[derive(Clone)]
+pub struct AccSignature {
+  pub public_key: BytesN<32>,
+  pub signature: BytesN<64>,
+}
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum AccError {
+  NotEnoughSigners = 1,
+  UnknownSigner = 2,
+  BadSignatureOrder = 3,
+}
+
+#[contractimpl]
+impl MultisigAccount {
+  pub fn __constructor(env: Env, signers: Vec<BytesN<32>>, threshold: u32) {
+    env.storage().instance().set(&symbol_short!("signers"), &signers);
+    env.storage().instance().set(&symbol_short!("threshold"), &threshold);
+  }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for MultisigAccount {
+  type Signature = Vec<AccSignature>;
+  type Error = AccError;
+
+  fn __check_auth(env: Env, signature_payload: Hash<32>, signatures: Vec<AccSignature>, _auth_contexts: Vec<Context>) -> Result<(), AccError> {
+    let signers: Vec<BytesN<32>> = env.storage().instance().get(&symbol_short!("signers")).unwrap();
+    let threshold: u32 = env.storage().instance().get(&symbol_short!("threshold")).unwrap();
+    let payload: Bytes = signature_payload.to_bytes().into();
+    let mut previous: Option<BytesN<32>> = None;
+    for signature in signatures.iter() {
+      // Ascending keys rule out counting one signer twice
+      if previous.is_some_and(|previous| previous >= signature.public_key) {
+        return Err(AccError::BadSignatureOrder);
+      }
+      if !signers.contains(&signature.public_key) {
+        return Err(AccError::UnknownSigner);
+      }
+      // Traps on a bad signature, failing the invocation
+      env.crypto().ed25519_verify(&signature.public_key, &payload, &signature.signature);
+      previous = Some(signature.public_key);
+    }
+    if signatures.len() < threshold {
+      return Err(AccError::NotEnoughSigners);
+    }
+    Ok(())
+  }
+}
+
+struct Wallet {
+  address: Address,
+  keys: std::vec::Vec<SigningKey>,
+}
+
+impl Wallet {
+  fn new(env: &Env, seeds: &[u8], threshold: u32) -> Self {
+    let mut keys: std::vec::Vec<SigningKey> = seeds.iter().map(|seed| SigningKey::from_bytes(&[*seed; 32])).collect();
+    keys.sort_by_key(|key| key.verifying_key().to_bytes());
+    let mut signers = Vec::new(env);
+    for key in &keys {
+      signers.push_back(BytesN::from_array(env, &key.verifying_key().to_bytes()));
+    }
+    let address = env.register(MultisigAccount, (signers, threshold));
+    Wallet { address, keys }
+  }
+}
+
+// A call the marketplace makes on a party's behalf, here a token transfer
+struct SubCall {
+  contract: Address,
+  function: &'static str,
+  args: Vec<Val>,
+}
+
+struct Session<'a> {
+  market: Marketplace<'a>,
+  nonce: Cell<i64>,
+}
+
+impl Session<'_> {
+  fn new() -> Self {
+    Session { market: Marketplace::new(), nonce: Cell::new(0) }
+  }
+
+  fn env(&self) -> &Env {
+    &self.market.env
+  }
+
+  // Replaces the mocked auths with one signed entry per signer, each authorizing `function`
+  // on the marketplace with `args` and the sub-calls nested under it. `keys` picks which of
+  // each wallet's keys sign.
+  fn sign(&self, signers: &[(&Wallet, &[usize])], function: &str, args: Vec<Val>, sub_calls: &[SubCall]) {
+    let env = self.env();
+    let mut sub_invocations = std::vec::Vec::new();
+    for call in sub_calls {
+      sub_invocations.push(invocation(env, &call.contract, call.function, &call.args, std::vec::Vec::new()));
+    }
+    let root = invocation(env, &self.market.contract.address, function, &args, sub_invocations);
+    let mut entries = std::vec::Vec::new();
+    for (wallet, keys) in signers {
+      self.nonce.set(self.nonce.get() + 1);
+      entries.push(entry(env, wallet, keys, self.nonce.get(), root.clone()));
+    }
+    env.set_auths(&entries);
+  }
+
+  // Reads go through the admin, so they run under mocked auths again
+  fn summary(&self, escrow_id: u64) -> EscrowSummary {
+    self.env().mock_all_auths();
+    self.market.escrow(escrow_id)
+  }
+}
+
+fn invocation(env: &Env, contract: &Address, function: &str, args: &Vec<Val>, sub_invocations: std::vec::Vec<SorobanAuthorizedInvocation>) -> SorobanAuthorizedInvocation {
+  let args: std::vec::Vec<ScVal> = args.iter().map(|arg| ScVal::try_from_val(env, &arg).unwrap()).collect();
+  SorobanAuthorizedInvocation {
+    function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+      contract_address: ScAddress::from(contract),
+      function_name: ScSymbol(function.try_into().unwrap()),
+      args: args.try_into().unwrap(),
+    }),
+    sub_invocations: sub_invocations.try_into().unwrap_or(VecM::default()),
+  }
+}
+
+// Signs the payload the host derives from the entry, the way a wallet's owners would
+fn entry(env: &Env, wallet: &Wallet, keys: &[usize], nonce: i64, root_invocation: SorobanAuthorizedInvocation) -> SorobanAuthorizationEntry {
+  let signature_expiration_ledger = env.ledger().sequence() + 100;
+  let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+    network_id: xdr::Hash(env.ledger().network_id().to_array()),
+    nonce,
+    signature_expiration_ledger,
+    invocation: root_invocation.clone(),
+  });
+  let preimage = Bytes::from_slice(env, &preimage.to_xdr(Limits::none()).unwrap());
+  let payload = env.crypto().sha256(&preimage).to_array();
+  let mut signatures = Vec::new(env);
+  for index in keys {
+    let key = &wallet.keys[*index];
+    signatures.push_back(AccSignature {
+      public_key: BytesN::from_array(env, &key.verifying_key().to_bytes()),
+      signature: BytesN::from_array(env, &key.sign(&payload).to_bytes()),
+    });
+  }
+  SorobanAuthorizationEntry {
+    credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+      address: ScAddress::from(&wallet.address),
+      nonce,
+      signature_expiration_ledger,
+      signature: ScVal::try_from_val(env, &signatures.to_val()).unwrap(),
+    }),
+    root_invocation,
+  }
+}
+
+#[test]
+fn smart_wallets_run_an_escrow_to_completion() {
+  let session = Session::new();
+  let (env, market) = (session.env(), &session.market);
+  // A two-of-two client wallet and a one-key freelancer wallet
+  let client = Wallet::new(env, &[1, 2], 2);
+  let freelancer = Wallet::new(env, &[3], 1);
+  let contract = &market.contract;
+  let bond_token = env.register_stellar_asset_contract_v2(market.admin.clone()).address();
+  StellarAssetClient::new(env, &bond_token).mint(&freelancer.address, &BOND);
+
+  let (title, description, category) = (String::from_str(env, "Site"), String::from_str(env, "A website"), String::from_str(env, "web"));
+  let milestones = vec![env, milestone(env, "Design", DESIGN_AMOUNT), milestone(env, "Build", BUDGET - DESIGN_AMOUNT)];
+  let args = (client.address.clone(), title.clone(), description.clone(), category.clone(), BUDGET, market.asset.clone(), PROJECT_DEADLINE, milestones.clone(), Locale::default());
+  session.sign(&[(&client, &[0, 1])], "post_project", args.into_val(env), &[]);
+  let project_id = contract.post_project(&client.address, &title, &description, &category, &BUDGET, &market.asset, &PROJECT_DEADLINE, &milestones, &Locale::default());
+
+  session.sign(&[(&client, &[0, 1])], "initiate_escrow", (client.address.clone(), project_id, freelancer.address.clone()).into_val(env), &[]);
+  let escrow_id = contract.initiate_escrow(&client.address, &project_id, &freelancer.address);
+
+  session.sign(&[(&client, &[0, 1])], "require_bond", (client.address.clone(), escrow_id, bond_token.clone(), BOND).into_val(env), &[]);
+  contract.require_bond(&client.address, &escrow_id, &bond_token, &BOND);
+
+  session.sign(&[(&client, &[0, 1])], "deposit_funds", (client.address.clone(), escrow_id, BUDGET, None::<BytesN<32>>).into_val(env), &[]);
+  contract.deposit_funds(&client.address, &escrow_id, &BUDGET, &None);
+
+  // The bond moves out of the freelancer's wallet, so its transfer is declared under the call
+  let transfer = SubCall { contract: bond_token.clone(), function: "transfer", args: (freelancer.address.clone(), contract.address.clone(), BOND).into_val(env) };
+  session.sign(&[(&freelancer, &[0])], "post_bond", (freelancer.address.clone(), escrow_id).into_val(env), &[transfer]);
+  contract.post_bond(&freelancer.address, &escrow_id);
+  assert_eq!(TokenClient::new(env, &bond_token).balance(&freelancer.address), 0);
+
+  let digest = contract.terms_digest(&escrow_id);
+  session.sign(&[(&freelancer, &[0])], "accept_engagement", (freelancer.address.clone(), escrow_id, digest.clone()).into_val(env), &[]);
+  contract.accept_engagement(&freelancer.address, &escrow_id, &digest);
+
+  for milestone_index in 0..2u32 {
+    let deliverable = BytesN::from_array(env, &[milestone_index as u8 + 1; 32]);
+    session.sign(&[(&freelancer, &[0])], "submit_milestone", (freelancer.address.clone(), escrow_id, milestone_index, deliverable.clone()).into_val(env), &[]);
+    contract.submit_milestone(&freelancer.address, &escrow_id, &milestone_index, &deliverable);
+    session.sign(&[(&client, &[0, 1])], "release_funds", (client.address.clone(), escrow_id, milestone_index).into_val(env), &[]);
+    contract.release_funds(&client.address, &escrow_id, &milestone_index);
+  }
+
+  let escrow = session.summary(escrow_id);
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!(escrow.released, BUDGET);
+  // Completion hands the bond back to the wallet it came from
+  assert_eq!(TokenClient::new(env, &bond_token).balance(&freelancer.address), BOND);
+}
+
+#[test]
+fn a_release_needs_both_of_the_client_wallets_signers() {
+  let session = Session::new();
+  let (env, market) = (session.env(), &session.market);
+  let client = Wallet::new(env, &[1, 2], 2);
+  let freelancer = Wallet::new(env, &[3], 1);
+  let contract = &market.contract;
+  // The setup runs mocked; only the release below is signed
+  let escrow_id = EscrowBuilder::new(market, &client.address, &freelancer.address).funded().accepted().open();
+  contract.submit_milestone(&freelancer.address, &escrow_id, &0, &BytesN::from_array(env, &[1; 32]));
+
+  let args: Vec<Val> = (client.address.clone(), escrow_id, 0u32).into_val(env);
+  for keys in [&[0][..], &[1][..]] {
+    session.sign(&[(&client, keys)], "release_funds", args.clone(), &[]);
+    assert!(contract.try_release_funds(&client.address, &escrow_id, &0).is_err());
+  }
+  // Nor can the freelancer's wallet sign for the client
+  session.sign(&[(&freelancer, &[0])], "release_funds", args.clone(), &[]);
+  assert!(contract.try_release_funds(&client.address, &escrow_id, &0).is_err());
+  assert_eq!(session.summary(escrow_id).released, 0);
+
+  session.sign(&[(&client, &[0, 1])], "release_funds", args, &[]);
+  contract.release_funds(&client.address, &escrow_id, &0);
+  assert_eq!(session.summary(escrow_id).released, DESIGN_AMOUNT);
+}
+
+#[test]
+fn both_wallets_sign_one_call_to_add_a_milestone() {
+  let session = Session::new();
+  let (env, market) = (session.env(), &session.market);
+  let client = Wallet::new(env, &[1, 2], 2);
+  let freelancer = Wallet::new(env, &[3], 1);
+  let contract = &market.contract;
+  let escrow_id = EscrowBuilder::new(market, &client.address, &freelancer.address).funded().accepted().open();
+
+  let extra = milestone(env, "Polish", 50);
+  let args: Vec<Val> = (client.address.clone(), freelancer.address.clone(), escrow_id, extra.clone(), Vec::<u32>::new(env)).into_val(env);
+  // One party's signature is not agreement
+  session.sign(&[(&client, &[0, 1])], "add_milestone", args.clone(), &[]);
+  assert!(contract.try_add_milestone(&client.address, &freelancer.address, &escrow_id, &extra, &Vec::new(env)).is_err());
+
+  session.sign(&[(&client, &[0, 1]), (&freelancer, &[0])], "add_milestone", args, &[]);
+  assert_eq!(contract.add_milestone(&client.address, &freelancer.address, &escrow_id, &extra, &Vec::new(env)), 2);
+  assert_eq!(session.summary(escrow_id).total, BUDGET + 50);
+}
@@ -0,0 +1,45 @@
+// The golden fixtures, driven from outside the crate as a frontend or bot test would
+
+use freelance_marketplace::testutils::{ completed_escrow, disputed_escrow, refunded_escrow, Marketplace, BUDGET, DESIGN_AMOUNT };
+use freelance_marketplace::EscrowState;
+
+#[test]
+fn a_completed_escrow_paid_the_whole_budget() {
+  let market = Marketplace::new();
+  let golden = completed_escrow(&market);
+  let escrow = market.escrow(golden.escrow_id);
+  assert_eq!((escrow.client, escrow.freelancer), (golden.client, golden.freelancer));
+  assert_eq!(escrow.state, EscrowState::Completed);
+  assert_eq!((escrow.deposited, escrow.released, escrow.refunded), (BUDGET, BUDGET, 0));
+  assert!(escrow.accepted);
+}
+
+#[test]
+fn a_disputed_escrow_is_in_progress_with_the_build_milestone_in_dispute() {
+  let market = Marketplace::new();
+  let golden = disputed_escrow(&market);
+  let escrow = market.escrow(golden.escrow_id);
+  assert_eq!(escrow.state, EscrowState::InProgress);
+  assert_eq!((escrow.deposited, escrow.released), (BUDGET, DESIGN_AMOUNT));
+  assert!(market.contract.get_dispute(&golden.escrow_id, &0).is_none());
+  assert!(market.contract.get_dispute(&golden.escrow_id, &1).is_some());
+}
+
+#[test]
+fn a_refunded_escrow_gave_the_partial_deposit_back() {
+  let market = Marketplace::new();
+  let golden = refunded_escrow(&market);
+  let escrow = market.escrow(golden.escrow_id);
+  assert_eq!(escrow.state, EscrowState::Refunded);
+  assert_eq!((escrow.deposited, escrow.released, escrow.refunded), (DESIGN_AMOUNT, 0, DESIGN_AMOUNT));
+  assert!(!escrow.accepted);
+}
+
+#[test]
+fn each_fixture_gets_its_own_parties() {
+  let market = Marketplace::new();
+  let (first, second) = (completed_escrow(&market), completed_escrow(&market));
+  assert_ne!(first.escrow_id, second.escrow_id);
+  assert_ne!(first.client, second.client);
+  assert_ne!(first.freelancer, second.freelancer);
+}